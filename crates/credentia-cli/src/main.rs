@@ -0,0 +1,694 @@
+//! `credentia-cli`: operate a Credentia platform and manage loans from the terminal.
+
+mod output;
+mod views;
+
+use std::{path::PathBuf, str::FromStr};
+
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use credentia_client::{
+    hook::{collection_proof, transfer_hook_program, with_request_transfer_hook},
+    instructions::{
+        self, Currency, InsuranceConfig, LoanKeys, LoanTerms, RequestOptions,
+    },
+    pda,
+    quote::{repayment_quote, transfer_fee},
+    rpc::RpcClient,
+    AcceptanceConstraints, Loan, LoanStatus, Platform,
+};
+use solana_sdk::{
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use views::{CounterOfferView, InsuranceFundView, LoanView, PlatformView, QuoteView, TxView};
+
+const SPL_TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+#[derive(Parser)]
+#[command(name = "credentia-cli", version, about)]
+struct Cli {
+    /// RPC endpoint, a local test validator by default
+    #[arg(long, short = 'u', env = "CREDENTIA_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair that signs and pays for transactions
+    #[arg(long, short = 'k', env = "CREDENTIA_KEYPAIR")]
+    keypair: Option<PathBuf>,
+    /// Platform id the command runs against
+    #[arg(long, default_value_t = 1)]
+    platform_id: u64,
+    /// Platform address, overrides --platform-id (e.g. the legacy singleton)
+    #[arg(long)]
+    platform: Option<Pubkey>,
+    /// Token program owning the NFT mints of existing loans, `request` reads it off the mint
+    #[arg(long, default_value = SPL_TOKEN_PROGRAM)]
+    token_program: Pubkey,
+    /// Token program owning loan currency mints
+    #[arg(long, default_value = SPL_TOKEN_PROGRAM)]
+    currency_token_program: Pubkey,
+    /// Print machine readable JSON
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StatusArg {
+    Requested,
+    Funded,
+    Repaid,
+    Defaulted,
+}
+
+impl StatusArg {
+    fn matches(self, status: &LoanStatus) -> bool {
+        matches!(
+            (self, status),
+            (StatusArg::Requested, LoanStatus::Requested)
+                | (StatusArg::Funded, LoanStatus::Funded)
+                | (StatusArg::Repaid, LoanStatus::Repaid)
+                | (StatusArg::Defaulted, LoanStatus::Defaulted)
+        )
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the platform, its treasury and reward mint
+    InitPlatform {
+        #[arg(long, default_value_t = 500)]
+        fee_bps: u16,
+    },
+    /// Change the platform fee and referral share
+    UpdatePlatform {
+        /// keeps the current fee when omitted
+        #[arg(long)]
+        fee_bps: Option<u16>,
+        /// keeps the current share when omitted
+        #[arg(long)]
+        referral_share_bps: Option<u16>,
+    },
+    /// Show the platform account
+    Platform,
+    /// List loans, optionally filtered by status or borrower
+    Loans {
+        #[arg(long, value_enum)]
+        status: Option<StatusArg>,
+        #[arg(long)]
+        borrower: Option<Pubkey>,
+    },
+    /// Show a loan account
+    Loan { loan: Pubkey },
+    /// Print what repaying a loan costs and where the fee goes
+    Quote { loan: Pubkey },
+    /// Lock an NFT and request a loan against it
+    Request {
+        #[arg(long)]
+        mint: Pubkey,
+        /// metaplex collection, or the token group a Token-2022 nft is a member of
+        #[arg(long)]
+        collection: Pubkey,
+        /// lamports, or base units of --currency
+        #[arg(long)]
+        amount: u64,
+        /// seconds
+        #[arg(long)]
+        duration: u32,
+        /// bps of principal
+        #[arg(long)]
+        interest_rate: u16,
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+        /// spl token mint the loan is paid in, lamports when left out
+        #[arg(long)]
+        currency: Option<Pubkey>,
+        /// unix timestamp after which the request can no longer be funded
+        #[arg(long)]
+        expires_at: Option<i64>,
+        /// only these lenders may fund the request, repeat for several
+        #[arg(long = "allowed-lender")]
+        allowed_lenders: Vec<Pubkey>,
+        /// loans borrowers must already have repaid to a lender on the platform
+        #[arg(long, default_value_t = 0)]
+        min_lender_reputation: u64,
+        /// seconds past the deadline the loan can still be repaid, paid for up front
+        #[arg(long)]
+        protection: Option<u32>,
+    },
+    /// Fund a requested loan as the lender
+    Fund {
+        loan: Pubkey,
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+        /// pay the insurance fund's premium for cover if the loan is not repaid
+        #[arg(long)]
+        insured: bool,
+    },
+    /// Change the terms of your unfunded loan request
+    Update {
+        loan: Pubkey,
+        /// lamports, or base units of the loan currency; keeps the current amount when omitted
+        #[arg(long)]
+        amount: Option<u64>,
+        /// seconds; keeps the current duration when omitted
+        #[arg(long)]
+        duration: Option<u32>,
+        /// bps of principal; keeps the current rate when omitted
+        #[arg(long)]
+        interest_rate: Option<u16>,
+    },
+    /// Escrow an offer with other terms on a requested loan as a lender
+    Offer {
+        loan: Pubkey,
+        /// lamports
+        #[arg(long)]
+        amount: u64,
+        /// seconds
+        #[arg(long)]
+        duration: u32,
+        /// bps of principal
+        #[arg(long)]
+        interest_rate: u16,
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+    },
+    /// List the open counter offers on a loan
+    Offers { loan: Pubkey },
+    /// Accept a lender's counter offer on your loan, which funds it on the offered terms
+    AcceptOffer {
+        loan: Pubkey,
+        #[arg(long)]
+        lender: Pubkey,
+    },
+    /// Reject a lender's counter offer on your loan and refund them
+    RejectOffer {
+        loan: Pubkey,
+        #[arg(long)]
+        lender: Pubkey,
+    },
+    /// Withdraw your counter offer on a loan
+    WithdrawOffer { loan: Pubkey },
+    /// Repay a funded loan and take the NFT back
+    Repay { loan: Pubkey },
+    /// Cancel an unfunded loan and take the NFT back
+    Cancel { loan: Pubkey },
+    /// Claim the NFT of an expired loan as the lender
+    Default { loan: Pubkey },
+    /// Default anyone's expired loan, the NFT goes to the lender and you keep the rent refunds
+    MarkDefaulted { loan: Pubkey },
+    /// Create or change the platform's insurance fund as the platform authority
+    ConfigureInsurance {
+        /// bps of every lamport platform fee paid into the fund
+        #[arg(long)]
+        insurance_share_bps: u16,
+        /// bps of principal a lender pays for cover
+        #[arg(long)]
+        premium_bps: u16,
+        /// bps of the principal shortfall a claim pays
+        #[arg(long)]
+        coverage_bps: u16,
+        /// bps of the fund's balance a single claim can take
+        #[arg(long)]
+        max_claim_bps: u16,
+        /// account that posts collateral valuations
+        #[arg(long)]
+        oracle: Pubkey,
+    },
+    /// Show the platform's insurance fund
+    Insurance,
+    /// Post what an NFT is worth as the insurance oracle
+    SetValue {
+        mint: Pubkey,
+        /// lamports
+        #[arg(long)]
+        value: u64,
+    },
+    /// Claim on the insurance of a loan you funded that was not repaid
+    ClaimInsurance { loan: Pubkey },
+    /// Let borrowers vote through a governance program with their escrowed NFTs
+    AllowGovernance {
+        program: Pubkey,
+        /// hex prefix of an instruction borrowers may relay, repeat for several
+        #[arg(long = "instruction")]
+        instructions: Vec<InstructionPrefix>,
+        /// allow SPL Governance's cast and relinquish vote
+        #[arg(long)]
+        spl_governance: bool,
+    },
+    /// Stop relaying to a governance program
+    RemoveGovernance { program: Pubkey },
+}
+
+/// Start of an instruction's data, written in hex.
+#[derive(Clone, Debug)]
+struct InstructionPrefix(Vec<u8>);
+
+impl FromStr for InstructionPrefix {
+    type Err = String;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        if hex.is_empty() || !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+            return Err(format!("{hex} is not an even number of hex digits"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| format!("{hex}: {e}")))
+            .collect::<Result<_, _>>()
+            .map(InstructionPrefix)
+    }
+}
+
+struct Session {
+    rpc: RpcClient,
+    keypair: Option<PathBuf>,
+    platform: Pubkey,
+    token_program: Pubkey,
+    currency_token_program: Pubkey,
+}
+
+impl Session {
+    fn payer(&self) -> Result<Keypair> {
+        let path = match &self.keypair {
+            Some(path) => path.clone(),
+            None => {
+                let home = std::env::var("HOME").context("HOME is not set, pass --keypair")?;
+                PathBuf::from(home).join(".config/solana/id.json")
+            }
+        };
+        read_keypair_file(&path)
+            .map_err(|e| anyhow!("could not read keypair {}: {e}", path.display()))
+    }
+
+    fn send(&self, payer: &Keypair, instruction: Instruction) -> Result<String> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+        Ok(self.rpc.send_and_confirm(&bincode::serialize(&transaction)?)?)
+    }
+
+    fn platform(&self) -> Result<Platform> {
+        self.rpc
+            .fetch_platform(&self.platform)?
+            .ok_or_else(|| anyhow!("platform {} not found", self.platform))
+    }
+
+    fn loan(&self, address: &Pubkey) -> Result<Loan> {
+        self.rpc
+            .fetch_loan(address)?
+            .ok_or_else(|| anyhow!("loan {address} not found"))
+    }
+
+    fn loan_keys(&self, loan: &Loan) -> LoanKeys {
+        LoanKeys::from_loan(self.platform, loan, self.token_program)
+    }
+
+    fn currency(&self, loan: &Loan) -> Option<Currency> {
+        loan.currency.map(|mint| Currency {
+            mint,
+            token_program: self.currency_token_program,
+        })
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let ctx = Session {
+        rpc: RpcClient::new(&cli.url),
+        keypair: cli.keypair,
+        platform: cli
+            .platform
+            .unwrap_or_else(|| pda::platform(cli.platform_id).0),
+        token_program: cli.token_program,
+        currency_token_program: cli.currency_token_program,
+    };
+    let json = cli.json;
+
+    match cli.command {
+        Command::InitPlatform { fee_bps } => {
+            let payer = ctx.payer()?;
+            let ix = instructions::initialize_platform(
+                payer.pubkey(),
+                cli.platform_id,
+                fee_bps,
+                ctx.token_program,
+            );
+            let signature = ctx.send(&payer, ix)?;
+            output::print(
+                &TxView { signature, account: Some(pda::platform(cli.platform_id).0.to_string()) },
+                json,
+            )
+        }
+        Command::UpdatePlatform {
+            fee_bps,
+            referral_share_bps,
+        } => {
+            let payer = ctx.payer()?;
+            let platform = ctx.platform()?;
+            let ix = instructions::update_platform(
+                payer.pubkey(),
+                ctx.platform,
+                fee_bps.unwrap_or(platform.fee_bps),
+                referral_share_bps.unwrap_or(platform.referral_share_bps),
+            );
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(ctx.platform.to_string()) }, json)
+        }
+        Command::Platform => {
+            let platform = ctx.platform()?;
+            output::print(&PlatformView::new(&ctx.platform, &platform), json)
+        }
+        Command::Loans { status, borrower } => {
+            let mut loans: Vec<_> = ctx
+                .rpc
+                .fetch_loans(borrower.as_ref())?
+                .into_iter()
+                //loans don't store their platform, it is part of the address
+                .filter(|(address, loan)| {
+                    pda::loan(&loan.nft_mint, &ctx.platform, loan.loan_id).0 == *address
+                })
+                .filter(|(_, loan)| status.is_none_or(|s| s.matches(&loan.status)))
+                .collect();
+            loans.sort_by_key(|(_, loan)| loan.loan_id);
+            let views: Vec<_> = loans
+                .iter()
+                .map(|(address, loan)| LoanView::new(address, loan))
+                .collect();
+            output::print(&views, json)
+        }
+        Command::Loan { loan } => output::print(&LoanView::new(&loan, &ctx.loan(&loan)?), json),
+        Command::Quote { loan: address } => {
+            let loan = ctx.loan(&address)?;
+            let fee = match loan.currency {
+                Some(mint) => {
+                    let data = ctx
+                        .rpc
+                        .get_account_data(&mint)?
+                        .ok_or_else(|| anyhow!("currency mint {mint} not found"))?;
+                    transfer_fee(&data, ctx.rpc.get_epoch()?)
+                }
+                None => None,
+            };
+            let quote = repayment_quote(&loan, &ctx.platform()?, fee.as_ref())
+                .ok_or_else(|| anyhow!("repayment overflows for loan {address}"))?;
+            output::print(&QuoteView::new(&address, &loan, &quote), json)
+        }
+        Command::Request {
+            mint,
+            collection,
+            amount,
+            duration,
+            interest_rate,
+            referrer,
+            currency,
+            expires_at,
+            allowed_lenders,
+            min_lender_reputation,
+            protection,
+        } => {
+            let payer = ctx.payer()?;
+            //the nft's own token program, and the proof and hook accounts its extensions call for
+            let (token_program, mint_data) = ctx
+                .rpc
+                .get_account(&mint)?
+                .ok_or_else(|| anyhow!("nft mint {mint} not found"))?;
+            let keys = LoanKeys {
+                platform: ctx.platform,
+                nft_mint: mint,
+                loan_id: ctx.platform()?.loan_count,
+                borrower: payer.pubkey(),
+                token_program,
+            };
+            let terms = LoanTerms {
+                amount,
+                duration,
+                interest_rate,
+            };
+            let constraints = AcceptanceConstraints {
+                expires_at,
+                allowed_lenders,
+                min_lender_reputation,
+            };
+            let options = RequestOptions {
+                currency_mint: currency,
+                constraints: (constraints != AcceptanceConstraints::default()).then_some(constraints),
+                protection,
+            };
+            let ix = instructions::request_loan_with(
+                &keys,
+                collection,
+                collection_proof(&mint, &mint_data),
+                terms,
+                referrer,
+                &options,
+            );
+            let ix = match transfer_hook_program(&mint_data) {
+                Some(hook_program) => with_request_transfer_hook(ix, &keys, &hook_program, |address| {
+                    ctx.rpc.get_account_data(address).ok().flatten()
+                })
+                .map_err(|e| anyhow!("could not resolve the transfer hook accounts: {e}"))?,
+                None => ix,
+            };
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(keys.loan().to_string()) }, json)
+        }
+        Command::Fund {
+            loan: address,
+            referrer,
+            insured,
+        } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            if loan.status != LoanStatus::Requested {
+                bail!("loan {address} is {}", views::status_name(&loan.status));
+            }
+            let keys = ctx.loan_keys(&loan);
+            //funds the terms just read, the program rejects them if the borrower updates first
+            let terms = LoanTerms::from_loan(&loan);
+            let ix = match ctx.currency(&loan) {
+                Some(_) if insured => bail!("insurance only covers lamport loans"),
+                Some(currency) => instructions::fund_borrower_in_currency(
+                    &keys,
+                    payer.pubkey(),
+                    referrer,
+                    terms,
+                    &currency,
+                ),
+                None if insured => {
+                    instructions::fund_borrower_insured(&keys, payer.pubkey(), referrer, terms)
+                }
+                None => instructions::fund_borrower(&keys, payer.pubkey(), referrer, terms),
+            };
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::Update {
+            loan: address,
+            amount,
+            duration,
+            interest_rate,
+        } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            if loan.borrower != payer.pubkey() {
+                bail!("only the borrower {} can update loan {address}", loan.borrower);
+            }
+            if loan.status != LoanStatus::Requested {
+                bail!("loan {address} is {}", views::status_name(&loan.status));
+            }
+            let terms = LoanTerms {
+                amount: amount.unwrap_or(loan.loan_amount),
+                duration: duration.unwrap_or(loan.duration),
+                interest_rate: interest_rate.unwrap_or(loan.interest_rate),
+            };
+            let ix = instructions::update_loan_request(&ctx.loan_keys(&loan), terms);
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::Offer {
+            loan: address,
+            amount,
+            duration,
+            interest_rate,
+            referrer,
+        } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            if loan.status != LoanStatus::Requested {
+                bail!("loan {address} is {}", views::status_name(&loan.status));
+            }
+            let terms = LoanTerms {
+                amount,
+                duration,
+                interest_rate,
+            };
+            let ix = instructions::make_counter_offer(&ctx.loan_keys(&loan), payer.pubkey(), referrer, terms);
+            let signature = ctx.send(&payer, ix)?;
+            let offer = pda::counter_offer(&address, &payer.pubkey()).0;
+            output::print(&TxView { signature, account: Some(offer.to_string()) }, json)
+        }
+        Command::Offers { loan: address } => {
+            let mut offers = ctx.rpc.fetch_counter_offers(&address)?;
+            offers.sort_by_key(|(_, offer)| offer.created_at);
+            let views: Vec<_> = offers
+                .iter()
+                .map(|(offer_address, offer)| CounterOfferView::new(offer_address, offer))
+                .collect();
+            output::print(&views, json)
+        }
+        Command::AcceptOffer { loan: address, lender } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            if loan.borrower != payer.pubkey() {
+                bail!("only the borrower {} can accept offers on loan {address}", loan.borrower);
+            }
+            let offer = ctx
+                .rpc
+                .fetch_counter_offer(&pda::counter_offer(&address, &lender).0)?
+                .ok_or_else(|| anyhow!("{lender} has no offer on loan {address}"))?;
+            let terms = LoanTerms::from_offer(&offer);
+            let ix = instructions::accept_counter_offer(&ctx.loan_keys(&loan), lender, terms);
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::RejectOffer { loan: address, lender } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            if loan.borrower != payer.pubkey() {
+                bail!("only the borrower {} can reject offers on loan {address}", loan.borrower);
+            }
+            let ix = instructions::reject_counter_offer(&ctx.loan_keys(&loan), lender);
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::WithdrawOffer { loan: address } => {
+            let payer = ctx.payer()?;
+            let ix = instructions::withdraw_counter_offer(&address, payer.pubkey());
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::Repay { loan: address } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            if loan.borrower != payer.pubkey() {
+                bail!("only the borrower {} can repay loan {address}", loan.borrower);
+            }
+            let keys = ctx.loan_keys(&loan);
+            let ix = match ctx.currency(&loan) {
+                Some(currency) => instructions::resolve_loan_in_currency(&keys, &loan, &currency),
+                None => instructions::resolve_loan(&keys, &loan),
+            };
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::Cancel { loan: address } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            if loan.borrower != payer.pubkey() {
+                bail!("only the borrower {} can cancel loan {address}", loan.borrower);
+            }
+            let ix = instructions::cancel_loan(&ctx.loan_keys(&loan));
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::Default { loan: address } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            let ix = instructions::default_loan(&ctx.loan_keys(&loan), payer.pubkey());
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::MarkDefaulted { loan: address } => {
+            let payer = ctx.payer()?;
+            let loan = ctx.loan(&address)?;
+            let lender = loan
+                .lender
+                .ok_or_else(|| anyhow!("loan {address} has not been funded"))?;
+            let ix = instructions::mark_defaulted(&ctx.loan_keys(&loan), payer.pubkey(), lender);
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::ConfigureInsurance {
+            insurance_share_bps,
+            premium_bps,
+            coverage_bps,
+            max_claim_bps,
+            oracle,
+        } => {
+            let payer = ctx.payer()?;
+            let config = InsuranceConfig {
+                insurance_share_bps,
+                premium_bps,
+                coverage_bps,
+                max_claim_bps,
+                oracle,
+            };
+            let ix = instructions::configure_insurance(payer.pubkey(), ctx.platform, config);
+            let signature = ctx.send(&payer, ix)?;
+            let fund = pda::insurance_fund(&ctx.platform).0;
+            output::print(&TxView { signature, account: Some(fund.to_string()) }, json)
+        }
+        Command::Insurance => {
+            let address = pda::insurance_fund(&ctx.platform).0;
+            let fund = ctx
+                .rpc
+                .fetch_insurance_fund(&address)?
+                .ok_or_else(|| anyhow!("platform {} has no insurance fund", ctx.platform))?;
+            output::print(&InsuranceFundView::new(&address, &fund), json)
+        }
+        Command::SetValue { mint, value } => {
+            let payer = ctx.payer()?;
+            let ix = instructions::set_collateral_value(payer.pubkey(), ctx.platform, mint, value);
+            let signature = ctx.send(&payer, ix)?;
+            let valuation = pda::collateral_valuation(&ctx.platform, &mint).0;
+            output::print(&TxView { signature, account: Some(valuation.to_string()) }, json)
+        }
+        Command::ClaimInsurance { loan: address } => {
+            let payer = ctx.payer()?;
+            //the loan account may be gone by now, the policy keeps what the claim needs
+            let policy = ctx
+                .rpc
+                .fetch_insurance_policy(&pda::insurance_policy(&address).0)?
+                .ok_or_else(|| anyhow!("loan {address} has no open insurance policy"))?;
+            let keys = LoanKeys {
+                platform: ctx.platform,
+                nft_mint: policy.nft_mint,
+                loan_id: policy.loan_id,
+                borrower: Pubkey::default(),
+                token_program: ctx.token_program,
+            };
+            let ix = instructions::claim_insurance(&keys, payer.pubkey());
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: Some(address.to_string()) }, json)
+        }
+        Command::AllowGovernance {
+            program,
+            instructions: prefixes,
+            spl_governance,
+        } => {
+            let payer = ctx.payer()?;
+            let mut allowed: Vec<_> = prefixes.into_iter().map(|p| p.0).collect();
+            if spl_governance {
+                allowed.push(vec![instructions::SPL_GOVERNANCE_CAST_VOTE]);
+                allowed.push(vec![instructions::SPL_GOVERNANCE_RELINQUISH_VOTE]);
+            }
+            let ix = instructions::set_governance_program(payer.pubkey(), ctx.platform, program, allowed);
+            let signature = ctx.send(&payer, ix)?;
+            let account = pda::governance_program(&ctx.platform, &program).0;
+            output::print(&TxView { signature, account: Some(account.to_string()) }, json)
+        }
+        Command::RemoveGovernance { program } => {
+            let payer = ctx.payer()?;
+            let ix = instructions::remove_governance_program(payer.pubkey(), ctx.platform, program);
+            let signature = ctx.send(&payer, ix)?;
+            output::print(&TxView { signature, account: None }, json)
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Prints command results either as JSON or as aligned `key: value` text.
+
+use serde::Serialize;
+use serde_json::Value;
+
+pub fn print<T: Serialize>(value: &T, json: bool) -> anyhow::Result<()> {
+    let value = serde_json::to_value(value)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else {
+        print!("{}", to_text(&value));
+    }
+    Ok(())
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Objects become `key: value` lines, arrays of objects are separated by blank lines.
+pub fn to_text(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let width = map.keys().map(String::len).max().unwrap_or(0);
+            map.iter()
+                .map(|(k, v)| format!("{k:<width$}  {}\n", scalar(v)))
+                .collect()
+        }
+        Value::Array(items) if items.is_empty() => "no results\n".to_string(),
+        Value::Array(items) => items
+            .iter()
+            .map(to_text)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => format!("{}\n", scalar(other)),
+    }
+}
@@ -0,0 +1,207 @@
+//! Serializable views of program accounts for JSON and text output.
+
+use anchor_lang::prelude::Pubkey;
+use credentia_client::{
+    quote::{repayment_deadline, RepaymentQuote},
+    CounterOffer, InsuranceFund, Loan, LoanStatus, Platform,
+};
+use serde::Serialize;
+
+fn key(pubkey: &Pubkey) -> String {
+    pubkey.to_string()
+}
+
+fn opt_key(pubkey: &Option<Pubkey>) -> Option<String> {
+    pubkey.as_ref().map(key)
+}
+
+pub fn status_name(status: &LoanStatus) -> &'static str {
+    match status {
+        LoanStatus::Requested => "requested",
+        LoanStatus::Funded => "funded",
+        LoanStatus::Repaid => "repaid",
+        LoanStatus::Defaulted => "defaulted",
+    }
+}
+
+#[derive(Serialize)]
+pub struct LoanView {
+    pub address: String,
+    pub loan_id: u64,
+    pub status: &'static str,
+    pub borrower: String,
+    pub lender: Option<String>,
+    pub nft_mint: String,
+    pub loan_amount: u64,
+    pub duration: u32,
+    pub interest_rate: u16,
+    pub start_time: Option<i64>,
+    pub deadline: Option<i64>,
+    pub pool: Option<String>,
+    pub borrower_referrer: Option<String>,
+    pub lender_referrer: Option<String>,
+    pub version: u8,
+    pub currency: Option<String>,
+    pub expires_at: Option<i64>,
+    pub allowed_lenders: Vec<String>,
+    pub min_lender_reputation: u64,
+    pub insured: bool,
+    pub protection_extension: Option<u32>,
+    pub protection_premium: Option<u64>,
+}
+
+impl LoanView {
+    pub fn new(address: &Pubkey, loan: &Loan) -> Self {
+        Self {
+            address: key(address),
+            loan_id: loan.loan_id,
+            status: status_name(&loan.status),
+            borrower: key(&loan.borrower),
+            lender: opt_key(&loan.lender),
+            nft_mint: key(&loan.nft_mint),
+            loan_amount: loan.loan_amount,
+            duration: loan.duration,
+            interest_rate: loan.interest_rate,
+            start_time: loan.start_time,
+            deadline: repayment_deadline(loan),
+            pool: opt_key(&loan.pool),
+            borrower_referrer: opt_key(&loan.borrower_referrer),
+            lender_referrer: opt_key(&loan.lender_referrer),
+            version: loan.version,
+            currency: opt_key(&loan.currency),
+            expires_at: loan.expires_at,
+            allowed_lenders: loan.allowed_lenders.iter().map(key).collect(),
+            min_lender_reputation: loan.min_lender_reputation,
+            insured: loan.insured,
+            protection_extension: loan.protection.as_ref().map(|p| p.extension),
+            protection_premium: loan.protection.as_ref().map(|p| p.premium),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CounterOfferView {
+    pub address: String,
+    pub loan: String,
+    pub lender: String,
+    pub amount: u64,
+    pub duration: u32,
+    pub interest_rate: u16,
+    pub lender_referrer: Option<String>,
+    pub created_at: i64,
+}
+
+impl CounterOfferView {
+    pub fn new(address: &Pubkey, offer: &CounterOffer) -> Self {
+        Self {
+            address: key(address),
+            loan: key(&offer.loan),
+            lender: key(&offer.lender),
+            amount: offer.amount,
+            duration: offer.duration,
+            interest_rate: offer.interest_rate,
+            lender_referrer: opt_key(&offer.lender_referrer),
+            created_at: offer.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PlatformView {
+    pub address: String,
+    pub platform_id: u64,
+    pub authority: String,
+    pub fee_bps: u16,
+    pub referral_share_bps: u16,
+    pub insurance_share_bps: u16,
+    pub loan_count: u64,
+    pub version: u8,
+}
+
+impl PlatformView {
+    pub fn new(address: &Pubkey, platform: &Platform) -> Self {
+        Self {
+            address: key(address),
+            platform_id: platform.platform_id,
+            authority: key(&platform.authority),
+            fee_bps: platform.fee_bps,
+            referral_share_bps: platform.referral_share_bps,
+            insurance_share_bps: platform.insurance_share_bps,
+            loan_count: platform.loan_count,
+            version: platform.version,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct InsuranceFundView {
+    pub address: String,
+    pub oracle: String,
+    pub premium_bps: u16,
+    pub coverage_bps: u16,
+    pub max_claim_bps: u16,
+    pub fee_income: u64,
+    pub total_premiums: u64,
+    pub total_claimed: u64,
+    pub claims_paid: u64,
+    pub policies_written: u64,
+}
+
+impl InsuranceFundView {
+    pub fn new(address: &Pubkey, fund: &InsuranceFund) -> Self {
+        Self {
+            address: key(address),
+            oracle: key(&fund.oracle),
+            premium_bps: fund.premium_bps,
+            coverage_bps: fund.coverage_bps,
+            max_claim_bps: fund.max_claim_bps,
+            fee_income: fund.fee_income,
+            total_premiums: fund.total_premiums,
+            total_claimed: fund.total_claimed,
+            claims_paid: fund.claims_paid,
+            policies_written: fund.policies_written,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct QuoteView {
+    pub loan: String,
+    pub principal: u64,
+    pub interest: u64,
+    pub fee_for_platform: u64,
+    pub amount_to_pay_lender: u64,
+    pub total_repayment: u64,
+    pub transfer_fee: u64,
+    pub treasury_amount: u64,
+    pub borrower_referrer_amount: u64,
+    pub lender_referrer_amount: u64,
+    pub insurance_amount: u64,
+    pub deadline: Option<i64>,
+}
+
+impl QuoteView {
+    pub fn new(address: &Pubkey, loan: &Loan, quote: &RepaymentQuote) -> Self {
+        Self {
+            loan: key(address),
+            principal: quote.principal,
+            interest: quote.interest,
+            fee_for_platform: quote.fee_for_platform,
+            amount_to_pay_lender: quote.amount_to_pay_lender,
+            total_repayment: quote.total_repayment,
+            transfer_fee: quote.transfer_fee,
+            treasury_amount: quote.treasury_amount,
+            borrower_referrer_amount: quote.borrower_referrer_amount,
+            lender_referrer_amount: quote.lender_referrer_amount,
+            insurance_amount: quote.insurance_amount,
+            deadline: repayment_deadline(loan),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TxView {
+    pub signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    process::Command,
+    str::FromStr,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use anchor_lang::{solana_program::hash::Hash, AccountSerialize};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        group_member_pointer::GroupMemberPointer, transfer_hook::TransferHook,
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    },
+    state::Mint,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use credentia_client::{
+    instructions::{self, CollectionProof, LoanKeys, LoanTerms, RequestOptions},
+    pda, Platform, PROGRAM_ID,
+};
+use serde_json::{json, Value};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{write_keypair_file, Keypair},
+    signer::Signer,
+    transaction::Transaction,
+};
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+fn cli() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_credentia-cli"))
+}
+
+#[test]
+fn help_lists_loan_commands() {
+    let out = cli().arg("--help").output().unwrap();
+    assert!(out.status.success());
+    let help = String::from_utf8(out.stdout).unwrap();
+    for command in ["init-platform", "loans", "request", "fund", "repay", "cancel", "default", "quote"] {
+        assert!(help.contains(command), "missing {command}");
+    }
+}
+
+#[test]
+fn rejects_unknown_status_filter() {
+    let out = cli().args(["loans", "--status", "lost"]).output().unwrap();
+    assert!(!out.status.success());
+}
+
+#[test]
+fn unreachable_rpc_is_an_error() {
+    let out = cli()
+        .args(["--url", "http://127.0.0.1:1", "platform", "--json"])
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("rpc transport error"));
+}
+
+fn read_call(stream: &TcpStream) -> Value {
+    let mut reader = BufReader::new(stream);
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Node answering the calls `request` makes out of `accounts` (owner and data), every
+/// transaction sent to it comes out of the receiver.
+fn serve(accounts: HashMap<Pubkey, (Pubkey, Vec<u8>)>) -> (String, Receiver<Transaction>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sent, received) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let call = read_call(&stream);
+            let result = match call["method"].as_str().unwrap() {
+                "getAccountInfo" => {
+                    let address = Pubkey::from_str(call["params"][0].as_str().unwrap()).unwrap();
+                    let value = accounts.get(&address).map(|(owner, data)| {
+                        json!({
+                            "owner": owner.to_string(),
+                            "data": [STANDARD.encode(data), "base64"],
+                            "lamports": 1_000_000,
+                            "executable": false,
+                            "rentEpoch": 0,
+                        })
+                    });
+                    json!({ "context": { "slot": 1 }, "value": value })
+                }
+                "getLatestBlockhash" => json!({
+                    "context": { "slot": 1 },
+                    "value": { "blockhash": Hash::default().to_string(), "lastValidBlockHeight": 100 },
+                }),
+                "sendTransaction" => {
+                    let wire = STANDARD.decode(call["params"][0].as_str().unwrap()).unwrap();
+                    let transaction: Transaction = bincode::deserialize(&wire).unwrap();
+                    let signature = transaction.signatures[0].to_string();
+                    sent.send(transaction).unwrap();
+                    json!(signature)
+                }
+                "getSignatureStatuses" => json!({
+                    "context": { "slot": 1 },
+                    "value": [{ "err": null, "confirmationStatus": "confirmed" }],
+                }),
+                method => panic!("unexpected rpc call {method}"),
+            };
+            let body = json!({ "jsonrpc": "2.0", "id": call["id"], "result": result }).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        }
+    });
+    (url, received)
+}
+
+/// Token-2022 nft that is its own group member and has a transfer hook.
+fn hooked_group_member(mint: &Pubkey, hook_program: &Pubkey) -> Vec<u8> {
+    let extensions = [ExtensionType::GroupMemberPointer, ExtensionType::TransferHook];
+    let len = ExtensionType::try_calculate_account_len::<Mint>(&extensions).unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    state.init_extension::<GroupMemberPointer>(true).unwrap().member_address = Some(*mint).try_into().unwrap();
+    state.init_extension::<TransferHook>(true).unwrap().program_id = Some(*hook_program).try_into().unwrap();
+    state.base = Mint { decimals: 0, supply: 1, is_initialized: true, ..Default::default() };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+#[test]
+fn request_proves_token_group_membership_and_forwards_hook_accounts() {
+    let platform = pda::platform(1).0;
+    let mint = Pubkey::new_unique();
+    let group = Pubkey::new_unique();
+    let hook_program = Pubkey::new_unique();
+    let validation = pda::extra_account_metas(&mint, &hook_program);
+
+    let mut platform_data = Vec::new();
+    Platform {
+        authority: Pubkey::new_unique(),
+        fee_bps: 500,
+        reward_bump: 0,
+        treasury_bump: 0,
+        bump: 0,
+        loan_count: 4,
+        platform_id: 1,
+        referral_share_bps: 0,
+        version: Platform::VERSION,
+        insurance_share_bps: 0,
+    }
+    .try_serialize(&mut platform_data)
+    .unwrap();
+    //one fixed account and one derived from the transfer's destination, the vault
+    let fixed = Pubkey::new_unique();
+    let metas = [
+        ExtraAccountMeta::new_with_pubkey(&fixed, false, false).unwrap(),
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"allow".to_vec() }, Seed::AccountKey { index: 2 }],
+            false,
+            true,
+        )
+        .unwrap(),
+    ];
+    let mut metas_data = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut metas_data, &metas).unwrap();
+    let accounts = HashMap::from([
+        (platform, (PROGRAM_ID, platform_data)),
+        (mint, (spl_token_2022::ID, hooked_group_member(&mint, &hook_program))),
+        (validation, (hook_program, metas_data)),
+    ]);
+    let (url, sent) = serve(accounts);
+
+    let payer = Keypair::new();
+    let keypair = std::env::temp_dir().join(format!("credentia-cli-{}.json", payer.pubkey()));
+    write_keypair_file(&payer, &keypair).unwrap();
+    //--token-program is left at spl-token, the mint's owner wins
+    let out = cli()
+        .args(["--url", &url, "--keypair", keypair.to_str().unwrap(), "request"])
+        .args(["--mint", &mint.to_string(), "--collection", &group.to_string()])
+        .args(["--amount", "1000000000", "--duration", "86400", "--interest-rate", "500", "--json"])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&keypair).unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let keys = LoanKeys {
+        platform,
+        nft_mint: mint,
+        loan_id: 4,
+        borrower: payer.pubkey(),
+        token_program: spl_token_2022::ID,
+    };
+    assert!(String::from_utf8_lossy(&out.stdout).contains(&keys.loan().to_string()));
+    let terms = LoanTerms {
+        amount: 1_000_000_000,
+        duration: 86_400,
+        interest_rate: 500,
+    };
+    let expected = instructions::request_loan_with(
+        &keys,
+        group,
+        CollectionProof::TokenGroup,
+        terms,
+        None,
+        &RequestOptions::default(),
+    );
+
+    let transaction = sent.recv().unwrap();
+    let message = &transaction.message;
+    let [ix] = &message.instructions[..] else {
+        panic!("expected one instruction");
+    };
+    let key = |index: &u8| message.account_keys[*index as usize];
+    assert_eq!(key(&ix.program_id_index), PROGRAM_ID);
+    assert_eq!(ix.data, expected.data);
+    let accounts: Vec<_> = ix.accounts.iter().map(key).collect();
+    //the request's own accounts, without metaplex metadata, then what the hook asks for
+    let derived = Pubkey::find_program_address(&[b"allow", keys.nft_vault().as_ref()], &hook_program).0;
+    let hook_accounts = [validation, fixed, derived, hook_program];
+    let (request_accounts, appended) = accounts.split_at(accounts.len() - hook_accounts.len());
+    assert_eq!(request_accounts, expected.accounts.iter().map(|meta| meta.pubkey).collect::<Vec<_>>());
+    assert_eq!(appended, hook_accounts);
+    assert!(!accounts.contains(&pda::metadata(&mint)));
+    //the derived account was listed writable
+    assert!(message.is_maybe_writable(ix.accounts[accounts.len() - 2] as usize, None));
+}
@@ -0,0 +1,20 @@
+//! Decoders for Credentia accounts fetched over RPC.
+
+use anchor_lang::{AccountDeserialize, Result};
+
+use crate::{LendingPool, Loan, Platform};
+
+/// Decodes raw account data (discriminator included) into a `Loan`.
+pub fn decode_loan(data: &[u8]) -> Result<Loan> {
+    Loan::try_deserialize(&mut &data[..])
+}
+
+/// Decodes raw account data (discriminator included) into a `Platform`.
+pub fn decode_platform(data: &[u8]) -> Result<Platform> {
+    Platform::try_deserialize(&mut &data[..])
+}
+
+/// Decodes raw account data (discriminator included) into a `LendingPool`.
+pub fn decode_lending_pool(data: &[u8]) -> Result<LendingPool> {
+    LendingPool::try_deserialize(&mut &data[..])
+}
@@ -0,0 +1,118 @@
+//! Resolves the accounts a Token-2022 transfer hook asks for when the program moves an nft,
+//! and how a mint proves its collection.
+
+use std::{
+    future::{ready, Future},
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::{instruction::Instruction, program_error::ProgramError},
+};
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        group_member_pointer::GroupMemberPointer, transfer_hook::TransferHook,
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
+};
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::{execute, ExecuteInstruction};
+
+use crate::{
+    instructions::{with_transfer_hook, CollectionProof, LoanKeys},
+    pda,
+};
+
+/// Hook program of a mint, `None` for spl-token mints and Token-2022 mints without a hook.
+pub fn transfer_hook_program(mint_data: &[u8]) -> Option<Pubkey> {
+    let state = StateWithExtensions::<Mint>::unpack(mint_data).ok()?;
+    let hook = state.get_extension::<TransferHook>().ok()?;
+    Option::<Pubkey>::from(hook.program_id)
+}
+
+/// How `mint` proves its collection: Token-2022 group membership when its member pointer points
+/// at the mint itself, as the program requires, Metaplex metadata otherwise.
+pub fn collection_proof(mint: &Pubkey, mint_data: &[u8]) -> CollectionProof {
+    let member_address = StateWithExtensions::<Mint>::unpack(mint_data)
+        .ok()
+        .and_then(|state| {
+            let pointer = state.get_extension::<GroupMemberPointer>().ok()?;
+            Option::<Pubkey>::from(pointer.member_address)
+        });
+    if member_address == Some(*mint) {
+        CollectionProof::TokenGroup
+    } else {
+        CollectionProof::Metaplex
+    }
+}
+
+/// Appends what `hook_program` needs to `ix`, which moves the loan's nft out of its vault to
+/// `recipient`'s associated token account. The extra accounts are resolved from the hook's
+/// validation account, `fetch` returns an account's data or `None` if it doesn't exist.
+pub fn with_vault_transfer_hook(
+    ix: Instruction,
+    keys: &LoanKeys,
+    recipient: &Pubkey,
+    hook_program: &Pubkey,
+    fetch: impl Fn(&Pubkey) -> Option<Vec<u8>>,
+) -> Result<Instruction, ProgramError> {
+    let transfer = (keys.nft_vault(), keys.ata(recipient), keys.loan());
+    with_hook_accounts(ix, keys, transfer, hook_program, fetch)
+}
+
+/// Like `with_vault_transfer_hook` for `request_loan`, which moves the nft from the borrower's
+/// associated token account into the vault.
+pub fn with_request_transfer_hook(
+    ix: Instruction,
+    keys: &LoanKeys,
+    hook_program: &Pubkey,
+    fetch: impl Fn(&Pubkey) -> Option<Vec<u8>>,
+) -> Result<Instruction, ProgramError> {
+    let transfer = (keys.ata(&keys.borrower), keys.nft_vault(), keys.borrower);
+    with_hook_accounts(ix, keys, transfer, hook_program, fetch)
+}
+
+//`transfer` is the source, destination and authority of the nft transfer `ix` makes
+fn with_hook_accounts(
+    ix: Instruction,
+    keys: &LoanKeys,
+    (source, destination, authority): (Pubkey, Pubkey, Pubkey),
+    hook_program: &Pubkey,
+    fetch: impl Fn(&Pubkey) -> Option<Vec<u8>>,
+) -> Result<Instruction, ProgramError> {
+    let validation = pda::extra_account_metas(&keys.nft_mint, hook_program);
+    let validation_data = fetch(&validation).ok_or(ProgramError::InvalidAccountData)?;
+
+    //the hook's execute instruction for the same transfer, extra accounts are resolved against it
+    let mut execute_ix = execute(
+        hook_program,
+        &source,
+        &keys.nft_mint,
+        &destination,
+        &authority,
+        1,
+    );
+    execute_ix
+        .accounts
+        .push(AccountMeta::new_readonly(validation, false));
+    let resolve = ExtraAccountMetaList::add_to_instruction::<ExecuteInstruction, _, _>(
+        &mut execute_ix,
+        |address| ready(Ok(fetch(&address))),
+        &validation_data,
+    );
+    resolve_now(resolve)?;
+
+    let extra_accounts = &execute_ix.accounts[5..];
+    Ok(with_transfer_hook(ix, &keys.nft_mint, hook_program, extra_accounts))
+}
+
+//every fetch is answered synchronously, so the future is ready on its first poll
+fn resolve_now<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("account fetches are never pending"),
+    }
+}
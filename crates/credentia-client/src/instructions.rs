@@ -0,0 +1,1171 @@
+//! Typed builders for every Credentia instruction. Each builder derives the
+//! PDAs and associated token accounts the program expects.
+
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::{instruction::Instruction, sysvar},
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{associated_token, associated_token::get_associated_token_address_with_program_id, metadata::mpl_token_metadata};
+use credentia::{accounts, instruction};
+
+use crate::{pda, AcceptanceConstraints, CounterOffer, Loan, RateCurve, PROGRAM_ID};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Keys that identify an existing loan.
+#[derive(Clone, Copy, Debug)]
+pub struct LoanKeys {
+    pub platform: Pubkey,
+    pub nft_mint: Pubkey,
+    pub loan_id: u64,
+    pub borrower: Pubkey,
+    /// token program owning the nft mint
+    pub token_program: Pubkey,
+}
+
+impl LoanKeys {
+    pub fn from_loan(platform: Pubkey, loan: &Loan, token_program: Pubkey) -> Self {
+        Self {
+            platform,
+            nft_mint: loan.nft_mint,
+            loan_id: loan.loan_id,
+            borrower: loan.borrower,
+            token_program,
+        }
+    }
+
+    pub fn loan(&self) -> Pubkey {
+        pda::loan(&self.nft_mint, &self.platform, self.loan_id).0
+    }
+
+    pub fn nft_vault(&self) -> Pubkey {
+        pda::nft_vault(&self.loan(), &self.nft_mint, &self.token_program)
+    }
+
+    pub(crate) fn ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.nft_mint, &self.token_program)
+    }
+}
+
+pub fn initialize_platform(
+    admin: Pubkey,
+    platform_id: u64,
+    fee_bps: u16,
+    token_program: Pubkey,
+) -> Instruction {
+    let platform = pda::platform(platform_id).0;
+    build(
+        accounts::Initialize {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            admin,
+            platform,
+            treasury_vault: pda::treasury_vault(&platform).0,
+            reward_mint: pda::reward_mint(&platform).0,
+            token_program,
+            system_program: system_program::ID,
+        },
+        instruction::InitializePlatform {
+            platform_id,
+            fee_bps,
+        },
+    )
+}
+
+pub fn update_platform(
+    authority: Pubkey,
+    platform: Pubkey,
+    fee_bps: u16,
+    referral_share_bps: u16,
+) -> Instruction {
+    build(
+        accounts::UpdatePlatform {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            platform,
+        },
+        instruction::UpdatePlatform {
+            fee_bps,
+            referral_share_bps,
+        },
+    )
+}
+
+pub fn register_referrer(referrer: Pubkey, platform: Pubkey) -> Instruction {
+    build(
+        accounts::RegisterReferrer {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            referrer,
+            platform,
+            referrer_account: pda::referrer(&platform, &referrer).0,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterReferrer {},
+    )
+}
+
+pub fn claim_referral_fees(referrer: Pubkey, platform: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimReferralFees {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            referrer,
+            referrer_account: pda::referrer(&platform, &referrer).0,
+        },
+        instruction::ClaimReferralFees {},
+    )
+}
+
+/// How collateral shows it belongs to a collection when a loan is opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionProof {
+    /// Metaplex metadata with a verified collection, on either token program
+    Metaplex,
+    /// Token-2022 group membership stored on the mint itself
+    TokenGroup,
+}
+
+impl CollectionProof {
+    /// Metadata and master edition accounts the proof passes.
+    fn accounts(self, nft_mint: &Pubkey) -> (Option<Pubkey>, Option<Pubkey>) {
+        match self {
+            CollectionProof::Metaplex => (
+                Some(pda::metadata(nft_mint)),
+                Some(pda::master_edition(nft_mint)),
+            ),
+            CollectionProof::TokenGroup => (None, None),
+        }
+    }
+}
+
+/// Appends what a Token-2022 transfer hook needs to an instruction that moves `nft_mint`:
+/// the hook program, its extra account metas account and the accounts that one lists.
+/// Every instruction that moves collateral forwards its remaining accounts to the hook.
+pub fn with_transfer_hook(
+    mut ix: Instruction,
+    nft_mint: &Pubkey,
+    hook_program: &Pubkey,
+    extra_accounts: &[AccountMeta],
+) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(
+        pda::extra_account_metas(nft_mint, hook_program),
+        false,
+    ));
+    ix.accounts.extend(extra_accounts.iter().cloned());
+    ix.accounts.push(AccountMeta::new_readonly(*hook_program, false));
+    ix
+}
+
+/// Spl token a loan is paid in instead of lamports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Currency {
+    pub mint: Pubkey,
+    /// token program owning the mint
+    pub token_program: Pubkey,
+}
+
+impl Currency {
+    /// Associated token account of `owner` for the currency, which is where builders move it.
+    pub fn account(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &self.token_program)
+    }
+}
+
+/// Terms a borrower asks for in `request_loan`.
+#[derive(Clone, Copy, Debug)]
+pub struct LoanTerms {
+    pub amount: u64,
+    /// seconds
+    pub duration: u32,
+    /// bps of principal charged over the loan
+    pub interest_rate: u16,
+}
+
+impl LoanTerms {
+    /// Current terms of a request, what a lender funding it agrees to.
+    pub fn from_loan(loan: &Loan) -> Self {
+        Self {
+            amount: loan.loan_amount,
+            duration: loan.duration,
+            interest_rate: loan.interest_rate,
+        }
+    }
+
+    /// Terms of a counter offer, what the borrower accepting it agrees to.
+    pub fn from_offer(offer: &CounterOffer) -> Self {
+        Self {
+            amount: offer.amount,
+            duration: offer.duration,
+            interest_rate: offer.interest_rate,
+        }
+    }
+}
+
+/// Optional parts of a loan request.
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+    /// spl token the loan is paid in, lamports when `None`
+    pub currency_mint: Option<Pubkey>,
+    /// who may fund the request and until when
+    pub constraints: Option<AcceptanceConstraints>,
+    /// seconds of default protection to buy, the borrower escrows the premium with the request
+    pub protection: Option<u32>,
+}
+
+/// `loan_id` must be the platform's current `loan_count`. The nft proves its collection
+/// through Metaplex metadata.
+pub fn request_loan(
+    keys: &LoanKeys,
+    collection: Pubkey,
+    terms: LoanTerms,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    let options = RequestOptions::default();
+    request_loan_with(keys, collection, CollectionProof::Metaplex, terms, referrer, &options)
+}
+
+/// Like `request_loan` for a Token-2022 nft that is a member of the `collection` token group.
+pub fn request_loan_as_group_member(
+    keys: &LoanKeys,
+    collection: Pubkey,
+    terms: LoanTerms,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    let options = RequestOptions::default();
+    request_loan_with(keys, collection, CollectionProof::TokenGroup, terms, referrer, &options)
+}
+
+/// Like `request_loan` for a loan paid in `currency_mint` rather than lamports.
+pub fn request_loan_in_currency(
+    keys: &LoanKeys,
+    collection: Pubkey,
+    proof: CollectionProof,
+    terms: LoanTerms,
+    referrer: Option<Pubkey>,
+    currency_mint: Pubkey,
+) -> Instruction {
+    let options = RequestOptions {
+        currency_mint: Some(currency_mint),
+        ..Default::default()
+    };
+    request_loan_with(keys, collection, proof, terms, referrer, &options)
+}
+
+/// `request_loan` with every option spelled out.
+pub fn request_loan_with(
+    keys: &LoanKeys,
+    collection: Pubkey,
+    proof: CollectionProof,
+    terms: LoanTerms,
+    referrer: Option<Pubkey>,
+    options: &RequestOptions,
+) -> Instruction {
+    let (metadata, master_edition) = proof.accounts(&keys.nft_mint);
+    build(
+        accounts::CreateLoan {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_collection: collection,
+            borrower_nft_ata: keys.ata(&keys.borrower),
+            metadata,
+            master_edition,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            platform: keys.platform,
+            referrer_account: referrer.map(|r| pda::referrer(&keys.platform, &r).0),
+            currency_mint: options.currency_mint,
+            metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::RequestLoan {
+            amount: terms.amount,
+            duration: terms.duration,
+            interest_rate: terms.interest_rate,
+            constraints: options.constraints.clone(),
+            protection: options.protection,
+        },
+    )
+}
+
+/// Funds a request at `terms`, the program rejects it if the borrower updated them since.
+pub fn fund_borrower(
+    keys: &LoanKeys,
+    lender: Pubkey,
+    referrer: Option<Pubkey>,
+    terms: LoanTerms,
+) -> Instruction {
+    lend(keys, lender, referrer, terms, None, false)
+}
+
+/// Funds a lamport loan and buys the platform insurance fund's cover on it, the lender pays the
+/// fund's premium on top of the loan amount.
+pub fn fund_borrower_insured(
+    keys: &LoanKeys,
+    lender: Pubkey,
+    referrer: Option<Pubkey>,
+    terms: LoanTerms,
+) -> Instruction {
+    lend(keys, lender, referrer, terms, None, true)
+}
+
+/// Funds a loan paid in `currency` from the lender's to the borrower's associated token
+/// account, the borrower's has to exist.
+pub fn fund_borrower_in_currency(
+    keys: &LoanKeys,
+    lender: Pubkey,
+    referrer: Option<Pubkey>,
+    terms: LoanTerms,
+    currency: &Currency,
+) -> Instruction {
+    lend(keys, lender, referrer, terms, Some(currency), false)
+}
+
+fn lend(
+    keys: &LoanKeys,
+    lender: Pubkey,
+    referrer: Option<Pubkey>,
+    terms: LoanTerms,
+    currency: Option<&Currency>,
+    insured: bool,
+) -> Instruction {
+    build(
+        accounts::FundBorrower {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            lender,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            loan_account: keys.loan(),
+            platform: keys.platform,
+            referrer_account: referrer.map(|r| pda::referrer(&keys.platform, &r).0),
+            lender_reputation: pda::lender_reputation(&keys.platform, &lender).0,
+            insurance_fund: insured.then(|| pda::insurance_fund(&keys.platform).0),
+            insurance_policy: insured.then(|| pda::insurance_policy(&keys.loan()).0),
+            currency_mint: currency.map(|c| c.mint),
+            lender_currency_account: currency.map(|c| c.account(&lender)),
+            borrower_currency_account: currency.map(|c| c.account(&keys.borrower)),
+            currency_token_program: currency.map(|c| c.token_program),
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::FundBorrower {
+            expected_amount: terms.amount,
+            expected_duration: terms.duration,
+            expected_interest_rate: terms.interest_rate,
+        },
+    )
+}
+
+/// Repays `loan` and returns the nft, passing the pool, referrer and insurance accounts the loan
+/// needs.
+pub fn resolve_loan(keys: &LoanKeys, loan: &Loan) -> Instruction {
+    repay(keys, loan, None)
+}
+
+/// Like `resolve_loan` for a loan paid in `currency`. The lender's, the treasury vault's and
+/// any referrer's associated token accounts have to exist.
+pub fn resolve_loan_in_currency(keys: &LoanKeys, loan: &Loan, currency: &Currency) -> Instruction {
+    repay(keys, loan, Some(currency))
+}
+
+fn repay(keys: &LoanKeys, loan: &Loan, currency: Option<&Currency>) -> Instruction {
+    let lender = loan.lender.unwrap_or_default();
+    let treasury_vault = pda::treasury_vault(&keys.platform).0;
+    let referrer_account = |referrer: Option<Pubkey>| {
+        referrer.and_then(|r| currency.map(|c| c.account(&r)))
+    };
+    build(
+        accounts::ResolveLoan {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            lender,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_ata: keys.ata(&keys.borrower),
+            platform: keys.platform,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            treasury_vault,
+            lending_pool: loan.pool,
+            borrower_referrer_account: loan
+                .borrower_referrer
+                .map(|r| pda::referrer(&keys.platform, &r).0),
+            lender_referrer_account: loan
+                .lender_referrer
+                .map(|r| pda::referrer(&keys.platform, &r).0),
+            insurance_fund: pda::insurance_fund(&keys.platform).0,
+            lender_reputation: pda::lender_reputation(&keys.platform, &lender).0,
+            insurance_policy: loan.insured.then(|| pda::insurance_policy(&keys.loan()).0),
+            currency_mint: currency.map(|c| c.mint),
+            borrower_currency_account: currency.map(|c| c.account(&keys.borrower)),
+            lender_currency_account: currency.map(|c| c.account(&lender)),
+            treasury_currency_account: currency.map(|c| c.account(&treasury_vault)),
+            borrower_referrer_currency_account: referrer_account(loan.borrower_referrer),
+            lender_referrer_currency_account: referrer_account(loan.lender_referrer),
+            currency_token_program: currency.map(|c| c.token_program),
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::ResolveLoan {},
+    )
+}
+
+/// Replaces the terms of an unfunded request, a protected request's premium is repriced and the
+/// difference paid or refunded.
+pub fn update_loan_request(keys: &LoanKeys, terms: LoanTerms) -> Instruction {
+    build(
+        accounts::UpdateLoanRequest {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            loan_account: keys.loan(),
+            platform: keys.platform,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateLoanRequest {
+            amount: terms.amount,
+            duration: terms.duration,
+            interest_rate: terms.interest_rate,
+        },
+    )
+}
+
+pub fn cancel_loan(keys: &LoanKeys) -> Instruction {
+    build(
+        accounts::BorrowerCancelLoan {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_ata: keys.ata(&keys.borrower),
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            platform: keys.platform,
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::CancelLoan {},
+    )
+}
+
+/// Moves the escrowed nft to the borrower for the rest of the transaction. The program rejects it
+/// unless a `return_collateral` for the same loan follows, see `with_flash_collateral`.
+pub fn flash_use_collateral(keys: &LoanKeys) -> Instruction {
+    build(
+        accounts::FlashUseCollateral {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_ata: keys.ata(&keys.borrower),
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            platform: keys.platform,
+            instructions: sysvar::instructions::ID,
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::FlashUseCollateral {},
+    )
+}
+
+/// Puts a flash used nft back into the loan's vault.
+pub fn return_collateral(keys: &LoanKeys) -> Instruction {
+    build(
+        accounts::ReturnCollateral {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_ata: keys.ata(&keys.borrower),
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            platform: keys.platform,
+            token_program: keys.token_program,
+        },
+        instruction::ReturnCollateral {},
+    )
+}
+
+/// `uses`, run while the borrower holds the loan's nft, between a flash use and its return.
+pub fn with_flash_collateral(keys: &LoanKeys, uses: Vec<Instruction>) -> Vec<Instruction> {
+    let mut ixs = vec![flash_use_collateral(keys)];
+    ixs.extend(uses);
+    ixs.push(return_collateral(keys));
+    ixs
+}
+
+pub fn default_loan(keys: &LoanKeys, lender: Pubkey) -> Instruction {
+    build(
+        accounts::DefaultLoan {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            lender,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            lender_nft_ata: keys.ata(&lender),
+            platform: keys.platform,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            treasury_vault: pda::treasury_vault(&keys.platform).0,
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::DefaultLoan {},
+    )
+}
+
+/// Escrows `terms.amount` from `lender` as an offer of `terms` on an open request.
+pub fn make_counter_offer(
+    keys: &LoanKeys,
+    lender: Pubkey,
+    referrer: Option<Pubkey>,
+    terms: LoanTerms,
+) -> Instruction {
+    let loan_account = keys.loan();
+    build(
+        accounts::MakeCounterOffer {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            lender,
+            loan_account,
+            platform: keys.platform,
+            counter_offer: pda::counter_offer(&loan_account, &lender).0,
+            referrer_account: referrer.map(|r| pda::referrer(&keys.platform, &r).0),
+            lender_reputation: pda::lender_reputation(&keys.platform, &lender).0,
+            system_program: system_program::ID,
+        },
+        instruction::MakeCounterOffer {
+            amount: terms.amount,
+            duration: terms.duration,
+            interest_rate: terms.interest_rate,
+        },
+    )
+}
+
+/// Borrower takes `lender`'s offer, the loan is funded on the offered terms, which have to still be
+/// `terms`.
+pub fn accept_counter_offer(keys: &LoanKeys, lender: Pubkey, terms: LoanTerms) -> Instruction {
+    let loan_account = keys.loan();
+    build(
+        accounts::AcceptCounterOffer {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            lender,
+            loan_account,
+            platform: keys.platform,
+            counter_offer: pda::counter_offer(&loan_account, &lender).0,
+            lender_reputation: pda::lender_reputation(&keys.platform, &lender).0,
+        },
+        instruction::AcceptCounterOffer {
+            expected_amount: terms.amount,
+            expected_duration: terms.duration,
+            expected_interest_rate: terms.interest_rate,
+        },
+    )
+}
+
+/// Borrower turns `lender`'s offer down, refunding the lender.
+pub fn reject_counter_offer(keys: &LoanKeys, lender: Pubkey) -> Instruction {
+    let loan_account = keys.loan();
+    build(
+        accounts::RejectCounterOffer {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            lender,
+            loan_account,
+            platform: keys.platform,
+            counter_offer: pda::counter_offer(&loan_account, &lender).0,
+        },
+        instruction::RejectCounterOffer {},
+    )
+}
+
+/// Lender takes their offer on `loan` back, also after the loan account is closed.
+pub fn withdraw_counter_offer(loan: &Pubkey, lender: Pubkey) -> Instruction {
+    build(
+        accounts::WithdrawCounterOffer {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            lender,
+            counter_offer: pda::counter_offer(loan, &lender).0,
+        },
+        instruction::WithdrawCounterOffer {},
+    )
+}
+
+/// Permissionless default of an expired direct loan; `caller` receives the rent refunds.
+pub fn mark_defaulted(keys: &LoanKeys, caller: Pubkey, lender: Pubkey) -> Instruction {
+    build(
+        accounts::MarkDefaulted {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            caller,
+            lender,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            lender_nft_ata: keys.ata(&lender),
+            platform: keys.platform,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::MarkDefaulted {},
+    )
+}
+
+/// Closes a request past its expiry, the nft goes back to the borrower and `caller` keeps the
+/// rent refunds.
+pub fn close_expired_request(keys: &LoanKeys, caller: Pubkey) -> Instruction {
+    build(
+        accounts::CloseExpiredRequest {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            caller,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_ata: keys.ata(&keys.borrower),
+            platform: keys.platform,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::CloseExpiredRequest {},
+    )
+}
+
+/// `keys.loan_id` must be the platform's current `loan_count`, the id the migrated loan gets. A
+/// platform still in its layout from before loan ids has none yet, its first loan gets id 0.
+pub fn migrate_legacy_loan(keys: &LoanKeys, payer: Pubkey) -> Instruction {
+    let legacy_loan = pda::legacy_loan(&keys.nft_mint, &keys.platform).0;
+    build(
+        accounts::MigrateLegacyLoan {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            payer,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            platform: keys.platform,
+            legacy_loan,
+            legacy_nft_vault: pda::nft_vault(&legacy_loan, &keys.nft_mint, &keys.token_program),
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::MigrateLegacyLoan {},
+    )
+}
+
+/// Upgrades a loan written under an older account layout, `payer` covers the extra rent.
+pub fn migrate_loan(payer: Pubkey, platform: Pubkey, loan_account: Pubkey) -> Instruction {
+    build(
+        accounts::MigrateLoan {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            payer,
+            platform,
+            loan_account,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateLoan {},
+    )
+}
+
+/// Upgrades a platform written under an older account layout, `payer` covers the extra rent.
+pub fn migrate_platform(payer: Pubkey, platform: Pubkey) -> Instruction {
+    build(
+        accounts::MigratePlatform {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            payer,
+            platform,
+            system_program: system_program::ID,
+        },
+        instruction::MigratePlatform {},
+    )
+}
+
+pub fn create_pool(
+    authority: Pubkey,
+    platform: Pubkey,
+    pool_id: u64,
+    max_duration: u32,
+    rate_curve: RateCurve,
+    token_program: Pubkey,
+) -> Instruction {
+    let lending_pool = pda::lending_pool(&platform, pool_id).0;
+    build(
+        accounts::CreatePool {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            platform,
+            lending_pool,
+            pool_vault: pda::pool_vault(&lending_pool).0,
+            share_mint: pda::pool_share_mint(&lending_pool).0,
+            token_program,
+            system_program: system_program::ID,
+        },
+        instruction::CreatePool {
+            pool_id,
+            max_duration,
+            rate_curve,
+        },
+    )
+}
+
+pub fn update_pool(
+    authority: Pubkey,
+    lending_pool: Pubkey,
+    max_duration: u32,
+    rate_curve: RateCurve,
+) -> Instruction {
+    build(
+        accounts::UpdatePool {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            lending_pool,
+        },
+        instruction::UpdatePool {
+            max_duration,
+            rate_curve,
+        },
+    )
+}
+
+pub fn set_pool_collection(
+    authority: Pubkey,
+    lending_pool: Pubkey,
+    collection: Pubkey,
+    max_loan_amount: u64,
+) -> Instruction {
+    build(
+        accounts::SetPoolCollection {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            lending_pool,
+            collection,
+            collection_config: pda::collection_config(&lending_pool, &collection).0,
+            system_program: system_program::ID,
+        },
+        instruction::SetPoolCollection { max_loan_amount },
+    )
+}
+
+pub fn remove_pool_collection(
+    authority: Pubkey,
+    lending_pool: Pubkey,
+    collection: Pubkey,
+) -> Instruction {
+    build(
+        accounts::RemovePoolCollection {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            lending_pool,
+            collection_config: pda::collection_config(&lending_pool, &collection).0,
+        },
+        instruction::RemovePoolCollection {},
+    )
+}
+
+pub fn deposit_to_pool(
+    depositor: Pubkey,
+    lending_pool: Pubkey,
+    amount: u64,
+    token_program: Pubkey,
+) -> Instruction {
+    let share_mint = pda::pool_share_mint(&lending_pool).0;
+    build(
+        accounts::DepositToPool {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            depositor,
+            lending_pool,
+            pool_vault: pda::pool_vault(&lending_pool).0,
+            share_mint,
+            depositor_share_ata: get_associated_token_address_with_program_id(
+                &depositor,
+                &share_mint,
+                &token_program,
+            ),
+            system_program: system_program::ID,
+            token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::DepositToPool { amount },
+    )
+}
+
+pub fn withdraw_from_pool(
+    depositor: Pubkey,
+    lending_pool: Pubkey,
+    shares: u64,
+    token_program: Pubkey,
+) -> Instruction {
+    let share_mint = pda::pool_share_mint(&lending_pool).0;
+    build(
+        accounts::WithdrawFromPool {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            depositor,
+            lending_pool,
+            pool_vault: pda::pool_vault(&lending_pool).0,
+            share_mint,
+            depositor_share_ata: get_associated_token_address_with_program_id(
+                &depositor,
+                &share_mint,
+                &token_program,
+            ),
+            system_program: system_program::ID,
+            token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::WithdrawFromPool { shares },
+    )
+}
+
+/// Funds a requested loan from `lending_pool`. The nft proves its collection through
+/// Metaplex metadata.
+pub fn pool_fund_loan(
+    keys: &LoanKeys,
+    caller: Pubkey,
+    collection: Pubkey,
+    lending_pool: Pubkey,
+) -> Instruction {
+    pool_fund(keys, caller, collection, CollectionProof::Metaplex, lending_pool)
+}
+
+/// Like `pool_fund_loan` for a Token-2022 nft that is a member of the `collection` token group.
+pub fn pool_fund_loan_as_group_member(
+    keys: &LoanKeys,
+    caller: Pubkey,
+    collection: Pubkey,
+    lending_pool: Pubkey,
+) -> Instruction {
+    pool_fund(keys, caller, collection, CollectionProof::TokenGroup, lending_pool)
+}
+
+fn pool_fund(
+    keys: &LoanKeys,
+    caller: Pubkey,
+    collection: Pubkey,
+    proof: CollectionProof,
+    lending_pool: Pubkey,
+) -> Instruction {
+    let (metadata, master_edition) = proof.accounts(&keys.nft_mint);
+    build(
+        accounts::PoolFundLoan {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            caller,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_collection: collection,
+            metadata,
+            master_edition,
+            loan_account: keys.loan(),
+            platform: keys.platform,
+            lending_pool,
+            pool_vault: pda::pool_vault(&lending_pool).0,
+            collection_config: pda::collection_config(&lending_pool, &collection).0,
+            metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+        },
+        instruction::PoolFundLoan {},
+    )
+}
+
+/// `keys.loan_id` must be the platform's current `loan_count`. The nft proves its
+/// collection through Metaplex metadata. Fails if the pool's curve rate is above
+/// `max_interest_rate` when the transaction lands.
+pub fn borrow_from_pool(
+    keys: &LoanKeys,
+    collection: Pubkey,
+    lending_pool: Pubkey,
+    amount: u64,
+    duration: u32,
+    max_interest_rate: u16,
+) -> Instruction {
+    let proof = CollectionProof::Metaplex;
+    pool_loan(keys, collection, proof, lending_pool, amount, duration, max_interest_rate)
+}
+
+/// Like `borrow_from_pool` for a Token-2022 nft that is a member of the `collection` token group.
+pub fn borrow_from_pool_as_group_member(
+    keys: &LoanKeys,
+    collection: Pubkey,
+    lending_pool: Pubkey,
+    amount: u64,
+    duration: u32,
+    max_interest_rate: u16,
+) -> Instruction {
+    let proof = CollectionProof::TokenGroup;
+    pool_loan(keys, collection, proof, lending_pool, amount, duration, max_interest_rate)
+}
+
+fn pool_loan(
+    keys: &LoanKeys,
+    collection: Pubkey,
+    proof: CollectionProof,
+    lending_pool: Pubkey,
+    amount: u64,
+    duration: u32,
+    max_interest_rate: u16,
+) -> Instruction {
+    let (metadata, master_edition) = proof.accounts(&keys.nft_mint);
+    build(
+        accounts::BorrowFromPool {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            borrower_nft_collection: collection,
+            borrower_nft_ata: keys.ata(&keys.borrower),
+            metadata,
+            master_edition,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            platform: keys.platform,
+            lending_pool,
+            pool_vault: pda::pool_vault(&lending_pool).0,
+            collection_config: pda::collection_config(&lending_pool, &collection).0,
+            metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::BorrowFromPool {
+            amount,
+            duration,
+            max_interest_rate,
+        },
+    )
+}
+
+pub fn pool_claim_default(keys: &LoanKeys, caller: Pubkey, lending_pool: Pubkey) -> Instruction {
+    build(
+        accounts::PoolClaimDefault {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            caller,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            platform: keys.platform,
+            lending_pool,
+            pool_nft_ata: keys.ata(&lending_pool),
+            pool_collateral: pda::pool_collateral(&lending_pool, &keys.nft_mint).0,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            system_program: system_program::ID,
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::PoolClaimDefault {},
+    )
+}
+
+/// Buys `nft_mint` out of `lending_pool`'s custody at its auction price, paying at most
+/// `max_price`. `payer` is `PoolCollateral::payer`, whoever ran `pool_claim_default`, it only
+/// gets the rent back and doesn't sign.
+pub fn liquidate_pool_collateral(
+    payer: Pubkey,
+    buyer: Pubkey,
+    nft_mint: Pubkey,
+    lending_pool: Pubkey,
+    max_price: u64,
+    token_program: Pubkey,
+) -> Instruction {
+    build(
+        accounts::LiquidatePoolCollateral {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            payer,
+            buyer,
+            nft_mint,
+            lending_pool,
+            pool_vault: pda::pool_vault(&lending_pool).0,
+            pool_collateral: pda::pool_collateral(&lending_pool, &nft_mint).0,
+            pool_nft_ata: get_associated_token_address_with_program_id(
+                &lending_pool,
+                &nft_mint,
+                &token_program,
+            ),
+            buyer_nft_ata: get_associated_token_address_with_program_id(
+                &buyer,
+                &nft_mint,
+                &token_program,
+            ),
+            system_program: system_program::ID,
+            token_program,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::LiquidatePoolCollateral { max_price },
+    )
+}
+
+/// Parameters of a platform's insurance fund, see `InsuranceFund`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InsuranceConfig {
+    /// share of every lamport platform fee paid into the fund
+    pub insurance_share_bps: u16,
+    pub premium_bps: u16,
+    pub coverage_bps: u16,
+    pub max_claim_bps: u16,
+    pub oracle: Pubkey,
+}
+
+/// Creates the platform's insurance fund or replaces its parameters.
+pub fn configure_insurance(authority: Pubkey, platform: Pubkey, config: InsuranceConfig) -> Instruction {
+    build(
+        accounts::ConfigureInsurance {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            platform,
+            insurance_fund: pda::insurance_fund(&platform).0,
+            system_program: system_program::ID,
+        },
+        instruction::ConfigureInsurance {
+            insurance_share_bps: config.insurance_share_bps,
+            premium_bps: config.premium_bps,
+            coverage_bps: config.coverage_bps,
+            max_claim_bps: config.max_claim_bps,
+            oracle: config.oracle,
+        },
+    )
+}
+
+/// Oracle posts `value` lamports as what `nft_mint` is worth.
+pub fn set_collateral_value(oracle: Pubkey, platform: Pubkey, nft_mint: Pubkey, value: u64) -> Instruction {
+    build(
+        accounts::SetCollateralValue {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            oracle,
+            platform,
+            insurance_fund: pda::insurance_fund(&platform).0,
+            nft_mint,
+            valuation: pda::collateral_valuation(&platform, &nft_mint).0,
+            system_program: system_program::ID,
+        },
+        instruction::SetCollateralValue { value },
+    )
+}
+
+/// Insured lender claims on a loan that was not repaid by its deadline, settled against the
+/// collateral's latest valuation. Works after the loan account is closed.
+pub fn claim_insurance(keys: &LoanKeys, lender: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimInsurance {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            lender,
+            platform: keys.platform,
+            insurance_fund: pda::insurance_fund(&keys.platform).0,
+            insurance_policy: pda::insurance_policy(&keys.loan()).0,
+            valuation: pda::collateral_valuation(&keys.platform, &keys.nft_mint).0,
+        },
+        instruction::ClaimInsurance {},
+    )
+}
+
+/// Tags of `CastVote` and `RelinquishVote` in spl-governance's `GovernanceInstruction`, the
+/// prefixes to allow for voting through SPL Governance.
+pub const SPL_GOVERNANCE_CAST_VOTE: u8 = 13;
+pub const SPL_GOVERNANCE_RELINQUISH_VOTE: u8 = 15;
+
+/// Lets borrowers relay instructions of `program_id` starting with one of `instructions`, or
+/// replaces the ones already allowed.
+pub fn set_governance_program(
+    authority: Pubkey,
+    platform: Pubkey,
+    program_id: Pubkey,
+    instructions: Vec<Vec<u8>>,
+) -> Instruction {
+    build(
+        accounts::SetGovernanceProgram {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            platform,
+            target_program: program_id,
+            governance_program: pda::governance_program(&platform, &program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::SetGovernanceProgram { instructions },
+    )
+}
+
+pub fn remove_governance_program(authority: Pubkey, platform: Pubkey, program_id: Pubkey) -> Instruction {
+    build(
+        accounts::RemoveGovernanceProgram {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            authority,
+            platform,
+            governance_program: pda::governance_program(&platform, &program_id).0,
+        },
+        instruction::RemoveGovernanceProgram {},
+    )
+}
+
+/// Relays `data` to governance program `program_id` with the loan account signing for the
+/// escrowed nft. `accounts` are the governance instruction's own, the loan account among them
+/// is passed as a non signer and signed for by the program.
+pub fn governance_vote(
+    keys: &LoanKeys,
+    program_id: Pubkey,
+    accounts: &[AccountMeta],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut ix = build(
+        accounts::GovernanceVote {
+            event_authority: pda::event_authority().0,
+            program: PROGRAM_ID,
+            borrower: keys.borrower,
+            borrower_nft_mint: keys.nft_mint,
+            loan_account: keys.loan(),
+            nft_vault: keys.nft_vault(),
+            platform: keys.platform,
+            governance_program: pda::governance_program(&keys.platform, &program_id).0,
+            target_program: program_id,
+            token_program: keys.token_program,
+        },
+        instruction::GovernanceVote { data },
+    );
+    let loan = keys.loan();
+    ix.accounts.extend(accounts.iter().map(|meta| AccountMeta {
+        is_signer: meta.is_signer && meta.pubkey != loan,
+        ..meta.clone()
+    }));
+    ix
+}
@@ -0,0 +1,17 @@
+//! Client helpers for the Credentia program: PDA derivation, typed instruction
+//! builders, account decoders and repayment quotes that mirror the on-chain math.
+
+pub mod accounts;
+pub mod hook;
+pub mod instructions;
+pub mod pda;
+pub mod quote;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+pub use credentia::ID as PROGRAM_ID;
+pub use credentia::{
+    AcceptanceConstraints, CollateralValuation, CounterOffer, DefaultProtection, GovernanceProgram,
+    InsuranceFund, InsurancePolicy, LenderReputation, LendingPool, Loan, LoanStatus, Platform,
+    PoolCollateral, RateCurve,
+};
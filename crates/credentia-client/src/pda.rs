@@ -0,0 +1,146 @@
+//! Program derived addresses used by Credentia.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::{associated_token::get_associated_token_address_with_program_id, metadata::mpl_token_metadata};
+
+use crate::PROGRAM_ID;
+
+pub fn platform(platform_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"platform", &platform_id.to_le_bytes()], &PROGRAM_ID)
+}
+
+pub fn treasury_vault(platform: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury_vault", platform.as_ref()], &PROGRAM_ID)
+}
+
+pub fn reward_mint(platform: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"reward_mint", platform.as_ref()], &PROGRAM_ID)
+}
+
+pub fn loan(nft_mint: &Pubkey, platform: &Pubkey, loan_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"loan", nft_mint.as_ref(), platform.as_ref(), &loan_id.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+}
+
+/// Signer of the self invocations that carry `emit_cpi!` events.
+pub fn event_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], &PROGRAM_ID)
+}
+
+/// Address of a loan opened before loan ids existed.
+pub fn legacy_loan(nft_mint: &Pubkey, platform: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"loan", nft_mint.as_ref(), platform.as_ref()], &PROGRAM_ID)
+}
+
+/// Token account holding the collateral, owned by the loan PDA.
+pub fn nft_vault(loan: &Pubkey, nft_mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(loan, nft_mint, token_program)
+}
+
+pub fn metadata(nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), nft_mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+pub fn master_edition(nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            nft_mint.as_ref(),
+            b"edition",
+        ],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+/// Account a transfer hook program reads its extra accounts from, per the transfer hook interface.
+pub fn extra_account_metas(mint: &Pubkey, hook_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], hook_program).0
+}
+
+pub fn lending_pool(platform: &Pubkey, pool_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pool", platform.as_ref(), &pool_id.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+}
+
+pub fn pool_vault(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool_vault", pool.as_ref()], &PROGRAM_ID)
+}
+
+pub fn pool_share_mint(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool_share_mint", pool.as_ref()], &PROGRAM_ID)
+}
+
+pub fn collection_config(pool: &Pubkey, collection: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"collection_config", pool.as_ref(), collection.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+/// Liquidation auction of a defaulted `nft_mint` in `pool` custody.
+pub fn pool_collateral(pool: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pool_collateral", pool.as_ref(), nft_mint.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+pub fn referrer(platform: &Pubkey, referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"referrer", platform.as_ref(), referrer.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+/// Count of `lender`'s lamport loans on `platform` that other borrowers repaid with at least
+/// `MIN_REPUTATION_FEE` in platform fees, opened by its first `fund_borrower`.
+pub fn lender_reputation(platform: &Pubkey, lender: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"lender", platform.as_ref(), lender.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+/// `lender`'s counter offer on `loan`, holding the offered amount until it is settled.
+pub fn counter_offer(loan: &Pubkey, lender: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"counter_offer", loan.as_ref(), lender.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+/// `platform`'s insurance fund, holding the lamports claims are paid from.
+pub fn insurance_fund(platform: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"insurance_fund", platform.as_ref()], &PROGRAM_ID)
+}
+
+/// Cover the lender bought on `loan` when funding it.
+pub fn insurance_policy(loan: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"insurance_policy", loan.as_ref()], &PROGRAM_ID)
+}
+
+/// Allowed instructions of governance program `program_id` on `platform`.
+pub fn governance_program(platform: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"governance_program", platform.as_ref(), program_id.as_ref()],
+        &PROGRAM_ID,
+    )
+}
+
+/// The insurance oracle's value of `nft_mint` on `platform`.
+pub fn collateral_valuation(platform: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"valuation", platform.as_ref(), nft_mint.as_ref()],
+        &PROGRAM_ID,
+    )
+}
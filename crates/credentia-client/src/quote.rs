@@ -0,0 +1,93 @@
+//! Repayment quotes, computed with the same `credentia::math` as `ResolveLoan::transfer_amount`.
+
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
+};
+use credentia::math::{FeeSplit, Repayment};
+
+use crate::{Loan, Platform};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepaymentQuote {
+    pub principal: u64,
+    pub interest: u64,
+    pub fee_for_platform: u64,
+    /// principal + interest - fee_for_platform
+    pub amount_to_pay_lender: u64,
+    /// what leaves the borrower's wallet, Token-2022 transfer fees included
+    pub total_repayment: u64,
+    /// transfer fees on top of principal + interest, 0 for lamport loans and mints without one
+    pub transfer_fee: u64,
+    pub treasury_amount: u64,
+    pub borrower_referrer_amount: u64,
+    pub lender_referrer_amount: u64,
+    /// share of the fee paid into the platform's insurance fund, lamport loans only
+    pub insurance_amount: u64,
+}
+
+/// Transfer fee `mint_data` charges in `epoch`, `None` for mints without a `TransferFeeConfig`.
+pub fn transfer_fee(mint_data: &[u8], epoch: u64) -> Option<TransferFee> {
+    let state = StateWithExtensions::<Mint>::unpack(mint_data).ok()?;
+    let config = state.get_extension::<TransferFeeConfig>().ok()?;
+    Some(*config.get_epoch_fee(epoch))
+}
+
+/// Quote for repaying `loan` on `platform`, `None` if the on-chain math would overflow.
+/// `transfer_fee` is the loan currency's fee for the current epoch, see `transfer_fee`.
+pub fn repayment_quote(
+    loan: &Loan,
+    platform: &Platform,
+    transfer_fee: Option<&TransferFee>,
+) -> Option<RepaymentQuote> {
+    let repayment = Repayment::new(loan.loan_amount, loan.interest_rate, platform.fee_bps).ok()?;
+    let split = FeeSplit::new(
+        repayment.fee_for_platform,
+        platform.referral_share_bps,
+        loan.borrower_referrer.is_some(),
+        loan.lender_referrer.is_some(),
+    )
+    .ok()?;
+    let insurance_share_bps = if loan.currency.is_none() { platform.insurance_share_bps } else { 0 };
+    let split = split
+        .with_insurance(repayment.fee_for_platform, insurance_share_bps)
+        .ok()?;
+
+    //every share is a transfer of its own, grossed up as `currency::gross_amount` does so that
+    //its recipient is credited exactly the share
+    let gross = |amount: u64| match transfer_fee {
+        Some(fee) => fee.calculate_pre_fee_amount(amount),
+        None => Some(amount),
+    };
+    let total_repayment = [
+        split.borrower_referrer_amount,
+        split.lender_referrer_amount,
+        split.insurance_amount,
+        split.treasury_amount,
+        repayment.amount_to_pay_lender,
+    ]
+    .into_iter()
+    .try_fold(0u64, |total, amount| total.checked_add(gross(amount)?))?;
+
+    Some(RepaymentQuote {
+        principal: loan.loan_amount,
+        interest: repayment.interest,
+        fee_for_platform: repayment.fee_for_platform,
+        amount_to_pay_lender: repayment.amount_to_pay_lender,
+        total_repayment,
+        transfer_fee: total_repayment - repayment.total_repayment,
+        treasury_amount: split.treasury_amount,
+        borrower_referrer_amount: split.borrower_referrer_amount,
+        lender_referrer_amount: split.lender_referrer_amount,
+        insurance_amount: split.insurance_amount,
+    })
+}
+
+/// Last unix timestamp at which the loan can still be repaid, including any default protection
+/// extension, `None` until it is funded.
+pub fn repayment_deadline(loan: &Loan) -> Option<i64> {
+    loan.start_time.map(|start| start + loan.repayment_window())
+}
@@ -0,0 +1,452 @@
+//! Minimal blocking JSON-RPC client covering the calls the Credentia tools need.
+
+use std::{fmt, str::FromStr, thread, time::Duration};
+
+use anchor_lang::{
+    prelude::Pubkey, solana_program::hash::Hash, AccountDeserialize, Discriminator,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    CollateralValuation, CounterOffer, GovernanceProgram, InsuranceFund, InsurancePolicy,
+    LenderReputation, LendingPool, Loan, Platform, PoolCollateral, PROGRAM_ID,
+};
+
+/// Offset of `Loan::borrower`, right after the discriminator.
+pub const LOAN_BORROWER_OFFSET: usize = 8;
+/// Offset of `CounterOffer::loan`, right after the discriminator.
+pub const COUNTER_OFFER_LOAN_OFFSET: usize = 8;
+
+#[derive(Debug)]
+pub enum RpcError {
+    Transport(String),
+    /// error object returned by the node
+    Node { code: i64, message: String },
+    Decode(String),
+    /// transaction landed but failed
+    Transaction(String),
+    Timeout(String),
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Transport(e) => write!(f, "rpc transport error: {e}"),
+            RpcError::Node { code, message } => write!(f, "rpc error {code}: {message}"),
+            RpcError::Decode(e) => write!(f, "could not decode rpc response: {e}"),
+            RpcError::Transaction(e) => write!(f, "transaction failed: {e}"),
+            RpcError::Timeout(sig) => write!(f, "timed out waiting for {sig}"),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+pub type RpcResult<T> = std::result::Result<T, RpcError>;
+
+/// A signature returned by `getSignaturesForAddress`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    pub err: Option<Value>,
+    pub block_time: Option<i64>,
+}
+
+/// The parts of a confirmed transaction the indexer reads.
+#[derive(Clone, Debug)]
+pub struct TransactionLogs {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    pub logs: Vec<String>,
+    /// instructions invoked through CPIs, in execution order
+    pub inner_instructions: Vec<InnerInstruction>,
+}
+
+/// A CPI made during a transaction, with its program id resolved from the account keys.
+#[derive(Clone, Debug)]
+pub struct InnerInstruction {
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// Inner instructions of a `getTransaction` result in `json` encoding. Program ids are
+/// indexes into the static account keys followed by the lookup table addresses.
+fn inner_instructions(result: &Value) -> RpcResult<Vec<InnerInstruction>> {
+    let meta = &result["meta"];
+    let keys: Vec<Pubkey> = [
+        &result["transaction"]["message"]["accountKeys"],
+        &meta["loadedAddresses"]["writable"],
+        &meta["loadedAddresses"]["readonly"],
+    ]
+    .into_iter()
+    .filter_map(Value::as_array)
+    .flatten()
+    .map(|key| {
+        key.as_str()
+            .and_then(|key| Pubkey::from_str(key).ok())
+            .ok_or_else(|| RpcError::Decode(format!("bad account key {key}")))
+    })
+    .collect::<RpcResult<_>>()?;
+
+    let mut instructions = Vec::new();
+    for group in meta["innerInstructions"].as_array().into_iter().flatten() {
+        for ix in group["instructions"].as_array().into_iter().flatten() {
+            let program_id = ix["programIdIndex"]
+                .as_u64()
+                .and_then(|i| keys.get(i as usize))
+                .ok_or_else(|| RpcError::Decode("bad program id index".into()))?;
+            let data = ix["data"]
+                .as_str()
+                .and_then(|data| bs58::decode(data).into_vec().ok())
+                .ok_or_else(|| RpcError::Decode("bad instruction data".into()))?;
+            instructions.push(InnerInstruction {
+                program_id: *program_id,
+                data,
+            });
+        }
+    }
+    Ok(instructions)
+}
+
+pub struct RpcClient {
+    url: String,
+    agent: ureq::Agent,
+}
+
+/// `memcmp` filter for `getProgramAccounts`.
+pub fn memcmp(offset: usize, bytes: &[u8]) -> Value {
+    json!({ "memcmp": { "offset": offset, "bytes": STANDARD.encode(bytes), "encoding": "base64" } })
+}
+
+fn decode_data(value: &Value) -> RpcResult<Vec<u8>> {
+    let encoded = value
+        .get(0)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::Decode("missing account data".into()))?;
+    STANDARD
+        .decode(encoded)
+        .map_err(|e| RpcError::Decode(e.to_string()))
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn call(&self, method: &str, params: Value) -> RpcResult<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = self
+            .agent
+            .post(&self.url)
+            .send_json(body)
+            .map_err(|e| RpcError::Transport(e.to_string()))?
+            .into_json()
+            .map_err(|e| RpcError::Decode(e.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::Node {
+                code: error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Raw account data, `None` if the account does not exist.
+    pub fn get_account_data(&self, address: &Pubkey) -> RpcResult<Option<Vec<u8>>> {
+        Ok(self.get_account(address)?.map(|(_, data)| data))
+    }
+
+    /// Owner program and raw data of an account, `None` if the account does not exist.
+    pub fn get_account(&self, address: &Pubkey) -> RpcResult<Option<(Pubkey, Vec<u8>)>> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), { "encoding": "base64", "commitment": "confirmed" }]),
+        )?;
+        match result.get("value") {
+            Some(Value::Null) | None => Ok(None),
+            Some(account) => {
+                let owner = account["owner"]
+                    .as_str()
+                    .and_then(|s| Pubkey::from_str(s).ok())
+                    .ok_or_else(|| RpcError::Decode("bad owner".into()))?;
+                Ok(Some((owner, decode_data(&account["data"])?)))
+            }
+        }
+    }
+
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<Value>,
+    ) -> RpcResult<Vec<(Pubkey, Vec<u8>)>> {
+        let result = self.call(
+            "getProgramAccounts",
+            json!([program_id.to_string(), {
+                "encoding": "base64",
+                "commitment": "confirmed",
+                "filters": filters,
+            }]),
+        )?;
+        let accounts = result
+            .as_array()
+            .ok_or_else(|| RpcError::Decode("expected an array of accounts".into()))?;
+
+        accounts
+            .iter()
+            .map(|entry| {
+                let pubkey = entry["pubkey"]
+                    .as_str()
+                    .and_then(|s| Pubkey::from_str(s).ok())
+                    .ok_or_else(|| RpcError::Decode("bad pubkey".into()))?;
+                Ok((pubkey, decode_data(&entry["account"]["data"])?))
+            })
+            .collect()
+    }
+
+    pub fn get_latest_blockhash(&self) -> RpcResult<Hash> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": "confirmed" }]))?;
+        result["value"]["blockhash"]
+            .as_str()
+            .and_then(|s| Hash::from_str(s).ok())
+            .ok_or_else(|| RpcError::Decode("bad blockhash".into()))
+    }
+
+    /// Cluster time of the latest confirmed slot.
+    pub fn get_unix_timestamp(&self) -> RpcResult<i64> {
+        let slot = self.call("getSlot", json!([{ "commitment": "confirmed" }]))?;
+        self.call("getBlockTime", json!([slot]))?
+            .as_i64()
+            .ok_or_else(|| RpcError::Decode("missing block time".into()))
+    }
+
+    /// Epoch of the latest confirmed slot, the one Token-2022 transfer fees are charged for.
+    pub fn get_epoch(&self) -> RpcResult<u64> {
+        let result = self.call("getEpochInfo", json!([{ "commitment": "confirmed" }]))?;
+        result["epoch"]
+            .as_u64()
+            .ok_or_else(|| RpcError::Decode("bad epoch info".into()))
+    }
+
+    pub fn get_balance(&self, address: &Pubkey) -> RpcResult<u64> {
+        let result = self.call(
+            "getBalance",
+            json!([address.to_string(), { "commitment": "confirmed" }]),
+        )?;
+        result["value"]
+            .as_u64()
+            .ok_or_else(|| RpcError::Decode("bad balance".into()))
+    }
+
+    pub fn request_airdrop(&self, address: &Pubkey, lamports: u64) -> RpcResult<String> {
+        self.call("requestAirdrop", json!([address.to_string(), lamports]))?
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| RpcError::Decode("bad signature".into()))
+    }
+
+    /// Sends a serialized, signed transaction and returns its signature.
+    pub fn send_transaction(&self, wire_transaction: &[u8]) -> RpcResult<String> {
+        self.call(
+            "sendTransaction",
+            json!([STANDARD.encode(wire_transaction), {
+                "encoding": "base64",
+                "preflightCommitment": "confirmed",
+            }]),
+        )?
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| RpcError::Decode("bad signature".into()))
+    }
+
+    /// Polls until `signature` is confirmed, failing if the transaction errored.
+    pub fn confirm_transaction(&self, signature: &str, timeout: Duration) -> RpcResult<()> {
+        let poll = Duration::from_millis(500);
+        let mut waited = Duration::ZERO;
+        while waited < timeout {
+            let result = self.call(
+                "getSignatureStatuses",
+                json!([[signature], { "searchTransactionHistory": true }]),
+            )?;
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    return Err(RpcError::Transaction(status["err"].to_string()));
+                }
+                if matches!(
+                    status["confirmationStatus"].as_str(),
+                    Some("confirmed") | Some("finalized")
+                ) {
+                    return Ok(());
+                }
+            }
+            thread::sleep(poll);
+            waited += poll;
+        }
+        Err(RpcError::Timeout(signature.to_string()))
+    }
+
+    pub fn send_and_confirm(&self, wire_transaction: &[u8]) -> RpcResult<String> {
+        let signature = self.send_transaction(wire_transaction)?;
+        self.confirm_transaction(&signature, Duration::from_secs(60))?;
+        Ok(signature)
+    }
+
+    /// Signatures touching `address`, newest first, starting before `before`.
+    pub fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+    ) -> RpcResult<Vec<SignatureInfo>> {
+        let result = self.call(
+            "getSignaturesForAddress",
+            json!([address.to_string(), {
+                "before": before,
+                "until": until,
+                "limit": limit,
+                "commitment": "confirmed",
+            }]),
+        )?;
+        serde_json::from_value(result).map_err(|e| RpcError::Decode(e.to_string()))
+    }
+
+    /// Log messages of a confirmed transaction, `None` if the node doesn't have it.
+    pub fn get_transaction_logs(&self, signature: &str) -> RpcResult<Option<TransactionLogs>> {
+        let result = self.call(
+            "getTransaction",
+            json!([signature, {
+                "encoding": "json",
+                "commitment": "confirmed",
+                "maxSupportedTransactionVersion": 0,
+            }]),
+        )?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        let meta = &result["meta"];
+        let logs = meta["logMessages"]
+            .as_array()
+            .map(|lines| {
+                lines
+                    .iter()
+                    .filter_map(|l| l.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Some(TransactionLogs {
+            signature: signature.to_string(),
+            slot: result["slot"].as_u64().unwrap_or_default(),
+            block_time: result["blockTime"].as_i64(),
+            failed: !meta["err"].is_null(),
+            logs,
+            inner_instructions: inner_instructions(&result)?,
+        }))
+    }
+
+    fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> RpcResult<Option<T>> {
+        self.get_account_data(address)?
+            .map(|data| {
+                T::try_deserialize(&mut &data[..]).map_err(|e| RpcError::Decode(e.to_string()))
+            })
+            .transpose()
+    }
+
+    pub fn fetch_loan(&self, address: &Pubkey) -> RpcResult<Option<Loan>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_platform(&self, address: &Pubkey) -> RpcResult<Option<Platform>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_lending_pool(&self, address: &Pubkey) -> RpcResult<Option<LendingPool>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_pool_collateral(&self, address: &Pubkey) -> RpcResult<Option<PoolCollateral>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_lender_reputation(&self, address: &Pubkey) -> RpcResult<Option<LenderReputation>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_counter_offer(&self, address: &Pubkey) -> RpcResult<Option<CounterOffer>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_insurance_fund(&self, address: &Pubkey) -> RpcResult<Option<InsuranceFund>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_insurance_policy(&self, address: &Pubkey) -> RpcResult<Option<InsurancePolicy>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_collateral_valuation(
+        &self,
+        address: &Pubkey,
+    ) -> RpcResult<Option<CollateralValuation>> {
+        self.fetch(address)
+    }
+
+    pub fn fetch_governance_program(&self, address: &Pubkey) -> RpcResult<Option<GovernanceProgram>> {
+        self.fetch(address)
+    }
+
+    /// Open counter offers on `loan`.
+    pub fn fetch_counter_offers(&self, loan: &Pubkey) -> RpcResult<Vec<(Pubkey, CounterOffer)>> {
+        let filters = vec![
+            memcmp(0, CounterOffer::DISCRIMINATOR),
+            memcmp(COUNTER_OFFER_LOAN_OFFSET, loan.as_ref()),
+        ];
+        Ok(self
+            .get_program_accounts(&PROGRAM_ID, filters)?
+            .into_iter()
+            .filter_map(|(key, data)| {
+                CounterOffer::try_deserialize(&mut &data[..])
+                    .ok()
+                    .map(|offer| (key, offer))
+            })
+            .collect())
+    }
+
+    /// Every loan account, optionally only those of `borrower`. Accounts that
+    /// don't decode with the current layout (legacy loans) are skipped.
+    pub fn fetch_loans(&self, borrower: Option<&Pubkey>) -> RpcResult<Vec<(Pubkey, Loan)>> {
+        let mut filters = vec![memcmp(0, Loan::DISCRIMINATOR)];
+        if let Some(borrower) = borrower {
+            filters.push(memcmp(LOAN_BORROWER_OFFSET, borrower.as_ref()));
+        }
+        Ok(self
+            .get_program_accounts(&PROGRAM_ID, filters)?
+            .into_iter()
+            .filter_map(|(key, data)| {
+                Loan::try_deserialize(&mut &data[..])
+                    .ok()
+                    .map(|loan| (key, loan))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,197 @@
+use anchor_lang::{prelude::Pubkey, Discriminator};
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFee;
+use credentia_client::{
+    instructions::{self, LoanKeys, LoanTerms},
+    pda,
+    quote::{repayment_deadline, repayment_quote},
+    Loan, LoanStatus, Platform, PROGRAM_ID,
+};
+
+fn platform(fee_bps: u16, referral_share_bps: u16) -> Platform {
+    Platform {
+        authority: Pubkey::new_unique(),
+        fee_bps,
+        reward_bump: 0,
+        treasury_bump: 0,
+        bump: 0,
+        loan_count: 0,
+        platform_id: 1,
+        referral_share_bps,
+        version: Platform::VERSION,
+        insurance_share_bps: 0,
+    }
+}
+
+fn loan(amount: u64, interest_rate: u16) -> Loan {
+    Loan {
+        borrower: Pubkey::new_unique(),
+        lender: Some(Pubkey::new_unique()),
+        nft_mint: Pubkey::new_unique(),
+        loan_amount: amount,
+        duration: 3600,
+        start_time: Some(1_000),
+        status: LoanStatus::Funded,
+        time_of_liquidation_or_repayment: None,
+        interest_rate,
+        bump: 0,
+        pool: None,
+        loan_id: 7,
+        borrower_referrer: None,
+        lender_referrer: None,
+        version: Loan::VERSION,
+        currency: None,
+        expires_at: None,
+        allowed_lenders: Vec::new(),
+        min_lender_reputation: 0,
+        insured: false,
+        protection: None,
+    }
+}
+
+#[test]
+fn quote_without_referrers() {
+    let q = repayment_quote(&loan(1_000_000_000, 1000), &platform(500, 2000), None).unwrap();
+    assert_eq!(q.interest, 100_000_000);
+    assert_eq!(q.fee_for_platform, 5_000_000);
+    assert_eq!(q.amount_to_pay_lender, 1_095_000_000);
+    assert_eq!(q.total_repayment, 1_100_000_000);
+    assert_eq!(q.treasury_amount, 5_000_000);
+    assert_eq!(q.borrower_referrer_amount, 0);
+    assert_eq!(q.lender_referrer_amount, 0);
+}
+
+#[test]
+fn quote_splits_referral_share() {
+    let mut l = loan(1_000_000_000, 1000);
+    l.borrower_referrer = Some(Pubkey::new_unique());
+    l.lender_referrer = Some(Pubkey::new_unique());
+    let q = repayment_quote(&l, &platform(500, 2000), None).unwrap();
+    assert_eq!(q.borrower_referrer_amount, 500_000);
+    assert_eq!(q.lender_referrer_amount, 500_000);
+    assert_eq!(q.treasury_amount, 4_000_000);
+
+    l.lender_referrer = None;
+    let q = repayment_quote(&l, &platform(500, 2000), None).unwrap();
+    assert_eq!(q.borrower_referrer_amount, 1_000_000);
+    assert_eq!(q.treasury_amount, 4_000_000);
+}
+
+#[test]
+fn quote_routes_insurance_share_of_lamport_fees() {
+    let mut p = platform(500, 2000);
+    p.insurance_share_bps = 1000;
+    let mut l = loan(1_000_000_000, 1000);
+    l.borrower_referrer = Some(Pubkey::new_unique());
+    let q = repayment_quote(&l, &p, None).unwrap();
+    assert_eq!(q.insurance_amount, 500_000);
+    assert_eq!(q.borrower_referrer_amount, 1_000_000);
+    assert_eq!(q.treasury_amount, 3_500_000);
+
+    //token loans keep the whole fee out of the lamport fund
+    l.currency = Some(Pubkey::new_unique());
+    let q = repayment_quote(&l, &p, None).unwrap();
+    assert_eq!(q.insurance_amount, 0);
+    assert_eq!(q.treasury_amount, 4_000_000);
+}
+
+#[test]
+fn quote_grosses_up_token_transfer_fees() {
+    let mut l = loan(1_000_000_000, 1000);
+    l.currency = Some(Pubkey::new_unique());
+    l.borrower_referrer = Some(Pubkey::new_unique());
+    //1% on every transfer, capped well above what the loan moves
+    let fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: 100_000_000.into(),
+        transfer_fee_basis_points: 100.into(),
+    };
+    let q = repayment_quote(&l, &platform(500, 2000), Some(&fee)).unwrap();
+    //the shares recipients are credited don't change
+    assert_eq!(q.amount_to_pay_lender, 1_095_000_000);
+    assert_eq!(q.treasury_amount, 4_000_000);
+    assert_eq!(q.borrower_referrer_amount, 1_000_000);
+    //each of the three transfers carries its own fee
+    let gross = |amount| fee.calculate_pre_fee_amount(amount).unwrap();
+    let total = gross(1_095_000_000) + gross(4_000_000) + gross(1_000_000);
+    assert_eq!(q.total_repayment, total);
+    assert_eq!(q.transfer_fee, total - 1_100_000_000);
+    assert!(q.transfer_fee >= 11_000_000);
+
+    //a mint with a zero fee costs principal + interest, as a lamport loan does
+    let free = TransferFee {
+        transfer_fee_basis_points: 0.into(),
+        ..fee
+    };
+    let q = repayment_quote(&l, &platform(500, 2000), Some(&free)).unwrap();
+    assert_eq!(q.total_repayment, 1_100_000_000);
+    assert_eq!(q.transfer_fee, 0);
+}
+
+#[test]
+fn quote_overflow_is_none() {
+    assert!(repayment_quote(&loan(u64::MAX, 10_000), &platform(500, 0), None).is_none());
+}
+
+#[test]
+fn deadline_needs_start_time() {
+    let mut l = loan(1, 1);
+    assert_eq!(repayment_deadline(&l), Some(4_600));
+    l.start_time = None;
+    assert_eq!(repayment_deadline(&l), None);
+}
+
+#[test]
+fn request_loan_targets_derived_loan_pda() {
+    let platform = pda::platform(1).0;
+    let keys = LoanKeys {
+        platform,
+        nft_mint: Pubkey::new_unique(),
+        loan_id: 3,
+        borrower: Pubkey::new_unique(),
+        token_program: anchor_spl::token::ID,
+    };
+    let ix = instructions::request_loan(
+        &keys,
+        Pubkey::new_unique(),
+        LoanTerms {
+            amount: 10,
+            duration: 60,
+            interest_rate: 500,
+        },
+        None,
+    );
+
+    assert_eq!(ix.program_id, PROGRAM_ID);
+    assert_eq!(
+        &ix.data[..8],
+        credentia::instruction::RequestLoan::DISCRIMINATOR
+    );
+    let loan = pda::loan(&keys.nft_mint, &platform, 3).0;
+    assert!(ix.accounts.iter().any(|m| m.pubkey == loan && m.is_writable));
+    assert!(ix
+        .accounts
+        .iter()
+        .any(|m| m.pubkey == keys.borrower && m.is_signer));
+    //missing optional referrer is encoded as the program id
+    assert!(ix.accounts.iter().any(|m| m.pubkey == PROGRAM_ID));
+}
+
+#[test]
+fn flash_collateral_returns_where_the_program_looks() {
+    let keys = LoanKeys {
+        platform: pda::platform(1).0,
+        nft_mint: Pubkey::new_unique(),
+        loan_id: 0,
+        borrower: Pubkey::new_unique(),
+        token_program: anchor_spl::token::ID,
+    };
+    let ixs = instructions::with_flash_collateral(&keys, vec![]);
+
+    let [flash, ret] = &ixs[..] else {
+        panic!("expected the flash use and its return");
+    };
+    assert_eq!(&flash.data[..8], credentia::instruction::FlashUseCollateral::DISCRIMINATOR);
+    assert_eq!(&ret.data[..8], credentia::instruction::ReturnCollateral::DISCRIMINATOR);
+    //flash_use_collateral finds its return by the loan account at this position
+    assert_eq!(ret.accounts[credentia::RETURN_COLLATERAL_LOAN_INDEX].pubkey, keys.loan());
+}
@@ -0,0 +1,404 @@
+//! SQLite schema, event application and the history queries built on it.
+
+use anchor_lang::prelude::Pubkey;
+use credentia_client::pda;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::events::CredentiaEvent;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS loans (
+    platform TEXT NOT NULL,
+    loan_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    borrower TEXT NOT NULL,
+    nft_mint TEXT NOT NULL,
+    lender TEXT,
+    loan_amount INTEGER NOT NULL,
+    duration INTEGER NOT NULL,
+    interest_rate INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    requested_at INTEGER,
+    funded_at INTEGER,
+    repaid_amount INTEGER,
+    platform_fee INTEGER,
+    repaid_at INTEGER,
+    closed_at INTEGER,
+    PRIMARY KEY (platform, loan_id)
+);
+CREATE INDEX IF NOT EXISTS loans_borrower ON loans (borrower);
+CREATE INDEX IF NOT EXISTS loans_nft_mint ON loans (nft_mint);
+CREATE TABLE IF NOT EXISTS events (
+    signature TEXT NOT NULL,
+    idx INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    platform TEXT NOT NULL,
+    name TEXT NOT NULL,
+    loan_id INTEGER,
+    PRIMARY KEY (signature, idx)
+);
+CREATE TABLE IF NOT EXISTS nft_collections (
+    nft_mint TEXT PRIMARY KEY,
+    collection TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS cursors (
+    platform TEXT PRIMARY KEY,
+    last_signature TEXT NOT NULL
+);
+";
+
+/// Where an event came from.
+#[derive(Clone, Copy, Debug)]
+pub struct EventSource<'a> {
+    pub signature: &'a str,
+    pub index: usize,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoanRecord {
+    pub loan_id: u64,
+    pub address: String,
+    pub borrower: String,
+    pub nft_mint: String,
+    pub lender: Option<String>,
+    pub loan_amount: u64,
+    pub duration: u32,
+    pub interest_rate: u16,
+    /// requested, funded, repaid, defaulted, cancelled or expired
+    pub status: String,
+    pub requested_at: Option<i64>,
+    pub funded_at: Option<i64>,
+    pub repaid_amount: Option<u64>,
+    pub platform_fee: Option<u64>,
+    pub repaid_at: Option<i64>,
+    pub closed_at: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VolumeStats {
+    pub loans_funded: u64,
+    pub principal_funded: u64,
+    pub total_repaid: u64,
+    pub platform_fees: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollectionStats {
+    pub collection: String,
+    pub loans: u64,
+    pub principal_funded: u64,
+    pub repaid: u64,
+    pub defaulted: u64,
+    pub default_rate: f64,
+    pub avg_interest_rate: f64,
+}
+
+pub struct Database {
+    conn: Connection,
+    platform: Pubkey,
+}
+
+fn to_sql(value: u64) -> i64 {
+    value as i64
+}
+
+fn update_terms(
+    conn: &Connection,
+    platform: &str,
+    loan_id: u64,
+    loan_amount: u64,
+    duration: u32,
+    interest_rate: u16,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE loans SET loan_amount = ?3, duration = ?4, interest_rate = ?5
+         WHERE platform = ?1 AND loan_id = ?2",
+        params![platform, to_sql(loan_id), to_sql(loan_amount), duration, interest_rate],
+    )?;
+    Ok(())
+}
+
+impl Database {
+    /// Opens (or creates) the database for one platform's history.
+    pub fn open(path: &str, platform: Pubkey) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?, platform)
+    }
+
+    pub fn in_memory(platform: Pubkey) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, platform)
+    }
+
+    fn with_connection(conn: Connection, platform: Pubkey) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn, platform })
+    }
+
+    pub fn platform(&self) -> &Pubkey {
+        &self.platform
+    }
+
+    /// Records and applies one event. Returns false if it was already indexed.
+    pub fn apply(&mut self, source: EventSource, event: &CredentiaEvent) -> rusqlite::Result<bool> {
+        let platform = self.platform.to_string();
+        let tx = self.conn.transaction()?;
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO events (signature, idx, slot, block_time, platform, name, loan_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                source.signature,
+                source.index as i64,
+                to_sql(source.slot),
+                source.block_time,
+                platform,
+                event.name(),
+                event.loan_id().map(to_sql),
+            ],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        match event {
+            CredentiaEvent::LoanRequested(e) => {
+                let address = pda::loan(&e.nft_mint, &self.platform, e.loan_id).0;
+                tx.execute(
+                    "INSERT OR REPLACE INTO loans
+                     (platform, loan_id, address, borrower, nft_mint, loan_amount, duration, interest_rate, status, requested_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'requested', ?9)",
+                    params![
+                        platform,
+                        to_sql(e.loan_id),
+                        address.to_string(),
+                        e.borrower.to_string(),
+                        e.nft_mint.to_string(),
+                        to_sql(e.loan_amount),
+                        e.duration,
+                        e.interest_rate,
+                        e.timestamp,
+                    ],
+                )?;
+            }
+            //the loan is funded on the offer's terms, LoanFunded follows in the same instruction
+            CredentiaEvent::CounterOfferAccepted(e) => {
+                update_terms(&tx, &platform, e.loan_id, e.amount, e.duration, e.interest_rate)?;
+            }
+            CredentiaEvent::LoanTermsUpdated(e) => {
+                update_terms(&tx, &platform, e.loan_id, e.loan_amount, e.duration, e.interest_rate)?;
+            }
+            CredentiaEvent::LoanFunded(e) => {
+                tx.execute(
+                    "UPDATE loans SET status = 'funded', lender = ?3, funded_at = ?4, interest_rate = ?5
+                     WHERE platform = ?1 AND loan_id = ?2",
+                    params![
+                        platform,
+                        to_sql(e.loan_id),
+                        e.lender.to_string(),
+                        e.funded_at,
+                        e.interest_rate,
+                    ],
+                )?;
+            }
+            CredentiaEvent::LoanRepaid(e) => {
+                tx.execute(
+                    "UPDATE loans SET status = 'repaid', repaid_amount = ?3, platform_fee = ?4, repaid_at = ?5
+                     WHERE platform = ?1 AND loan_id = ?2",
+                    params![
+                        platform,
+                        to_sql(e.loan_id),
+                        to_sql(e.repaid_amount),
+                        to_sql(e.fee_for_platform),
+                        e.timestamp,
+                    ],
+                )?;
+            }
+            //programs from before LoanDefaulted also emitted NFTClaimed when the lender took the nft
+            CredentiaEvent::NFTClaimed(e) => {
+                tx.execute(
+                    "UPDATE loans SET closed_at = ?3,
+                     status = CASE WHEN status = 'repaid' THEN 'repaid' ELSE 'defaulted' END
+                     WHERE platform = ?1 AND loan_id = ?2",
+                    params![platform, to_sql(e.loan_id), e.timestamp],
+                )?;
+            }
+            CredentiaEvent::LoanDefaulted(e) => {
+                tx.execute(
+                    "UPDATE loans SET status = 'defaulted', closed_at = ?3
+                     WHERE platform = ?1 AND loan_id = ?2",
+                    params![platform, to_sql(e.loan_id), e.timestamp],
+                )?;
+            }
+            CredentiaEvent::LoanCancelled(e) => {
+                tx.execute(
+                    "UPDATE loans SET status = 'cancelled', closed_at = ?3
+                     WHERE platform = ?1 AND loan_id = ?2",
+                    params![platform, to_sql(e.loan_id), e.timestamp],
+                )?;
+            }
+            CredentiaEvent::LoanRequestExpired(e) => {
+                tx.execute(
+                    "UPDATE loans SET status = 'expired', closed_at = ?3
+                     WHERE platform = ?1 AND loan_id = ?2",
+                    params![platform, to_sql(e.loan_id), e.timestamp],
+                )?;
+            }
+            _ => {}
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    pub fn set_collection(&self, nft_mint: &Pubkey, collection: &Pubkey) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO nft_collections (nft_mint, collection) VALUES (?1, ?2)",
+            params![nft_mint.to_string(), collection.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Indexed nft mints whose collection is not known yet.
+    pub fn mints_without_collection(&self) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT l.nft_mint FROM loans l
+             LEFT JOIN nft_collections c ON c.nft_mint = l.nft_mint
+             WHERE l.platform = ?1 AND c.collection IS NULL",
+        )?;
+        let rows = stmt.query_map(params![self.platform.to_string()], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    pub fn cursor(&self) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT last_signature FROM cursors WHERE platform = ?1",
+                params![self.platform.to_string()],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn set_cursor(&self, signature: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO cursors (platform, last_signature) VALUES (?1, ?2)",
+            params![self.platform.to_string(), signature],
+        )?;
+        Ok(())
+    }
+
+    fn loan_from_row(row: &rusqlite::Row) -> rusqlite::Result<LoanRecord> {
+        Ok(LoanRecord {
+            loan_id: row.get::<_, i64>("loan_id")? as u64,
+            address: row.get("address")?,
+            borrower: row.get("borrower")?,
+            nft_mint: row.get("nft_mint")?,
+            lender: row.get("lender")?,
+            loan_amount: row.get::<_, i64>("loan_amount")? as u64,
+            duration: row.get("duration")?,
+            interest_rate: row.get("interest_rate")?,
+            status: row.get("status")?,
+            requested_at: row.get("requested_at")?,
+            funded_at: row.get("funded_at")?,
+            repaid_amount: row.get::<_, Option<i64>>("repaid_amount")?.map(|v| v as u64),
+            platform_fee: row.get::<_, Option<i64>>("platform_fee")?.map(|v| v as u64),
+            repaid_at: row.get("repaid_at")?,
+            closed_at: row.get("closed_at")?,
+        })
+    }
+
+    pub fn loan(&self, loan_id: u64) -> rusqlite::Result<Option<LoanRecord>> {
+        self.conn
+            .query_row(
+                "SELECT * FROM loans WHERE platform = ?1 AND loan_id = ?2",
+                params![self.platform.to_string(), to_sql(loan_id)],
+                Self::loan_from_row,
+            )
+            .optional()
+    }
+
+    pub fn loans_by_borrower(&self, borrower: &Pubkey) -> rusqlite::Result<Vec<LoanRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM loans WHERE platform = ?1 AND borrower = ?2 ORDER BY loan_id",
+        )?;
+        let rows = stmt.query_map(
+            params![self.platform.to_string(), borrower.to_string()],
+            Self::loan_from_row,
+        )?;
+        rows.collect()
+    }
+
+    /// Names of the events seen for a loan, oldest first.
+    pub fn loan_events(&self, loan_id: u64) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name FROM events WHERE platform = ?1 AND loan_id = ?2 ORDER BY slot, signature, idx",
+        )?;
+        let rows = stmt.query_map(
+            params![self.platform.to_string(), to_sql(loan_id)],
+            |row| row.get(0),
+        )?;
+        rows.collect()
+    }
+
+    /// Lending volume of loans funded in `[from, to)`; `None` leaves a side open.
+    pub fn volume(&self, from: Option<i64>, to: Option<i64>) -> rusqlite::Result<VolumeStats> {
+        self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(loan_amount), 0),
+                    COALESCE(SUM(repaid_amount), 0), COALESCE(SUM(platform_fee), 0)
+             FROM loans
+             WHERE platform = ?1 AND funded_at IS NOT NULL
+               AND (?2 IS NULL OR funded_at >= ?2) AND (?3 IS NULL OR funded_at < ?3)",
+            params![self.platform.to_string(), from, to],
+            |row| {
+                Ok(VolumeStats {
+                    loans_funded: row.get::<_, i64>(0)? as u64,
+                    principal_funded: row.get::<_, i64>(1)? as u64,
+                    total_repaid: row.get::<_, i64>(2)? as u64,
+                    platform_fees: row.get::<_, i64>(3)? as u64,
+                })
+            },
+        )
+    }
+
+    /// Share of settled loans (repaid or defaulted) that defaulted, 0 when none settled.
+    pub fn default_rate(&self) -> rusqlite::Result<f64> {
+        self.conn.query_row(
+            "SELECT COALESCE(
+                 CAST(SUM(status = 'defaulted') AS REAL) / NULLIF(SUM(status IN ('repaid', 'defaulted')), 0),
+                 0.0)
+             FROM loans WHERE platform = ?1",
+            params![self.platform.to_string()],
+            |row| row.get(0),
+        )
+    }
+
+    /// Per-collection stats over funded loans whose collection is known.
+    pub fn collection_stats(&self) -> rusqlite::Result<Vec<CollectionStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.collection, COUNT(*), COALESCE(SUM(l.loan_amount), 0),
+                    SUM(l.status = 'repaid'), SUM(l.status = 'defaulted'), AVG(l.interest_rate)
+             FROM loans l JOIN nft_collections c ON c.nft_mint = l.nft_mint
+             WHERE l.platform = ?1 AND l.funded_at IS NOT NULL
+             GROUP BY c.collection ORDER BY c.collection",
+        )?;
+        let rows = stmt.query_map(params![self.platform.to_string()], |row| {
+            let repaid = row.get::<_, i64>(3)? as u64;
+            let defaulted = row.get::<_, i64>(4)? as u64;
+            let settled = repaid + defaulted;
+            Ok(CollectionStats {
+                collection: row.get(0)?,
+                loans: row.get::<_, i64>(1)? as u64,
+                principal_funded: row.get::<_, i64>(2)? as u64,
+                repaid,
+                defaulted,
+                default_rate: if settled == 0 {
+                    0.0
+                } else {
+                    defaulted as f64 / settled as f64
+                },
+                avg_interest_rate: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
@@ -0,0 +1,224 @@
+//! Decodes Credentia events out of `emit_cpi!` self invocations, and out of the
+//! log messages of transactions that `emit!`ed them in the same layouts. The
+//! originally deployed program logged events without loan ids (`LoanFunded` has
+//! no loan key at all), they are not decoded.
+
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::Pubkey, AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use credentia::events::{
+    AccountMigrated, CollateralFlashUsed, CollateralReturned, CollateralValued, CounterOfferAccepted, CounterOfferClosed, CounterOfferMade,
+    DefaultProtectionPurchased, GovernanceProgramRemoved, GovernanceProgramSet,
+    GovernanceVoteRelayed, InsuranceClaimed, InsuranceConfigured, InsuranceFeeCredited, LegacyLoanMigrated, LoanInsured,
+    LoanTermsUpdated, LoanCancelled, LoanDefaulted, LoanRequestExpired, LoanFunded, LoanRepaid,
+    LoanRequested, NFTClaimed, PlatformFeeSplit, PlatformInitialized, PlatformUpdated,
+    PoolCollateralLiquidated, PoolCollectionRemoved, PoolCollectionSet, PoolCreated,
+    PoolDeposited, PoolUpdated, PoolWithdrawn, ReferralFeesClaimed, ReferrerRegistered,
+};
+use credentia_client::rpc::InnerInstruction;
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+pub enum CredentiaEvent {
+    PlatformInitialized(PlatformInitialized),
+    PlatformUpdated(PlatformUpdated),
+    LoanRequested(LoanRequested),
+    LoanFunded(LoanFunded),
+    LoanRepaid(LoanRepaid),
+    NFTClaimed(NFTClaimed),
+    LoanDefaulted(LoanDefaulted),
+    LoanCancelled(LoanCancelled),
+    LoanRequestExpired(LoanRequestExpired),
+    LegacyLoanMigrated(LegacyLoanMigrated),
+    PlatformFeeSplit(PlatformFeeSplit),
+    ReferrerRegistered(ReferrerRegistered),
+    ReferralFeesClaimed(ReferralFeesClaimed),
+    PoolCreated(PoolCreated),
+    PoolUpdated(PoolUpdated),
+    PoolCollectionSet(PoolCollectionSet),
+    PoolCollectionRemoved(PoolCollectionRemoved),
+    PoolDeposited(PoolDeposited),
+    PoolWithdrawn(PoolWithdrawn),
+    PoolCollateralLiquidated(PoolCollateralLiquidated),
+    AccountMigrated(AccountMigrated),
+    CounterOfferMade(CounterOfferMade),
+    CounterOfferAccepted(CounterOfferAccepted),
+    CounterOfferClosed(CounterOfferClosed),
+    LoanTermsUpdated(LoanTermsUpdated),
+    InsuranceConfigured(InsuranceConfigured),
+    CollateralValued(CollateralValued),
+    LoanInsured(LoanInsured),
+    InsuranceFeeCredited(InsuranceFeeCredited),
+    InsuranceClaimed(InsuranceClaimed),
+    DefaultProtectionPurchased(DefaultProtectionPurchased),
+    CollateralFlashUsed(CollateralFlashUsed),
+    CollateralReturned(CollateralReturned),
+    GovernanceProgramSet(GovernanceProgramSet),
+    GovernanceProgramRemoved(GovernanceProgramRemoved),
+    GovernanceVoteRelayed(GovernanceVoteRelayed),
+}
+
+impl CredentiaEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CredentiaEvent::PlatformInitialized(_) => "PlatformInitialized",
+            CredentiaEvent::PlatformUpdated(_) => "PlatformUpdated",
+            CredentiaEvent::LoanRequested(_) => "LoanRequested",
+            CredentiaEvent::LoanFunded(_) => "LoanFunded",
+            CredentiaEvent::LoanRepaid(_) => "LoanRepaid",
+            CredentiaEvent::NFTClaimed(_) => "NFTClaimed",
+            CredentiaEvent::LoanDefaulted(_) => "LoanDefaulted",
+            CredentiaEvent::LoanCancelled(_) => "LoanCancelled",
+            CredentiaEvent::LoanRequestExpired(_) => "LoanRequestExpired",
+            CredentiaEvent::LegacyLoanMigrated(_) => "LegacyLoanMigrated",
+            CredentiaEvent::PlatformFeeSplit(_) => "PlatformFeeSplit",
+            CredentiaEvent::ReferrerRegistered(_) => "ReferrerRegistered",
+            CredentiaEvent::ReferralFeesClaimed(_) => "ReferralFeesClaimed",
+            CredentiaEvent::PoolCreated(_) => "PoolCreated",
+            CredentiaEvent::PoolUpdated(_) => "PoolUpdated",
+            CredentiaEvent::PoolCollectionSet(_) => "PoolCollectionSet",
+            CredentiaEvent::PoolCollectionRemoved(_) => "PoolCollectionRemoved",
+            CredentiaEvent::PoolDeposited(_) => "PoolDeposited",
+            CredentiaEvent::PoolWithdrawn(_) => "PoolWithdrawn",
+            CredentiaEvent::PoolCollateralLiquidated(_) => "PoolCollateralLiquidated",
+            CredentiaEvent::AccountMigrated(_) => "AccountMigrated",
+            CredentiaEvent::CounterOfferMade(_) => "CounterOfferMade",
+            CredentiaEvent::CounterOfferAccepted(_) => "CounterOfferAccepted",
+            CredentiaEvent::CounterOfferClosed(_) => "CounterOfferClosed",
+            CredentiaEvent::LoanTermsUpdated(_) => "LoanTermsUpdated",
+            CredentiaEvent::InsuranceConfigured(_) => "InsuranceConfigured",
+            CredentiaEvent::CollateralValued(_) => "CollateralValued",
+            CredentiaEvent::LoanInsured(_) => "LoanInsured",
+            CredentiaEvent::InsuranceFeeCredited(_) => "InsuranceFeeCredited",
+            CredentiaEvent::InsuranceClaimed(_) => "InsuranceClaimed",
+            CredentiaEvent::DefaultProtectionPurchased(_) => "DefaultProtectionPurchased",
+            CredentiaEvent::CollateralFlashUsed(_) => "CollateralFlashUsed",
+            CredentiaEvent::CollateralReturned(_) => "CollateralReturned",
+            CredentiaEvent::GovernanceProgramSet(_) => "GovernanceProgramSet",
+            CredentiaEvent::GovernanceProgramRemoved(_) => "GovernanceProgramRemoved",
+            CredentiaEvent::GovernanceVoteRelayed(_) => "GovernanceVoteRelayed",
+        }
+    }
+
+    /// Platform-scoped loan id the event refers to, if any.
+    pub fn loan_id(&self) -> Option<u64> {
+        match self {
+            CredentiaEvent::LoanRequested(e) => Some(e.loan_id),
+            CredentiaEvent::LoanFunded(e) => Some(e.loan_id),
+            CredentiaEvent::LoanRepaid(e) => Some(e.loan_id),
+            CredentiaEvent::NFTClaimed(e) => Some(e.loan_id),
+            CredentiaEvent::LoanDefaulted(e) => Some(e.loan_id),
+            CredentiaEvent::LoanCancelled(e) => Some(e.loan_id),
+            CredentiaEvent::LoanRequestExpired(e) => Some(e.loan_id),
+            CredentiaEvent::LegacyLoanMigrated(e) => Some(e.loan_id),
+            CredentiaEvent::PlatformFeeSplit(e) => Some(e.loan_id),
+            CredentiaEvent::CounterOfferMade(e) => Some(e.loan_id),
+            CredentiaEvent::CounterOfferAccepted(e) => Some(e.loan_id),
+            CredentiaEvent::CounterOfferClosed(e) => Some(e.loan_id),
+            CredentiaEvent::LoanTermsUpdated(e) => Some(e.loan_id),
+            CredentiaEvent::LoanInsured(e) => Some(e.loan_id),
+            CredentiaEvent::InsuranceFeeCredited(e) => Some(e.loan_id),
+            CredentiaEvent::InsuranceClaimed(e) => Some(e.loan_id),
+            CredentiaEvent::PoolCollateralLiquidated(e) => Some(e.loan_id),
+            CredentiaEvent::DefaultProtectionPurchased(e) => Some(e.loan_id),
+            CredentiaEvent::CollateralFlashUsed(e) => Some(e.loan_id),
+            CredentiaEvent::CollateralReturned(e) => Some(e.loan_id),
+            CredentiaEvent::GovernanceVoteRelayed(e) => Some(e.loan_id),
+            _ => None,
+        }
+    }
+}
+
+fn decode<T: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<T> {
+    data.strip_prefix(T::DISCRIMINATOR)
+        .and_then(|mut body| T::deserialize(&mut body).ok())
+}
+
+/// Decodes one event payload (discriminator followed by the borsh encoded event).
+pub fn decode_event(data: &[u8]) -> Option<CredentiaEvent> {
+    use CredentiaEvent::*;
+    None.or_else(|| decode(data).map(PlatformInitialized))
+        .or_else(|| decode(data).map(PlatformUpdated))
+        .or_else(|| decode(data).map(LoanRequested))
+        .or_else(|| decode(data).map(LoanFunded))
+        .or_else(|| decode(data).map(LoanRepaid))
+        .or_else(|| decode(data).map(NFTClaimed))
+        .or_else(|| decode(data).map(LoanDefaulted))
+        .or_else(|| decode(data).map(LoanCancelled))
+        .or_else(|| decode(data).map(LoanRequestExpired))
+        .or_else(|| decode(data).map(LegacyLoanMigrated))
+        .or_else(|| decode(data).map(PlatformFeeSplit))
+        .or_else(|| decode(data).map(ReferrerRegistered))
+        .or_else(|| decode(data).map(ReferralFeesClaimed))
+        .or_else(|| decode(data).map(PoolCreated))
+        .or_else(|| decode(data).map(PoolUpdated))
+        .or_else(|| decode(data).map(PoolCollectionSet))
+        .or_else(|| decode(data).map(PoolCollectionRemoved))
+        .or_else(|| decode(data).map(PoolDeposited))
+        .or_else(|| decode(data).map(PoolWithdrawn))
+        .or_else(|| decode(data).map(PoolCollateralLiquidated))
+        .or_else(|| decode(data).map(AccountMigrated))
+        .or_else(|| decode(data).map(CounterOfferMade))
+        .or_else(|| decode(data).map(CounterOfferAccepted))
+        .or_else(|| decode(data).map(CounterOfferClosed))
+        .or_else(|| decode(data).map(LoanTermsUpdated))
+        .or_else(|| decode(data).map(InsuranceConfigured))
+        .or_else(|| decode(data).map(CollateralValued))
+        .or_else(|| decode(data).map(LoanInsured))
+        .or_else(|| decode(data).map(InsuranceFeeCredited))
+        .or_else(|| decode(data).map(InsuranceClaimed))
+        .or_else(|| decode(data).map(DefaultProtectionPurchased))
+        .or_else(|| decode(data).map(CollateralFlashUsed))
+        .or_else(|| decode(data).map(CollateralReturned))
+        .or_else(|| decode(data).map(GovernanceProgramSet))
+        .or_else(|| decode(data).map(GovernanceProgramRemoved))
+        .or_else(|| decode(data).map(GovernanceVoteRelayed))
+}
+
+/// Events `program_id` emitted through `emit_cpi!`, in emission order. Only the
+/// program itself can sign as its event authority, so a self invocation carrying
+/// the event tag can't be forged by another program.
+pub fn parse_cpi_events(program_id: &Pubkey, instructions: &[InnerInstruction]) -> Vec<CredentiaEvent> {
+    instructions
+        .iter()
+        .filter(|ix| ix.program_id == *program_id)
+        .filter_map(|ix| ix.data.strip_prefix(EVENT_IX_TAG_LE))
+        .filter_map(decode_event)
+        .collect()
+}
+
+/// Events `emit!`ed by `program_id` in a transaction's logs, in emission order.
+/// `Program data:` lines are attributed to the program on top of the invoke
+/// stack, so data logged by other programs (or CPIs into them) is ignored.
+pub fn parse_logs(program_id: &Pubkey, logs: &[String]) -> Vec<CredentiaEvent> {
+    let program = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        if let Some(data) = line.strip_prefix(PROGRAM_DATA) {
+            if stack.last() == Some(&program.as_str()) {
+                let event = STANDARD
+                    .decode(data)
+                    .ok()
+                    .and_then(|d| decode_event(&d));
+                if let Some(event) = event {
+                    events.push(event);
+                }
+            }
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        if words.next() != Some("Program") {
+            continue;
+        }
+        match (words.next(), words.next()) {
+            (Some(id), Some("invoke")) => stack.push(id),
+            (Some(_), Some("success")) | (Some(_), Some("failed:")) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    events
+}
@@ -0,0 +1,14 @@
+//! Builds a loan history database from the events Credentia emits.
+//!
+//! Loan accounts are closed at the end of their life, so the event log is the
+//! only complete record. The indexer follows one platform: every loan
+//! instruction touches the platform account, so walking its signatures finds
+//! every event, and loan ids are unique within it.
+
+pub mod db;
+pub mod events;
+pub mod sync;
+
+pub use db::{CollectionStats, Database, LoanRecord, VolumeStats};
+pub use events::{decode_event, parse_cpi_events, parse_logs, CredentiaEvent};
+pub use sync::{ingest, Indexer};
@@ -0,0 +1,91 @@
+//! `credentia-indexer`: sync a platform's events into SQLite and query the history.
+
+use std::{thread, time::Duration};
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use credentia_client::{pda, rpc::RpcClient};
+use credentia_indexer::{Database, Indexer};
+
+#[derive(Parser)]
+#[command(name = "credentia-indexer", version, about)]
+struct Cli {
+    #[arg(long, short = 'u', env = "CREDENTIA_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// SQLite database file
+    #[arg(long, env = "CREDENTIA_INDEXER_DB", default_value = "credentia.db")]
+    db: String,
+    #[arg(long, default_value_t = 1)]
+    platform_id: u64,
+    /// Platform address, overrides --platform-id
+    #[arg(long)]
+    platform: Option<Pubkey>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index new transactions, optionally polling forever
+    Sync {
+        #[arg(long)]
+        follow: bool,
+        /// seconds between polls when following
+        #[arg(long, default_value_t = 10)]
+        interval: u64,
+    },
+    /// Print volume, default rate and per-collection stats
+    Stats,
+    /// Print the indexed history of one loan
+    Loan { loan_id: u64 },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let platform = cli
+        .platform
+        .unwrap_or_else(|| pda::platform(cli.platform_id).0);
+    let db = Database::open(&cli.db, platform)?;
+
+    match cli.command {
+        Command::Sync { follow, interval } => {
+            let mut indexer = Indexer::new(db, RpcClient::new(&cli.url));
+            loop {
+                let applied = indexer.sync()?;
+                println!("indexed {applied} new events");
+                if !follow {
+                    return Ok(());
+                }
+                thread::sleep(Duration::from_secs(interval));
+            }
+        }
+        Command::Stats => {
+            let volume = db.volume(None, None)?;
+            println!("loans funded      {}", volume.loans_funded);
+            println!("principal funded  {}", volume.principal_funded);
+            println!("total repaid      {}", volume.total_repaid);
+            println!("platform fees     {}", volume.platform_fees);
+            println!("default rate      {:.2}%", db.default_rate()? * 100.0);
+            for c in db.collection_stats()? {
+                println!(
+                    "{}  loans {}  principal {}  defaults {:.2}%  avg rate {:.0} bps",
+                    c.collection,
+                    c.loans,
+                    c.principal_funded,
+                    c.default_rate * 100.0,
+                    c.avg_interest_rate
+                );
+            }
+            Ok(())
+        }
+        Command::Loan { loan_id } => {
+            let loan = db
+                .loan(loan_id)?
+                .ok_or_else(|| anyhow!("loan {loan_id} is not indexed"))?;
+            println!("{loan:#?}");
+            println!("events: {}", db.loan_events(loan_id)?.join(" -> "));
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Feeds transactions into the database, from recorded logs or a live RPC node.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::metadata::mpl_token_metadata::accounts::Metadata;
+use credentia_client::{
+    pda,
+    rpc::{RpcClient, RpcResult, TransactionLogs},
+    PROGRAM_ID,
+};
+
+use crate::{
+    db::EventSource,
+    events::{parse_cpi_events, parse_logs},
+    Database,
+};
+
+/// Applies the Credentia events of one transaction, returning how many were new.
+/// Failed transactions are skipped, their state changes never landed. Events come
+/// from the program's `emit_cpi!` self invocations, or from its logs when a
+/// transaction has none.
+pub fn ingest(db: &mut Database, tx: &TransactionLogs) -> rusqlite::Result<usize> {
+    if tx.failed {
+        return Ok(0);
+    }
+    let mut events = parse_cpi_events(&PROGRAM_ID, &tx.inner_instructions);
+    if events.is_empty() {
+        events = parse_logs(&PROGRAM_ID, &tx.logs);
+    }
+    let mut applied = 0;
+    for (index, event) in events.iter().enumerate() {
+        let source = EventSource {
+            signature: &tx.signature,
+            index,
+            slot: tx.slot,
+            block_time: tx.block_time,
+        };
+        if db.apply(source, event)? {
+            applied += 1;
+        }
+    }
+    Ok(applied)
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    Rpc(credentia_client::rpc::RpcError),
+    Db(rusqlite::Error),
+}
+
+impl std::fmt::Display for SyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncError::Rpc(e) => e.fmt(f),
+            SyncError::Db(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+impl From<credentia_client::rpc::RpcError> for SyncError {
+    fn from(e: credentia_client::rpc::RpcError) -> Self {
+        SyncError::Rpc(e)
+    }
+}
+
+impl From<rusqlite::Error> for SyncError {
+    fn from(e: rusqlite::Error) -> Self {
+        SyncError::Db(e)
+    }
+}
+
+pub struct Indexer {
+    pub db: Database,
+    rpc: RpcClient,
+    page_size: usize,
+}
+
+impl Indexer {
+    pub fn new(db: Database, rpc: RpcClient) -> Self {
+        Self {
+            db,
+            rpc,
+            page_size: 1000,
+        }
+    }
+
+    /// Pulls every platform transaction newer than the stored cursor, oldest
+    /// first, and returns the number of new events applied.
+    pub fn sync(&mut self) -> Result<usize, SyncError> {
+        let platform = *self.db.platform();
+        let until = self.db.cursor()?;
+
+        //signatures come newest first, page back until the cursor
+        let mut pending = Vec::new();
+        let mut before: Option<String> = None;
+        loop {
+            let page = self.rpc.get_signatures_for_address(
+                &platform,
+                before.as_deref(),
+                until.as_deref(),
+                self.page_size,
+            )?;
+            let done = page.len() < self.page_size;
+            before = page.last().map(|s| s.signature.clone());
+            pending.extend(page);
+            if done {
+                break;
+            }
+        }
+
+        let mut applied = 0;
+        for info in pending.iter().rev() {
+            if info.err.is_none() {
+                if let Some(tx) = self.rpc.get_transaction_logs(&info.signature)? {
+                    applied += ingest(&mut self.db, &tx)?;
+                }
+            }
+            self.db.set_cursor(&info.signature)?;
+        }
+        self.resolve_collections()?;
+        Ok(applied)
+    }
+
+    /// Looks up the verified collection of indexed nfts from their metadata accounts.
+    pub fn resolve_collections(&mut self) -> RpcResult<()> {
+        let mints = self.db.mints_without_collection().unwrap_or_default();
+        for mint in mints {
+            let Ok(mint) = mint.parse::<Pubkey>() else {
+                continue;
+            };
+            let Some(data) = self.rpc.get_account_data(&pda::metadata(&mint))? else {
+                continue;
+            };
+            let collection = Metadata::safe_deserialize(&data)
+                .ok()
+                .and_then(|m| m.collection)
+                .filter(|c| c.verified);
+            if let Some(collection) = collection {
+                //a failed write is retried on the next sync
+                let _ = self.db.set_collection(&mint, &collection.key);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,441 @@
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::Pubkey, Event};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use credentia::events::{
+    CounterOfferAccepted, CounterOfferMade, LoanCancelled, LoanRequestExpired, LoanTermsUpdated, LoanDefaulted, LoanFunded, LoanRepaid, LoanRequested, NFTClaimed,
+    PoolCollateralLiquidated,
+};
+use credentia_client::{
+    pda,
+    rpc::{InnerInstruction, TransactionLogs},
+    PROGRAM_ID,
+};
+use credentia_indexer::{ingest, parse_cpi_events, parse_logs, Database};
+
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+fn data_line(event: &impl Event) -> String {
+    format!("Program data: {}", STANDARD.encode(event.data()))
+}
+
+/// The self invocation `emit_cpi!` makes for an event.
+fn cpi(event: &impl Event) -> InnerInstruction {
+    InnerInstruction {
+        program_id: PROGRAM_ID,
+        data: [EVENT_IX_TAG_LE, &event.data()].concat(),
+    }
+}
+
+fn token_transfer() -> InnerInstruction {
+    InnerInstruction {
+        program_id: TOKEN_PROGRAM.parse().unwrap(),
+        data: vec![12, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+    }
+}
+
+/// A credentia transaction emitting `events` through self invocations, after a token CPI.
+fn recorded(signature: &str, slot: u64, events: &[InnerInstruction]) -> TransactionLogs {
+    let mut tx = logged(signature, slot, &[]);
+    tx.inner_instructions.push(token_transfer());
+    tx.inner_instructions.extend(events.iter().cloned());
+    tx
+}
+
+/// Logs shaped like a credentia transaction from before events moved to self
+/// invocations, with a token CPI in the middle.
+fn logged(signature: &str, slot: u64, events: &[String]) -> TransactionLogs {
+    let mut logs = vec![
+        format!("Program {PROGRAM_ID} invoke [1]"),
+        "Program log: Instruction: ResolveLoan".to_string(),
+        format!("Program {TOKEN_PROGRAM} invoke [2]"),
+        "Program log: Instruction: TransferChecked".to_string(),
+        format!("Program {TOKEN_PROGRAM} consumed 6200 of 180000 compute units"),
+        format!("Program {TOKEN_PROGRAM} success"),
+    ];
+    logs.extend(events.iter().cloned());
+    logs.push(format!("Program {PROGRAM_ID} consumed 40000 of 200000 compute units"));
+    logs.push(format!("Program {PROGRAM_ID} success"));
+    TransactionLogs {
+        signature: signature.to_string(),
+        slot,
+        block_time: Some(slot as i64),
+        failed: false,
+        logs,
+        inner_instructions: Vec::new(),
+    }
+}
+
+struct Fixture {
+    platform: Pubkey,
+    borrower: Pubkey,
+    lender: Pubkey,
+    mints: [Pubkey; 3],
+}
+
+fn fixture() -> Fixture {
+    Fixture {
+        platform: pda::platform(1).0,
+        borrower: Pubkey::new_unique(),
+        lender: Pubkey::new_unique(),
+        mints: [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()],
+    }
+}
+
+fn loan(f: &Fixture, loan_id: u64) -> Pubkey {
+    pda::loan(&f.mints[loan_id as usize], &f.platform, loan_id).0
+}
+
+fn requested(f: &Fixture, loan_id: u64, amount: u64) -> LoanRequested {
+    LoanRequested {
+        loan_id,
+        loan: loan(f, loan_id),
+        borrower: f.borrower,
+        nft_mint: f.mints[loan_id as usize],
+        loan_amount: amount,
+        duration: 3600,
+        interest_rate: 100,
+        timestamp: 10,
+    }
+}
+
+fn funded(f: &Fixture, loan_id: u64, amount: u64) -> LoanFunded {
+    LoanFunded {
+        loan_id,
+        loan: loan(f, loan_id),
+        borrower: f.borrower,
+        lender: f.lender,
+        loan_amount: amount,
+        interest_rate: 100,
+        funded_at: 20,
+    }
+}
+
+fn claimed(f: &Fixture, loan_id: u64, at: i64) -> NFTClaimed {
+    NFTClaimed {
+        loan_id,
+        loan: loan(f, loan_id),
+        borrower: f.borrower,
+        nft_mint: f.mints[loan_id as usize],
+        timestamp: at,
+    }
+}
+
+fn defaulted(f: &Fixture, loan_id: u64, at: i64) -> LoanDefaulted {
+    LoanDefaulted {
+        loan_id,
+        loan: loan(f, loan_id),
+        borrower: f.borrower,
+        lender: f.lender,
+        nft_mint: f.mints[loan_id as usize],
+        caller: Pubkey::new_unique(),
+        timestamp: at,
+    }
+}
+
+fn history(f: &Fixture) -> Vec<TransactionLogs> {
+    vec![
+        recorded("request-0", 1, &[cpi(&requested(f, 0, 1_000))]),
+        recorded("fund-0", 2, &[cpi(&funded(f, 0, 1_000))]),
+        recorded(
+            "repay-0",
+            3,
+            &[
+                cpi(&LoanRepaid {
+                    loan_id: 0,
+                    loan: loan(f, 0),
+                    borrower: f.borrower,
+                    lender: f.lender,
+                    repaid_amount: 1_095,
+                    fee_for_platform: 5,
+                    timestamp: 30,
+                }),
+                cpi(&claimed(f, 0, 30)),
+            ],
+        ),
+        recorded("request-1", 4, &[cpi(&requested(f, 1, 3_000))]),
+        recorded("fund-1", 5, &[cpi(&funded(f, 1, 3_000))]),
+        recorded("default-1", 6, &[cpi(&defaulted(f, 1, 99))]),
+        recorded("request-2", 7, &[cpi(&requested(f, 2, 500))]),
+        recorded(
+            "cancel-2",
+            8,
+            &[cpi(&LoanCancelled {
+                loan_id: 2,
+                loan: loan(f, 2),
+                borrower: f.borrower,
+                nft_mint: f.mints[2],
+                platform: f.platform,
+                timestamp: 40,
+            })],
+        ),
+    ]
+}
+
+fn indexed(f: &Fixture) -> Database {
+    let mut db = Database::in_memory(f.platform).unwrap();
+    for tx in history(f) {
+        ingest(&mut db, &tx).unwrap();
+    }
+    db
+}
+
+#[test]
+fn reconstructs_loan_lifecycles() {
+    let f = fixture();
+    let db = indexed(&f);
+
+    let repaid = db.loan(0).unwrap().unwrap();
+    assert_eq!(repaid.status, "repaid");
+    assert_eq!(repaid.address, pda::loan(&f.mints[0], &f.platform, 0).0.to_string());
+    assert_eq!(repaid.lender, Some(f.lender.to_string()));
+    assert_eq!(repaid.repaid_amount, Some(1_095));
+    assert_eq!(repaid.platform_fee, Some(5));
+    assert_eq!(repaid.closed_at, Some(30));
+    assert_eq!(
+        db.loan_events(0).unwrap(),
+        ["LoanRequested", "LoanFunded", "LoanRepaid", "NFTClaimed"]
+    );
+
+    let defaulted = db.loan(1).unwrap().unwrap();
+    assert_eq!(defaulted.status, "defaulted");
+    assert_eq!(defaulted.repaid_amount, None);
+    assert_eq!(defaulted.closed_at, Some(99));
+
+    let cancelled = db.loan(2).unwrap().unwrap();
+    assert_eq!(cancelled.status, "cancelled");
+    assert_eq!(cancelled.funded_at, None);
+
+    assert_eq!(db.loans_by_borrower(&f.borrower).unwrap().len(), 3);
+}
+
+#[test]
+fn expired_request_is_not_a_cancellation() {
+    let f = fixture();
+    let mut db = Database::in_memory(f.platform).unwrap();
+    let txs = [
+        recorded("request-0", 1, &[cpi(&requested(&f, 0, 1_000))]),
+        recorded(
+            "expire-0",
+            2,
+            &[cpi(&LoanRequestExpired {
+                loan_id: 0,
+                loan: loan(&f, 0),
+                borrower: f.borrower,
+                closed_by: Pubkey::new_unique(),
+                timestamp: 50,
+            })],
+        ),
+    ];
+    for tx in &txs {
+        ingest(&mut db, tx).unwrap();
+    }
+
+    let expired = db.loan(0).unwrap().unwrap();
+    assert_eq!(expired.status, "expired");
+    assert_eq!(expired.closed_at, Some(50));
+    assert_eq!(db.loan_events(0).unwrap(), ["LoanRequested", "LoanRequestExpired"]);
+}
+
+#[test]
+fn accepted_counter_offer_rewrites_terms() {
+    let f = fixture();
+    let mut db = Database::in_memory(f.platform).unwrap();
+    let counter_offer = Pubkey::new_unique();
+    let made = CounterOfferMade {
+        loan_id: 0,
+        loan: loan(&f, 0),
+        borrower: f.borrower,
+        counter_offer,
+        lender: f.lender,
+        amount: 800,
+        duration: 7200,
+        interest_rate: 250,
+        timestamp: 15,
+    };
+    let accepted = CounterOfferAccepted {
+        loan_id: 0,
+        loan: loan(&f, 0),
+        borrower: f.borrower,
+        counter_offer,
+        lender: f.lender,
+        amount: 800,
+        duration: 7200,
+        interest_rate: 250,
+        timestamp: 20,
+    };
+    let txs = [
+        recorded("request-0", 1, &[cpi(&requested(&f, 0, 1_000))]),
+        recorded("offer-0", 2, &[cpi(&made)]),
+        recorded(
+            "accept-0",
+            3,
+            &[
+                cpi(&accepted),
+                cpi(&LoanFunded {
+                    interest_rate: 250,
+                    ..funded(&f, 0, 800)
+                }),
+            ],
+        ),
+    ];
+    for tx in &txs {
+        ingest(&mut db, tx).unwrap();
+    }
+
+    let loan = db.loan(0).unwrap().unwrap();
+    assert_eq!(loan.status, "funded");
+    assert_eq!(loan.loan_amount, 800);
+    assert_eq!(loan.duration, 7200);
+    assert_eq!(loan.interest_rate, 250);
+    assert_eq!(
+        db.loan_events(0).unwrap(),
+        ["LoanRequested", "CounterOfferMade", "CounterOfferAccepted", "LoanFunded"]
+    );
+}
+
+#[test]
+fn updated_request_terms_are_indexed() {
+    let f = fixture();
+    let mut db = Database::in_memory(f.platform).unwrap();
+    let updated = LoanTermsUpdated {
+        loan_id: 0,
+        loan: loan(&f, 0),
+        borrower: f.borrower,
+        loan_amount: 2_000,
+        duration: 600,
+        interest_rate: 50,
+        timestamp: 15,
+    };
+    ingest(&mut db, &recorded("request-0", 1, &[cpi(&requested(&f, 0, 1_000))])).unwrap();
+    ingest(&mut db, &recorded("update-0", 2, &[cpi(&updated)])).unwrap();
+
+    let loan = db.loan(0).unwrap().unwrap();
+    assert_eq!(loan.status, "requested");
+    assert_eq!(loan.loan_amount, 2_000);
+    assert_eq!(loan.duration, 600);
+    assert_eq!(loan.interest_rate, 50);
+}
+
+#[test]
+fn pool_funded_loans_take_the_funding_rate() {
+    let f = fixture();
+    let mut db = Database::in_memory(f.platform).unwrap();
+    //the pool prices the loan below the requested maximum of 100
+    let pool_funded = LoanFunded {
+        interest_rate: 60,
+        ..funded(&f, 0, 1_000)
+    };
+    ingest(&mut db, &recorded("request-0", 1, &[cpi(&requested(&f, 0, 1_000))])).unwrap();
+    ingest(&mut db, &recorded("pool-fund-0", 2, &[cpi(&pool_funded)])).unwrap();
+
+    let loan = db.loan(0).unwrap().unwrap();
+    assert_eq!(loan.status, "funded");
+    assert_eq!(loan.interest_rate, 60);
+
+    let collection = Pubkey::new_unique();
+    db.set_collection(&f.mints[0], &collection).unwrap();
+    assert_eq!(db.collection_stats().unwrap()[0].avg_interest_rate, 60.0);
+}
+
+#[test]
+fn pool_liquidations_are_recorded_against_the_loan() {
+    let f = fixture();
+    let mut db = indexed(&f);
+    let liquidated = PoolCollateralLiquidated {
+        loan_id: 1,
+        loan: loan(&f, 1),
+        borrower: f.borrower,
+        pool: Pubkey::new_unique(),
+        buyer: Pubkey::new_unique(),
+        nft_mint: f.mints[1],
+        price: 1_500,
+        timestamp: 200,
+    };
+    ingest(&mut db, &recorded("liquidate-1", 9, &[cpi(&liquidated)])).unwrap();
+
+    assert_eq!(db.loan_events(1).unwrap().last().unwrap(), "PoolCollateralLiquidated");
+}
+
+#[test]
+fn reingesting_is_idempotent() {
+    let f = fixture();
+    let mut db = indexed(&f);
+    for tx in history(&f) {
+        assert_eq!(ingest(&mut db, &tx).unwrap(), 0);
+    }
+    assert_eq!(db.loan(0).unwrap().unwrap().status, "repaid");
+}
+
+#[test]
+fn failed_transactions_are_skipped() {
+    let f = fixture();
+    let mut db = Database::in_memory(f.platform).unwrap();
+    let mut tx = recorded("failed", 1, &[cpi(&requested(&f, 0, 1_000))]);
+    tx.failed = true;
+    assert_eq!(ingest(&mut db, &tx).unwrap(), 0);
+    assert!(db.loan(0).unwrap().is_none());
+}
+
+#[test]
+fn ignores_data_logged_by_other_programs() {
+    let f = fixture();
+    let logs = vec![
+        format!("Program {PROGRAM_ID} invoke [1]"),
+        format!("Program {TOKEN_PROGRAM} invoke [2]"),
+        data_line(&claimed(&f, 0, 1)),
+        format!("Program {TOKEN_PROGRAM} success"),
+        data_line(&claimed(&f, 0, 2)),
+        format!("Program {PROGRAM_ID} success"),
+        "Program data: bm90IGFuIGV2ZW50".to_string(),
+    ];
+    let events = parse_logs(&PROGRAM_ID, &logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name(), "NFTClaimed");
+}
+
+#[test]
+fn ignores_event_tagged_instructions_of_other_programs() {
+    let f = fixture();
+    let mut forged = cpi(&defaulted(&f, 0, 1));
+    forged.program_id = Pubkey::new_unique();
+    let instructions = [
+        forged,
+        token_transfer(),
+        InnerInstruction {
+            program_id: PROGRAM_ID,
+            data: [EVENT_IX_TAG_LE, b"not an event"].concat(),
+        },
+        cpi(&claimed(&f, 0, 2)),
+    ];
+    let events = parse_cpi_events(&PROGRAM_ID, &instructions);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name(), "NFTClaimed");
+}
+
+#[test]
+fn volume_default_rate_and_collection_stats() {
+    let f = fixture();
+    let db = indexed(&f);
+
+    let volume = db.volume(None, None).unwrap();
+    assert_eq!(volume.loans_funded, 2);
+    assert_eq!(volume.principal_funded, 4_000);
+    assert_eq!(volume.total_repaid, 1_095);
+    assert_eq!(volume.platform_fees, 5);
+    assert_eq!(db.volume(Some(21), None).unwrap().loans_funded, 0);
+
+    assert_eq!(db.default_rate().unwrap(), 0.5);
+
+    let collection = Pubkey::new_unique();
+    for mint in &f.mints {
+        db.set_collection(mint, &collection).unwrap();
+    }
+    assert!(db.mints_without_collection().unwrap().is_empty());
+    let stats = db.collection_stats().unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].collection, collection.to_string());
+    assert_eq!(stats[0].loans, 2);
+    assert_eq!(stats[0].principal_funded, 4_000);
+    assert_eq!(stats[0].defaulted, 1);
+    assert_eq!(stats[0].default_rate, 0.5);
+    assert_eq!(stats[0].avg_interest_rate, 100.0);
+}
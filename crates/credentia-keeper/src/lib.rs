@@ -0,0 +1,175 @@
+//! Finds funded loans past their deadline and requests past their expiry, and
+//! settles them.
+//!
+//! Pool loans are settled with `pool_claim_default`, direct loans with
+//! `mark_defaulted` and expired requests with `close_expired_request`, the last
+//! two pay the keeper the loan's rent refunds. All are permissionless, the
+//! keeper never needs a lender's or borrower's key.
+//!
+//! Each settlement moves the nft out of its vault, so instructions are built on
+//! the token program owning the nft's mint and carry the accounts a Token-2022
+//! transfer hook on it asks for.
+
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction};
+use credentia_client::{
+    hook::{transfer_hook_program, with_vault_transfer_hook},
+    instructions::{self, LoanKeys},
+    pda,
+    quote::repayment_deadline,
+    rpc::{RpcClient, RpcResult},
+    Loan, LoanStatus,
+};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Settlement {
+    /// anyone moves the nft into pool custody
+    PoolClaimDefault { pool: Pubkey },
+    /// the nft goes to the lender, the keeper keeps the rent refunds
+    MarkDefaulted { lender: Pubkey },
+    /// the nft goes back to the borrower, the keeper keeps the rent refunds
+    CloseExpiredRequest,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlannedSettlement {
+    pub loan: Pubkey,
+    pub loan_id: u64,
+    pub settlement: Settlement,
+    /// seconds past the repayment deadline, or the request's expiry
+    pub overdue_by: i64,
+}
+
+/// Settlements due at `now` for the loans of `platform`, oldest deadline first.
+pub fn plan(platform: &Pubkey, loans: &[(Pubkey, Loan)], now: i64) -> Vec<PlannedSettlement> {
+    let mut due: Vec<_> = loans
+        .iter()
+        .filter(|(address, loan)| pda::loan(&loan.nft_mint, platform, loan.loan_id).0 == *address)
+        .filter_map(|(address, loan)| {
+            //same bounds as the program: default is allowed once now - start >= the repayment window,
+            //closing a request once now >= expires_at
+            let (deadline, settlement) = match (&loan.status, loan.pool) {
+                (LoanStatus::Funded, Some(pool)) => {
+                    (repayment_deadline(loan)?, Settlement::PoolClaimDefault { pool })
+                }
+                (LoanStatus::Funded, None) => (
+                    repayment_deadline(loan)?,
+                    Settlement::MarkDefaulted {
+                        lender: loan.lender?,
+                    },
+                ),
+                (LoanStatus::Requested, _) => (loan.expires_at?, Settlement::CloseExpiredRequest),
+                _ => return None,
+            };
+            let overdue_by = now - deadline;
+            if overdue_by < 0 {
+                return None;
+            }
+            Some(PlannedSettlement {
+                loan: *address,
+                loan_id: loan.loan_id,
+                settlement,
+                overdue_by,
+            })
+        })
+        .collect();
+    due.sort_by_key(|p| std::cmp::Reverse(p.overdue_by));
+    due
+}
+
+/// Instruction carrying out `settlement` for `loan`, paid for by `payer`. `fetch` returns an
+/// account's owner and data, or `None` if it doesn't exist.
+pub fn settlement_instruction(
+    platform: &Pubkey,
+    payer: &Pubkey,
+    loan: &Loan,
+    settlement: Settlement,
+    fetch: impl Fn(&Pubkey) -> Option<(Pubkey, Vec<u8>)>,
+) -> Result<Instruction, String> {
+    let (token_program, mint_data) =
+        fetch(&loan.nft_mint).ok_or_else(|| format!("nft mint {} not found", loan.nft_mint))?;
+    let keys = LoanKeys::from_loan(*platform, loan, token_program);
+    let (instruction, recipient) = match settlement {
+        Settlement::PoolClaimDefault { pool } => {
+            (instructions::pool_claim_default(&keys, *payer, pool), pool)
+        }
+        Settlement::MarkDefaulted { lender } => {
+            (instructions::mark_defaulted(&keys, *payer, lender), lender)
+        }
+        Settlement::CloseExpiredRequest => {
+            (instructions::close_expired_request(&keys, *payer), keys.borrower)
+        }
+    };
+    match transfer_hook_program(&mint_data) {
+        Some(hook_program) => with_vault_transfer_hook(
+            instruction,
+            &keys,
+            &recipient,
+            &hook_program,
+            |address| fetch(address).map(|(_, data)| data),
+        )
+        .map_err(|e| format!("could not resolve the transfer hook accounts: {e}")),
+        None => Ok(instruction),
+    }
+}
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub planned: PlannedSettlement,
+    /// `None` in dry-run mode
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
+
+pub struct Keeper {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    pub platform: Pubkey,
+    pub dry_run: bool,
+}
+
+impl Keeper {
+    fn instruction(&self, loan: &Loan, planned: &PlannedSettlement) -> Result<Instruction, String> {
+        //a node error reads as a missing account, the settlement is then reported as failed
+        settlement_instruction(&self.platform, &self.payer.pubkey(), loan, planned.settlement, |address| {
+            self.rpc.get_account(address).ok().flatten()
+        })
+    }
+
+    fn send(&self, instruction: Instruction) -> Result<String, String> {
+        let blockhash = self.rpc.get_latest_blockhash().map_err(|e| e.to_string())?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        let wire = bincode::serialize(&transaction).map_err(|e| e.to_string())?;
+        self.rpc.send_and_confirm(&wire).map_err(|e| e.to_string())
+    }
+
+    /// One scan: plans against the cluster clock and submits each settlement.
+    /// A failed settlement is reported and doesn't stop the others.
+    pub fn run_once(&self) -> RpcResult<Vec<Outcome>> {
+        let loans = self.rpc.fetch_loans(None)?;
+        let now = self.rpc.get_unix_timestamp()?;
+
+        Ok(plan(&self.platform, &loans, now)
+            .into_iter()
+            .map(|planned| {
+                if self.dry_run {
+                    return Outcome { planned, signature: None, error: None };
+                }
+                let loan = &loans
+                    .iter()
+                    .find(|(address, _)| *address == planned.loan)
+                    .expect("planned loans come from the scan")
+                    .1;
+                match self.instruction(loan, &planned).and_then(|ix| self.send(ix)) {
+                    Ok(signature) => Outcome { planned, signature: Some(signature), error: None },
+                    Err(error) => Outcome { planned, signature: None, error: Some(error) },
+                }
+            })
+            .collect())
+    }
+}
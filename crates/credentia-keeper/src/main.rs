@@ -0,0 +1,82 @@
+//! `credentia-keeper`: periodically settle expired loans on a platform.
+
+use std::{path::PathBuf, thread, time::Duration};
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use credentia_client::{pda, rpc::RpcClient};
+use credentia_keeper::{Keeper, Settlement};
+use solana_sdk::signature::read_keypair_file;
+
+#[derive(Parser)]
+#[command(name = "credentia-keeper", version, about)]
+struct Cli {
+    #[arg(long, short = 'u', env = "CREDENTIA_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair paying for settlements and collecting the bounties
+    #[arg(long, short = 'k', env = "CREDENTIA_KEYPAIR")]
+    keypair: Option<PathBuf>,
+    #[arg(long, default_value_t = 1)]
+    platform_id: u64,
+    /// Platform address, overrides --platform-id
+    #[arg(long)]
+    platform: Option<Pubkey>,
+    /// Print what would be settled without sending transactions
+    #[arg(long)]
+    dry_run: bool,
+    /// Scan once and exit
+    #[arg(long)]
+    once: bool,
+    /// Seconds between scans
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair = match cli.keypair {
+        Some(path) => path,
+        None => PathBuf::from(std::env::var("HOME").context("HOME is not set, pass --keypair")?)
+            .join(".config/solana/id.json"),
+    };
+    let keeper = Keeper {
+        rpc: RpcClient::new(&cli.url),
+        payer: read_keypair_file(&keypair)
+            .map_err(|e| anyhow!("could not read keypair {}: {e}", keypair.display()))?,
+        platform: cli
+            .platform
+            .unwrap_or_else(|| pda::platform(cli.platform_id).0),
+        dry_run: cli.dry_run,
+    };
+
+    loop {
+        match keeper.run_once() {
+            Ok(outcomes) => {
+                for o in outcomes {
+                    let action = match o.planned.settlement {
+                        Settlement::PoolClaimDefault { .. } => "pool_claim_default",
+                        Settlement::MarkDefaulted { .. } => "mark_defaulted",
+                        Settlement::CloseExpiredRequest => "close_expired_request",
+                    };
+                    let result = match (&o.signature, &o.error) {
+                        (Some(sig), _) => format!("sent {sig}"),
+                        (None, Some(err)) => format!("failed: {err}"),
+                        (None, None) => "dry run".to_string(),
+                    };
+                    println!(
+                        "loan {} ({}) overdue {}s: {action} {result}",
+                        o.planned.loan, o.planned.loan_id, o.planned.overdue_by
+                    );
+                }
+            }
+            //a flaky node shouldn't kill a long running keeper
+            Err(e) if !cli.once => eprintln!("scan failed: {e}"),
+            Err(e) => return Err(e.into()),
+        }
+        if cli.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(cli.interval));
+    }
+}
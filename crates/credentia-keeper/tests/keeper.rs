@@ -0,0 +1,273 @@
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_spl::{
+    token::spl_token::{self, solana_program::program_pack::Pack},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_hook::TransferHook, BaseStateWithExtensionsMut, ExtensionType,
+            StateWithExtensionsMut,
+        },
+        state::Mint,
+    },
+};
+use credentia_client::{
+    instructions::LoanKeys, pda, rpc::RpcClient, DefaultProtection, Loan, LoanStatus,
+};
+use credentia_keeper::{plan, settlement_instruction, Keeper, Settlement};
+use solana_sdk::signature::Keypair;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+const START: i64 = 1_700_000_000;
+const DURATION: u32 = 3_600;
+
+fn funded(platform: &Pubkey, loan_id: u64, lender: Pubkey, pool: Option<Pubkey>) -> (Pubkey, Loan) {
+    let loan = Loan {
+        borrower: Pubkey::new_unique(),
+        lender: Some(lender),
+        nft_mint: Pubkey::new_unique(),
+        loan_amount: 1_000,
+        duration: DURATION,
+        start_time: Some(START),
+        status: LoanStatus::Funded,
+        time_of_liquidation_or_repayment: None,
+        interest_rate: 500,
+        bump: 0,
+        pool,
+        loan_id,
+        borrower_referrer: None,
+        lender_referrer: None,
+        version: Loan::VERSION,
+        currency: None,
+        expires_at: None,
+        allowed_lenders: Vec::new(),
+        min_lender_reputation: 0,
+        insured: false,
+        protection: None,
+    };
+    (pda::loan(&loan.nft_mint, platform, loan_id).0, loan)
+}
+
+#[test]
+fn nothing_is_due_before_the_deadline() {
+    let platform = pda::platform(1).0;
+    let loans = vec![funded(&platform, 0, Pubkey::new_unique(), None)];
+    assert!(plan(&platform, &loans, START).is_empty());
+    assert!(plan(&platform, &loans, START + DURATION as i64 - 1).is_empty());
+}
+
+#[test]
+fn warping_past_the_deadline_makes_loans_due() {
+    let platform = pda::platform(1).0;
+    let lender = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let loans = vec![
+        funded(&platform, 0, lender, None),
+        funded(&platform, 1, Pubkey::new_unique(), Some(pool)),
+    ];
+
+    let due = plan(&platform, &loans, START + DURATION as i64);
+    assert_eq!(due.len(), 2);
+    assert!(due.iter().all(|p| p.overdue_by == 0));
+    assert!(due
+        .iter()
+        .any(|p| p.loan_id == 0 && p.settlement == Settlement::MarkDefaulted { lender }));
+    assert!(due
+        .iter()
+        .any(|p| p.loan_id == 1 && p.settlement == Settlement::PoolClaimDefault { pool }));
+}
+
+#[test]
+fn protected_loans_are_due_after_their_extension() {
+    let platform = pda::platform(1).0;
+    let mut loan = funded(&platform, 0, Pubkey::new_unique(), None);
+    loan.1.protection = Some(DefaultProtection { extension: 600, premium: 10 });
+    let loans = vec![loan];
+
+    let deadline = START + DURATION as i64 + 600;
+    assert!(plan(&platform, &loans, deadline - 1).is_empty());
+    assert_eq!(plan(&platform, &loans, deadline).len(), 1);
+}
+
+#[test]
+fn most_overdue_first_and_settled_loans_skipped() {
+    let platform = pda::platform(1).0;
+    let lender = Pubkey::new_unique();
+    let mut early = funded(&platform, 0, lender, None);
+    early.1.start_time = Some(START - 500);
+    let late = funded(&platform, 1, lender, None);
+    let mut repaid = funded(&platform, 2, lender, None);
+    repaid.1.status = LoanStatus::Repaid;
+    let mut requested = funded(&platform, 3, lender, None);
+    requested.1.status = LoanStatus::Requested;
+    requested.1.start_time = None;
+
+    let due = plan(&platform, &[late, early, repaid, requested], START + 10_000);
+    assert_eq!(due.iter().map(|p| p.loan_id).collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(due[0].overdue_by, 10_000 - DURATION as i64 + 500);
+}
+
+#[test]
+fn expired_requests_are_closed() {
+    let platform = pda::platform(1).0;
+    let mut expiring = funded(&platform, 0, Pubkey::new_unique(), None);
+    expiring.1.status = LoanStatus::Requested;
+    expiring.1.lender = None;
+    expiring.1.start_time = None;
+    expiring.1.expires_at = Some(START);
+    let mut open = expiring.clone();
+    open.1.loan_id = 1;
+    open.0 = pda::loan(&open.1.nft_mint, &platform, 1).0;
+    open.1.expires_at = None;
+
+    let loans = [expiring, open];
+    assert!(plan(&platform, &loans, START - 1).is_empty());
+    let due = plan(&platform, &loans, START + 30);
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].loan_id, 0);
+    assert_eq!(due[0].settlement, Settlement::CloseExpiredRequest);
+    assert_eq!(due[0].overdue_by, 30);
+}
+
+#[test]
+fn loans_of_other_platforms_are_ignored() {
+    let platform = pda::platform(1).0;
+    let other = funded(&pda::platform(2).0, 0, Pubkey::new_unique(), None);
+    assert!(plan(&platform, &[other], START + 10_000).is_empty());
+}
+
+fn mint(hook_program: Option<Pubkey>) -> Vec<u8> {
+    let base = Mint { decimals: 0, supply: 1, is_initialized: true, ..Default::default() };
+    let Some(hook_program) = hook_program else {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(base, &mut data).unwrap();
+        return data;
+    };
+    let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook]).unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    state.init_extension::<TransferHook>(true).unwrap().program_id = Some(hook_program).try_into().unwrap();
+    state.base = base;
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+#[test]
+fn settlements_use_the_token_program_owning_the_mint() {
+    let platform = pda::platform(1).0;
+    let lender = Pubkey::new_unique();
+    let (_, loan) = funded(&platform, 0, lender, None);
+    let payer = Pubkey::new_unique();
+
+    for token_program in [spl_token::ID, spl_token_2022::ID] {
+        let data = mint(None);
+        let ix = settlement_instruction(
+            &platform,
+            &payer,
+            &loan,
+            Settlement::MarkDefaulted { lender },
+            |address| (*address == loan.nft_mint).then(|| (token_program, data.clone())),
+        )
+        .unwrap();
+
+        let keys = LoanKeys::from_loan(platform, &loan, token_program);
+        assert!(ix.accounts.iter().any(|meta| meta.pubkey == keys.nft_vault()));
+        assert!(ix.accounts.iter().any(|meta| meta.pubkey == token_program));
+    }
+
+    let missing = settlement_instruction(&platform, &payer, &loan, Settlement::MarkDefaulted { lender }, |_| None);
+    assert!(missing.is_err());
+}
+
+#[test]
+fn transfer_hook_accounts_are_resolved_and_forwarded() {
+    let platform = pda::platform(1).0;
+    let pool = Pubkey::new_unique();
+    let (_, loan) = funded(&platform, 0, Pubkey::new_unique(), Some(pool));
+    let keys = LoanKeys::from_loan(platform, &loan, spl_token_2022::ID);
+    let hook_program = Pubkey::new_unique();
+    let validation = pda::extra_account_metas(&loan.nft_mint, &hook_program);
+
+    //one fixed account and one derived from the transfer's destination
+    let fixed = Pubkey::new_unique();
+    let metas = [
+        ExtraAccountMeta::new_with_pubkey(&fixed, false, false).unwrap(),
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"allow".to_vec() }, Seed::AccountKey { index: 2 }],
+            false,
+            true,
+        )
+        .unwrap(),
+    ];
+    let mut metas_data = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut metas_data, &metas).unwrap();
+    let mint_data = mint(Some(hook_program));
+
+    let ix = settlement_instruction(
+        &platform,
+        &Pubkey::new_unique(),
+        &loan,
+        Settlement::PoolClaimDefault { pool },
+        |address| match *address {
+            a if a == loan.nft_mint => Some((spl_token_2022::ID, mint_data.clone())),
+            a if a == validation => Some((hook_program, metas_data.clone())),
+            _ => None,
+        },
+    )
+    .unwrap();
+
+    let destination = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        &pool,
+        &loan.nft_mint,
+        &spl_token_2022::ID,
+    );
+    let derived = Pubkey::find_program_address(&[b"allow", destination.as_ref()], &hook_program).0;
+    let appended = &ix.accounts[ix.accounts.len() - 4..];
+    assert_eq!(
+        appended,
+        [
+            AccountMeta::new_readonly(validation, false),
+            AccountMeta::new_readonly(fixed, false),
+            AccountMeta::new(derived, false),
+            AccountMeta::new_readonly(hook_program, false),
+        ]
+    );
+    assert!(ix.accounts.iter().any(|meta| meta.pubkey == keys.nft_vault()));
+
+    //without its validation account the hook's accounts can't be known
+    let unresolved = settlement_instruction(
+        &platform,
+        &Pubkey::new_unique(),
+        &loan,
+        Settlement::PoolClaimDefault { pool },
+        |address| (*address == loan.nft_mint).then(|| (spl_token_2022::ID, mint_data.clone())),
+    );
+    assert!(unresolved.is_err());
+}
+
+/// Dry run against a local validator, e.g. after the TypeScript suite seeded
+/// loans and the validator was restarted with `--warp-slot` past their deadlines:
+/// `CREDENTIA_RPC_URL=http://127.0.0.1:8899 cargo test -p credentia-keeper -- --ignored`
+#[test]
+#[ignore = "needs a local validator"]
+fn dry_run_against_local_validator() {
+    let url = std::env::var("CREDENTIA_RPC_URL").unwrap_or("http://127.0.0.1:8899".into());
+    let keeper = Keeper {
+        rpc: RpcClient::new(url),
+        payer: Keypair::new(),
+        platform: pda::platform(1).0,
+        dry_run: true,
+    };
+    let now = keeper.rpc.get_unix_timestamp().unwrap();
+    for outcome in keeper.run_once().unwrap() {
+        assert!(outcome.signature.is_none() && outcome.error.is_none());
+        let loan = keeper.rpc.fetch_loan(&outcome.planned.loan).unwrap().unwrap();
+        let deadline = match outcome.planned.settlement {
+            Settlement::CloseExpiredRequest => loan.expires_at.unwrap(),
+            _ => loan.start_time.unwrap() + loan.duration as i64,
+        };
+        //the keeper read the clock a moment after us
+        assert!(deadline <= now + 60);
+    }
+}
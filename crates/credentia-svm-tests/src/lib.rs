@@ -0,0 +1,795 @@
+//! In-process test harness for Credentia built on LiteSVM.
+//!
+//! Loads the programs built by `anchor build` (`target/deploy/credentia.so` and the
+//! `test_transfer_hook.so` hooked mints transfer through) and the Metaplex token
+//! metadata program from `tests/`, initializes a platform and mints real master
+//! edition nfts in a verified collection, so every instruction runs against the same
+//! accounts it sees on a validator.
+
+use std::path::PathBuf;
+
+use anchor_lang::{
+    event::EVENT_IX_TAG_LE,
+    prelude::{AccountMeta, Pubkey},
+    solana_program::{instruction::Instruction, native_token::LAMPORTS_PER_SOL},
+    AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, Event, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id,
+        spl_associated_token_account::instruction::{
+            create_associated_token_account, create_associated_token_account_idempotent,
+        },
+    },
+    metadata::mpl_token_metadata::{
+        self,
+        instructions::{
+            CreateMasterEditionV3Builder, CreateMetadataAccountV3Builder, VerifyCollectionBuilder,
+        },
+        types::{Collection, DataV2},
+    },
+    token::spl_token::{self, solana_program::program_pack::Pack},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            default_account_state, group_member_pointer, group_pointer, transfer_fee,
+            transfer_hook, ExtensionType, StateWithExtensions,
+        },
+        state::AccountState,
+    },
+};
+use credentia::{error::ErrorCode, LegacyLoan, LoanStatus};
+use credentia_client::{
+    instructions::{self, Currency, LoanKeys, LoanTerms},
+    pda, LendingPool, Loan, Platform, RateCurve, PROGRAM_ID,
+};
+use litesvm::{
+    types::{FailedTransactionMetadata, TransactionMetadata},
+    LiteSVM,
+};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::InstructionError,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use solana_system_interface::instruction as system_instruction;
+use test_transfer_hook::TransferCounter;
+
+/// Program the transfer hook of hooked currencies and nfts points at.
+pub const HOOK_PROGRAM: Pubkey = test_transfer_hook::ID;
+/// Governance program whose instructions try to take the collateral with the loan's signature.
+pub const GOVERNANCE_PROGRAM: Pubkey = test_governance::ID;
+
+pub const SOL: u64 = LAMPORTS_PER_SOL;
+pub const PLATFORM_ID: u64 = 1;
+pub const FEE_BPS: u16 = 500;
+pub const POOL_ID: u64 = 0;
+pub const DAY: u32 = 86_400;
+/// Transfer fee of currencies `mint_currency` creates with a `TransferFeeConfig`.
+pub const CURRENCY_FEE_BPS: u16 = 100;
+
+/// Terms used by loans that don't care about their exact terms.
+pub const TERMS: LoanTerms = LoanTerms {
+    amount: SOL,
+    duration: 7 * DAY,
+    interest_rate: 1_000,
+};
+
+pub type TransactionResult = Result<TransactionMetadata, FailedTransactionMetadata>;
+
+fn repo_path(relative: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .join(relative)
+}
+
+/// Custom error code Anchor reports for a Credentia `ErrorCode`.
+pub fn error_code(code: ErrorCode) -> u32 {
+    code as u32 + anchor_lang::error::ERROR_CODE_OFFSET
+}
+
+pub fn curve() -> RateCurve {
+    RateCurve {
+        base_rate: 200,
+        slope1: 400,
+        kink: 8_000,
+        slope2: 3_000,
+    }
+}
+
+fn process(svm: &mut LiteSVM, ixs: &[Instruction], signers: &[&Keypair]) -> TransactionResult {
+    let transaction = Transaction::new_signed_with_payer(
+        ixs,
+        Some(&signers[0].pubkey()),
+        signers,
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(transaction);
+    //retrying the same instructions must not be rejected as already processed
+    svm.expire_blockhash();
+    result
+}
+
+/// Events of type `E` emitted through `emit_cpi!` during a transaction, in emission order.
+pub fn events<E: Event>(meta: &TransactionMetadata) -> Vec<E> {
+    meta.inner_instructions
+        .iter()
+        .flatten()
+        .filter_map(|ix| ix.instruction.data.strip_prefix(EVENT_IX_TAG_LE))
+        .filter_map(|data| data.strip_prefix(E::DISCRIMINATOR))
+        .filter_map(|mut data| E::deserialize(&mut data).ok())
+        .collect()
+}
+
+fn hook_counter(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"counter", mint.as_ref()], &HOOK_PROGRAM).0
+}
+
+fn expect_ok(result: TransactionResult) -> TransactionMetadata {
+    result.unwrap_or_else(|failed| {
+        panic!(
+            "transaction failed: {:?}\n{}",
+            failed.err,
+            failed.meta.logs.join("\n")
+        )
+    })
+}
+
+pub struct Harness {
+    pub svm: LiteSVM,
+    /// platform authority, also mint and update authority of every nft the harness mints
+    pub admin: Keypair,
+    pub platform: Pubkey,
+    /// collection nft the harness verifies its nfts into
+    pub collection: Pubkey,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    /// Fresh SVM with platform `PLATFORM_ID` initialized and a collection nft minted.
+    pub fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(PROGRAM_ID, repo_path("target/deploy/credentia.so"))
+            .expect("target/deploy/credentia.so is missing, run `anchor build` first");
+        svm.add_program_from_file(
+            mpl_token_metadata::ID,
+            repo_path("tests/mpl_token_metadata.so"),
+        )
+        .expect("tests/mpl_token_metadata.so is missing");
+        svm.add_program_from_file(HOOK_PROGRAM, repo_path("target/deploy/test_transfer_hook.so"))
+            .expect("target/deploy/test_transfer_hook.so is missing, run `anchor build` first");
+        svm.add_program_from_file(GOVERNANCE_PROGRAM, repo_path("target/deploy/test_governance.so"))
+            .expect("target/deploy/test_governance.so is missing, run `anchor build` first");
+
+        let admin = Keypair::new();
+        svm.airdrop(&admin.pubkey(), 1_000 * SOL).unwrap();
+        let mut harness = Self {
+            svm,
+            admin,
+            platform: pda::platform(PLATFORM_ID).0,
+            collection: Pubkey::default(),
+        };
+
+        let ix = instructions::initialize_platform(
+            harness.admin.pubkey(),
+            PLATFORM_ID,
+            FEE_BPS,
+            spl_token::ID,
+        );
+        harness.send_admin(&[ix]);
+        let admin = harness.admin.pubkey();
+        harness.collection = harness.mint_nft(&admin, None);
+        harness
+    }
+
+    pub fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TransactionResult {
+        process(&mut self.svm, ixs, signers)
+    }
+
+    /// Sends a transaction paid by the first signer, panicking with the logs if it fails.
+    pub fn send_ok(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TransactionMetadata {
+        expect_ok(self.send(ixs, signers))
+    }
+
+    /// Sends a transaction signed and paid by the admin.
+    pub fn send_admin(&mut self, ixs: &[Instruction]) -> TransactionMetadata {
+        let Self { svm, admin, .. } = self;
+        expect_ok(process(svm, ixs, &[admin]))
+    }
+
+    /// Asserts the transaction fails with `code`.
+    pub fn expect_error(&mut self, ixs: &[Instruction], signers: &[&Keypair], code: ErrorCode) {
+        let expected = error_code(code);
+        match self.send(ixs, signers) {
+            Ok(_) => panic!("expected {code:?} but the transaction succeeded"),
+            Err(failed) => match failed.err {
+                TransactionError::InstructionError(_, InstructionError::Custom(actual))
+                    if actual == expected => {}
+                err => panic!(
+                    "expected {code:?} ({expected}), got {err:?}\n{}",
+                    failed.meta.logs.join("\n")
+                ),
+            },
+        }
+    }
+
+    pub fn expect_admin_error(&mut self, ixs: &[Instruction], code: ErrorCode) {
+        let admin = self.admin.insecure_clone();
+        self.expect_error(ixs, &[&admin], code);
+    }
+
+    pub fn keypair(&mut self, lamports: u64) -> Keypair {
+        let keypair = Keypair::new();
+        self.svm.airdrop(&keypair.pubkey(), lamports).unwrap();
+        keypair
+    }
+
+    pub fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    /// Moves the clock's unix timestamp forward by `seconds`.
+    pub fn warp(&mut self, seconds: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp += seconds;
+        self.svm.set_sysvar(&clock);
+    }
+
+    pub fn exists(&self, address: &Pubkey) -> bool {
+        self.svm
+            .get_account(address)
+            .is_some_and(|account| account.lamports > 0)
+    }
+
+    pub fn balance(&self, address: &Pubkey) -> u64 {
+        self.svm.get_balance(address).unwrap_or(0)
+    }
+
+    pub fn set_balance(&mut self, address: &Pubkey, lamports: u64) {
+        let mut account = self.svm.get_account(address).unwrap();
+        account.lamports = lamports;
+        self.svm.set_account(*address, account).unwrap();
+    }
+
+    /// Token program owning `mint`.
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.svm.get_account(mint).unwrap().owner
+    }
+
+    /// Tokens of `mint` held by `owner`'s associated token account, 0 if it doesn't exist.
+    pub fn token_balance(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        let token_program = self.token_program(mint);
+        let ata = get_associated_token_address_with_program_id(owner, mint, &token_program);
+        self.svm
+            .get_account(&ata)
+            .filter(|account| account.lamports > 0)
+            .map(|account| {
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                    .unwrap()
+                    .base
+                    .amount
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self
+            .svm
+            .get_account(address)
+            .unwrap_or_else(|| panic!("account {address} not found"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub fn platform_account(&self) -> Platform {
+        self.account(&self.platform)
+    }
+
+    pub fn loan(&self, keys: &LoanKeys) -> Loan {
+        self.account(&keys.loan())
+    }
+
+    pub fn pool(&self, pool: &Pubkey) -> LendingPool {
+        self.account(pool)
+    }
+
+    /// Rewrites a loan in place, for states the instructions never leave an open loan in.
+    pub fn set_loan(&mut self, keys: &LoanKeys, update: impl FnOnce(&mut Loan)) {
+        let address = keys.loan();
+        let mut account = self.svm.get_account(&address).unwrap();
+        let mut loan = Loan::try_deserialize(&mut account.data.as_slice()).unwrap();
+        update(&mut loan);
+        let mut data = Vec::new();
+        loan.try_serialize(&mut data).unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.svm.set_account(address, account).unwrap();
+    }
+
+    /// Copies a loan to the address it would have against `nft_mint`, for loans opened before a
+    /// check on their nft existed. Only the loan account is written, the nft stays where it is.
+    pub fn copy_loan(&mut self, keys: &LoanKeys, nft_mint: Pubkey) -> LoanKeys {
+        let copy = LoanKeys {
+            nft_mint,
+            token_program: self.token_program(&nft_mint),
+            ..*keys
+        };
+        let mut account = self.svm.get_account(&keys.loan()).unwrap();
+        let mut loan = Loan::try_deserialize(&mut account.data.as_slice()).unwrap();
+        loan.nft_mint = nft_mint;
+        loan.bump = pda::loan(&nft_mint, &keys.platform, keys.loan_id).1;
+        let mut data = Vec::new();
+        loan.try_serialize(&mut data).unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.svm.set_account(copy.loan(), account).unwrap();
+        copy
+    }
+
+    /// Cuts an account down to `len` bytes, the size it had under an older layout.
+    pub fn truncate_account(&mut self, address: &Pubkey, len: usize) {
+        let mut account = self.svm.get_account(address).unwrap();
+        account.data.truncate(len);
+        self.svm.set_account(*address, account).unwrap();
+    }
+
+    /// Mints a master edition nft to `owner`, optionally naming (without verifying) a collection.
+    pub fn mint_nft(&mut self, owner: &Pubkey, collection: Option<Pubkey>) -> Pubkey {
+        let mint = Keypair::new();
+        let admin = self.admin.pubkey();
+        let metadata = pda::metadata(&mint.pubkey());
+        let rent = self
+            .svm
+            .minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN);
+
+        let ixs = [
+            system_instruction::create_account(
+                &admin,
+                &mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &admin, None, 0)
+                .unwrap(),
+            create_associated_token_account(&admin, owner, &mint.pubkey(), &spl_token::ID),
+            spl_token::instruction::mint_to(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &get_associated_token_address_with_program_id(owner, &mint.pubkey(), &spl_token::ID),
+                &admin,
+                &[],
+                1,
+            )
+            .unwrap(),
+            CreateMetadataAccountV3Builder::new()
+                .metadata(metadata)
+                .mint(mint.pubkey())
+                .mint_authority(admin)
+                .payer(admin)
+                .update_authority(admin, true)
+                .data(DataV2 {
+                    name: "Credentia".to_string(),
+                    symbol: "CRD".to_string(),
+                    uri: String::new(),
+                    seller_fee_basis_points: 0,
+                    creators: None,
+                    collection: collection.map(|key| Collection {
+                        verified: false,
+                        key,
+                    }),
+                    uses: None,
+                })
+                .is_mutable(true)
+                .instruction(),
+            CreateMasterEditionV3Builder::new()
+                .edition(pda::master_edition(&mint.pubkey()))
+                .mint(mint.pubkey())
+                .update_authority(admin)
+                .mint_authority(admin)
+                .payer(admin)
+                .metadata(metadata)
+                .max_supply(0)
+                .instruction(),
+        ];
+        let Self { svm, admin, .. } = self;
+        expect_ok(process(svm, &ixs, &[admin, &mint]));
+        mint.pubkey()
+    }
+
+    /// Mints an nft to `owner` and verifies it into the harness collection.
+    pub fn mint_collection_nft(&mut self, owner: &Pubkey) -> Pubkey {
+        let nft_mint = self.mint_nft(owner, Some(self.collection));
+        let admin = self.admin.pubkey();
+        let ix = VerifyCollectionBuilder::new()
+            .metadata(pda::metadata(&nft_mint))
+            .collection_authority(admin)
+            .payer(admin)
+            .collection_mint(self.collection)
+            .collection(pda::metadata(&self.collection))
+            .collection_master_edition_account(pda::master_edition(&self.collection))
+            .instruction();
+        self.send_admin(&[ix]);
+        nft_mint
+    }
+
+    /// Creates a Token-2022 mint that is its own token group, the collection of `mint_group_nft`.
+    pub fn mint_token_group(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let admin = self.admin.pubkey();
+        let ixs = vec![
+            group_pointer::instruction::initialize(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                None,
+                Some(mint.pubkey()),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                &admin,
+                None,
+                0,
+            )
+            .unwrap(),
+            spl_token_group_interface::instruction::initialize_group(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                &mint.pubkey(),
+                &admin,
+                Some(admin),
+                100,
+            ),
+        ];
+        self.create_token_2022_mint(&mint, &[ExtensionType::GroupPointer], &[ExtensionType::TokenGroup], ixs);
+        mint.pubkey()
+    }
+
+    /// Mints a Token-2022 nft to `owner` as a member of `group`, with its mint authority revoked.
+    /// `extensions` are extra mint extensions the nft is created with: a permanent delegate,
+    /// non transferable, or a transfer hook (without a program) whose authority is the admin.
+    pub fn mint_group_nft(&mut self, owner: &Pubkey, group: &Pubkey, extensions: &[ExtensionType]) -> Pubkey {
+        self.group_nft(owner, group, extensions, false)
+    }
+
+    /// Like `mint_group_nft` for an nft whose transfers run through `HOOK_PROGRAM`, with no
+    /// authority left to change the hook.
+    pub fn mint_hooked_group_nft(&mut self, owner: &Pubkey, group: &Pubkey) -> Pubkey {
+        let mint = self.group_nft(owner, group, &[ExtensionType::TransferHook], true);
+        self.init_hook(&mint);
+        mint
+    }
+
+    fn group_nft(&mut self, owner: &Pubkey, group: &Pubkey, extensions: &[ExtensionType], hooked: bool) -> Pubkey {
+        let mint = Keypair::new();
+        let admin = self.admin.pubkey();
+        let token_program = spl_token_2022::ID;
+        let mut ixs = vec![group_member_pointer::instruction::initialize(
+            &token_program,
+            &mint.pubkey(),
+            None,
+            Some(mint.pubkey()),
+        )
+        .unwrap()];
+        for extension in extensions {
+            ixs.push(match extension {
+                ExtensionType::PermanentDelegate => {
+                    spl_token_2022::instruction::initialize_permanent_delegate(
+                        &token_program,
+                        &mint.pubkey(),
+                        &admin,
+                    )
+                    .unwrap()
+                }
+                ExtensionType::NonTransferable => {
+                    spl_token_2022::instruction::initialize_non_transferable_mint(
+                        &token_program,
+                        &mint.pubkey(),
+                    )
+                    .unwrap()
+                }
+                ExtensionType::TransferHook if hooked => {
+                    transfer_hook::instruction::initialize(&token_program, &mint.pubkey(), None, Some(HOOK_PROGRAM))
+                        .unwrap()
+                }
+                ExtensionType::TransferHook => {
+                    transfer_hook::instruction::initialize(&token_program, &mint.pubkey(), Some(admin), None)
+                        .unwrap()
+                }
+                other => panic!("mint_group_nft does not set up {other:?}"),
+            });
+        }
+        let ata = get_associated_token_address_with_program_id(owner, &mint.pubkey(), &token_program);
+        ixs.extend([
+            spl_token_2022::instruction::initialize_mint2(&token_program, &mint.pubkey(), &admin, None, 0)
+                .unwrap(),
+            spl_token_group_interface::instruction::initialize_member(
+                &token_program,
+                &mint.pubkey(),
+                &mint.pubkey(),
+                &admin,
+                group,
+                &admin,
+            ),
+            create_associated_token_account(&admin, owner, &mint.pubkey(), &token_program),
+            spl_token_2022::instruction::mint_to(&token_program, &mint.pubkey(), &ata, &admin, &[], 1)
+                .unwrap(),
+            spl_token_2022::instruction::set_authority(
+                &token_program,
+                &mint.pubkey(),
+                None,
+                spl_token_2022::instruction::AuthorityType::MintTokens,
+                &admin,
+                &[],
+            )
+            .unwrap(),
+        ]);
+        let mut space = vec![ExtensionType::GroupMemberPointer];
+        space.extend_from_slice(extensions);
+        self.create_token_2022_mint(&mint, &space, &[ExtensionType::TokenGroupMember], ixs);
+        mint.pubkey()
+    }
+
+    /// Creates a 6 decimal Token-2022 loan currency with the admin as mint and freeze authority.
+    /// `extensions` can add a transfer fee of `CURRENCY_FEE_BPS` (uncapped), a frozen default
+    /// account state, make it non transferable or run its transfers through `HOOK_PROGRAM`
+    /// with no authority left to change the hook.
+    pub fn mint_currency(&mut self, extensions: &[ExtensionType]) -> Currency {
+        self.currency(extensions, None)
+    }
+
+    /// Like `mint_currency` with a transfer hook through `HOOK_PROGRAM` that the admin can
+    /// still point at another program.
+    pub fn mint_currency_with_mutable_hook(&mut self) -> Currency {
+        let admin = self.admin.pubkey();
+        self.currency(&[ExtensionType::TransferHook], Some(admin))
+    }
+
+    fn currency(&mut self, extensions: &[ExtensionType], hook_authority: Option<Pubkey>) -> Currency {
+        let mint = Keypair::new();
+        let admin = self.admin.pubkey();
+        let token_program = spl_token_2022::ID;
+        let mut ixs: Vec<Instruction> = extensions
+            .iter()
+            .map(|extension| match extension {
+                ExtensionType::TransferFeeConfig => {
+                    transfer_fee::instruction::initialize_transfer_fee_config(
+                        &token_program,
+                        &mint.pubkey(),
+                        Some(&admin),
+                        Some(&admin),
+                        CURRENCY_FEE_BPS,
+                        u64::MAX,
+                    )
+                    .unwrap()
+                }
+                ExtensionType::DefaultAccountState => {
+                    default_account_state::instruction::initialize_default_account_state(
+                        &token_program,
+                        &mint.pubkey(),
+                        &AccountState::Frozen,
+                    )
+                    .unwrap()
+                }
+                ExtensionType::NonTransferable => {
+                    spl_token_2022::instruction::initialize_non_transferable_mint(
+                        &token_program,
+                        &mint.pubkey(),
+                    )
+                    .unwrap()
+                }
+                ExtensionType::TransferHook => transfer_hook::instruction::initialize(
+                    &token_program,
+                    &mint.pubkey(),
+                    hook_authority,
+                    Some(HOOK_PROGRAM),
+                )
+                .unwrap(),
+                other => panic!("mint_currency does not set up {other:?}"),
+            })
+            .collect();
+        ixs.push(
+            spl_token_2022::instruction::initialize_mint2(&token_program, &mint.pubkey(), &admin, Some(&admin), 6)
+                .unwrap(),
+        );
+        self.create_token_2022_mint(&mint, extensions, &[], ixs);
+        if extensions.contains(&ExtensionType::TransferHook) {
+            self.init_hook(&mint.pubkey());
+        }
+        Currency {
+            mint: mint.pubkey(),
+            token_program,
+        }
+    }
+
+    /// Creates `HOOK_PROGRAM`'s validation account and transfer counter for `mint`.
+    fn init_hook(&mut self, mint: &Pubkey) {
+        let accounts = test_transfer_hook::accounts::InitializeExtraAccountMetaList {
+            payer: self.admin.pubkey(),
+            extra_account_meta_list: pda::extra_account_metas(mint, &HOOK_PROGRAM),
+            counter: hook_counter(mint),
+            mint: *mint,
+            system_program: anchor_lang::system_program::ID,
+        };
+        let ix = Instruction {
+            program_id: HOOK_PROGRAM,
+            accounts: accounts.to_account_metas(None),
+            data: test_transfer_hook::instruction::InitializeExtraAccountMetaList {}.data(),
+        };
+        self.send_admin(&[ix]);
+    }
+
+    /// Extra accounts `HOOK_PROGRAM` lists for transfers of `mint`, for `with_transfer_hook`.
+    pub fn hook_accounts(&self, mint: &Pubkey) -> Vec<AccountMeta> {
+        vec![AccountMeta::new(hook_counter(mint), false)]
+    }
+
+    /// Transfers of `mint` that went through `HOOK_PROGRAM`.
+    pub fn hook_transfers(&self, mint: &Pubkey) -> u64 {
+        self.account::<TransferCounter>(&hook_counter(mint)).transfers
+    }
+
+    /// Creates `owner`'s associated token account for `currency` if it is missing and mints
+    /// `amount` into it.
+    pub fn currency_tokens(&mut self, currency: &Currency, owner: &Pubkey, amount: u64) {
+        let admin = self.admin.pubkey();
+        let ixs = [
+            create_associated_token_account_idempotent(&admin, owner, &currency.mint, &currency.token_program),
+            spl_token_2022::instruction::mint_to(
+                &currency.token_program,
+                &currency.mint,
+                &currency.account(owner),
+                &admin,
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ];
+        self.send_admin(&ixs);
+    }
+
+    /// Creates `mint` with room for `extensions` and rent for the `grows_by` extensions token-2022
+    /// reallocates for when a group or member is initialized, then runs `ixs` on it.
+    fn create_token_2022_mint(
+        &mut self,
+        mint: &Keypair,
+        extensions: &[ExtensionType],
+        grows_by: &[ExtensionType],
+        ixs: Vec<Instruction>,
+    ) {
+        let admin = self.admin.pubkey();
+        let len = |extensions: &[ExtensionType]| {
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions).unwrap()
+        };
+        let space = len(extensions);
+        let rent = self
+            .svm
+            .minimum_balance_for_rent_exemption(len(&[extensions, grows_by].concat()));
+        let mut all = vec![system_instruction::create_account(
+            &admin,
+            &mint.pubkey(),
+            rent,
+            space as u64,
+            &spl_token_2022::ID,
+        )];
+        all.extend(ixs);
+        let Self { svm, admin, .. } = self;
+        expect_ok(process(svm, &all, &[admin, mint]));
+    }
+
+    /// Keys of the next loan the platform opens for `borrower` against `nft_mint`.
+    pub fn next_loan_keys(&self, borrower: Pubkey, nft_mint: Pubkey) -> LoanKeys {
+        LoanKeys {
+            platform: self.platform,
+            nft_mint,
+            loan_id: self.platform_account().loan_count,
+            borrower,
+            token_program: self.token_program(&nft_mint),
+        }
+    }
+
+    /// New borrower holding a collection nft.
+    pub fn borrower(&mut self) -> (Keypair, Pubkey) {
+        let borrower = self.keypair(10 * SOL);
+        let nft_mint = self.mint_collection_nft(&borrower.pubkey());
+        (borrower, nft_mint)
+    }
+
+    /// Opens a loan request, returns the borrower and the loan's keys.
+    pub fn requested_loan(&mut self, terms: LoanTerms, referrer: Option<Pubkey>) -> (Keypair, LoanKeys) {
+        let (borrower, nft_mint) = self.borrower();
+        let keys = self.next_loan_keys(borrower.pubkey(), nft_mint);
+        let ix = instructions::request_loan(&keys, self.collection, terms, referrer);
+        self.send_ok(&[ix], &[&borrower]);
+        (borrower, keys)
+    }
+
+    /// Opens a loan request and funds it, returns the borrower, the lender and the loan's keys.
+    pub fn funded_loan(&mut self, terms: LoanTerms) -> (Keypair, Keypair, LoanKeys) {
+        let (borrower, keys) = self.requested_loan(terms, None);
+        let lender = self.keypair(10 * SOL);
+        let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, terms);
+        self.send_ok(&[ix], &[&lender]);
+        (borrower, lender, keys)
+    }
+
+    /// Writes a loan at the pre loan id address `[b"loan", nft_mint, platform]` and moves the
+    /// borrower's nft into its vault, the state `migrate_legacy_loan` starts from.
+    pub fn legacy_loan(&mut self, borrower: &Keypair, nft_mint: &Pubkey, legacy: LegacyLoan) -> Pubkey {
+        let (address, _) = pda::legacy_loan(nft_mint, &self.platform);
+        let vault = pda::nft_vault(&address, nft_mint, &spl_token::ID);
+        let ixs = [
+            create_associated_token_account(&borrower.pubkey(), &address, nft_mint, &spl_token::ID),
+            spl_token::instruction::transfer_checked(
+                &spl_token::ID,
+                &get_associated_token_address_with_program_id(
+                    &borrower.pubkey(),
+                    nft_mint,
+                    &spl_token::ID,
+                ),
+                nft_mint,
+                &vault,
+                &borrower.pubkey(),
+                &[],
+                1,
+                0,
+            )
+            .unwrap(),
+        ];
+        self.send_ok(&ixs, &[borrower]);
+
+        let mut data = Loan::DISCRIMINATOR.to_vec();
+        legacy.serialize(&mut data).unwrap();
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(address, account).unwrap();
+        address
+    }
+
+    /// Legacy loan contents for `borrower`'s `nft_mint` with the harness terms.
+    pub fn legacy_terms(&self, borrower: &Pubkey, nft_mint: &Pubkey, status: LoanStatus) -> LegacyLoan {
+        LegacyLoan {
+            borrower: *borrower,
+            lender: None,
+            nft_mint: *nft_mint,
+            loan_amount: TERMS.amount,
+            duration: TERMS.duration,
+            start_time: None,
+            status,
+            time_of_liquidation_or_repayment: None,
+            interest_rate: TERMS.interest_rate,
+            bump: pda::legacy_loan(nft_mint, &self.platform).1,
+        }
+    }
+
+    /// Creates pool `pool_id` and whitelists the harness collection up to `max_loan_amount`.
+    pub fn create_pool(&mut self, pool_id: u64, max_duration: u32, max_loan_amount: u64) -> Pubkey {
+        let pool = pda::lending_pool(&self.platform, pool_id).0;
+        let admin = self.admin.pubkey();
+        self.send_admin(&[
+            instructions::create_pool(admin, self.platform, pool_id, max_duration, curve(), spl_token::ID),
+            instructions::set_pool_collection(admin, pool, self.collection, max_loan_amount),
+        ]);
+        pool
+    }
+
+    /// New depositor that put `amount` into `pool`.
+    pub fn depositor(&mut self, pool: &Pubkey, amount: u64) -> Keypair {
+        let depositor = self.keypair(amount + 10 * SOL);
+        let ix = instructions::deposit_to_pool(depositor.pubkey(), *pool, amount, spl_token::ID);
+        self.send_ok(&[ix], &[&depositor]);
+        depositor
+    }
+}
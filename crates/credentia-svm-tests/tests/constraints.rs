@@ -0,0 +1,250 @@
+use credentia::{
+    error::ErrorCode,
+    events::{LoanCancelled, LoanRequestExpired},
+    LenderReputation, LoanStatus, MAX_ALLOWED_LENDERS,
+};
+use credentia_client::{
+    instructions::{self, CollectionProof, LoanKeys, LoanTerms, RequestOptions},
+    pda, AcceptanceConstraints,
+};
+use credentia_svm_tests::{events, Harness, DAY, POOL_ID, SOL, TERMS};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+/// Opens a loan request limited by `constraints`, returns the borrower and the loan's keys.
+fn constrained(h: &mut Harness, constraints: AcceptanceConstraints) -> (Keypair, LoanKeys) {
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let options = RequestOptions { constraints: Some(constraints), ..Default::default() };
+    let ix = instructions::request_loan_with(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        None,
+        &options,
+    );
+    h.send_ok(&[ix], &[&borrower]);
+    (borrower, keys)
+}
+
+fn expiring(h: &Harness) -> AcceptanceConstraints {
+    AcceptanceConstraints { expires_at: Some(h.now() + DAY as i64), ..Default::default() }
+}
+
+#[test]
+fn request_loan_records_constraints() {
+    let mut h = Harness::new();
+    let lender = Pubkey::new_unique();
+    let constraints = AcceptanceConstraints {
+        allowed_lenders: vec![lender],
+        min_lender_reputation: 3,
+        ..expiring(&h)
+    };
+    let (_, keys) = constrained(&mut h, constraints.clone());
+
+    let loan = h.loan(&keys);
+    assert_eq!(loan.expires_at, constraints.expires_at);
+    assert_eq!(loan.allowed_lenders, vec![lender]);
+    assert_eq!(loan.min_lender_reputation, 3);
+}
+
+#[test]
+fn request_loan_rejects_invalid_constraints() {
+    let mut h = Harness::new();
+    let cases = [
+        (
+            AcceptanceConstraints { expires_at: Some(h.now()), ..Default::default() },
+            ErrorCode::InvalidRequestExpiry,
+        ),
+        (
+            AcceptanceConstraints {
+                allowed_lenders: (0..=MAX_ALLOWED_LENDERS).map(|_| Pubkey::new_unique()).collect(),
+                ..Default::default()
+            },
+            ErrorCode::TooManyAllowedLenders,
+        ),
+    ];
+    for (constraints, code) in cases {
+        let (borrower, nft_mint) = h.borrower();
+        let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+        let options = RequestOptions { constraints: Some(constraints), ..Default::default() };
+        let ix = instructions::request_loan_with(
+            &keys,
+            h.collection,
+            CollectionProof::Metaplex,
+            TERMS,
+            None,
+            &options,
+        );
+        h.expect_error(&[ix], &[&borrower], code);
+    }
+}
+
+#[test]
+fn fund_borrower_rejects_an_expired_request() {
+    let mut h = Harness::new();
+    let constraints = expiring(&h);
+    let (_, keys) = constrained(&mut h, constraints);
+    h.warp(DAY as i64);
+
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::RequestExpired);
+}
+
+#[test]
+fn anyone_can_close_an_expired_request_for_its_rent() {
+    let mut h = Harness::new();
+    let constraints = expiring(&h);
+    let (borrower, keys) = constrained(&mut h, constraints);
+    let caller = h.keypair(SOL);
+
+    let ix = instructions::close_expired_request(&keys, caller.pubkey());
+    h.expect_error(std::slice::from_ref(&ix), &[&caller], ErrorCode::RequestNotExpired);
+
+    h.warp(DAY as i64);
+    let rent = h.balance(&keys.loan()) + h.balance(&keys.nft_vault());
+    let before = h.balance(&caller.pubkey());
+    let meta = h.send_ok(&[ix], &[&caller]);
+
+    let [expired] = &events::<LoanRequestExpired>(&meta)[..] else {
+        panic!("expected one LoanRequestExpired");
+    };
+    assert_eq!(expired.loan_id, keys.loan_id);
+    assert_eq!(expired.loan, keys.loan());
+    assert_eq!(expired.borrower, borrower.pubkey());
+    assert_eq!(expired.closed_by, caller.pubkey());
+    assert!(events::<LoanCancelled>(&meta).is_empty());
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+    assert!(!h.exists(&keys.nft_vault()));
+    assert_eq!(h.balance(&caller.pubkey()), before + rent - 5_000);
+}
+
+#[test]
+fn close_expired_request_ignores_open_ended_and_funded_loans() {
+    let mut h = Harness::new();
+    let caller = h.keypair(SOL);
+    let (_, keys) = h.requested_loan(TERMS, None);
+    h.warp(365 * DAY as i64);
+    let ix = instructions::close_expired_request(&keys, caller.pubkey());
+    h.expect_error(&[ix], &[&caller], ErrorCode::RequestNotExpired);
+
+    let constraints = expiring(&h);
+    let (_, keys) = constrained(&mut h, constraints);
+    let lender = h.keypair(10 * SOL);
+    h.send_ok(&[instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS)], &[&lender]);
+    h.warp(DAY as i64);
+    let ix = instructions::close_expired_request(&keys, caller.pubkey());
+    h.expect_error(&[ix], &[&caller], ErrorCode::LoanAlreadyFunded);
+    assert!(h.loan(&keys).status == LoanStatus::Funded);
+}
+
+#[test]
+fn fund_borrower_enforces_the_lender_allowlist() {
+    let mut h = Harness::new();
+    let allowed = h.keypair(10 * SOL);
+    let other = h.keypair(10 * SOL);
+    let constraints = AcceptanceConstraints {
+        allowed_lenders: vec![allowed.pubkey()],
+        ..Default::default()
+    };
+    let (_, keys) = constrained(&mut h, constraints);
+
+    let ix = instructions::fund_borrower(&keys, other.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&other], ErrorCode::LenderNotAllowed);
+
+    h.send_ok(&[instructions::fund_borrower(&keys, allowed.pubkey(), None, TERMS)], &[&allowed]);
+    assert_eq!(h.loan(&keys).lender, Some(allowed.pubkey()));
+}
+
+#[test]
+fn fund_borrower_enforces_a_minimum_lender_reputation() {
+    let mut h = Harness::new();
+    let lender = h.keypair(100 * SOL);
+    let constraints = AcceptanceConstraints { min_lender_reputation: 2, ..Default::default() };
+    let (_, keys) = constrained(&mut h, constraints);
+
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(std::slice::from_ref(&ix), &[&lender], ErrorCode::LenderReputationTooLow);
+
+    //every loan repaid to the lender counts towards their reputation on the platform, funding
+    //alone doesn't
+    let address = pda::lender_reputation(&h.platform, &lender.pubkey()).0;
+    for repaid in 0..2 {
+        let (borrower, other) = h.requested_loan(TERMS, None);
+        h.send_ok(&[instructions::fund_borrower(&other, lender.pubkey(), None, TERMS)], &[&lender]);
+        assert_eq!(h.account::<LenderReputation>(&address).loans_repaid, repaid);
+        let loan = h.loan(&other);
+        h.send_ok(&[instructions::resolve_loan(&other, &loan)], &[&borrower]);
+    }
+    let reputation: LenderReputation = h.account(&address);
+    assert_eq!(reputation.lender, lender.pubkey());
+    assert_eq!(reputation.loans_repaid, 2);
+
+    h.send_ok(&[ix], &[&lender]);
+    assert_eq!(h.account::<LenderReputation>(&address).loans_repaid, 2);
+}
+
+#[test]
+fn repaying_a_self_funded_loan_builds_no_reputation() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    h.send_ok(&[instructions::fund_borrower(&keys, borrower.pubkey(), None, TERMS)], &[&borrower]);
+    let loan = h.loan(&keys);
+    h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+
+    let address = pda::lender_reputation(&h.platform, &borrower.pubkey()).0;
+    assert_eq!(h.account::<LenderReputation>(&address).loans_repaid, 0);
+}
+
+#[test]
+fn repayments_paying_almost_no_fee_build_no_reputation() {
+    let mut h = Harness::new();
+    let lender = h.keypair(10 * SOL);
+    //no interest pays no fee and 1 bps on a sol a 5_000 lamport one, both cost a wallet repaying
+    //its own lender next to nothing
+    for interest_rate in [0, 1] {
+        let terms = LoanTerms { interest_rate, ..TERMS };
+        let (borrower, keys) = h.requested_loan(terms, None);
+        h.send_ok(&[instructions::fund_borrower(&keys, lender.pubkey(), None, terms)], &[&lender]);
+        let loan = h.loan(&keys);
+        h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+    }
+
+    let address = pda::lender_reputation(&h.platform, &lender.pubkey()).0;
+    assert_eq!(h.account::<LenderReputation>(&address).loans_repaid, 0);
+}
+
+#[test]
+fn pool_fund_loan_respects_constraints() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, 30 * DAY, 2 * SOL);
+    h.depositor(&pool, 10 * SOL);
+    let caller = h.keypair(SOL);
+    let cases = [
+        (
+            AcceptanceConstraints { allowed_lenders: vec![Pubkey::new_unique()], ..Default::default() },
+            ErrorCode::LenderNotAllowed,
+        ),
+        (
+            AcceptanceConstraints { min_lender_reputation: 1, ..Default::default() },
+            ErrorCode::LenderReputationTooLow,
+        ),
+    ];
+    for (constraints, code) in cases {
+        let (_, keys) = constrained(&mut h, constraints);
+        let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+        h.expect_error(&[ix], &[&caller], code);
+    }
+
+    //allowlisting the pool vault lets the pool fund
+    let constraints = AcceptanceConstraints {
+        allowed_lenders: vec![pda::pool_vault(&pool).0],
+        ..Default::default()
+    };
+    let (_, keys) = constrained(&mut h, constraints);
+    let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+    h.send_ok(&[ix], &[&caller]);
+    assert_eq!(h.loan(&keys).pool, Some(pool));
+}
@@ -0,0 +1,247 @@
+use anchor_lang::Space;
+use credentia::{
+    error::ErrorCode,
+    events::{CounterOfferAccepted, CounterOfferClosed, LoanFunded},
+    CounterOffer, LenderReputation, LoanStatus,
+};
+use credentia_client::{
+    instructions::{self, CollectionProof, LoanKeys, LoanTerms, RequestOptions},
+    pda, AcceptanceConstraints,
+};
+use credentia_svm_tests::{events, Harness, DAY, SOL, TERMS};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+/// Terms a lender offers instead of `TERMS`.
+const OFFER: LoanTerms = LoanTerms {
+    amount: SOL / 2,
+    duration: 14 * DAY,
+    interest_rate: 1_500,
+};
+
+/// Escrows `OFFER` from a new lender on `keys`, returns the lender and the offer's address.
+fn offered(h: &mut Harness, keys: &LoanKeys) -> (Keypair, Pubkey) {
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::make_counter_offer(keys, lender.pubkey(), None, OFFER);
+    h.send_ok(&[ix], &[&lender]);
+    let address = pda::counter_offer(&keys.loan(), &lender.pubkey()).0;
+    (lender, address)
+}
+
+#[test]
+fn make_counter_offer_escrows_the_offered_amount() {
+    let mut h = Harness::new();
+    let (_, keys) = h.requested_loan(TERMS, None);
+    let (lender, address) = offered(&mut h, &keys);
+
+    let offer: CounterOffer = h.account(&address);
+    assert_eq!(offer.loan, keys.loan());
+    assert_eq!(offer.lender, lender.pubkey());
+    assert_eq!(offer.amount, OFFER.amount);
+    assert_eq!(offer.duration, OFFER.duration);
+    assert_eq!(offer.interest_rate, OFFER.interest_rate);
+    let rent = h.svm.minimum_balance_for_rent_exemption(8 + CounterOffer::INIT_SPACE);
+    assert_eq!(h.balance(&address), rent + OFFER.amount);
+    //the request itself is untouched until the borrower accepts
+    let loan = h.loan(&keys);
+    assert!(loan.status == LoanStatus::Requested);
+    assert_eq!(loan.loan_amount, TERMS.amount);
+}
+
+#[test]
+fn accept_counter_offer_rewrites_the_terms_and_funds() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let (lender, address) = offered(&mut h, &keys);
+    let borrower_before = h.balance(&borrower.pubkey());
+    let lender_before = h.balance(&lender.pubkey());
+    let rent = h.balance(&address) - OFFER.amount;
+
+    let ix = instructions::accept_counter_offer(&keys, lender.pubkey(), OFFER);
+    let meta = h.send_ok(&[ix], &[&borrower]);
+
+    let loan = h.loan(&keys);
+    assert!(loan.status == LoanStatus::Funded);
+    assert_eq!(loan.lender, Some(lender.pubkey()));
+    assert_eq!(loan.loan_amount, OFFER.amount);
+    assert_eq!(loan.duration, OFFER.duration);
+    assert_eq!(loan.interest_rate, OFFER.interest_rate);
+    assert_eq!(loan.start_time, Some(h.now()));
+    assert_eq!(h.balance(&borrower.pubkey()), borrower_before + OFFER.amount - 5_000);
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + rent);
+    assert!(!h.exists(&address));
+    let reputation: LenderReputation =
+        h.account(&pda::lender_reputation(&h.platform, &lender.pubkey()).0);
+    assert_eq!(reputation.lender, lender.pubkey());
+    //only repayments build reputation
+    assert_eq!(reputation.loans_repaid, 0);
+
+    let [accepted] = &events::<CounterOfferAccepted>(&meta)[..] else {
+        panic!("expected one CounterOfferAccepted");
+    };
+    assert_eq!(accepted.counter_offer, address);
+    assert_eq!(accepted.amount, OFFER.amount);
+    let [funded] = &events::<LoanFunded>(&meta)[..] else {
+        panic!("expected one LoanFunded");
+    };
+    assert_eq!(funded.lender, lender.pubkey());
+    assert_eq!(funded.loan_amount, OFFER.amount);
+
+    //the loan repays on the offered terms
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.send_ok(&[ix], &[&borrower]);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+}
+
+#[test]
+fn reject_counter_offer_refunds_the_lender() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let (lender, address) = offered(&mut h, &keys);
+    let escrowed = h.balance(&address);
+    let lender_before = h.balance(&lender.pubkey());
+
+    let ix = instructions::reject_counter_offer(&keys, lender.pubkey());
+    let meta = h.send_ok(&[ix], &[&borrower]);
+
+    assert!(!h.exists(&address));
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + escrowed);
+    assert!(h.loan(&keys).status == LoanStatus::Requested);
+    let [closed] = &events::<CounterOfferClosed>(&meta)[..] else {
+        panic!("expected one CounterOfferClosed");
+    };
+    assert!(closed.rejected);
+    assert_eq!(closed.refunded, OFFER.amount);
+
+    //only the borrower decides on offers
+    let (other, _) = offered(&mut h, &keys);
+    let mut keys_as_other = keys;
+    keys_as_other.borrower = other.pubkey();
+    let ix = instructions::reject_counter_offer(&keys_as_other, other.pubkey());
+    assert!(h.send(&[ix], &[&other]).is_err());
+}
+
+#[test]
+fn withdraw_counter_offer_refunds_after_the_request_is_gone() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let (lender, address) = offered(&mut h, &keys);
+    h.send_ok(&[instructions::cancel_loan(&keys)], &[&borrower]);
+    let escrowed = h.balance(&address);
+    let lender_before = h.balance(&lender.pubkey());
+
+    let ix = instructions::withdraw_counter_offer(&keys.loan(), lender.pubkey());
+    let meta = h.send_ok(&[ix], &[&lender]);
+
+    assert!(!h.exists(&address));
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + escrowed - 5_000);
+    let [closed] = &events::<CounterOfferClosed>(&meta)[..] else {
+        panic!("expected one CounterOfferClosed");
+    };
+    assert!(!closed.rejected);
+}
+
+#[test]
+fn accept_counter_offer_rejects_a_funded_loan() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let (first, _) = offered(&mut h, &keys);
+    let (second, second_offer) = offered(&mut h, &keys);
+    h.send_ok(&[instructions::accept_counter_offer(&keys, first.pubkey(), OFFER)], &[&borrower]);
+
+    let ix = instructions::accept_counter_offer(&keys, second.pubkey(), OFFER);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanFunded);
+    let ix = instructions::make_counter_offer(&keys, second.pubkey(), None, OFFER);
+    h.expect_error(&[ix], &[&second], ErrorCode::LoanFunded);
+
+    //the losing offer is still the second lender's to take back
+    let ix = instructions::withdraw_counter_offer(&keys.loan(), second.pubkey());
+    h.send_ok(&[ix], &[&second]);
+    assert!(!h.exists(&second_offer));
+}
+
+#[test]
+fn accept_counter_offer_rejects_a_short_escrow() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let (lender, address) = offered(&mut h, &keys);
+    //the escrow no longer holds the offered amount
+    h.set_balance(&address, OFFER.amount - 1);
+
+    let ix = instructions::accept_counter_offer(&keys, lender.pubkey(), OFFER);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::MathOverflow);
+    assert!(h.loan(&keys).status == LoanStatus::Requested);
+}
+
+#[test]
+fn accept_counter_offer_rejects_an_offer_remade_on_other_terms() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let (lender, _) = offered(&mut h, &keys);
+    //the lender swaps the offer for a worse one at the same address before the borrower accepts
+    let ix = instructions::withdraw_counter_offer(&keys.loan(), lender.pubkey());
+    h.send_ok(&[ix], &[&lender]);
+    let worse = LoanTerms { interest_rate: 5_000, ..OFFER };
+    let ix = instructions::make_counter_offer(&keys, lender.pubkey(), None, worse);
+    h.send_ok(&[ix], &[&lender]);
+
+    let ix = instructions::accept_counter_offer(&keys, lender.pubkey(), OFFER);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanTermsChanged);
+    assert!(h.loan(&keys).status == LoanStatus::Requested);
+}
+
+#[test]
+fn counter_offers_respect_acceptance_constraints() {
+    let mut h = Harness::new();
+    let allowed = h.keypair(10 * SOL);
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let options = RequestOptions {
+        constraints: Some(AcceptanceConstraints {
+            expires_at: Some(h.now() + DAY as i64),
+            allowed_lenders: vec![allowed.pubkey()],
+            min_lender_reputation: 0,
+        }),
+        ..Default::default()
+    };
+    let ix = instructions::request_loan_with(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        None,
+        &options,
+    );
+    h.send_ok(&[ix], &[&borrower]);
+
+    let other = h.keypair(10 * SOL);
+    let ix = instructions::make_counter_offer(&keys, other.pubkey(), None, OFFER);
+    h.expect_error(&[ix], &[&other], ErrorCode::LenderNotAllowed);
+
+    let ix = instructions::make_counter_offer(&keys, allowed.pubkey(), None, OFFER);
+    h.send_ok(&[ix], &[&allowed]);
+    h.warp(DAY as i64);
+    let ix = instructions::accept_counter_offer(&keys, allowed.pubkey(), OFFER);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::RequestExpired);
+}
+
+#[test]
+fn counter_offers_are_not_made_on_token_loans() {
+    let mut h = Harness::new();
+    let currency = h.mint_currency(&[]);
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let options = RequestOptions { currency_mint: Some(currency.mint), ..Default::default() };
+    let ix = instructions::request_loan_with(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        None,
+        &options,
+    );
+    h.send_ok(&[ix], &[&borrower]);
+
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::make_counter_offer(&keys, lender.pubkey(), None, OFFER);
+    h.expect_error(&[ix], &[&lender], ErrorCode::CounterOfferNotSupported);
+}
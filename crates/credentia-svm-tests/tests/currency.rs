@@ -0,0 +1,172 @@
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use credentia::{error::ErrorCode, math::Repayment, LenderReputation, ReferrerAccount, MIN_REPUTATION_FEE};
+use credentia_client::{
+    instructions::{self, CollectionProof, Currency, LoanKeys},
+    pda,
+};
+use credentia_svm_tests::{
+    Harness, CURRENCY_FEE_BPS, DAY, FEE_BPS, POOL_ID, SOL, TERMS,
+};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+/// What has to be sent for `amount` to arrive through the `CURRENCY_FEE_BPS` transfer fee.
+fn gross(amount: u64) -> u64 {
+    let bps = CURRENCY_FEE_BPS as u128;
+    (amount as u128 * 10_000).div_ceil(10_000 - bps) as u64
+}
+
+/// Opens a loan request paid in `currency`, returns the borrower and the loan's keys.
+fn requested_in(h: &mut Harness, currency: &Currency, referrer: Option<&Keypair>) -> (Keypair, LoanKeys) {
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::request_loan_in_currency(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        referrer.map(|r| r.pubkey()),
+        currency.mint,
+    );
+    h.send_ok(&[ix], &[&borrower]);
+    (borrower, keys)
+}
+
+#[test]
+fn token_loan_credits_exact_amounts_through_a_transfer_fee() {
+    let mut h = Harness::new();
+    let currency = h.mint_currency(&[ExtensionType::TransferFeeConfig]);
+    let (borrower, keys) = requested_in(&mut h, &currency, None);
+    assert_eq!(h.loan(&keys).currency, Some(currency.mint));
+
+    let lender = h.keypair(10 * SOL);
+    h.currency_tokens(&currency, &lender.pubkey(), 2 * TERMS.amount);
+    h.currency_tokens(&currency, &borrower.pubkey(), 0);
+    let lender_lamports = h.balance(&lender.pubkey());
+    let ix = instructions::fund_borrower_in_currency(&keys, lender.pubkey(), None, TERMS, &currency);
+    h.send_ok(&[ix], &[&lender]);
+
+    //the lender covers the fee, the borrower gets the full principal and no lamports move
+    assert_eq!(h.token_balance(&borrower.pubkey(), &currency.mint), TERMS.amount);
+    assert_eq!(
+        h.token_balance(&lender.pubkey(), &currency.mint),
+        2 * TERMS.amount - gross(TERMS.amount)
+    );
+    assert_eq!(h.balance(&lender.pubkey()), lender_lamports - 5_000);
+
+    let treasury = pda::treasury_vault(&h.platform).0;
+    h.currency_tokens(&currency, &treasury, 0);
+    h.currency_tokens(&currency, &borrower.pubkey(), TERMS.amount);
+    let lender_before = h.token_balance(&lender.pubkey(), &currency.mint);
+    let loan = h.loan(&keys);
+    let ix = instructions::resolve_loan_in_currency(&keys, &loan, &currency);
+    h.send_ok(&[ix], &[&borrower]);
+
+    let repayment = Repayment::new(TERMS.amount, TERMS.interest_rate, FEE_BPS).unwrap();
+    assert_eq!(
+        h.token_balance(&lender.pubkey(), &currency.mint),
+        lender_before + repayment.amount_to_pay_lender
+    );
+    assert_eq!(h.token_balance(&treasury, &currency.mint), repayment.fee_for_platform);
+    assert_eq!(
+        h.token_balance(&borrower.pubkey(), &currency.mint),
+        2 * TERMS.amount
+            - gross(repayment.amount_to_pay_lender)
+            - gross(repayment.fee_for_platform)
+    );
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+    //only lamport loans build reputation, whatever the token fee was
+    assert!(repayment.fee_for_platform >= MIN_REPUTATION_FEE);
+    let reputation: LenderReputation = h.account(&pda::lender_reputation(&h.platform, &lender.pubkey()).0);
+    assert_eq!(reputation.loans_repaid, 0);
+}
+
+#[test]
+fn token_loan_pays_referrers_into_their_wallets() {
+    let mut h = Harness::new();
+    h.send_admin(&[instructions::update_platform(h.admin.pubkey(), h.platform, 500, 5_000)]);
+    let borrower_referrer = h.keypair(SOL);
+    let lender_referrer = h.keypair(SOL);
+    for referrer in [&borrower_referrer, &lender_referrer] {
+        let ix = instructions::register_referrer(referrer.pubkey(), h.platform);
+        h.send_ok(&[ix], &[referrer]);
+    }
+    let currency = h.mint_currency(&[]);
+    let (borrower, keys) = requested_in(&mut h, &currency, Some(&borrower_referrer));
+    let lender = h.keypair(10 * SOL);
+    h.currency_tokens(&currency, &lender.pubkey(), TERMS.amount);
+    h.currency_tokens(&currency, &borrower.pubkey(), TERMS.amount);
+    let ix = instructions::fund_borrower_in_currency(
+        &keys,
+        lender.pubkey(),
+        Some(lender_referrer.pubkey()),
+        TERMS,
+        &currency,
+    );
+    h.send_ok(&[ix], &[&lender]);
+
+    let treasury = pda::treasury_vault(&h.platform).0;
+    for owner in [treasury, borrower_referrer.pubkey(), lender_referrer.pubkey()] {
+        h.currency_tokens(&currency, &owner, 0);
+    }
+    let loan = h.loan(&keys);
+    h.send_ok(&[instructions::resolve_loan_in_currency(&keys, &loan, &currency)], &[&borrower]);
+
+    //half of the 5_000_000 fee goes to referrers, split evenly
+    assert_eq!(h.token_balance(&treasury, &currency.mint), 2_500_000);
+    for referrer in [&borrower_referrer, &lender_referrer] {
+        assert_eq!(h.token_balance(&referrer.pubkey(), &currency.mint), 1_250_000);
+        let account: ReferrerAccount = h.account(&pda::referrer(&h.platform, &referrer.pubkey()).0);
+        assert_eq!(account.total_earned, 0);
+        assert_eq!(account.loans_paid, 1);
+    }
+}
+
+#[test]
+fn request_loan_rejects_unsupported_currencies() {
+    let mut h = Harness::new();
+    for extension in [ExtensionType::NonTransferable, ExtensionType::DefaultAccountState] {
+        let currency = h.mint_currency(&[extension]);
+        let (borrower, nft_mint) = h.borrower();
+        let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+        let ix = instructions::request_loan_in_currency(
+            &keys,
+            h.collection,
+            CollectionProof::Metaplex,
+            TERMS,
+            None,
+            currency.mint,
+        );
+        h.expect_error(&[ix], &[&borrower], ErrorCode::UnsupportedCurrency);
+    }
+}
+
+#[test]
+fn token_loans_reject_lamports_and_other_currencies() {
+    let mut h = Harness::new();
+    let currency = h.mint_currency(&[]);
+    let (_, keys) = requested_in(&mut h, &currency, None);
+    let lender = h.keypair(10 * SOL);
+
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::CurrencyMismatch);
+
+    let other = h.mint_currency(&[]);
+    h.currency_tokens(&other, &lender.pubkey(), TERMS.amount);
+    h.currency_tokens(&other, &keys.borrower, 0);
+    let ix = instructions::fund_borrower_in_currency(&keys, lender.pubkey(), None, TERMS, &other);
+    h.expect_error(&[ix], &[&lender], ErrorCode::CurrencyMismatch);
+}
+
+#[test]
+fn pools_do_not_fund_token_loans() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, 30 * DAY, 2 * SOL);
+    h.depositor(&pool, 10 * SOL);
+    let currency = h.mint_currency(&[]);
+    let (_, keys) = requested_in(&mut h, &currency, None);
+
+    let caller = h.keypair(SOL);
+    let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+    h.expect_error(&[ix], &[&caller], ErrorCode::UnsupportedCurrency);
+}
@@ -0,0 +1,112 @@
+use anchor_spl::token::spl_token;
+use credentia::events::{
+    LoanDefaulted, LoanFunded, LoanRepaid, LoanRequested, NFTClaimed, PlatformFeeSplit,
+    PlatformInitialized,
+};
+use credentia_client::{instructions, pda};
+use credentia_svm_tests::{events, Harness, SOL, TERMS};
+use solana_sdk::signer::Signer;
+
+#[test]
+fn initialize_platform_emits_platform_initialized() {
+    let mut h = Harness::new();
+    let ix = instructions::initialize_platform(h.admin.pubkey(), 2, 300, spl_token::ID);
+    let meta = h.send_admin(&[ix]);
+
+    let [event] = &events::<PlatformInitialized>(&meta)[..] else {
+        panic!("expected one PlatformInitialized");
+    };
+    assert_eq!(event.platform, pda::platform(2).0);
+    assert_eq!(event.authority, h.admin.pubkey());
+    assert_eq!(event.platform_id, 2);
+    assert_eq!(event.fee_bps, 300);
+}
+
+#[test]
+fn loan_events_carry_the_loan_keys() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::request_loan(&keys, h.collection, TERMS, None);
+    let meta = h.send_ok(&[ix], &[&borrower]);
+
+    let [requested] = &events::<LoanRequested>(&meta)[..] else {
+        panic!("expected one LoanRequested");
+    };
+    assert_eq!(requested.loan_id, keys.loan_id);
+    assert_eq!(requested.loan, keys.loan());
+    assert_eq!(requested.borrower, borrower.pubkey());
+    assert_eq!(requested.interest_rate, TERMS.interest_rate);
+
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    let meta = h.send_ok(&[ix], &[&lender]);
+
+    let [funded] = &events::<LoanFunded>(&meta)[..] else {
+        panic!("expected one LoanFunded");
+    };
+    assert_eq!(funded.loan_id, keys.loan_id);
+    assert_eq!(funded.loan, keys.loan());
+    assert_eq!(funded.borrower, borrower.pubkey());
+    assert_eq!(funded.lender, lender.pubkey());
+}
+
+#[test]
+fn resolve_loan_emits_repaid_fee_split_and_claimed() {
+    let mut h = Harness::new();
+    let (borrower, lender, keys) = h.funded_loan(TERMS);
+    let loan = h.loan(&keys);
+    let meta = h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+
+    let [repaid] = &events::<LoanRepaid>(&meta)[..] else {
+        panic!("expected one LoanRepaid");
+    };
+    assert_eq!(repaid.loan, keys.loan());
+    assert_eq!(repaid.lender, lender.pubkey());
+    assert_eq!(repaid.repaid_amount, 1_095_000_000);
+    assert_eq!(repaid.fee_for_platform, 5_000_000);
+
+    let [split] = &events::<PlatformFeeSplit>(&meta)[..] else {
+        panic!("expected one PlatformFeeSplit");
+    };
+    assert_eq!(split.borrower, borrower.pubkey());
+    assert_eq!(split.treasury_amount, 5_000_000);
+    assert_eq!(split.insurance_amount, 0);
+    assert_eq!(split.timestamp, h.now());
+
+    let [claimed] = &events::<NFTClaimed>(&meta)[..] else {
+        panic!("expected one NFTClaimed");
+    };
+    assert_eq!(claimed.borrower, borrower.pubkey());
+    assert_eq!(claimed.nft_mint, keys.nft_mint);
+    assert!(events::<LoanDefaulted>(&meta).is_empty());
+}
+
+#[test]
+fn defaults_emit_loan_defaulted_with_the_caller() {
+    let mut h = Harness::new();
+    let (borrower, lender, keys) = h.funded_loan(TERMS);
+    h.warp(TERMS.duration as i64);
+    let meta = h.send_ok(&[instructions::default_loan(&keys, lender.pubkey())], &[&lender]);
+
+    let [defaulted] = &events::<LoanDefaulted>(&meta)[..] else {
+        panic!("expected one LoanDefaulted");
+    };
+    assert_eq!(defaulted.loan, keys.loan());
+    assert_eq!(defaulted.borrower, borrower.pubkey());
+    assert_eq!(defaulted.lender, lender.pubkey());
+    assert_eq!(defaulted.caller, lender.pubkey());
+    assert!(events::<NFTClaimed>(&meta).is_empty());
+
+    let (_, lender, keys) = h.funded_loan(TERMS);
+    h.warp(TERMS.duration as i64);
+    let caller = h.keypair(SOL);
+    let ix = instructions::mark_defaulted(&keys, caller.pubkey(), lender.pubkey());
+    let meta = h.send_ok(&[ix], &[&caller]);
+
+    let [defaulted] = &events::<LoanDefaulted>(&meta)[..] else {
+        panic!("expected one LoanDefaulted");
+    };
+    assert_eq!(defaulted.lender, lender.pubkey());
+    assert_eq!(defaulted.caller, caller.pubkey());
+}
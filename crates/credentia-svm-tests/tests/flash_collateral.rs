@@ -0,0 +1,139 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    token::spl_token,
+};
+use credentia::{
+    error::ErrorCode,
+    events::{CollateralFlashUsed, CollateralReturned},
+    LoanStatus,
+};
+use credentia_client::instructions::{self, LoanKeys};
+use credentia_svm_tests::{events, Harness, SOL, TERMS};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+fn nft_transfer(keys: &LoanKeys, from: &Pubkey, to: &Pubkey) -> Instruction {
+    spl_token::instruction::transfer_checked(
+        &spl_token::ID,
+        &get_associated_token_address_with_program_id(from, &keys.nft_mint, &spl_token::ID),
+        &keys.nft_mint,
+        &get_associated_token_address_with_program_id(to, &keys.nft_mint, &spl_token::ID),
+        from,
+        &[],
+        1,
+        0,
+    )
+    .unwrap()
+}
+
+/// A wallet with an empty token account for the loan's nft.
+fn holder(h: &mut Harness, keys: &LoanKeys) -> Keypair {
+    let holder = h.keypair(SOL);
+    let ix = create_associated_token_account(
+        &holder.pubkey(),
+        &holder.pubkey(),
+        &keys.nft_mint,
+        &spl_token::ID,
+    );
+    h.send_ok(&[ix], &[&holder]);
+    holder
+}
+
+#[test]
+fn flash_use_lends_the_nft_for_the_transaction() {
+    let mut h = Harness::new();
+    let (borrower, lender, keys) = h.funded_loan(TERMS);
+    let other = holder(&mut h, &keys);
+
+    //while it holds the nft the borrower can move it like any other
+    let uses = vec![
+        nft_transfer(&keys, &keys.borrower, &other.pubkey()),
+        nft_transfer(&keys, &other.pubkey(), &keys.borrower),
+    ];
+    let ixs = instructions::with_flash_collateral(&keys, uses);
+    let meta = h.send_ok(&ixs, &[&borrower, &other]);
+
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 0);
+    let loan = h.loan(&keys);
+    assert!(loan.status == LoanStatus::Funded);
+    assert_eq!(loan.lender, Some(lender.pubkey()));
+
+    let [used] = &events::<CollateralFlashUsed>(&meta)[..] else {
+        panic!("expected one CollateralFlashUsed");
+    };
+    assert_eq!(used.loan, keys.loan());
+    assert_eq!(used.borrower, borrower.pubkey());
+    assert_eq!(used.nft_mint, keys.nft_mint);
+    assert_eq!(events::<CollateralReturned>(&meta).len(), 1);
+}
+
+#[test]
+fn flash_use_works_on_an_open_request() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+
+    h.send_ok(&instructions::with_flash_collateral(&keys, vec![]), &[&borrower]);
+
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+    assert!(h.loan(&keys).status == LoanStatus::Requested);
+}
+
+#[test]
+fn flash_use_without_a_return_fails() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+
+    let ix = instructions::flash_use_collateral(&keys);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::CollateralNotReturned);
+
+    //a return before the flash use doesn't count
+    let ixs = [instructions::return_collateral(&keys), instructions::flash_use_collateral(&keys)];
+    assert!(h.send(&ixs, &[&borrower]).is_err());
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+}
+
+#[test]
+fn flash_use_needs_a_return_for_the_same_loan() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    let (other_borrower, other_keys) = h.requested_loan(TERMS, None);
+
+    let ixs = [
+        instructions::flash_use_collateral(&keys),
+        instructions::return_collateral(&other_keys),
+    ];
+    h.expect_error(&ixs, &[&borrower, &other_borrower], ErrorCode::CollateralNotReturned);
+}
+
+#[test]
+fn keeping_the_nft_fails_the_transaction() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    let other = holder(&mut h, &keys);
+
+    let uses = vec![nft_transfer(&keys, &keys.borrower, &other.pubkey())];
+    let ixs = instructions::with_flash_collateral(&keys, uses);
+    assert!(h.send(&ixs, &[&borrower]).is_err());
+
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+    assert_eq!(h.token_balance(&other.pubkey(), &keys.nft_mint), 0);
+}
+
+#[test]
+fn flash_use_rejects_settled_loans_and_other_signers() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+
+    let stranger = h.keypair(SOL);
+    let stolen = LoanKeys { borrower: stranger.pubkey(), ..keys };
+    let ixs = instructions::with_flash_collateral(&stolen, vec![]);
+    assert!(h.send(&ixs, &[&stranger]).is_err());
+
+    h.set_loan(&keys, |loan| loan.status = LoanStatus::Defaulted);
+    let ixs = instructions::with_flash_collateral(&keys, vec![]);
+    h.expect_error(&ixs, &[&borrower], ErrorCode::LoanNotActive);
+}
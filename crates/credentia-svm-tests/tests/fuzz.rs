@@ -0,0 +1,293 @@
+//! Random loan lifecycles against the real program.
+//!
+//! Actors request, fund, repay, cancel and default loans on each other's nfts while the
+//! clock and the platform fee move underneath them. Any step may fail, and after every
+//! step the collateral must sit in exactly one token account, no lamports may appear
+//! out of nowhere and a repayment's platform fee must stay within the interest paid.
+//!
+//! `PROPTEST_CASES=5000 cargo test --test fuzz` runs a longer campaign.
+
+use std::collections::BTreeSet;
+
+use anchor_spl::{associated_token::get_associated_token_address_with_program_id, token::spl_token};
+use credentia::{Loan, LoanStatus};
+use credentia_client::{
+    instructions::{self, LoanKeys, LoanTerms},
+    pda,
+};
+use credentia_svm_tests::{Harness, DAY, SOL};
+use proptest::{prelude::*, sample::Index};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+const ACTORS: usize = 3;
+const NFTS_PER_ACTOR: usize = 2;
+const TX_FEE: u64 = 5_000;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Request {
+        actor: Index,
+        nft: Index,
+        amount: u64,
+        duration: u32,
+        interest_rate: u16,
+    },
+    Fund { actor: Index, loan: Index },
+    Repay { actor: Index, loan: Index },
+    Cancel { actor: Index, loan: Index },
+    Default { actor: Index, loan: Index },
+    MarkDefaulted { actor: Index, loan: Index },
+    Warp { seconds: u32 },
+    SetFee { fee_bps: u16 },
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        3 => (any::<Index>(), any::<Index>(), 0..5 * SOL, 0..4 * DAY, any::<u16>()).prop_map(
+            |(actor, nft, amount, duration, interest_rate)| Action::Request {
+                actor,
+                nft,
+                amount,
+                duration,
+                interest_rate,
+            }
+        ),
+        3 => (any::<Index>(), any::<Index>()).prop_map(|(actor, loan)| Action::Fund { actor, loan }),
+        2 => (any::<Index>(), any::<Index>()).prop_map(|(actor, loan)| Action::Repay { actor, loan }),
+        1 => (any::<Index>(), any::<Index>()).prop_map(|(actor, loan)| Action::Cancel { actor, loan }),
+        1 => (any::<Index>(), any::<Index>()).prop_map(|(actor, loan)| Action::Default { actor, loan }),
+        1 => (any::<Index>(), any::<Index>())
+            .prop_map(|(actor, loan)| Action::MarkDefaulted { actor, loan }),
+        2 => (0..5 * DAY).prop_map(|seconds| Action::Warp { seconds }),
+        1 => (0..12_000u16).prop_map(|fee_bps| Action::SetFee { fee_bps }),
+    ]
+}
+
+struct World {
+    h: Harness,
+    actors: Vec<Keypair>,
+    nfts: Vec<Pubkey>,
+    /// every loan ever opened with its last observed state
+    loans: Vec<(LoanKeys, Loan)>,
+}
+
+impl World {
+    fn new() -> Self {
+        let mut h = Harness::new();
+        let actors: Vec<_> = (0..ACTORS).map(|_| h.keypair(100 * SOL)).collect();
+        let nfts = actors
+            .iter()
+            .flat_map(|actor| [actor.pubkey(); NFTS_PER_ACTOR])
+            .map(|owner| h.mint_collection_nft(&owner))
+            .collect();
+        Self {
+            h,
+            actors,
+            nfts,
+            loans: Vec::new(),
+        }
+    }
+
+    fn holders(&self) -> Vec<Pubkey> {
+        let mut holders: Vec<_> = self.actors.iter().map(Keypair::pubkey).collect();
+        holders.push(self.h.admin.pubkey());
+        holders
+    }
+
+    /// Every account a loan instruction can move lamports between.
+    fn universe(&self) -> BTreeSet<Pubkey> {
+        let mut accounts: BTreeSet<_> = self.holders().into_iter().collect();
+        accounts.insert(self.h.platform);
+        accounts.insert(pda::treasury_vault(&self.h.platform).0);
+        for (keys, _) in &self.loans {
+            accounts.insert(keys.loan());
+            accounts.insert(keys.nft_vault());
+        }
+        for holder in self.holders() {
+            for nft in &self.nfts {
+                accounts.insert(get_associated_token_address_with_program_id(
+                    &holder,
+                    nft,
+                    &spl_token::ID,
+                ));
+            }
+        }
+        accounts
+    }
+
+    fn lamports(&self) -> u64 {
+        self.universe().iter().map(|a| self.h.balance(a)).sum()
+    }
+
+    fn pick_loan(&self, loan: &Index) -> Option<(LoanKeys, Loan)> {
+        (!self.loans.is_empty()).then(|| self.loans[loan.index(self.loans.len())].clone())
+    }
+
+    /// Runs one action, `None` when there was nothing to act on.
+    fn step(&mut self, action: &Action) -> Option<bool> {
+        match action {
+            Action::Request {
+                actor,
+                nft,
+                amount,
+                duration,
+                interest_rate,
+            } => {
+                let actor = &self.actors[actor.index(ACTORS)];
+                let nft = self.nfts[nft.index(self.nfts.len())];
+                let keys = self.h.next_loan_keys(actor.pubkey(), nft);
+                let terms = LoanTerms {
+                    amount: *amount,
+                    duration: *duration,
+                    interest_rate: *interest_rate,
+                };
+                let ix = instructions::request_loan(&keys, self.h.collection, terms, None);
+                let ok = self.h.send(&[ix], &[actor]).is_ok();
+                if ok {
+                    let loan = self.h.loan(&keys);
+                    self.loans.push((keys, loan));
+                }
+                Some(ok)
+            }
+            Action::Fund { actor, loan } => {
+                let (keys, loan) = self.pick_loan(loan)?;
+                let actor = &self.actors[actor.index(ACTORS)];
+                let terms = LoanTerms::from_loan(&loan);
+                let ix = instructions::fund_borrower(&keys, actor.pubkey(), None, terms);
+                Some(self.h.send(&[ix], &[actor]).is_ok())
+            }
+            Action::Repay { actor, loan } => {
+                let (keys, loan) = self.pick_loan(loan)?;
+                let actor = &self.actors[actor.index(ACTORS)];
+                let payer = LoanKeys {
+                    borrower: actor.pubkey(),
+                    ..keys
+                };
+                let treasury = pda::treasury_vault(&self.h.platform).0;
+                let lender = loan.lender.unwrap_or_default();
+                let (treasury_before, lender_before) =
+                    (self.h.balance(&treasury), self.h.balance(&lender));
+
+                let ix = instructions::resolve_loan(&payer, &loan);
+                let ok = self.h.send(&[ix], &[actor]).is_ok();
+
+                //a self funded loan pays the lender out of the same account
+                if ok && lender != keys.borrower {
+                    let fee = self.h.balance(&treasury) - treasury_before;
+                    let paid = (self.h.balance(&lender) - lender_before) as i128;
+                    let interest = paid + fee as i128 - loan.loan_amount as i128;
+                    assert!(interest >= 0, "lender got back less than the principal");
+                    assert!(
+                        fee as i128 <= interest,
+                        "platform fee {fee} exceeds interest {interest}"
+                    );
+                }
+                Some(ok)
+            }
+            Action::Cancel { actor, loan } => {
+                let (keys, _) = self.pick_loan(loan)?;
+                let actor = &self.actors[actor.index(ACTORS)];
+                let keys = LoanKeys {
+                    borrower: actor.pubkey(),
+                    ..keys
+                };
+                let ix = instructions::cancel_loan(&keys);
+                Some(self.h.send(&[ix], &[actor]).is_ok())
+            }
+            Action::Default { actor, loan } => {
+                let (keys, _) = self.pick_loan(loan)?;
+                let actor = &self.actors[actor.index(ACTORS)];
+                let ix = instructions::default_loan(&keys, actor.pubkey());
+                Some(self.h.send(&[ix], &[actor]).is_ok())
+            }
+            Action::MarkDefaulted { actor, loan } => {
+                let (keys, loan) = self.pick_loan(loan)?;
+                let actor = &self.actors[actor.index(ACTORS)];
+                let lender = loan.lender.unwrap_or_else(|| actor.pubkey());
+                let ix = instructions::mark_defaulted(&keys, actor.pubkey(), lender);
+                Some(self.h.send(&[ix], &[actor]).is_ok())
+            }
+            Action::Warp { seconds } => {
+                self.h.warp(*seconds as i64);
+                None
+            }
+            Action::SetFee { fee_bps } => {
+                let ix = instructions::update_platform(self.h.admin.pubkey(), self.h.platform, *fee_bps, 0);
+                let admin = self.h.admin.insecure_clone();
+                Some(self.h.send(&[ix], &[&admin]).is_ok())
+            }
+        }
+    }
+
+    /// Each nft is held by exactly one token account, the loan vault while the loan is open.
+    fn check_collateral(&self) {
+        let holders = self.holders();
+        for nft in &self.nfts {
+            let open: Vec<_> = self
+                .loans
+                .iter()
+                .filter(|(keys, _)| keys.nft_mint == *nft && self.h.exists(&keys.loan()))
+                .collect();
+            assert!(open.len() <= 1, "nft {nft} backs {} open loans", open.len());
+
+            let in_wallets: u64 = holders.iter().map(|h| self.h.token_balance(h, nft)).sum();
+            let in_vaults: u64 = self
+                .loans
+                .iter()
+                .filter(|(keys, _)| keys.nft_mint == *nft)
+                .map(|(keys, _)| self.h.token_balance(&keys.loan(), nft))
+                .sum();
+            assert_eq!(in_wallets + in_vaults, 1, "nft {nft} is not in exactly one place");
+            if let Some((keys, _)) = open.first() {
+                assert_eq!(self.h.token_balance(&keys.loan(), nft), 1, "open loan's nft left its vault");
+            }
+        }
+    }
+
+    /// Open loans are requested or funded, with `lender` and `start_time` set only once funded.
+    fn check_loans(&mut self) {
+        for (keys, known) in &mut self.loans {
+            if !self.h.exists(&keys.loan()) {
+                continue;
+            }
+            let loan: Loan = self.h.account(&keys.loan());
+            match loan.status {
+                LoanStatus::Requested => {
+                    assert!(loan.lender.is_none() && loan.start_time.is_none());
+                }
+                LoanStatus::Funded => {
+                    assert!(loan.lender.is_some() && loan.start_time.is_some());
+                }
+                LoanStatus::Repaid | LoanStatus::Defaulted => {
+                    panic!("loan {} was left open after settling", loan.loan_id)
+                }
+            }
+            assert_eq!(loan.borrower, known.borrower);
+            assert_eq!(loan.loan_amount, known.loan_amount);
+            *known = loan;
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn loan_lifecycles_keep_invariants(actions in prop::collection::vec(action(), 1..40)) {
+        let mut world = World::new();
+        world.check_collateral();
+
+        for action in &actions {
+            let before = world.lamports();
+            let sent = world.step(action);
+            let after = world.lamports();
+
+            prop_assert!(after <= before, "{action:?} created {} lamports", after - before);
+            let burned = before - after;
+            match sent {
+                Some(_) => prop_assert!(burned == 0 || burned == TX_FEE, "{action:?} lost {burned} lamports"),
+                None => prop_assert_eq!(burned, 0),
+            }
+            world.check_collateral();
+            world.check_loans();
+        }
+    }
+}
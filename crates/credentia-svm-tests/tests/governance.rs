@@ -0,0 +1,198 @@
+use anchor_lang::{prelude::AccountMeta, Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    token::spl_token,
+};
+use credentia::{
+    error::ErrorCode, events::GovernanceVoteRelayed, GovernanceProgram, LoanStatus,
+    MAX_GOVERNANCE_INSTRUCTIONS,
+};
+use credentia_client::{
+    instructions::{self, LoanKeys},
+    pda,
+};
+use credentia_svm_tests::{events, Harness, GOVERNANCE_PROGRAM, SOL, TERMS};
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+// the memo program stands in for a governance program: it only succeeds when every account it is
+// given signed, so a memo naming the loan account proves the loan signed the relayed instruction
+
+fn memo_program() -> Pubkey {
+    "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr".parse().unwrap()
+}
+
+/// Lets borrowers relay memos starting with `vote`.
+fn allow_votes(h: &mut Harness) {
+    let ix = instructions::set_governance_program(
+        h.admin.pubkey(),
+        h.platform,
+        memo_program(),
+        vec![b"vote".to_vec()],
+    );
+    h.send_admin(&[ix]);
+}
+
+fn vote(keys: &LoanKeys, memo: &str) -> anchor_lang::solana_program::instruction::Instruction {
+    let accounts = [AccountMeta::new_readonly(keys.loan(), false)];
+    instructions::governance_vote(keys, memo_program(), &accounts, memo.as_bytes().to_vec())
+}
+
+#[test]
+fn set_governance_program_records_the_allowed_instructions() {
+    let mut h = Harness::new();
+    allow_votes(&mut h);
+
+    let address = pda::governance_program(&h.platform, &memo_program()).0;
+    let program: GovernanceProgram = h.account(&address);
+    assert_eq!(program.platform, h.platform);
+    assert_eq!(program.program_id, memo_program());
+    assert_eq!(program.instructions, vec![b"vote".to_vec()]);
+    assert!(program.allows(b"vote:yes"));
+    assert!(!program.allows(b"sell"));
+
+    //setting it again replaces the list, removing it closes the account
+    let ix = instructions::set_governance_program(
+        h.admin.pubkey(),
+        h.platform,
+        memo_program(),
+        vec![vec![13], vec![15]],
+    );
+    h.send_admin(&[ix]);
+    assert_eq!(h.account::<GovernanceProgram>(&address).instructions, vec![vec![13], vec![15]]);
+    let admin = h.admin.pubkey();
+    h.send_admin(&[instructions::remove_governance_program(admin, h.platform, memo_program())]);
+    assert!(!h.exists(&address));
+}
+
+#[test]
+fn set_governance_program_rejects_custodial_programs_and_bad_prefixes() {
+    let mut h = Harness::new();
+    let admin = h.admin.pubkey();
+    let platform = h.platform;
+
+    for program in [spl_token::ID, credentia::ID] {
+        let ix = instructions::set_governance_program(admin, platform, program, vec![vec![3]]);
+        h.expect_admin_error(&[ix], ErrorCode::GovernanceProgramNotAllowed);
+    }
+    let cases = [
+        vec![vec![]],
+        vec![vec![0; 9]],
+        vec![vec![1]; MAX_GOVERNANCE_INSTRUCTIONS + 1],
+    ];
+    for prefixes in cases {
+        let ix = instructions::set_governance_program(admin, platform, memo_program(), prefixes);
+        h.expect_admin_error(&[ix], ErrorCode::InvalidGovernanceInstructions);
+    }
+
+    let other = h.keypair(SOL);
+    let ix = instructions::set_governance_program(other.pubkey(), platform, memo_program(), vec![]);
+    assert!(h.send(&[ix], &[&other]).is_err());
+}
+
+#[test]
+fn governance_vote_relays_with_the_loan_signing() {
+    let mut h = Harness::new();
+    allow_votes(&mut h);
+    let (borrower, lender, keys) = h.funded_loan(TERMS);
+
+    let meta = h.send_ok(&[vote(&keys, "vote:yes")], &[&borrower]);
+
+    let [relayed] = &events::<GovernanceVoteRelayed>(&meta)[..] else {
+        panic!("expected one GovernanceVoteRelayed");
+    };
+    assert_eq!(relayed.loan, keys.loan());
+    assert_eq!(relayed.borrower, borrower.pubkey());
+    assert_eq!(relayed.program_id, memo_program());
+    assert_eq!(relayed.instruction, b"vote:yes".to_vec());
+    //the nft never left the vault
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+    assert_eq!(h.loan(&keys).lender, Some(lender.pubkey()));
+
+    //open requests can vote too
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    h.send_ok(&[vote(&keys, "vote:no")], &[&borrower]);
+}
+
+#[test]
+fn governance_vote_only_relays_allowed_instructions() {
+    let mut h = Harness::new();
+    allow_votes(&mut h);
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+
+    let ix = vote(&keys, "sell");
+    h.expect_error(&[ix], &[&borrower], ErrorCode::GovernanceInstructionNotAllowed);
+
+    let admin = h.admin.pubkey();
+    h.send_admin(&[instructions::remove_governance_program(admin, h.platform, memo_program())]);
+    assert!(h.send(&[vote(&keys, "vote:yes")], &[&borrower]).is_err());
+}
+
+#[test]
+fn governance_vote_rejects_other_signers_and_settled_loans() {
+    let mut h = Harness::new();
+    allow_votes(&mut h);
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+
+    let stranger = h.keypair(SOL);
+    let stolen = LoanKeys { borrower: stranger.pubkey(), ..keys };
+    assert!(h.send(&[vote(&stolen, "vote:yes")], &[&stranger]).is_err());
+
+    h.set_loan(&keys, |loan| loan.status = LoanStatus::Defaulted);
+    h.expect_error(&[vote(&keys, "vote:yes")], &[&borrower], ErrorCode::LoanNotActive);
+}
+
+#[test]
+fn governance_vote_fails_when_the_program_takes_the_collateral() {
+    let mut h = Harness::new();
+    let ix = instructions::set_governance_program(
+        h.admin.pubkey(),
+        h.platform,
+        GOVERNANCE_PROGRAM,
+        vec![
+            test_governance::instruction::MoveCollateral::DISCRIMINATOR.to_vec(),
+            test_governance::instruction::DelegateCollateral::DISCRIMINATOR.to_vec(),
+            test_governance::instruction::CloseCollateral::DISCRIMINATOR.to_vec(),
+        ],
+    );
+    h.send_admin(&[ix]);
+    let (borrower, lender, keys) = h.funded_loan(TERMS);
+
+    let attacker = h.keypair(SOL);
+    let ix = create_associated_token_account(
+        &attacker.pubkey(),
+        &attacker.pubkey(),
+        &keys.nft_mint,
+        &keys.token_program,
+    );
+    h.send_ok(&[ix], &[&attacker]);
+    let accounts = test_governance::accounts::TakeCollateral {
+        loan: keys.loan(),
+        vault: keys.nft_vault(),
+        mint: keys.nft_mint,
+        attacker: attacker.pubkey(),
+        attacker_tokens: get_associated_token_address_with_program_id(
+            &attacker.pubkey(),
+            &keys.nft_mint,
+            &keys.token_program,
+        ),
+        token_program: keys.token_program,
+    }
+    .to_account_metas(None);
+
+    let attempts = [
+        test_governance::instruction::MoveCollateral {}.data(),
+        test_governance::instruction::DelegateCollateral {}.data(),
+        test_governance::instruction::CloseCollateral {}.data(),
+    ];
+    for data in attempts {
+        let ix = instructions::governance_vote(&keys, GOVERNANCE_PROGRAM, &accounts, data);
+        h.expect_error(&[ix], &[&borrower], ErrorCode::CollateralCustodyChanged);
+    }
+    //every attempt was rolled back
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+    assert_eq!(h.token_balance(&attacker.pubkey(), &keys.nft_mint), 0);
+    assert_eq!(h.loan(&keys).lender, Some(lender.pubkey()));
+}
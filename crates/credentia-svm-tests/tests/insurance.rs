@@ -0,0 +1,337 @@
+use anchor_lang::Space;
+use credentia::{
+    error::ErrorCode,
+    events::{InsuranceClaimed, InsuranceFeeCredited, LoanInsured},
+    InsuranceFund, InsurancePolicy,
+};
+use credentia_client::{
+    instructions::{self, CollectionProof, InsuranceConfig, LoanKeys, RequestOptions},
+    pda,
+};
+use credentia_svm_tests::{events, Harness, SOL, TERMS};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+/// 20% of the platform fee into the fund, 1% premium, 80% of the shortfall covered, half the
+/// fund per claim at most.
+fn config(oracle: Pubkey) -> InsuranceConfig {
+    InsuranceConfig {
+        insurance_share_bps: 2_000,
+        premium_bps: 100,
+        coverage_bps: 8_000,
+        max_claim_bps: 5_000,
+        oracle,
+    }
+}
+
+/// Sets up the fund with `config`, returns the oracle.
+fn insured_platform(h: &mut Harness) -> Keypair {
+    let oracle = h.keypair(SOL);
+    let ix = instructions::configure_insurance(h.admin.pubkey(), h.platform, config(oracle.pubkey()));
+    h.send_admin(&[ix]);
+    oracle
+}
+
+/// A loan on `TERMS` funded with insurance, returns the borrower, the lender and its keys.
+fn insured_loan(h: &mut Harness) -> (Keypair, Keypair, LoanKeys) {
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::fund_borrower_insured(&keys, lender.pubkey(), None, TERMS);
+    h.send_ok(&[ix], &[&lender]);
+    (borrower, lender, keys)
+}
+
+fn fund_rent(h: &Harness) -> u64 {
+    h.svm.minimum_balance_for_rent_exemption(8 + InsuranceFund::INIT_SPACE)
+}
+
+#[test]
+fn configure_insurance_creates_the_fund() {
+    let mut h = Harness::new();
+    let oracle = insured_platform(&mut h);
+
+    let address = pda::insurance_fund(&h.platform).0;
+    let fund: InsuranceFund = h.account(&address);
+    assert_eq!(fund.platform, h.platform);
+    assert_eq!(fund.oracle, oracle.pubkey());
+    assert_eq!(fund.premium_bps, 100);
+    assert_eq!(fund.coverage_bps, 8_000);
+    assert_eq!(fund.max_claim_bps, 5_000);
+    assert_eq!(h.platform_account().insurance_share_bps, 2_000);
+    assert_eq!(h.balance(&address), fund_rent(&h));
+
+    //reconfiguring keeps the fund's accounting
+    let ix = instructions::configure_insurance(
+        h.admin.pubkey(),
+        h.platform,
+        InsuranceConfig { premium_bps: 200, ..config(oracle.pubkey()) },
+    );
+    h.send_admin(&[ix]);
+    assert_eq!(h.account::<InsuranceFund>(&address).premium_bps, 200);
+}
+
+#[test]
+fn configure_insurance_rejects_invalid_shares() {
+    let mut h = Harness::new();
+    let admin = h.admin.pubkey();
+    let platform = h.platform;
+    h.send_admin(&[instructions::update_platform(admin, platform, 500, 6_000)]);
+
+    let oracle = Pubkey::new_unique();
+    //referrers and the fund can't take more than the whole fee between them
+    let ix = instructions::configure_insurance(
+        admin,
+        platform,
+        InsuranceConfig { insurance_share_bps: 5_000, ..config(oracle) },
+    );
+    h.expect_admin_error(&[ix], ErrorCode::InvalidFeeBps);
+    let ix = instructions::configure_insurance(
+        admin,
+        platform,
+        InsuranceConfig { coverage_bps: 10_001, ..config(oracle) },
+    );
+    h.expect_admin_error(&[ix], ErrorCode::InvalidFeeBps);
+
+    h.send_admin(&[instructions::configure_insurance(admin, platform, config(oracle))]);
+    let ix = instructions::update_platform(admin, platform, 500, 9_000);
+    h.expect_admin_error(&[ix], ErrorCode::InvalidFeeBps);
+
+    let other = h.keypair(SOL);
+    let ix = instructions::configure_insurance(other.pubkey(), platform, config(oracle));
+    assert!(h.send(&[ix], &[&other]).is_err());
+}
+
+#[test]
+fn fund_borrower_insured_pays_the_premium_and_writes_a_policy() {
+    let mut h = Harness::new();
+    insured_platform(&mut h);
+    let fund = pda::insurance_fund(&h.platform).0;
+    let fund_before = h.balance(&fund);
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let lender = h.keypair(10 * SOL);
+
+    let ix = instructions::fund_borrower_insured(&keys, lender.pubkey(), None, TERMS);
+    let meta = h.send_ok(&[ix], &[&lender]);
+
+    let premium = TERMS.amount / 100;
+    let address = pda::insurance_policy(&keys.loan()).0;
+    let policy: InsurancePolicy = h.account(&address);
+    assert_eq!(policy.platform, h.platform);
+    assert_eq!(policy.loan, keys.loan());
+    assert_eq!(policy.lender, lender.pubkey());
+    assert_eq!(policy.nft_mint, keys.nft_mint);
+    assert_eq!(policy.principal, TERMS.amount);
+    assert_eq!(policy.premium, premium);
+    assert_eq!(policy.coverage_bps, 8_000);
+    assert_eq!(policy.deadline, h.now() + TERMS.duration as i64);
+    assert!(h.loan(&keys).insured);
+    assert_eq!(h.balance(&fund), fund_before + premium);
+    let account: InsuranceFund = h.account(&fund);
+    assert_eq!(account.total_premiums, premium);
+    assert_eq!(account.policies_written, 1);
+
+    let [insured] = &events::<LoanInsured>(&meta)[..] else {
+        panic!("expected one LoanInsured");
+    };
+    assert_eq!(insured.borrower, borrower.pubkey());
+    assert_eq!(insured.policy, address);
+    assert_eq!(insured.premium, premium);
+}
+
+#[test]
+fn repayment_routes_a_fee_share_into_the_fund_and_ends_the_cover() {
+    let mut h = Harness::new();
+    insured_platform(&mut h);
+    let (borrower, lender, keys) = insured_loan(&mut h);
+    let fund = pda::insurance_fund(&h.platform).0;
+    let policy = pda::insurance_policy(&keys.loan()).0;
+    let fund_before = h.balance(&fund);
+    let policy_rent = h.balance(&policy);
+    let lender_before = h.balance(&lender.pubkey());
+
+    let loan = h.loan(&keys);
+    let meta = h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+
+    //10% interest on 1 sol, 5% of it is the platform fee and 20% of that is insurance
+    let fee = 5_000_000;
+    let share = fee / 5;
+    assert_eq!(h.balance(&fund), fund_before + share);
+    assert_eq!(h.account::<InsuranceFund>(&fund).fee_income, share);
+    assert!(!h.exists(&policy));
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + 1_100_000_000 - fee + policy_rent);
+
+    let [credited] = &events::<InsuranceFeeCredited>(&meta)[..] else {
+        panic!("expected one InsuranceFeeCredited");
+    };
+    assert_eq!(credited.amount, share);
+    assert_eq!(credited.insurance_fund, fund);
+}
+
+#[test]
+fn uninsured_loans_also_pay_the_fee_share() {
+    let mut h = Harness::new();
+    insured_platform(&mut h);
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    let fund = pda::insurance_fund(&h.platform).0;
+    let fund_before = h.balance(&fund);
+    assert!(!h.loan(&keys).insured);
+
+    let loan = h.loan(&keys);
+    h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+    assert_eq!(h.balance(&fund), fund_before + 1_000_000);
+}
+
+#[test]
+fn insured_repayment_needs_the_policy() {
+    let mut h = Harness::new();
+    insured_platform(&mut h);
+    let (borrower, _, keys) = insured_loan(&mut h);
+    let mut loan = h.loan(&keys);
+    loan.insured = false;
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::InsuranceMismatch);
+}
+
+#[test]
+fn claim_insurance_pays_the_covered_shortfall_after_default() {
+    let mut h = Harness::new();
+    let oracle = insured_platform(&mut h);
+    let (_, lender, keys) = insured_loan(&mut h);
+    let fund = pda::insurance_fund(&h.platform).0;
+    let policy = pda::insurance_policy(&keys.loan()).0;
+    let rent = fund_rent(&h);
+    h.set_balance(&fund, rent + 10 * SOL);
+
+    h.warp(TERMS.duration as i64 + 1);
+    h.send_ok(&[instructions::default_loan(&keys, lender.pubkey())], &[&lender]);
+    let ix = instructions::set_collateral_value(oracle.pubkey(), h.platform, keys.nft_mint, 6 * SOL / 10);
+    h.send_ok(&[ix], &[&oracle]);
+    let policy_rent = h.balance(&policy);
+    let lender_before = h.balance(&lender.pubkey());
+
+    let meta = h.send_ok(&[instructions::claim_insurance(&keys, lender.pubkey())], &[&lender]);
+
+    //80% of the 0.4 sol the collateral no longer covers
+    let payout = 320_000_000;
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + payout + policy_rent - 5_000);
+    assert_eq!(h.balance(&fund), rent + 10 * SOL - payout);
+    assert!(!h.exists(&policy));
+    let account: InsuranceFund = h.account(&fund);
+    assert_eq!(account.total_claimed, payout);
+    assert_eq!(account.claims_paid, 1);
+
+    let [claimed] = &events::<InsuranceClaimed>(&meta)[..] else {
+        panic!("expected one InsuranceClaimed");
+    };
+    assert_eq!(claimed.loan, keys.loan());
+    assert_eq!(claimed.borrower, keys.borrower);
+    assert_eq!(claimed.shortfall, 4 * SOL / 10);
+    assert_eq!(claimed.payout, payout);
+}
+
+#[test]
+fn claim_insurance_is_capped_by_the_fund() {
+    let mut h = Harness::new();
+    let oracle = insured_platform(&mut h);
+    let (_, lender, keys) = insured_loan(&mut h);
+    let fund = pda::insurance_fund(&h.platform).0;
+    let rent = fund_rent(&h);
+    h.set_balance(&fund, rent + SOL / 10);
+
+    h.warp(TERMS.duration as i64 + 1);
+    let ix = instructions::set_collateral_value(oracle.pubkey(), h.platform, keys.nft_mint, 0);
+    h.send_ok(&[ix], &[&oracle]);
+    let meta = h.send_ok(&[instructions::claim_insurance(&keys, lender.pubkey())], &[&lender]);
+
+    //half of the 0.1 sol the fund holds above rent
+    let [claimed] = &events::<InsuranceClaimed>(&meta)[..] else {
+        panic!("expected one InsuranceClaimed");
+    };
+    assert_eq!(claimed.payout, SOL / 20);
+    assert_eq!(h.balance(&fund), rent + SOL / 20);
+}
+
+#[test]
+fn claim_insurance_needs_the_deadline_and_a_fresh_valuation() {
+    let mut h = Harness::new();
+    let oracle = insured_platform(&mut h);
+    let (_, lender, keys) = insured_loan(&mut h);
+    let claim = instructions::claim_insurance(&keys, lender.pubkey());
+    let value = instructions::set_collateral_value(oracle.pubkey(), h.platform, keys.nft_mint, 0);
+
+    h.send_ok(std::slice::from_ref(&value), &[&oracle]);
+    h.expect_error(std::slice::from_ref(&claim), &[&lender], ErrorCode::PolicyNotClaimable);
+    //the loan can still be repaid at the deadline
+    h.warp(TERMS.duration as i64);
+    h.expect_error(std::slice::from_ref(&claim), &[&lender], ErrorCode::PolicyNotClaimable);
+
+    h.warp(1);
+    h.expect_error(std::slice::from_ref(&claim), &[&lender], ErrorCode::StaleCollateralValuation);
+    h.send_ok(&[value], &[&oracle]);
+    h.send_ok(&[claim], &[&lender]);
+}
+
+#[test]
+fn claim_insurance_waits_for_an_empty_fund() {
+    let mut h = Harness::new();
+    let oracle = h.keypair(SOL);
+    let ix = instructions::configure_insurance(
+        h.admin.pubkey(),
+        h.platform,
+        InsuranceConfig { premium_bps: 0, ..config(oracle.pubkey()) },
+    );
+    h.send_admin(&[ix]);
+    let (_, lender, keys) = insured_loan(&mut h);
+    h.warp(TERMS.duration as i64 + 1);
+    let ix = instructions::set_collateral_value(oracle.pubkey(), h.platform, keys.nft_mint, 0);
+    h.send_ok(&[ix], &[&oracle]);
+
+    let claim = instructions::claim_insurance(&keys, lender.pubkey());
+    h.expect_error(std::slice::from_ref(&claim), &[&lender], ErrorCode::NothingToClaim);
+    assert!(h.exists(&pda::insurance_policy(&keys.loan()).0));
+
+    let fund = pda::insurance_fund(&h.platform).0;
+    let rent = fund_rent(&h);
+    h.set_balance(&fund, rent + SOL);
+    h.send_ok(&[claim], &[&lender]);
+}
+
+#[test]
+fn only_the_oracle_values_collateral_and_only_the_lender_claims() {
+    let mut h = Harness::new();
+    let oracle = insured_platform(&mut h);
+    let (_, lender, keys) = insured_loan(&mut h);
+    let other = h.keypair(SOL);
+
+    let ix = instructions::set_collateral_value(other.pubkey(), h.platform, keys.nft_mint, 0);
+    assert!(h.send(&[ix], &[&other]).is_err());
+
+    h.warp(TERMS.duration as i64 + 1);
+    let ix = instructions::set_collateral_value(oracle.pubkey(), h.platform, keys.nft_mint, 0);
+    h.send_ok(&[ix], &[&oracle]);
+    let ix = instructions::claim_insurance(&keys, other.pubkey());
+    assert!(h.send(&[ix], &[&other]).is_err());
+    h.send_ok(&[instructions::claim_insurance(&keys, lender.pubkey())], &[&lender]);
+}
+
+#[test]
+fn insurance_is_not_sold_on_token_loans() {
+    let mut h = Harness::new();
+    insured_platform(&mut h);
+    let currency = h.mint_currency(&[]);
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let options = RequestOptions { currency_mint: Some(currency.mint), ..Default::default() };
+    let ix = instructions::request_loan_with(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        None,
+        &options,
+    );
+    h.send_ok(&[ix], &[&borrower]);
+
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::fund_borrower_insured(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::InsuranceNotSupported);
+}
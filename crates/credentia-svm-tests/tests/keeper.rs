@@ -0,0 +1,87 @@
+use credentia::interest_curve::MAX_BPS;
+use credentia_client::{
+    instructions::{self, CollectionProof, LoanKeys, RequestOptions},
+    pda, AcceptanceConstraints,
+};
+use credentia_keeper::{plan, settlement_instruction, PlannedSettlement, Settlement};
+use credentia_svm_tests::{Harness, DAY, POOL_ID, SOL, TERMS};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+/// Plans against the SVM clock and sends every settlement the keeper would, signed by `keeper`.
+fn run_keeper(h: &mut Harness, keeper: &Keypair, loans: &[LoanKeys]) -> Vec<PlannedSettlement> {
+    let loans: Vec<_> = loans.iter().map(|keys| (keys.loan(), h.loan(keys))).collect();
+    let due = plan(&h.platform, &loans, h.now());
+    for planned in &due {
+        let (_, loan) = loans.iter().find(|(address, _)| *address == planned.loan).unwrap();
+        let ix = settlement_instruction(&h.platform, &keeper.pubkey(), loan, planned.settlement, |address| {
+            h.svm.get_account(address).map(|account| (account.owner, account.data))
+        })
+        .unwrap();
+        h.send_ok(&[ix], &[keeper]);
+    }
+    due
+}
+
+#[test]
+fn keeper_settles_every_loan_once_the_clock_passes_its_deadline() {
+    let mut h = Harness::new();
+    let keeper = h.keypair(SOL);
+
+    //a direct loan on spl-token and one on a token-2022 group member
+    let (_, lender, direct) = h.funded_loan(TERMS);
+    let group = h.mint_token_group();
+    let member_borrower = h.keypair(10 * SOL);
+    let member_mint = h.mint_group_nft(&member_borrower.pubkey(), &group, &[]);
+    let member = h.next_loan_keys(member_borrower.pubkey(), member_mint);
+    h.send_ok(
+        &[instructions::request_loan_as_group_member(&member, group, TERMS, None)],
+        &[&member_borrower],
+    );
+    let member_lender = h.keypair(10 * SOL);
+    h.send_ok(
+        &[instructions::fund_borrower(&member, member_lender.pubkey(), None, TERMS)],
+        &[&member_lender],
+    );
+
+    //a pool loan and a request that expires unfunded
+    let pool = h.create_pool(POOL_ID, 30 * DAY, 2 * SOL);
+    h.depositor(&pool, 10 * SOL);
+    let (pool_borrower, pool_mint) = h.borrower();
+    let pooled = h.next_loan_keys(pool_borrower.pubkey(), pool_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&pooled, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&pool_borrower],
+    );
+    let (requester, request_mint) = h.borrower();
+    let request = h.next_loan_keys(requester.pubkey(), request_mint);
+    let constraints = AcceptanceConstraints { expires_at: Some(h.now() + DAY as i64), ..Default::default() };
+    let options = RequestOptions { constraints: Some(constraints), ..Default::default() };
+    let ix = instructions::request_loan_with(&request, h.collection, CollectionProof::Metaplex, TERMS, None, &options);
+    h.send_ok(&[ix], &[&requester]);
+
+    let loans = [direct, member, pooled, request];
+    assert!(run_keeper(&mut h, &keeper, &loans).is_empty());
+
+    //the pool loan and the request fall due after a day, the direct loans after their duration
+    h.warp(DAY as i64);
+    let due = run_keeper(&mut h, &keeper, &loans);
+    let settlements: Vec<_> = due.iter().map(|p| p.settlement).collect();
+    assert_eq!(settlements.len(), 2);
+    assert!(settlements.contains(&Settlement::PoolClaimDefault { pool }));
+    assert!(settlements.contains(&Settlement::CloseExpiredRequest));
+    assert!(!h.exists(&pooled.loan()));
+    assert_eq!(h.token_balance(&pool, &pool_mint), 1);
+    assert!(h.exists(&pda::pool_collateral(&pool, &pool_mint).0));
+    assert!(!h.exists(&request.loan()));
+    assert_eq!(h.token_balance(&requester.pubkey(), &request_mint), 1);
+
+    let loans = [direct, member];
+    h.warp(TERMS.duration as i64 - DAY as i64);
+    let due = run_keeper(&mut h, &keeper, &loans);
+    assert_eq!(due.len(), 2);
+    assert!(due.iter().all(|p| matches!(p.settlement, Settlement::MarkDefaulted { .. })));
+    assert!(!h.exists(&direct.loan()));
+    assert_eq!(h.token_balance(&lender.pubkey(), &direct.nft_mint), 1);
+    assert!(!h.exists(&member.loan()));
+    assert_eq!(h.token_balance(&member_lender.pubkey(), &member_mint), 1);
+}
@@ -0,0 +1,543 @@
+use anchor_spl::token::spl_token;
+use credentia::{error::ErrorCode, events::LoanTermsUpdated, LoanStatus, ReferrerAccount};
+use credentia_client::{
+    instructions::{self, LoanKeys, LoanTerms},
+    pda,
+};
+use credentia_svm_tests::{events, Harness, DAY, SOL, TERMS};
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+// LoanNotRepaided has no test: resolve_loan marks the loan repaid right before claim_nft checks it
+
+fn duration(terms: LoanTerms) -> i64 {
+    terms.duration as i64
+}
+
+#[test]
+fn request_loan_locks_the_nft_and_takes_the_next_id() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+
+    let loan = h.loan(&keys);
+    assert_eq!(loan.loan_id, 0);
+    assert_eq!(loan.borrower, borrower.pubkey());
+    assert_eq!(loan.nft_mint, keys.nft_mint);
+    assert_eq!(loan.loan_amount, TERMS.amount);
+    assert_eq!(loan.duration, TERMS.duration);
+    assert_eq!(loan.interest_rate, TERMS.interest_rate);
+    assert!(loan.status == LoanStatus::Requested);
+    assert_eq!(loan.lender, None);
+    assert_eq!(loan.start_time, None);
+    assert_eq!(h.platform_account().loan_count, 1);
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 0);
+}
+
+#[test]
+fn request_loan_rejects_zero_amount() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let terms = LoanTerms { amount: 0, ..TERMS };
+    let ix = instructions::request_loan(&keys, h.collection, terms, None);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::InvalidAmountError);
+}
+
+#[test]
+fn request_loan_rejects_zero_duration() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let terms = LoanTerms { duration: 0, ..TERMS };
+    let ix = instructions::request_loan(&keys, h.collection, terms, None);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::InvalidDurationError);
+}
+
+#[test]
+fn fund_borrower_pays_the_borrower_and_starts_the_clock() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let lender = h.keypair(10 * SOL);
+    let before = h.balance(&borrower.pubkey());
+
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.send_ok(&[ix], &[&lender]);
+
+    let loan = h.loan(&keys);
+    assert!(loan.status == LoanStatus::Funded);
+    assert_eq!(loan.lender, Some(lender.pubkey()));
+    assert_eq!(loan.start_time, Some(h.now()));
+    assert_eq!(h.balance(&borrower.pubkey()), before + TERMS.amount);
+}
+
+#[test]
+fn fund_borrower_rejects_a_funded_loan() {
+    let mut h = Harness::new();
+    let (_, _, keys) = h.funded_loan(TERMS);
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::LoanFunded);
+}
+
+#[test]
+fn fund_borrower_rejects_a_closed_loan() {
+    let mut h = Harness::new();
+    let (_, keys) = h.requested_loan(TERMS, None);
+    h.set_loan(&keys, |loan| loan.status = LoanStatus::Repaid);
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::LoanNotActive);
+}
+
+#[test]
+fn fund_borrower_rejects_a_lender_without_the_principal() {
+    let mut h = Harness::new();
+    let (_, keys) = h.requested_loan(TERMS, None);
+    let lender = h.keypair(TERMS.amount / 2);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::InsufficientBalance);
+}
+
+#[test]
+fn cancel_loan_returns_the_nft_and_closes_the_loan() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+
+    h.send_ok(&[instructions::cancel_loan(&keys)], &[&borrower]);
+
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+    assert!(!h.exists(&keys.nft_vault()));
+}
+
+#[test]
+fn cancel_loan_rejects_a_funded_loan() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    let ix = instructions::cancel_loan(&keys);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanAlreadyFunded);
+}
+
+#[test]
+fn update_loan_request_changes_the_terms_in_place() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let rent = h.balance(&keys.loan());
+    let terms = LoanTerms { amount: 2 * SOL, duration: 3 * DAY, interest_rate: 700 };
+
+    let meta = h.send_ok(&[instructions::update_loan_request(&keys, terms)], &[&borrower]);
+
+    let loan = h.loan(&keys);
+    assert!(loan.status == LoanStatus::Requested);
+    assert_eq!(loan.loan_amount, terms.amount);
+    assert_eq!(loan.duration, terms.duration);
+    assert_eq!(loan.interest_rate, terms.interest_rate);
+    assert_eq!(h.balance(&keys.loan()), rent);
+    assert_eq!(h.token_balance(&keys.loan(), &keys.nft_mint), 1);
+    let [updated] = &events::<LoanTermsUpdated>(&meta)[..] else {
+        panic!("expected one LoanTermsUpdated");
+    };
+    assert_eq!(updated.loan, keys.loan());
+    assert_eq!(updated.loan_amount, terms.amount);
+
+    //a lender funds the updated amount
+    let lender = h.keypair(10 * SOL);
+    let before = h.balance(&borrower.pubkey());
+    h.send_ok(&[instructions::fund_borrower(&keys, lender.pubkey(), None, terms)], &[&lender]);
+    assert_eq!(h.balance(&borrower.pubkey()), before + terms.amount);
+}
+
+#[test]
+fn update_loan_request_rejects_invalid_terms() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let cases = [
+        (LoanTerms { amount: 0, ..TERMS }, ErrorCode::InvalidAmountError),
+        (LoanTerms { duration: 0, ..TERMS }, ErrorCode::InvalidDurationError),
+    ];
+    for (terms, code) in cases {
+        let ix = instructions::update_loan_request(&keys, terms);
+        h.expect_error(&[ix], &[&borrower], code);
+    }
+}
+
+#[test]
+fn update_loan_request_rejects_a_funded_loan() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    let ix = instructions::update_loan_request(&keys, LoanTerms { interest_rate: 1, ..TERMS });
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanFunded);
+}
+
+#[test]
+fn update_loan_request_rejects_another_signer() {
+    let mut h = Harness::new();
+    let (_, mut keys) = h.requested_loan(TERMS, None);
+    let other = h.keypair(SOL);
+    keys.borrower = other.pubkey();
+    let ix = instructions::update_loan_request(&keys, LoanTerms { interest_rate: 1, ..TERMS });
+    assert!(h.send(&[ix], &[&other]).is_err());
+    assert_eq!(h.loan(&keys).interest_rate, TERMS.interest_rate);
+}
+
+#[test]
+fn fund_borrower_rejects_terms_updated_before_it_lands() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let lender = h.keypair(10 * SOL);
+    //the borrower sees the lender's transaction coming and raises the rate ahead of it
+    let updated = LoanTerms { interest_rate: 5_000, ..TERMS };
+    h.send_ok(&[instructions::update_loan_request(&keys, updated)], &[&borrower]);
+
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::LoanTermsChanged);
+    for terms in [
+        LoanTerms { amount: 2 * SOL, ..updated },
+        LoanTerms { duration: 3 * DAY, ..updated },
+    ] {
+        let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, terms);
+        h.expect_error(&[ix], &[&lender], ErrorCode::LoanTermsChanged);
+    }
+    assert!(h.loan(&keys).status == LoanStatus::Requested);
+
+    h.send_ok(&[instructions::fund_borrower(&keys, lender.pubkey(), None, updated)], &[&lender]);
+    assert_eq!(h.loan(&keys).lender, Some(lender.pubkey()));
+}
+
+#[test]
+fn resolve_loan_pays_lender_and_treasury_and_returns_the_nft() {
+    let mut h = Harness::new();
+    let (borrower, lender, keys) = h.funded_loan(TERMS);
+    let treasury = pda::treasury_vault(&h.platform).0;
+    let lender_before = h.balance(&lender.pubkey());
+    let treasury_before = h.balance(&treasury);
+
+    let loan = h.loan(&keys);
+    h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+
+    //10% interest on 1 SOL, 5% of it to the platform
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + 1_095_000_000);
+    assert_eq!(h.balance(&treasury), treasury_before + 5_000_000);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+    assert!(!h.exists(&keys.nft_vault()));
+}
+
+#[test]
+fn resolve_loan_succeeds_at_the_deadline() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    h.warp(duration(TERMS));
+
+    let loan = h.loan(&keys);
+    h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+}
+
+#[test]
+fn resolve_loan_after_the_deadline_fails() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    h.warp(duration(TERMS) + 1);
+
+    let loan = h.loan(&keys);
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanDefaulted);
+}
+
+#[test]
+fn resolve_loan_splits_the_fee_with_both_referrers() {
+    let mut h = Harness::new();
+    h.send_admin(&[instructions::update_platform(h.admin.pubkey(), h.platform, 500, 5_000)]);
+    let borrower_referrer = h.keypair(SOL);
+    let lender_referrer = h.keypair(SOL);
+    for referrer in [&borrower_referrer, &lender_referrer] {
+        let ix = instructions::register_referrer(referrer.pubkey(), h.platform);
+        h.send_ok(&[ix], &[referrer]);
+    }
+
+    let (borrower, keys) = h.requested_loan(TERMS, Some(borrower_referrer.pubkey()));
+    let lender = h.keypair(10 * SOL);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), Some(lender_referrer.pubkey()), TERMS);
+    h.send_ok(&[ix], &[&lender]);
+    let treasury = pda::treasury_vault(&h.platform).0;
+    let treasury_before = h.balance(&treasury);
+
+    let loan = h.loan(&keys);
+    assert_eq!(loan.borrower_referrer, Some(borrower_referrer.pubkey()));
+    assert_eq!(loan.lender_referrer, Some(lender_referrer.pubkey()));
+    h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+
+    //half of the 0.005 SOL fee goes to referrers, split evenly
+    assert_eq!(h.balance(&treasury), treasury_before + 2_500_000);
+    for referrer in [&borrower_referrer, &lender_referrer] {
+        let address = pda::referrer(&h.platform, &referrer.pubkey()).0;
+        let account: ReferrerAccount = h.account(&address);
+        assert_eq!(account.total_earned, 1_250_000);
+        assert_eq!(account.loans_paid, 1);
+
+        let before = h.balance(&referrer.pubkey());
+        let ix = instructions::claim_referral_fees(referrer.pubkey(), h.platform);
+        h.send_ok(&[ix], &[referrer]);
+        assert_eq!(h.balance(&referrer.pubkey()), before + 1_250_000 - 5_000);
+        let account: ReferrerAccount = h.account(&address);
+        assert_eq!(account.total_claimed, 1_250_000);
+    }
+}
+
+#[test]
+fn parties_cant_refer_their_own_loan() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let lender = h.keypair(10 * SOL);
+    for party in [&borrower, &lender] {
+        let ix = instructions::register_referrer(party.pubkey(), h.platform);
+        h.send_ok(&[ix], &[party]);
+    }
+
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::request_loan(&keys, h.collection, TERMS, Some(borrower.pubkey()));
+    h.expect_error(&[ix], &[&borrower], ErrorCode::SelfReferral);
+
+    //a lender can neither refer its own funding nor collect a borrower's share it was given
+    let ix = instructions::request_loan(&keys, h.collection, TERMS, Some(lender.pubkey()));
+    h.send_ok(&[ix], &[&borrower]);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::SelfReferral);
+
+    let (_, keys) = h.requested_loan(TERMS, None);
+    let ix = instructions::fund_borrower(&keys, lender.pubkey(), Some(lender.pubkey()), TERMS);
+    h.expect_error(&[ix], &[&lender], ErrorCode::SelfReferral);
+}
+
+#[test]
+fn resolve_loan_without_the_referrer_account_fails() {
+    let mut h = Harness::new();
+    let referrer = h.keypair(SOL);
+    h.send_ok(
+        &[instructions::register_referrer(referrer.pubkey(), h.platform)],
+        &[&referrer],
+    );
+    let (borrower, keys) = h.requested_loan(TERMS, Some(referrer.pubkey()));
+    let lender = h.keypair(10 * SOL);
+    h.send_ok(&[instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS)], &[&lender]);
+
+    let mut loan = h.loan(&keys);
+    loan.borrower_referrer = None;
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::ReferrerNotMatched);
+}
+
+#[test]
+fn resolve_loan_rejects_an_unfunded_loan() {
+    let mut h = Harness::new();
+    let (borrower, keys) = h.requested_loan(TERMS, None);
+    let loan = h.loan(&keys);
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanNotStarted);
+}
+
+#[test]
+fn resolve_loan_rejects_a_repaid_loan() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    h.set_loan(&keys, |loan| loan.status = LoanStatus::Repaid);
+    let loan = h.loan(&keys);
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanRepaided);
+}
+
+#[test]
+fn resolve_loan_rejects_another_lender() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    let mut loan = h.loan(&keys);
+    loan.lender = Some(Pubkey::new_unique());
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LenderNotMatched);
+}
+
+#[test]
+fn resolve_loan_rejects_a_borrower_who_cannot_repay() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    h.set_balance(&borrower.pubkey(), TERMS.amount / 2);
+    let loan = h.loan(&keys);
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::InsufficientBalance);
+}
+
+#[test]
+fn resolve_loan_overflowing_repayment_fails() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    h.set_loan(&keys, |loan| loan.loan_amount = u64::MAX);
+    let loan = h.loan(&keys);
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::MathOverflow);
+}
+
+#[test]
+fn default_loan_gives_the_lender_the_nft_at_the_deadline() {
+    let mut h = Harness::new();
+    let (_, lender, keys) = h.funded_loan(TERMS);
+    h.warp(duration(TERMS));
+
+    h.send_ok(&[instructions::default_loan(&keys, lender.pubkey())], &[&lender]);
+
+    assert_eq!(h.token_balance(&lender.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+    assert!(!h.exists(&keys.nft_vault()));
+}
+
+#[test]
+fn default_loan_before_the_deadline_fails() {
+    let mut h = Harness::new();
+    let (_, lender, keys) = h.funded_loan(TERMS);
+    h.warp(duration(TERMS) - 1);
+    let ix = instructions::default_loan(&keys, lender.pubkey());
+    h.expect_error(&[ix], &[&lender], ErrorCode::WaitForLoanToComplete);
+}
+
+#[test]
+fn default_loan_rejects_an_unfunded_loan() {
+    let mut h = Harness::new();
+    let (_, keys) = h.requested_loan(TERMS, None);
+    let lender = h.keypair(SOL);
+    let ix = instructions::default_loan(&keys, lender.pubkey());
+    h.expect_error(&[ix], &[&lender], ErrorCode::LoanNotStarted);
+}
+
+#[test]
+fn default_loan_rejects_another_lender() {
+    let mut h = Harness::new();
+    let (_, _, keys) = h.funded_loan(TERMS);
+    h.warp(duration(TERMS));
+    let other = h.keypair(SOL);
+    let ix = instructions::default_loan(&keys, other.pubkey());
+    h.expect_error(&[ix], &[&other], ErrorCode::LenderNotMatched);
+}
+
+#[test]
+fn default_loan_rejects_a_repaid_loan() {
+    let mut h = Harness::new();
+    let (_, lender, keys) = h.funded_loan(TERMS);
+    h.warp(duration(TERMS));
+    h.set_loan(&keys, |loan| loan.status = LoanStatus::Repaid);
+    let ix = instructions::default_loan(&keys, lender.pubkey());
+    h.expect_error(&[ix], &[&lender], ErrorCode::LoanAlreadyRepaid);
+}
+
+#[test]
+fn default_loan_rejects_a_defaulted_loan() {
+    let mut h = Harness::new();
+    let (_, lender, keys) = h.funded_loan(TERMS);
+    h.warp(duration(TERMS));
+    h.set_loan(&keys, |loan| loan.status = LoanStatus::Defaulted);
+    let ix = instructions::default_loan(&keys, lender.pubkey());
+    h.expect_error(&[ix], &[&lender], ErrorCode::LoanAlreadyDefaulted);
+}
+
+#[test]
+fn mark_defaulted_sends_the_nft_to_the_lender_and_rent_to_the_caller() {
+    let mut h = Harness::new();
+    let (_, lender, keys) = h.funded_loan(TERMS);
+    let caller = h.keypair(SOL);
+    let loan_rent = h.balance(&keys.loan());
+    h.warp(duration(TERMS));
+
+    let ix = instructions::mark_defaulted(&keys, caller.pubkey(), lender.pubkey());
+    h.send_ok(&[ix], &[&caller]);
+
+    assert_eq!(h.token_balance(&lender.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+    //the vault refund pays for the lender's token account, the caller nets the loan rent
+    assert_eq!(h.balance(&caller.pubkey()), SOL + loan_rent - 5_000);
+}
+
+#[test]
+fn mark_defaulted_before_the_deadline_fails() {
+    let mut h = Harness::new();
+    let (_, lender, keys) = h.funded_loan(TERMS);
+    let caller = h.keypair(SOL);
+    let ix = instructions::mark_defaulted(&keys, caller.pubkey(), lender.pubkey());
+    h.expect_error(&[ix], &[&caller], ErrorCode::WaitForLoanToComplete);
+}
+
+#[test]
+fn migrate_legacy_loan_moves_the_loan_to_its_id_address() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let legacy = h.legacy_terms(&borrower.pubkey(), &nft_mint, LoanStatus::Requested);
+    let legacy_address = h.legacy_loan(&borrower, &nft_mint, legacy);
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let payer = h.keypair(SOL);
+
+    h.send_ok(&[instructions::migrate_legacy_loan(&keys, payer.pubkey())], &[&payer]);
+
+    let loan = h.loan(&keys);
+    assert_eq!(loan.loan_id, keys.loan_id);
+    assert_eq!(loan.borrower, borrower.pubkey());
+    assert_eq!(loan.loan_amount, TERMS.amount);
+    assert!(loan.status == LoanStatus::Requested);
+    assert_eq!(h.platform_account().loan_count, keys.loan_id + 1);
+    assert_eq!(h.token_balance(&keys.loan(), &nft_mint), 1);
+    assert!(!h.exists(&legacy_address));
+    assert!(!h.exists(&pda::nft_vault(&legacy_address, &nft_mint, &spl_token::ID)));
+}
+
+#[test]
+fn migrate_legacy_loan_grows_a_platform_in_the_old_layout() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let legacy = h.legacy_terms(&borrower.pubkey(), &nft_mint, LoanStatus::Funded);
+    h.legacy_loan(&borrower, &nft_mint, legacy);
+    //the platform as it was written before loan ids, there is no loan count to read yet
+    let platform = h.platform;
+    h.truncate_account(&platform, 8 + 37);
+    let keys = LoanKeys {
+        platform,
+        nft_mint,
+        loan_id: 0,
+        borrower: borrower.pubkey(),
+        token_program: spl_token::ID,
+    };
+    let payer = h.keypair(SOL);
+
+    h.send_ok(&[instructions::migrate_legacy_loan(&keys, payer.pubkey())], &[&payer]);
+
+    let loan = h.loan(&keys);
+    assert_eq!(loan.loan_id, 0);
+    assert!(loan.status == LoanStatus::Funded);
+    assert_eq!(loan.pool, None);
+    assert_eq!(h.token_balance(&keys.loan(), &nft_mint), 1);
+    let account = h.platform_account();
+    assert_eq!(account.loan_count, 1);
+    assert_eq!(account.authority, h.admin.pubkey());
+    //the platform upgrade itself is still recorded by migrate_platform
+    assert_eq!(account.version, 0);
+    h.send_ok(&[instructions::migrate_platform(payer.pubkey(), platform)], &[&payer]);
+    assert_eq!(h.platform_account().loan_count, 1);
+}
+
+#[test]
+fn migrate_legacy_loan_rejects_another_borrowers_loan() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let legacy = h.legacy_terms(&Pubkey::new_unique(), &nft_mint, LoanStatus::Requested);
+    h.legacy_loan(&borrower, &nft_mint, legacy);
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::migrate_legacy_loan(&keys, borrower.pubkey());
+    h.expect_error(&[ix], &[&borrower], ErrorCode::InvalidLegacyLoan);
+}
+
+#[test]
+fn migrate_legacy_loan_rejects_a_closed_loan() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let legacy = h.legacy_terms(&borrower.pubkey(), &nft_mint, LoanStatus::Repaid);
+    h.legacy_loan(&borrower, &nft_mint, legacy);
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::migrate_legacy_loan(&keys, borrower.pubkey());
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanNotActive);
+}
@@ -0,0 +1,105 @@
+use anchor_lang::Space;
+use credentia::{error::ErrorCode, events::AccountMigrated, LoanStatus};
+use credentia_client::{instructions, Loan, Platform};
+use credentia_svm_tests::{events, Harness, FEE_BPS, PLATFORM_ID, SOL, TERMS};
+use solana_sdk::signer::Signer;
+
+//account sizes before the version byte, `Loan::MAX_SPACE` and `Platform::INIT_SPACE` back then
+const V0_LOAN_LEN: usize = 8 + 233;
+const V0_PLATFORM_LEN: usize = 8 + 55;
+
+#[test]
+fn new_accounts_start_at_the_current_version() {
+    let mut h = Harness::new();
+    let (_, keys) = h.requested_loan(TERMS, None);
+    assert_eq!(h.platform_account().version, Platform::VERSION);
+    assert_eq!(h.loan(&keys).version, Loan::VERSION);
+}
+
+#[test]
+fn migrate_platform_upgrades_an_unversioned_platform() {
+    let mut h = Harness::new();
+    let platform = h.platform;
+    h.truncate_account(&platform, V0_PLATFORM_LEN);
+    let admin = h.admin.insecure_clone();
+    //other instructions can't read the old layout until it is migrated
+    let update = instructions::update_platform(admin.pubkey(), platform, 100, 0);
+    assert!(h.send(std::slice::from_ref(&update), &[&admin]).is_err());
+
+    let payer = h.keypair(SOL);
+    let meta = h.send_ok(&[instructions::migrate_platform(payer.pubkey(), platform)], &[&payer]);
+
+    let [event] = &events::<AccountMigrated>(&meta)[..] else {
+        panic!("expected one AccountMigrated");
+    };
+    assert_eq!(event.account, platform);
+    assert_eq!(event.from_version, 0);
+    assert_eq!(event.to_version, Platform::VERSION);
+    assert_eq!(event.timestamp, h.now());
+
+    let migrated = h.platform_account();
+    assert_eq!(migrated.version, Platform::VERSION);
+    assert_eq!(migrated.platform_id, PLATFORM_ID);
+    assert_eq!(migrated.fee_bps, FEE_BPS);
+    assert_eq!(migrated.authority, admin.pubkey());
+    let account = h.svm.get_account(&platform).unwrap();
+    assert_eq!(account.data.len(), 8 + Platform::INIT_SPACE);
+    assert!(account.lamports >= h.svm.minimum_balance_for_rent_exemption(account.data.len()));
+
+    h.send_ok(&[update], &[&admin]);
+    assert_eq!(h.platform_account().fee_bps, 100);
+}
+
+#[test]
+fn migrate_platform_rejects_a_current_platform() {
+    let mut h = Harness::new();
+    let payer = h.keypair(SOL);
+    let ix = instructions::migrate_platform(payer.pubkey(), h.platform);
+    h.expect_error(&[ix], &[&payer], ErrorCode::AccountAlreadyMigrated);
+}
+
+#[test]
+fn unversioned_loans_stay_usable_and_migrate() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = h.funded_loan(TERMS);
+    h.set_loan(&keys, |loan| loan.version = 0);
+    h.truncate_account(&keys.loan(), V0_LOAN_LEN);
+    assert_eq!(h.loan(&keys).version, 0);
+
+    let payer = h.keypair(SOL);
+    let payer_before = h.balance(&payer.pubkey());
+    h.send_ok(&[instructions::migrate_loan(payer.pubkey(), h.platform, keys.loan())], &[&payer]);
+
+    let loan = h.loan(&keys);
+    assert_eq!(loan.version, Loan::VERSION);
+    assert_eq!(loan.loan_id, keys.loan_id);
+    assert_eq!(loan.loan_amount, TERMS.amount);
+    assert!(loan.status == LoanStatus::Funded);
+    let account = h.svm.get_account(&keys.loan()).unwrap();
+    assert_eq!(account.data.len(), 8 + Loan::INIT_SPACE);
+    assert!(account.lamports >= h.svm.minimum_balance_for_rent_exemption(account.data.len()));
+    assert!(h.balance(&payer.pubkey()) < payer_before);
+
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.send_ok(&[ix], &[&borrower]);
+    assert!(!h.exists(&keys.loan()));
+}
+
+#[test]
+fn migrate_loan_rejects_a_current_loan() {
+    let mut h = Harness::new();
+    let (_, keys) = h.requested_loan(TERMS, None);
+    let payer = h.keypair(SOL);
+    let ix = instructions::migrate_loan(payer.pubkey(), h.platform, keys.loan());
+    h.expect_error(&[ix], &[&payer], ErrorCode::AccountAlreadyMigrated);
+}
+
+#[test]
+fn migrate_loan_rejects_a_legacy_loan() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let legacy = h.legacy_terms(&borrower.pubkey(), &nft_mint, LoanStatus::Requested);
+    let legacy_address = h.legacy_loan(&borrower, &nft_mint, legacy);
+    let ix = instructions::migrate_loan(borrower.pubkey(), h.platform, legacy_address);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::InvalidLoanAccount);
+}
@@ -0,0 +1,70 @@
+use anchor_spl::token::spl_token;
+use credentia::{error::ErrorCode, ReferrerAccount};
+use credentia_client::{instructions, pda};
+use credentia_svm_tests::{Harness, FEE_BPS, PLATFORM_ID, SOL};
+use solana_sdk::signer::Signer;
+
+#[test]
+fn initialize_platform_creates_platform_and_reward_mint() {
+    let h = Harness::new();
+    let platform = h.platform_account();
+    assert_eq!(platform.authority, h.admin.pubkey());
+    assert_eq!(platform.platform_id, PLATFORM_ID);
+    assert_eq!(platform.fee_bps, FEE_BPS);
+    assert_eq!(platform.referral_share_bps, 0);
+    assert_eq!(platform.loan_count, 0);
+    assert!(h.exists(&pda::reward_mint(&h.platform).0));
+}
+
+#[test]
+fn initialize_platform_rejects_fee_above_max() {
+    let mut h = Harness::new();
+    let ix = instructions::initialize_platform(h.admin.pubkey(), 2, 10_001, spl_token::ID);
+    h.expect_admin_error(&[ix], ErrorCode::InvalidFeeBps);
+}
+
+#[test]
+fn update_platform_sets_fee_and_referral_share() {
+    let mut h = Harness::new();
+    let ix = instructions::update_platform(h.admin.pubkey(), h.platform, 250, 2_000);
+    h.send_admin(&[ix]);
+
+    let platform = h.platform_account();
+    assert_eq!(platform.fee_bps, 250);
+    assert_eq!(platform.referral_share_bps, 2_000);
+}
+
+#[test]
+fn update_platform_rejects_bps_above_max() {
+    let mut h = Harness::new();
+    let ix = instructions::update_platform(h.admin.pubkey(), h.platform, 10_001, 0);
+    h.expect_admin_error(&[ix], ErrorCode::InvalidFeeBps);
+    let ix = instructions::update_platform(h.admin.pubkey(), h.platform, 0, 10_001);
+    h.expect_admin_error(&[ix], ErrorCode::InvalidFeeBps);
+}
+
+#[test]
+fn register_referrer_opens_an_empty_account() {
+    let mut h = Harness::new();
+    let referrer = h.keypair(SOL);
+    let ix = instructions::register_referrer(referrer.pubkey(), h.platform);
+    h.send_ok(&[ix], &[&referrer]);
+
+    let account: ReferrerAccount = h.account(&pda::referrer(&h.platform, &referrer.pubkey()).0);
+    assert_eq!(account.referrer, referrer.pubkey());
+    assert_eq!(account.platform, h.platform);
+    assert_eq!(account.total_earned, 0);
+    assert_eq!(account.loans_paid, 0);
+}
+
+#[test]
+fn claim_referral_fees_with_nothing_earned_fails() {
+    let mut h = Harness::new();
+    let referrer = h.keypair(SOL);
+    h.send_ok(
+        &[instructions::register_referrer(referrer.pubkey(), h.platform)],
+        &[&referrer],
+    );
+    let ix = instructions::claim_referral_fees(referrer.pubkey(), h.platform);
+    h.expect_error(&[ix], &[&referrer], ErrorCode::NothingToClaim);
+}
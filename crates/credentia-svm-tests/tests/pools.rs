@@ -0,0 +1,508 @@
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token::{spl_token, Mint},
+};
+use credentia::{
+    error::ErrorCode, events::{LoanFunded, PoolCollateralLiquidated}, interest_curve::MAX_BPS, CollectionConfig, LoanStatus,
+    PoolCollateral, RateCurve, LIQUIDATION_AUCTION,
+};
+use credentia_client::{
+    instructions::{self, LoanTerms},
+    pda,
+};
+use credentia_svm_tests::{curve, events, Harness, DAY, POOL_ID, SOL, TERMS};
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+const MAX_DURATION: u32 = 30 * DAY;
+const MAX_LOAN: u64 = 2 * SOL;
+const DEPOSIT: u64 = 10 * SOL;
+
+/// Harness with pool `POOL_ID` created and holding `DEPOSIT`.
+fn funded_pool() -> (Harness, Pubkey) {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    h.depositor(&pool, DEPOSIT);
+    (h, pool)
+}
+
+#[test]
+fn create_pool_sets_up_vault_and_share_mint() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+
+    let account = h.pool(&pool);
+    assert_eq!(account.authority, h.admin.pubkey());
+    assert_eq!(account.platform, h.platform);
+    assert_eq!(account.max_duration, MAX_DURATION);
+    assert_eq!(account.share_mint, pda::pool_share_mint(&pool).0);
+    assert_eq!(account.available_liquidity, 0);
+    //vault is prefunded to stay rent exempt, the reserve isn't liquidity
+    let reserve = h.svm.minimum_balance_for_rent_exemption(0);
+    assert_eq!(h.balance(&pda::pool_vault(&pool).0), reserve);
+    assert_eq!(account.nav(h.now()).unwrap(), 0);
+}
+
+#[test]
+fn create_pool_rejects_an_invalid_curve() {
+    let mut h = Harness::new();
+    let bad = RateCurve { kink: 0, ..curve() };
+    let ix = instructions::create_pool(h.admin.pubkey(), h.platform, POOL_ID, MAX_DURATION, bad, spl_token::ID);
+    h.expect_admin_error(&[ix], ErrorCode::InvalidRateCurve);
+}
+
+#[test]
+fn update_pool_changes_limits_and_curve() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let steeper = RateCurve { slope2: 6_000, ..curve() };
+    h.send_admin(&[instructions::update_pool(h.admin.pubkey(), pool, DAY, steeper)]);
+
+    let account = h.pool(&pool);
+    assert_eq!(account.max_duration, DAY);
+    assert_eq!(account.rate_curve.slope2, 6_000);
+
+    let bad = RateCurve { kink: 10_001, ..curve() };
+    let ix = instructions::update_pool(h.admin.pubkey(), pool, DAY, bad);
+    h.expect_admin_error(&[ix], ErrorCode::InvalidRateCurve);
+}
+
+#[test]
+fn set_and_remove_pool_collection() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let config_address = pda::collection_config(&pool, &h.collection).0;
+    let config: CollectionConfig = h.account(&config_address);
+    assert_eq!(config.max_loan_amount, MAX_LOAN);
+
+    h.send_admin(&[instructions::set_pool_collection(h.admin.pubkey(), pool, h.collection, SOL)]);
+    let config: CollectionConfig = h.account(&config_address);
+    assert_eq!(config.max_loan_amount, SOL);
+
+    h.send_admin(&[instructions::remove_pool_collection(h.admin.pubkey(), pool, h.collection)]);
+    assert!(!h.exists(&config_address));
+}
+
+#[test]
+fn set_pool_collection_rejects_a_zero_limit() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let ix = instructions::set_pool_collection(h.admin.pubkey(), pool, h.collection, 0);
+    h.expect_admin_error(&[ix], ErrorCode::InvalidAmountError);
+}
+
+#[test]
+fn deposit_mints_shares_one_to_one_into_an_empty_pool() {
+    let (h, pool) = funded_pool();
+    let account = h.pool(&pool);
+    assert_eq!(account.available_liquidity, DEPOSIT);
+    let share_mint = pda::pool_share_mint(&pool).0;
+    let supply = h.account::<Mint>(&share_mint).supply;
+    assert_eq!(supply, DEPOSIT);
+}
+
+#[test]
+fn deposit_rejects_zero_and_unfunded_amounts() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let depositor = h.keypair(SOL);
+
+    let ix = instructions::deposit_to_pool(depositor.pubkey(), pool, 0, spl_token::ID);
+    h.expect_error(&[ix], &[&depositor], ErrorCode::InvalidAmountError);
+    let ix = instructions::deposit_to_pool(depositor.pubkey(), pool, 2 * SOL, spl_token::ID);
+    h.expect_error(&[ix], &[&depositor], ErrorCode::InsufficientBalance);
+}
+
+#[test]
+fn withdraw_burns_shares_for_lamports() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let depositor = h.depositor(&pool, DEPOSIT);
+    let before = h.balance(&depositor.pubkey());
+
+    let ix = instructions::withdraw_from_pool(depositor.pubkey(), pool, DEPOSIT / 2, spl_token::ID);
+    h.send_ok(&[ix], &[&depositor]);
+
+    assert_eq!(h.balance(&depositor.pubkey()), before + DEPOSIT / 2 - 5_000);
+    assert_eq!(h.pool(&pool).available_liquidity, DEPOSIT / 2);
+}
+
+#[test]
+fn withdrawing_down_to_dust_keeps_the_vault_rent_exempt() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let vault = pda::pool_vault(&pool).0;
+    let reserve = h.balance(&vault);
+    let depositor = h.depositor(&pool, DEPOSIT);
+
+    //one lamport of liquidity is left on top of the reserve
+    let ix = instructions::withdraw_from_pool(depositor.pubkey(), pool, DEPOSIT - 1, spl_token::ID);
+    h.send_ok(&[ix], &[&depositor]);
+    assert_eq!(h.balance(&vault), reserve + 1);
+    assert_eq!(h.pool(&pool).nav(h.now()).unwrap(), 1);
+
+    let ix = instructions::withdraw_from_pool(depositor.pubkey(), pool, 1, spl_token::ID);
+    h.send_ok(&[ix], &[&depositor]);
+    assert_eq!(h.balance(&vault), reserve);
+    assert_eq!(h.pool(&pool).available_liquidity, 0);
+}
+
+#[test]
+fn withdraw_rejects_zero_or_unowned_shares() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let depositor = h.depositor(&pool, DEPOSIT);
+
+    let ix = instructions::withdraw_from_pool(depositor.pubkey(), pool, 0, spl_token::ID);
+    h.expect_error(&[ix], &[&depositor], ErrorCode::InvalidShareAmount);
+    let ix = instructions::withdraw_from_pool(depositor.pubkey(), pool, DEPOSIT + 1, spl_token::ID);
+    h.expect_error(&[ix], &[&depositor], ErrorCode::InvalidShareAmount);
+}
+
+#[test]
+fn withdraw_cannot_take_principal_out_on_loan() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let depositor = h.depositor(&pool, MAX_LOAN);
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&borrower],
+    );
+
+    let ix = instructions::withdraw_from_pool(depositor.pubkey(), pool, MAX_LOAN, spl_token::ID);
+    h.expect_error(&[ix], &[&depositor], ErrorCode::InsufficientPoolLiquidity);
+}
+
+#[test]
+fn borrow_from_pool_funds_the_loan_at_the_curve_rate() {
+    let (mut h, pool) = funded_pool();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let rate = h.pool(&pool).origination_rate(SOL).unwrap();
+    let before = h.balance(&borrower.pubkey());
+
+    //the quoted rate is an inclusive ceiling
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, rate)],
+        &[&borrower],
+    );
+
+    let loan = h.loan(&keys);
+    assert!(loan.status == LoanStatus::Funded);
+    assert_eq!(loan.pool, Some(pool));
+    assert_eq!(loan.lender, Some(pda::pool_vault(&pool).0));
+    assert_eq!(loan.interest_rate, rate);
+    assert_eq!(loan.start_time, Some(h.now()));
+    assert_eq!(h.token_balance(&keys.loan(), &nft_mint), 1);
+    assert!(h.balance(&borrower.pubkey()) > before);
+
+    let account = h.pool(&pool);
+    assert_eq!(account.total_borrowed, SOL);
+    assert_eq!(account.available_liquidity, DEPOSIT - SOL);
+    assert_eq!(account.active_loans, 1);
+}
+
+#[test]
+fn borrow_from_pool_enforces_terms_and_limits() {
+    let (mut h, pool) = funded_pool();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let collection = h.collection;
+    let expect = |h: &mut Harness, amount, duration, code| {
+        let ix = instructions::borrow_from_pool(&keys, collection, pool, amount, duration, MAX_BPS);
+        h.expect_error(&[ix], &[&borrower], code);
+    };
+
+    expect(&mut h, 0, DAY, ErrorCode::InvalidAmountError);
+    expect(&mut h, SOL, 0, ErrorCode::InvalidDurationError);
+    expect(&mut h, MAX_LOAN + 1, DAY, ErrorCode::PoolLoanAmountExceeded);
+    expect(&mut h, SOL, MAX_DURATION + 1, ErrorCode::PoolDurationExceeded);
+
+    //a limit above the pool's liquidity leaves liquidity as the binding constraint
+    h.send_admin(&[instructions::set_pool_collection(h.admin.pubkey(), pool, collection, 100 * SOL)]);
+    expect(&mut h, DEPOSIT + 1, DAY, ErrorCode::InsufficientPoolLiquidity);
+}
+
+#[test]
+fn borrow_from_pool_rejects_an_nft_without_a_collection() {
+    let (mut h, pool) = funded_pool();
+    let borrower = h.keypair(10 * SOL);
+    let nft_mint = h.mint_nft(&borrower.pubkey(), None);
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+
+    let ix = instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::CollateralNotInCollection);
+}
+
+#[test]
+fn borrow_from_pool_rejects_a_rate_above_the_borrowers_max() {
+    let (mut h, pool) = funded_pool();
+    let (borrower, nft_mint) = h.borrower();
+    let quoted = h.pool(&pool).origination_rate(SOL).unwrap();
+
+    //another borrow lands first and pushes utilization up the curve
+    let (other, other_mint) = h.borrower();
+    let other_keys = h.next_loan_keys(other.pubkey(), other_mint);
+    let collection = h.collection;
+    let ix = instructions::borrow_from_pool(&other_keys, collection, pool, MAX_LOAN, DAY, MAX_BPS);
+    h.send_ok(&[ix], &[&other]);
+    assert!(h.pool(&pool).origination_rate(SOL).unwrap() > quoted);
+
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::borrow_from_pool(&keys, collection, pool, SOL, DAY, quoted);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::PoolInterestRateTooLow);
+    assert!(!h.exists(&keys.loan()));
+}
+
+#[test]
+fn pool_fund_loan_funds_a_request_at_the_curve_rate() {
+    let (mut h, pool) = funded_pool();
+    let terms = LoanTerms { interest_rate: 5_000, ..TERMS };
+    let (_, keys) = h.requested_loan(terms, None);
+    let rate = h.pool(&pool).origination_rate(terms.amount).unwrap();
+    let caller = h.keypair(SOL);
+
+    let meta = h.send_ok(
+        &[instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool)],
+        &[&caller],
+    );
+
+    let loan = h.loan(&keys);
+    assert!(loan.status == LoanStatus::Funded);
+    assert_eq!(loan.pool, Some(pool));
+    //the requested rate is a cap, the pool charges its own
+    assert_eq!(loan.interest_rate, rate);
+    let [funded] = &events::<LoanFunded>(&meta)[..] else {
+        panic!("expected one LoanFunded");
+    };
+    assert_eq!(funded.interest_rate, rate);
+    assert_eq!(h.pool(&pool).active_loans, 1);
+}
+
+#[test]
+fn pool_fund_loan_rejects_a_funded_loan() {
+    let (mut h, pool) = funded_pool();
+    let (_, _, keys) = h.funded_loan(TERMS);
+    let caller = h.keypair(SOL);
+    let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+    h.expect_error(&[ix], &[&caller], ErrorCode::LoanFunded);
+}
+
+#[test]
+fn pool_fund_loan_enforces_pool_limits_and_rate() {
+    let (mut h, pool) = funded_pool();
+    let caller = h.keypair(SOL);
+    let cases = [
+        (LoanTerms { amount: MAX_LOAN + 1, ..TERMS }, ErrorCode::PoolLoanAmountExceeded),
+        (LoanTerms { duration: MAX_DURATION + 1, ..TERMS }, ErrorCode::PoolDurationExceeded),
+        (LoanTerms { interest_rate: 1, ..TERMS }, ErrorCode::PoolInterestRateTooLow),
+    ];
+    for (terms, code) in cases {
+        let (_, keys) = h.requested_loan(terms, None);
+        let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+        h.expect_error(&[ix], &[&caller], code);
+    }
+
+    h.send_admin(&[instructions::set_pool_collection(h.admin.pubkey(), pool, h.collection, 100 * SOL)]);
+    let (_, keys) = h.requested_loan(LoanTerms { amount: DEPOSIT + 1, ..TERMS }, None);
+    let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+    h.expect_error(&[ix], &[&caller], ErrorCode::InsufficientPoolLiquidity);
+}
+
+#[test]
+fn repaying_a_pool_loan_returns_liquidity_to_the_pool() {
+    let (mut h, pool) = funded_pool();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&borrower],
+    );
+    let loan = h.loan(&keys);
+
+    h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+
+    let account = h.pool(&pool);
+    assert_eq!(account.total_borrowed, 0);
+    assert_eq!(account.active_loans, 0);
+    assert!(account.available_liquidity > DEPOSIT);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &nft_mint), 1);
+}
+
+#[test]
+fn repaying_a_pool_loan_without_the_pool_fails() {
+    let (mut h, pool) = funded_pool();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&borrower],
+    );
+    let mut loan = h.loan(&keys);
+    loan.pool = None;
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::PoolMismatch);
+}
+
+#[test]
+fn pool_claim_default_and_liquidation() {
+    let (mut h, pool) = funded_pool();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&borrower],
+    );
+    let interest = SOL * h.loan(&keys).interest_rate as u64 / 10_000;
+    let caller = h.keypair(SOL);
+    let claim = instructions::pool_claim_default(&keys, caller.pubkey(), pool);
+
+    h.warp(DAY as i64 - 1);
+    h.expect_error(std::slice::from_ref(&claim), &[&caller], ErrorCode::WaitForLoanToComplete);
+    h.warp(1);
+    h.send_ok(&[claim], &[&caller]);
+
+    assert_eq!(h.token_balance(&pool, &nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+    let account = h.pool(&pool);
+    assert_eq!(account.total_borrowed, 0);
+    assert_eq!(account.active_loans, 0);
+    let liquidity = account.available_liquidity;
+    //the auction opens at what the borrower owed
+    let address = pda::pool_collateral(&pool, &nft_mint).0;
+    let collateral: PoolCollateral = h.account(&address);
+    assert_eq!(collateral.start_price, SOL + interest);
+    assert_eq!(collateral.auction_start, h.now());
+    assert_eq!(collateral.payer, caller.pubkey());
+    let pool_nft_ata = get_associated_token_address_with_program_id(&pool, &nft_mint, &spl_token::ID);
+    let rent = h.balance(&address) + h.balance(&pool_nft_ata);
+    let caller_before = h.balance(&caller.pubkey());
+
+    //the authority can't hand the nft to itself below the auction price
+    let admin = h.admin.insecure_clone();
+    let sell = |buyer: Pubkey, max_price| {
+        instructions::liquidate_pool_collateral(caller.pubkey(), buyer, nft_mint, pool, max_price, spl_token::ID)
+    };
+    let ix = sell(admin.pubkey(), 1);
+    h.expect_error(&[ix], &[&admin], ErrorCode::BelowAuctionPrice);
+    let poor = h.keypair(SOL / 2);
+    let ix = sell(poor.pubkey(), 2 * SOL);
+    h.expect_error(&[ix], &[&poor], ErrorCode::InsufficientBalance);
+    //the rent only goes back to whoever paid it
+    let ix = instructions::liquidate_pool_collateral(
+        admin.pubkey(),
+        admin.pubkey(),
+        nft_mint,
+        pool,
+        2 * SOL,
+        spl_token::ID,
+    );
+    assert!(h.send(&[ix], &[&admin]).is_err());
+
+    //halfway through the price has fallen by half, anyone can buy without the authority
+    h.warp(LIQUIDATION_AUCTION / 2);
+    let price = (SOL + interest) / 2;
+    let buyer = h.keypair(SOL);
+    let ix = sell(buyer.pubkey(), price - 1);
+    h.expect_error(&[ix], &[&buyer], ErrorCode::BelowAuctionPrice);
+    let meta = h.send_ok(&[sell(buyer.pubkey(), price)], &[&buyer]);
+
+    assert_eq!(h.token_balance(&buyer.pubkey(), &nft_mint), 1);
+    let [liquidated] = &events::<PoolCollateralLiquidated>(&meta)[..] else {
+        panic!("expected one PoolCollateralLiquidated");
+    };
+    assert_eq!(liquidated.loan_id, keys.loan_id);
+    assert_eq!(liquidated.loan, keys.loan());
+    assert_eq!(liquidated.borrower, borrower.pubkey());
+    assert_eq!(liquidated.price, price);
+    assert_eq!(h.pool(&pool).available_liquidity, liquidity + price);
+    assert!(!h.exists(&address));
+    assert!(!h.exists(&pool_nft_ata));
+    //the keeper that claimed the default is made whole
+    assert_eq!(h.balance(&caller.pubkey()), caller_before + rent);
+}
+
+#[test]
+fn withdrawing_before_the_write_off_shares_the_loss() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, MAX_DURATION, MAX_LOAN);
+    let first = h.depositor(&pool, DEPOSIT);
+    let second = h.depositor(&pool, DEPOSIT);
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&borrower],
+    );
+    let liquidity = h.pool(&pool).available_liquidity;
+
+    //past the deadline nobody has claimed the default yet, the loan already counts for nothing
+    h.warp(DAY as i64);
+    let ix = instructions::withdraw_from_pool(first.pubkey(), pool, DEPOSIT, spl_token::ID);
+    h.send_ok(&[ix], &[&first]);
+    let first_out = liquidity - h.pool(&pool).available_liquidity;
+    assert_eq!(first_out, (2 * DEPOSIT - SOL) / 2);
+    //and deposits wait until the default is settled
+    let newcomer = h.keypair(DEPOSIT + SOL);
+    let deposit = instructions::deposit_to_pool(newcomer.pubkey(), pool, DEPOSIT, spl_token::ID);
+    h.expect_error(std::slice::from_ref(&deposit), &[&newcomer], ErrorCode::PoolSettlementPending);
+
+    let caller = h.keypair(SOL);
+    h.send_ok(&[instructions::pool_claim_default(&keys, caller.pubkey(), pool)], &[&caller]);
+    assert_eq!(h.pool(&pool).collateral_held, 1);
+    h.expect_error(std::slice::from_ref(&deposit), &[&newcomer], ErrorCode::PoolSettlementPending);
+
+    //the depositor who stayed lost exactly as much
+    let before = h.pool(&pool).available_liquidity;
+    let ix = instructions::withdraw_from_pool(second.pubkey(), pool, DEPOSIT, spl_token::ID);
+    h.send_ok(&[ix], &[&second]);
+    assert_eq!(before - h.pool(&pool).available_liquidity, first_out);
+
+    //once the collateral is sold the pool takes deposits again
+    let buyer = h.keypair(2 * SOL);
+    let ix = instructions::liquidate_pool_collateral(
+        caller.pubkey(),
+        buyer.pubkey(),
+        nft_mint,
+        pool,
+        2 * SOL,
+        spl_token::ID,
+    );
+    h.send_ok(&[ix], &[&buyer]);
+    assert_eq!(h.pool(&pool).collateral_held, 0);
+    h.send_ok(&[deposit], &[&newcomer]);
+}
+
+#[test]
+fn pool_claim_default_rejects_another_pools_loan() {
+    let (mut h, pool) = funded_pool();
+    let other = h.create_pool(POOL_ID + 1, MAX_DURATION, MAX_LOAN);
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&borrower],
+    );
+    h.warp(DAY as i64);
+
+    let caller = h.keypair(SOL);
+    let ix = instructions::pool_claim_default(&keys, caller.pubkey(), other);
+    h.expect_error(&[ix], &[&caller], ErrorCode::PoolMismatch);
+}
+
+#[test]
+fn mark_defaulted_rejects_pool_loans() {
+    let (mut h, pool) = funded_pool();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    h.send_ok(
+        &[instructions::borrow_from_pool(&keys, h.collection, pool, SOL, DAY, MAX_BPS)],
+        &[&borrower],
+    );
+    h.warp(DAY as i64);
+
+    let caller = h.keypair(SOL);
+    let ix = instructions::mark_defaulted(&keys, caller.pubkey(), pda::pool_vault(&pool).0);
+    h.expect_error(&[ix], &[&caller], ErrorCode::PoolLoanNotSupported);
+}
@@ -0,0 +1,348 @@
+use anchor_lang::Space;
+use credentia::{
+    error::ErrorCode, events::DefaultProtectionPurchased, DefaultProtection, InsurancePolicy, Loan,
+    LoanStatus, MAX_PROTECTION_EXTENSION,
+};
+use credentia_client::{
+    instructions::{self, CollectionProof, InsuranceConfig, LoanKeys, LoanTerms, RequestOptions},
+    pda, AcceptanceConstraints,
+};
+use credentia_svm_tests::{events, Harness, DAY, POOL_ID, SOL, TERMS};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+/// A day of protection on `TERMS`: a seventh of the 0.1 SOL interest.
+const EXTENSION: u32 = DAY;
+const PREMIUM: u64 = 14_285_714;
+
+fn request_options(extension: u32) -> RequestOptions {
+    RequestOptions { protection: Some(extension), ..Default::default() }
+}
+
+/// Opens a request on `terms` with `EXTENSION` of protection, returns the borrower and the loan's keys.
+fn protected(h: &mut Harness, terms: LoanTerms) -> (Keypair, LoanKeys) {
+    protected_with(h, terms, request_options(EXTENSION))
+}
+
+fn protected_with(h: &mut Harness, terms: LoanTerms, options: RequestOptions) -> (Keypair, LoanKeys) {
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::request_loan_with(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        terms,
+        None,
+        &options,
+    );
+    h.send_ok(&[ix], &[&borrower]);
+    (borrower, keys)
+}
+
+/// Protected request on `TERMS` funded by a new lender.
+fn protected_loan(h: &mut Harness) -> (Keypair, Keypair, LoanKeys) {
+    let (borrower, keys) = protected(h, TERMS);
+    let lender = h.keypair(10 * SOL);
+    h.send_ok(&[instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS)], &[&lender]);
+    (borrower, lender, keys)
+}
+
+fn loan_rent(h: &Harness) -> u64 {
+    h.svm.minimum_balance_for_rent_exemption(8 + Loan::INIT_SPACE)
+}
+
+#[test]
+fn request_loan_escrows_the_premium() {
+    let mut h = Harness::new();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::request_loan_with(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        None,
+        &request_options(EXTENSION),
+    );
+    let meta = h.send_ok(&[ix], &[&borrower]);
+
+    let loan = h.loan(&keys);
+    assert_eq!(loan.protection, Some(DefaultProtection { extension: EXTENSION, premium: PREMIUM }));
+    assert_eq!(loan.repayment_window(), (TERMS.duration + EXTENSION) as i64);
+    assert_eq!(h.balance(&keys.loan()), loan_rent(&h) + PREMIUM);
+
+    let [purchased] = &events::<DefaultProtectionPurchased>(&meta)[..] else {
+        panic!("expected one DefaultProtectionPurchased");
+    };
+    assert_eq!(purchased.loan, keys.loan());
+    assert_eq!(purchased.borrower, borrower.pubkey());
+    assert_eq!(purchased.extension, EXTENSION);
+    assert_eq!(purchased.premium, PREMIUM);
+}
+
+#[test]
+fn request_loan_rejects_invalid_protection() {
+    let mut h = Harness::new();
+    for extension in [0, MAX_PROTECTION_EXTENSION + 1] {
+        let (borrower, nft_mint) = h.borrower();
+        let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+        let ix = instructions::request_loan_with(
+            &keys,
+            h.collection,
+            CollectionProof::Metaplex,
+            TERMS,
+            None,
+            &request_options(extension),
+        );
+        h.expect_error(&[ix], &[&borrower], ErrorCode::InvalidProtectionExtension);
+    }
+
+    //the premium is lamports, so token loans can't buy it
+    let currency = h.mint_currency(&[]);
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let options = RequestOptions { currency_mint: Some(currency.mint), ..request_options(EXTENSION) };
+    let ix = instructions::request_loan_with(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        None,
+        &options,
+    );
+    h.expect_error(&[ix], &[&borrower], ErrorCode::ProtectionNotSupported);
+}
+
+#[test]
+fn fund_borrower_pays_the_premium_to_the_lender() {
+    let mut h = Harness::new();
+    let (borrower, keys) = protected(&mut h, TERMS);
+    let lender = h.keypair(10 * SOL);
+    let borrower_before = h.balance(&borrower.pubkey());
+    let lender_before = h.balance(&lender.pubkey());
+    let reputation = pda::lender_reputation(&h.platform, &lender.pubkey()).0;
+
+    h.send_ok(&[instructions::fund_borrower(&keys, lender.pubkey(), None, TERMS)], &[&lender]);
+
+    assert_eq!(h.balance(&borrower.pubkey()), borrower_before + TERMS.amount);
+    assert_eq!(
+        h.balance(&lender.pubkey()),
+        lender_before - TERMS.amount + PREMIUM - h.balance(&reputation) - 5_000
+    );
+    assert_eq!(h.balance(&keys.loan()), loan_rent(&h));
+}
+
+#[test]
+fn resolve_loan_is_allowed_within_the_extension() {
+    let mut h = Harness::new();
+    let (borrower, lender, keys) = protected_loan(&mut h);
+    h.warp((TERMS.duration + EXTENSION) as i64);
+    let lender_before = h.balance(&lender.pubkey());
+
+    let loan = h.loan(&keys);
+    h.send_ok(&[instructions::resolve_loan(&keys, &loan)], &[&borrower]);
+
+    //a late repayment costs no more than an on time one, the premium already paid for the delay
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + 1_095_000_000);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+}
+
+#[test]
+fn resolve_loan_after_the_extension_fails() {
+    let mut h = Harness::new();
+    let (borrower, _, keys) = protected_loan(&mut h);
+    h.warp((TERMS.duration + EXTENSION) as i64 + 1);
+
+    let loan = h.loan(&keys);
+    let ix = instructions::resolve_loan(&keys, &loan);
+    h.expect_error(&[ix], &[&borrower], ErrorCode::LoanDefaulted);
+}
+
+#[test]
+fn defaults_wait_for_the_extension() {
+    let mut h = Harness::new();
+    let (_, lender, keys) = protected_loan(&mut h);
+    let caller = h.keypair(SOL);
+    h.warp(TERMS.duration as i64);
+
+    let ix = instructions::default_loan(&keys, lender.pubkey());
+    h.expect_error(&[ix], &[&lender], ErrorCode::WaitForLoanToComplete);
+    let ix = instructions::mark_defaulted(&keys, caller.pubkey(), lender.pubkey());
+    h.expect_error(&[ix], &[&caller], ErrorCode::WaitForLoanToComplete);
+
+    h.warp(EXTENSION as i64);
+    let ix = instructions::mark_defaulted(&keys, caller.pubkey(), lender.pubkey());
+    h.send_ok(&[ix], &[&caller]);
+    assert_eq!(h.token_balance(&lender.pubkey(), &keys.nft_mint), 1);
+}
+
+#[test]
+fn pool_fund_loan_credits_the_premium_to_the_pool() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, 30 * DAY, 2 * SOL);
+    h.depositor(&pool, 10 * SOL);
+    let terms = LoanTerms { interest_rate: 5_000, ..TERMS };
+    let (borrower, keys) = protected(&mut h, terms);
+    let requested_premium = h.loan(&keys).protection_premium();
+    let rate = h.pool(&pool).origination_rate(terms.amount).unwrap();
+    let liquidity = h.pool(&pool).available_liquidity;
+    let vault = pda::pool_vault(&pool).0;
+    let vault_before = h.balance(&vault);
+    let borrower_before = h.balance(&borrower.pubkey());
+
+    let caller = h.keypair(SOL);
+    h.send_ok(
+        &[instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool)],
+        &[&caller],
+    );
+
+    //the pool charges its curve rate, so the premium is repriced at it and the rest refunded
+    let premium = h.loan(&keys).protection_premium();
+    assert_eq!(requested_premium, 71_428_571);
+    assert_eq!(premium, (terms.amount * rate as u64 / 10_000) * EXTENSION as u64 / TERMS.duration as u64);
+    assert!(premium < requested_premium);
+    assert_eq!(h.pool(&pool).available_liquidity, liquidity - terms.amount + premium);
+    assert_eq!(h.balance(&vault), vault_before - terms.amount + premium);
+    assert_eq!(
+        h.balance(&borrower.pubkey()),
+        borrower_before + terms.amount + requested_premium - premium
+    );
+    assert_eq!(h.balance(&keys.loan()), loan_rent(&h));
+
+    let loan = h.loan(&keys);
+    let ix = instructions::pool_claim_default(&keys, caller.pubkey(), pool);
+    h.warp(loan.repayment_window() - 1);
+    h.expect_error(std::slice::from_ref(&ix), &[&caller], ErrorCode::WaitForLoanToComplete);
+    h.warp(1);
+    h.send_ok(&[ix], &[&caller]);
+}
+
+#[test]
+fn pool_fund_loan_limits_the_repayment_window_to_the_pools_max_duration() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, TERMS.duration, 2 * SOL);
+    h.depositor(&pool, 10 * SOL);
+    let caller = h.keypair(SOL);
+
+    //the duration fits the pool but the extension pushes the default past it
+    let (_, keys) = protected(&mut h, TERMS);
+    let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+    h.expect_error(&[ix], &[&caller], ErrorCode::PoolDurationExceeded);
+
+    let (_, keys) = h.requested_loan(TERMS, None);
+    let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+    h.send_ok(&[ix], &[&caller]);
+}
+
+#[test]
+fn pool_fund_loan_rejects_a_premium_repriced_upwards() {
+    let mut h = Harness::new();
+    let pool = h.create_pool(POOL_ID, 30 * DAY, 2 * SOL);
+    h.depositor(&pool, 10 * SOL);
+    let (_, keys) = protected(&mut h, LoanTerms { interest_rate: 5_000, ..TERMS });
+    //less escrowed than the pool's rate prices the extension at
+    h.set_loan(&keys, |loan| {
+        loan.protection = Some(DefaultProtection { extension: EXTENSION, premium: 0 })
+    });
+
+    let caller = h.keypair(SOL);
+    let ix = instructions::pool_fund_loan(&keys, caller.pubkey(), h.collection, pool);
+    h.expect_error(&[ix], &[&caller], ErrorCode::MathOverflow);
+}
+
+#[test]
+fn update_loan_request_reprices_the_premium() {
+    let mut h = Harness::new();
+    let (borrower, keys) = protected(&mut h, TERMS);
+    let before = h.balance(&borrower.pubkey());
+
+    //doubling the rate doubles the premium, the borrower pays the difference
+    let terms = LoanTerms { interest_rate: 2_000, ..TERMS };
+    h.send_ok(&[instructions::update_loan_request(&keys, terms)], &[&borrower]);
+    assert_eq!(h.loan(&keys).protection_premium(), 2 * PREMIUM);
+    assert_eq!(h.balance(&borrower.pubkey()), before - PREMIUM - 5_000);
+    assert_eq!(h.balance(&keys.loan()), loan_rent(&h) + 2 * PREMIUM);
+
+    //a zero rate makes it free and refunds everything
+    let terms = LoanTerms { interest_rate: 0, ..TERMS };
+    h.send_ok(&[instructions::update_loan_request(&keys, terms)], &[&borrower]);
+    assert_eq!(h.loan(&keys).protection_premium(), 0);
+    assert_eq!(h.balance(&borrower.pubkey()), before - 10_000);
+    assert_eq!(h.balance(&keys.loan()), loan_rent(&h));
+}
+
+#[test]
+fn accept_counter_offer_reprices_the_premium_for_the_offer() {
+    let mut h = Harness::new();
+    let (borrower, keys) = protected(&mut h, TERMS);
+    let lender = h.keypair(10 * SOL);
+    let offer = LoanTerms { amount: SOL / 2, duration: 14 * DAY, interest_rate: 1_500 };
+    let ix = instructions::make_counter_offer(&keys, lender.pubkey(), None, offer);
+    h.send_ok(&[ix], &[&lender]);
+    let address = pda::counter_offer(&keys.loan(), &lender.pubkey()).0;
+    let rent = h.balance(&address) - offer.amount;
+    let borrower_before = h.balance(&borrower.pubkey());
+    let lender_before = h.balance(&lender.pubkey());
+
+    h.send_ok(&[instructions::accept_counter_offer(&keys, lender.pubkey(), offer)], &[&borrower]);
+
+    //0.075 SOL of interest over 14 days, a fourteenth of it for the day of extension
+    let premium = 5_357_142;
+    let loan = h.loan(&keys);
+    assert_eq!(loan.protection, Some(DefaultProtection { extension: EXTENSION, premium }));
+    assert_eq!(loan.repayment_window(), (offer.duration + EXTENSION) as i64);
+    assert_eq!(
+        h.balance(&borrower.pubkey()),
+        borrower_before + offer.amount + PREMIUM - premium - 5_000
+    );
+    assert_eq!(h.balance(&lender.pubkey()), lender_before + rent + premium);
+    assert_eq!(h.balance(&keys.loan()), loan_rent(&h));
+}
+
+#[test]
+fn unfunded_requests_refund_the_premium_to_the_borrower() {
+    let mut h = Harness::new();
+    let (borrower, keys) = protected(&mut h, TERMS);
+    let before = h.balance(&borrower.pubkey());
+    let vault_rent = h.balance(&keys.nft_vault());
+    h.send_ok(&[instructions::cancel_loan(&keys)], &[&borrower]);
+    assert_eq!(h.balance(&borrower.pubkey()), before + loan_rent(&h) + PREMIUM + vault_rent - 5_000);
+
+    //closing an expired request pays the caller the rent, not the premium
+    let expires_at = Some(h.now() + DAY as i64);
+    let options = RequestOptions {
+        constraints: Some(AcceptanceConstraints { expires_at, ..Default::default() }),
+        ..request_options(EXTENSION)
+    };
+    let (borrower, keys) = protected_with(&mut h, TERMS, options);
+    h.warp(DAY as i64);
+    let caller = h.keypair(SOL);
+    let borrower_before = h.balance(&borrower.pubkey());
+    h.send_ok(&[instructions::close_expired_request(&keys, caller.pubkey())], &[&caller]);
+    assert_eq!(h.balance(&borrower.pubkey()), borrower_before + PREMIUM);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+}
+
+#[test]
+fn insurance_covers_the_loan_until_the_extension_ends() {
+    let mut h = Harness::new();
+    let oracle = h.keypair(SOL);
+    let config = InsuranceConfig {
+        insurance_share_bps: 2_000,
+        premium_bps: 100,
+        coverage_bps: 8_000,
+        max_claim_bps: 5_000,
+        oracle: oracle.pubkey(),
+    };
+    h.send_admin(&[instructions::configure_insurance(h.admin.pubkey(), h.platform, config)]);
+    let (_, keys) = protected(&mut h, TERMS);
+    let lender = h.keypair(10 * SOL);
+
+    let ix = instructions::fund_borrower_insured(&keys, lender.pubkey(), None, TERMS);
+    h.send_ok(&[ix], &[&lender]);
+
+    let policy: InsurancePolicy = h.account(&pda::insurance_policy(&keys.loan()).0);
+    assert_eq!(policy.deadline, h.now() + (TERMS.duration + EXTENSION) as i64);
+    assert!(h.loan(&keys).status == LoanStatus::Funded);
+}
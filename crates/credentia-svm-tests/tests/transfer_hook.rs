@@ -0,0 +1,139 @@
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use credentia::{error::ErrorCode, math::Repayment};
+use credentia_client::{
+    hook::with_vault_transfer_hook,
+    instructions::{self, with_transfer_hook, CollectionProof, Currency, LoanKeys},
+    pda,
+};
+use credentia_keeper::{settlement_instruction, Settlement};
+use credentia_svm_tests::{Harness, FEE_BPS, HOOK_PROGRAM, SOL, TERMS};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+/// Adds what `HOOK_PROGRAM` needs for a transfer of `mint`.
+fn hooked(h: &Harness, ix: Instruction, mint: &Pubkey) -> Instruction {
+    with_transfer_hook(ix, mint, &HOOK_PROGRAM, &h.hook_accounts(mint))
+}
+
+/// Adds `HOOK_PROGRAM`'s accounts for releasing the loan's nft to `recipient`, resolved from
+/// the hook's validation account the way clients and the keeper do.
+fn releasing(h: &Harness, ix: Instruction, keys: &LoanKeys, recipient: &Pubkey) -> Instruction {
+    with_vault_transfer_hook(ix, keys, recipient, &HOOK_PROGRAM, |address| {
+        h.svm.get_account(address).map(|account| account.data)
+    })
+    .unwrap()
+}
+
+/// A loan paid in a hooked currency against a hooked group member nft, funded. Returns the
+/// borrower, the lender, the loan's keys and the currency.
+fn hooked_loan(h: &mut Harness) -> (Keypair, Keypair, LoanKeys, Currency) {
+    let currency = h.mint_currency(&[ExtensionType::TransferHook]);
+    let group = h.mint_token_group();
+    let borrower = h.keypair(10 * SOL);
+    let nft_mint = h.mint_hooked_group_nft(&borrower.pubkey(), &group);
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+
+    let ix = instructions::request_loan_in_currency(
+        &keys,
+        group,
+        CollectionProof::TokenGroup,
+        TERMS,
+        None,
+        currency.mint,
+    );
+    let ix = hooked(h, ix, &nft_mint);
+    h.send_ok(&[ix], &[&borrower]);
+
+    let lender = h.keypair(10 * SOL);
+    h.currency_tokens(&currency, &lender.pubkey(), TERMS.amount);
+    h.currency_tokens(&currency, &borrower.pubkey(), 0);
+    let ix = instructions::fund_borrower_in_currency(&keys, lender.pubkey(), None, TERMS, &currency);
+    let ix = hooked(h, ix, &currency.mint);
+    h.send_ok(&[ix], &[&lender]);
+
+    assert_eq!(h.hook_transfers(&nft_mint), 1);
+    assert_eq!(h.hook_transfers(&currency.mint), 1);
+    (borrower, lender, keys, currency)
+}
+
+#[test]
+fn repay_and_claim_run_through_both_hooks() {
+    let mut h = Harness::new();
+    let (borrower, lender, keys, currency) = hooked_loan(&mut h);
+    let treasury = pda::treasury_vault(&h.platform).0;
+    h.currency_tokens(&currency, &treasury, 0);
+    h.currency_tokens(&currency, &borrower.pubkey(), TERMS.amount);
+    let loan = h.loan(&keys);
+
+    //token-2022 can't call the hooks without their accounts
+    let ix = instructions::resolve_loan_in_currency(&keys, &loan, &currency);
+    assert!(h.send(std::slice::from_ref(&ix), &[&borrower]).is_err());
+
+    let ix = hooked(&h, ix, &currency.mint);
+    let ix = releasing(&h, ix, &keys, &borrower.pubkey());
+    h.send_ok(&[ix], &[&borrower]);
+
+    //the lender's and the treasury's share went through the currency's hook, the nft through its own
+    assert_eq!(h.hook_transfers(&currency.mint), 3);
+    assert_eq!(h.hook_transfers(&keys.nft_mint), 2);
+    let repayment = Repayment::new(TERMS.amount, TERMS.interest_rate, FEE_BPS).unwrap();
+    assert_eq!(
+        h.token_balance(&lender.pubkey(), &currency.mint),
+        repayment.amount_to_pay_lender
+    );
+    assert_eq!(h.token_balance(&treasury, &currency.mint), repayment.fee_for_platform);
+    assert_eq!(h.token_balance(&borrower.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+}
+
+#[test]
+fn lender_claims_a_defaulted_nft_through_its_hook() {
+    let mut h = Harness::new();
+    let (_, lender, keys, _) = hooked_loan(&mut h);
+    h.warp(TERMS.duration as i64);
+
+    let ix = instructions::default_loan(&keys, lender.pubkey());
+    assert!(h.send(std::slice::from_ref(&ix), &[&lender]).is_err());
+    let ix = releasing(&h, ix, &keys, &lender.pubkey());
+    h.send_ok(&[ix], &[&lender]);
+
+    assert_eq!(h.hook_transfers(&keys.nft_mint), 2);
+    assert_eq!(h.token_balance(&lender.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.nft_vault()));
+}
+
+#[test]
+fn keeper_forwards_the_hook_accounts_of_a_defaulted_nft() {
+    let mut h = Harness::new();
+    let (_, lender, keys, _) = hooked_loan(&mut h);
+    h.warp(TERMS.duration as i64);
+    let keeper = h.keypair(SOL);
+
+    let loan = h.loan(&keys);
+    let settlement = Settlement::MarkDefaulted { lender: lender.pubkey() };
+    let ix = settlement_instruction(&h.platform, &keeper.pubkey(), &loan, settlement, |address| {
+        h.svm.get_account(address).map(|account| (account.owner, account.data))
+    })
+    .unwrap();
+    h.send_ok(&[ix], &[&keeper]);
+
+    assert_eq!(h.hook_transfers(&keys.nft_mint), 2);
+    assert_eq!(h.token_balance(&lender.pubkey(), &keys.nft_mint), 1);
+    assert!(!h.exists(&keys.loan()));
+}
+
+#[test]
+fn request_loan_rejects_a_currency_whose_hook_can_still_change() {
+    let mut h = Harness::new();
+    let currency = h.mint_currency_with_mutable_hook();
+    let (borrower, nft_mint) = h.borrower();
+    let keys = h.next_loan_keys(borrower.pubkey(), nft_mint);
+    let ix = instructions::request_loan_in_currency(
+        &keys,
+        h.collection,
+        CollectionProof::Metaplex,
+        TERMS,
+        None,
+        currency.mint,
+    );
+    h.expect_error(&[ix], &[&borrower], ErrorCode::UnsupportedCurrency);
+}
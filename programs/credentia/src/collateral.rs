@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{
+            group_member_pointer::GroupMemberPointer, non_transferable::NonTransferable,
+            permanent_delegate::PermanentDelegate, transfer_hook::TransferHook,
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        onchain::invoke_transfer_checked,
+        state::Mint as MintState,
+    },
+    token_interface::{Mint, TransferChecked},
+};
+use spl_token_group_interface::state::TokenGroupMember;
+
+use crate::error::ErrorCode;
+
+//collateral is either a metaplex nft in a verified collection (on either token program) or a
+//token-2022 nft that is a member of the collection's token group. metaplex collection and
+//verified flag are checked by the account constraints, `metaplex` says whether metadata and
+//master edition were passed
+pub fn verify_collateral(mint: &InterfaceAccount<Mint>, collection: &Pubkey, metaplex: bool) -> Result<()> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    screen_extensions(&state)?;
+    if metaplex {
+        return Ok(());
+    }
+
+    //member data has to live on the mint itself, token-2022 only writes it with the group's
+    //update authority signing
+    let member_address = state
+        .get_extension::<GroupMemberPointer>()
+        .ok()
+        .and_then(|pointer| Option::<Pubkey>::from(pointer.member_address));
+    require!(member_address == Some(mint.key()), ErrorCode::CollateralNotInCollection);
+    let member = state
+        .get_extension::<TokenGroupMember>()
+        .map_err(|_| ErrorCode::CollateralNotInCollection)?;
+    require_keys_eq!(member.mint, mint.key(), ErrorCode::CollateralNotInCollection);
+    require_keys_eq!(member.group, *collection, ErrorCode::CollateralNotInCollection);
+
+    //without a master edition only a single token nobody can mint more of (or freeze) is an nft
+    require!(
+        state.base.supply == 1
+            && state.base.decimals == 0
+            && state.base.mint_authority.is_none()
+            && state.base.freeze_authority.is_none(),
+        ErrorCode::UnsupportedCollateral
+    );
+    Ok(())
+}
+
+//a permanent delegate could pull the nft out of the vault, a non transferable nft could never be
+//released and a hook whose program can still be swapped could start refusing the release
+fn screen_extensions(state: &StateWithExtensions<MintState>) -> Result<()> {
+    let permanent_delegate = state
+        .get_extension::<PermanentDelegate>()
+        .is_ok_and(|e| Option::<Pubkey>::from(e.delegate).is_some());
+    let non_transferable = state.get_extension::<NonTransferable>().is_ok();
+    let mutable_hook = state
+        .get_extension::<TransferHook>()
+        .is_ok_and(|e| Option::<Pubkey>::from(e.authority).is_some());
+    require!(
+        !permanent_delegate && !non_transferable && !mutable_hook,
+        ErrorCode::UnsafeCollateralExtension
+    );
+    Ok(())
+}
+
+//transfer_checked of the nft on whichever token program owns it. the context's remaining
+//accounts are the transfer hook's program, validation account and extra accounts, they are
+//only read when the mint has a hook
+pub fn transfer_nft<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    decimals: u8,
+) -> Result<()> {
+    invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        &ctx.remaining_accounts,
+        1,
+        decimals,
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{
+            transfer_fee::TransferFeeConfig, transfer_hook::TransferHook,
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+        onchain::invoke_transfer_checked,
+        state::Mint as MintState,
+    },
+    token_interface::{Mint, TransferChecked},
+};
+
+use crate::error::ErrorCode;
+
+//mint extensions a loan currency may carry. fees and hooks are paid for by the sender, the rest
+//only describe the mint. a hook has to be immutable, as for collateral, or its program could be
+//swapped for one refusing the repayment once the loan is funded. anything else (frozen default account state, non transferable, a
+//permanent delegate that could sweep the treasury's fees, ...) is rejected
+const SUPPORTED_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::TransferHook,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+//loans can be denominated in an spl token instead of lamports, on either token program
+pub fn verify_currency(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let supported = state
+        .get_extension_types()?
+        .iter()
+        .all(|extension| SUPPORTED_EXTENSIONS.contains(extension));
+    let mutable_hook = state
+        .get_extension::<TransferHook>()
+        .is_ok_and(|e| Option::<Pubkey>::from(e.authority).is_some());
+    require!(supported && !mutable_hook, ErrorCode::UnsupportedCurrency);
+    Ok(())
+}
+
+//what has to be sent for the recipient to be credited exactly `amount` after the mint's
+//transfer fee for the current epoch
+pub fn gross_amount(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let epoch = Clock::get()?.epoch;
+    let gross = config
+        .get_epoch_fee(epoch)
+        .calculate_pre_fee_amount(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(gross)
+}
+
+//transfer_checked of the loan currency so that `to` is credited exactly `amount`, the sender
+//covers the transfer fee. the context's remaining accounts are forwarded to the transfer hook
+//as in `transfer_nft`
+pub fn transfer_currency<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    mint: &InterfaceAccount<'info, Mint>,
+    amount: u64,
+) -> Result<()> {
+    let gross = gross_amount(mint, amount)?;
+    invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        &ctx.remaining_accounts,
+        gross,
+        mint.decimals,
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
@@ -31,4 +31,90 @@ pub enum ErrorCode {
     LoanNotStarted,
     #[msg("Loan is funded")]
     LoanAlreadyFunded,
+    #[msg("Loan amount exceeds pool limit for this collection")]
+    PoolLoanAmountExceeded,
+    #[msg("Loan duration exceeds pool limit")]
+    PoolDurationExceeded,
+    #[msg("Interest rate below pool rate")]
+    PoolInterestRateTooLow,
+    #[msg("Insufficient pool liquidity")]
+    InsufficientPoolLiquidity,
+    #[msg("Pool not matched")]
+    PoolMismatch,
+    #[msg("Invalid share amount")]
+    InvalidShareAmount,
+    #[msg("Invalid rate curve")]
+    InvalidRateCurve,
+    #[msg("Invalid legacy loan account")]
+    InvalidLegacyLoan,
+    #[msg("Invalid fee bps")]
+    InvalidFeeBps,
+    #[msg("Referrer not matched")]
+    ReferrerNotMatched,
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+    #[msg("Pool loans default through pool_claim_default")]
+    PoolLoanNotSupported,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Account already at the current version")]
+    AccountAlreadyMigrated,
+    #[msg("Loan account does not match its seeds")]
+    InvalidLoanAccount,
+    #[msg("Collateral mint is not a supported nft")]
+    UnsupportedCollateral,
+    #[msg("Collateral mint has an extension that can move or lock it")]
+    UnsafeCollateralExtension,
+    #[msg("Collateral is not a member of the collection")]
+    CollateralNotInCollection,
+    #[msg("Loan currency is not supported")]
+    UnsupportedCurrency,
+    #[msg("Currency accounts do not match the loan currency")]
+    CurrencyMismatch,
+    #[msg("Request expiry must be in the future")]
+    InvalidRequestExpiry,
+    #[msg("Too many allowed lenders")]
+    TooManyAllowedLenders,
+    #[msg("Loan request has expired")]
+    RequestExpired,
+    #[msg("Loan request has not expired")]
+    RequestNotExpired,
+    #[msg("Lender is not allowed to fund this request")]
+    LenderNotAllowed,
+    #[msg("Lender has not funded enough loans for this request")]
+    LenderReputationTooLow,
+    #[msg("Counter offers are only made on lamport loans")]
+    CounterOfferNotSupported,
+    #[msg("Insurance only covers lamport loans")]
+    InsuranceNotSupported,
+    #[msg("Insurance accounts do not match the loan")]
+    InsuranceMismatch,
+    #[msg("Policy can only be claimed after the repayment deadline")]
+    PolicyNotClaimable,
+    #[msg("Collateral has not been valued since the repayment deadline")]
+    StaleCollateralValuation,
+    #[msg("Default protection only covers lamport loans")]
+    ProtectionNotSupported,
+    #[msg("Protection extension must be between 1 second and the maximum window")]
+    InvalidProtectionExtension,
+    #[msg("Flash used collateral must be returned later in the same transaction")]
+    CollateralNotReturned,
+    #[msg("Governance program can't be relayed to")]
+    GovernanceProgramNotAllowed,
+    #[msg("Governance instruction prefixes must be 1 to 8 bytes, at most 8 of them")]
+    InvalidGovernanceInstructions,
+    #[msg("Instruction is not allowed for this governance program")]
+    GovernanceInstructionNotAllowed,
+    #[msg("Relayed instruction changed the collateral vault")]
+    CollateralCustodyChanged,
+    #[msg("Terms changed since the signer agreed to them")]
+    LoanTermsChanged,
+    #[msg("Offer is below the collateral's current auction price")]
+    BelowAuctionPrice,
+    #[msg("Pool has overdue loans or collateral in auction, deposits wait until they settle")]
+    PoolSettlementPending,
+    #[msg("Pool has reached its limit of outstanding loans")]
+    PoolLoanLimitReached,
+    #[msg("A borrower or lender can't be a referrer on their own loan")]
+    SelfReferral,
 }
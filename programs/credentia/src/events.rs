@@ -1,24 +1,56 @@
 use anchor_lang::prelude::*;
 
+use crate::RateCurve;
+
+//every event is emitted through emit_cpi! so it lands in the instruction data of a
+//self invocation instead of a log line that can be truncated
+//loan events are keyed by loan_id, loan and borrower
+
 #[event]
-pub struct LoanFunded {
-    pub lender: Pubkey,
-    pub loan_amount: u64,
-    pub funded_at: i64,
+pub struct PlatformInitialized {
+    pub platform: Pubkey,
+    pub authority: Pubkey,
+    pub platform_id: u64,
+    pub fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformUpdated {
+    pub platform: Pubkey,
+    pub authority: Pubkey,
+    pub fee_bps: u16,
+    pub referral_share_bps: u16,
+    pub timestamp: i64,
 }
 
 #[event]
 pub struct LoanRequested {
+    pub loan_id: u64,
+    pub loan: Pubkey,
     pub borrower: Pubkey,
     pub nft_mint: Pubkey,
     pub loan_amount: u64,
     pub duration: u32,
-    pub interest_rate: u8,
-    pub timestamp: u64,
+    pub interest_rate: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanFunded {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub loan_amount: u64,
+    //rate the loan was funded at, a pool replaces the requested rate with its own
+    pub interest_rate: u16,
+    pub funded_at: i64,
 }
 
 #[event]
 pub struct LoanRepaid {
+    pub loan_id: u64,
     pub loan: Pubkey,
     pub borrower: Pubkey,
     pub lender: Pubkey,
@@ -27,18 +59,326 @@ pub struct LoanRepaid {
     pub timestamp: i64,
 }
 
+//borrower got the nft back after repaying
 #[event]
 pub struct NFTClaimed {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+//collateral went to the lender (or pool custody), `caller` is whoever settled it
+#[event]
+pub struct LoanDefaulted {
+    pub loan_id: u64,
     pub loan: Pubkey,
     pub borrower: Pubkey,
+    pub lender: Pubkey,
     pub nft_mint: Pubkey,
+    pub caller: Pubkey,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct LoanCancelled {
+    pub loan_id: u64,
+    pub loan: Pubkey,
     pub borrower: Pubkey,
     pub nft_mint: Pubkey,
     pub platform: Pubkey,
     pub timestamp: i64,
-}
\ No newline at end of file
+}
+
+//a request past its expiry closed by `closed_by`, who keeps the rent
+#[event]
+pub struct LoanRequestExpired {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub closed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LegacyLoanMigrated {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub legacy_loan: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformFeeSplit {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub fee_for_platform: u64,
+    pub treasury_amount: u64,
+    pub insurance_amount: u64,
+    pub borrower_referrer: Option<Pubkey>,
+    pub borrower_referrer_amount: u64,
+    pub lender_referrer: Option<Pubkey>,
+    pub lender_referrer_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferrerRegistered {
+    pub referrer: Pubkey,
+    pub platform: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralFeesClaimed {
+    pub referrer: Pubkey,
+    pub platform: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub platform: Pubkey,
+    pub authority: Pubkey,
+    pub pool_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUpdated {
+    pub pool: Pubkey,
+    pub max_duration: u32,
+    pub rate_curve: RateCurve,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolCollectionSet {
+    pub pool: Pubkey,
+    pub collection: Pubkey,
+    pub max_loan_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolCollectionRemoved {
+    pub pool: Pubkey,
+    pub collection: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolDeposited {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub shares_minted: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolWithdrawn {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub shares_burned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolCollateralLiquidated {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub pool: Pubkey,
+    pub buyer: Pubkey,
+    pub nft_mint: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+//a lender escrowed `amount` against different terms for a request
+#[event]
+pub struct CounterOfferMade {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub counter_offer: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub duration: u32,
+    pub interest_rate: u16,
+    pub timestamp: i64,
+}
+
+//the borrower took the offer, the loan now has its terms and is funded
+#[event]
+pub struct CounterOfferAccepted {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub counter_offer: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub duration: u32,
+    pub interest_rate: u16,
+    pub timestamp: i64,
+}
+
+//the offer was closed without funding, by the borrower rejecting it or the lender withdrawing it,
+//and `refunded` went back to the lender
+#[event]
+pub struct CounterOfferClosed {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub counter_offer: Pubkey,
+    pub lender: Pubkey,
+    pub refunded: u64,
+    pub rejected: bool,
+    pub timestamp: i64,
+}
+
+//the borrower changed the terms of an unfunded request
+#[event]
+pub struct LoanTermsUpdated {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub loan_amount: u64,
+    pub duration: u32,
+    pub interest_rate: u16,
+    pub timestamp: i64,
+}
+
+//the platform authority set up or changed the insurance fund
+#[event]
+pub struct InsuranceConfigured {
+    pub platform: Pubkey,
+    pub insurance_fund: Pubkey,
+    pub oracle: Pubkey,
+    pub insurance_share_bps: u16,
+    pub premium_bps: u16,
+    pub coverage_bps: u16,
+    pub max_claim_bps: u16,
+    pub timestamp: i64,
+}
+
+//the oracle valued an nft, claims on loans it backed settle against `value`
+#[event]
+pub struct CollateralValued {
+    pub platform: Pubkey,
+    pub nft_mint: Pubkey,
+    pub value: u64,
+    pub timestamp: i64,
+}
+
+//the lender paid `premium` into the insurance fund for cover on the loan they funded
+#[event]
+pub struct LoanInsured {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub policy: Pubkey,
+    pub premium: u64,
+    pub coverage_bps: u16,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+//part of a repaid loan's platform fee went into the insurance fund
+#[event]
+pub struct InsuranceFeeCredited {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub insurance_fund: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+//an insured lender was paid `payout` out of the fund for a loan that was not repaid
+#[event]
+pub struct InsuranceClaimed {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub policy: Pubkey,
+    pub collateral_value: u64,
+    pub shortfall: u64,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+//borrower escrowed `premium` for `extension` extra seconds to repay after the deadline
+#[event]
+pub struct DefaultProtectionPurchased {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub extension: u32,
+    pub premium: u64,
+    pub timestamp: i64,
+}
+
+//borrower took the nft out of the vault, it is back by the end of the transaction
+#[event]
+pub struct CollateralFlashUsed {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+//the flash used nft is back in the vault
+#[event]
+pub struct CollateralReturned {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+//borrowers can relay `instructions` of `program_id` with their escrowed nft
+#[event]
+pub struct GovernanceProgramSet {
+    pub platform: Pubkey,
+    pub program_id: Pubkey,
+    pub instructions: Vec<Vec<u8>>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernanceProgramRemoved {
+    pub platform: Pubkey,
+    pub program_id: Pubkey,
+    pub timestamp: i64,
+}
+
+//the loan account signed a governance instruction for its borrower, `instruction` is the start of its data
+#[event]
+pub struct GovernanceVoteRelayed {
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    pub program_id: Pubkey,
+    pub instruction: Vec<u8>,
+    pub timestamp: i64,
+}
@@ -0,0 +1,229 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::collateral::{transfer_nft, verify_collateral};
+use crate::state::{CollectionConfig, LendingPool, Loan, LoanStatus, Platform};
+use crate::{
+    error::ErrorCode,
+    events::{LoanFunded, LoanRequested},
+};
+
+//borrower deposits an nft and is funded by a pool in the same instruction
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BorrowFromPool<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    pub borrower_nft_collection: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program,
+    )]
+    pub borrower_nft_ata: InterfaceAccount<'info, TokenAccount>,
+    //metaplex nfts pass metadata and master edition, token-2022 group members leave both out
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            borrower_nft_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata.collection.as_ref().is_some_and(|c| c.key == borrower_nft_collection.key() && c.verified) @ ErrorCode::CollateralNotInCollection,
+    )]
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            borrower_nft_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Option<Box<Account<'info, MasterEditionAccount>>>,
+
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), platform.loan_count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+
+    #[account(
+        init,
+        payer = borrower,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
+    )]
+    pub nft_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = platform,
+        seeds = [b"pool", platform.key().as_ref(), lending_pool.pool_id.to_le_bytes().as_ref()],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        mut,
+        seeds = [b"pool_vault", lending_pool.key().as_ref()],
+        bump = lending_pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(
+        seeds = [b"collection_config", lending_pool.key().as_ref(), borrower_nft_collection.key().as_ref()],
+        bump = collection_config.bump,
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> BorrowFromPool<'info> {
+    pub fn borrow(
+        &mut self,
+        amount: u64,
+        duration: u32,
+        max_interest_rate: u16,
+        bumps: &BorrowFromPoolBumps,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<(LoanRequested, LoanFunded)> {
+        require!(amount > 0, ErrorCode::InvalidAmountError);
+        require!(duration > 0, ErrorCode::InvalidDurationError);
+        verify_collateral(
+            &self.borrower_nft_mint,
+            &self.borrower_nft_collection.key(),
+            self.metadata.is_some() && self.master_edition.is_some(),
+        )?;
+        require!(
+            amount <= self.collection_config.max_loan_amount,
+            ErrorCode::PoolLoanAmountExceeded
+        );
+        require!(
+            amount <= self.lending_pool.available_liquidity,
+            ErrorCode::InsufficientPoolLiquidity
+        );
+
+        //the pool charges its curve rate, which moves with every borrow and withdrawal landing
+        //first, so the borrower names the most they will pay
+        let interest_rate = self.lending_pool.origination_rate(amount)?;
+        require!(
+            interest_rate <= max_interest_rate,
+            ErrorCode::PoolInterestRateTooLow
+        );
+        let current_time = Clock::get()?.unix_timestamp;
+        self.loan_account.set_inner(Loan {
+            borrower: self.borrower.key(),
+            nft_mint: self.borrower_nft_mint.key(),
+            lender: Some(self.pool_vault.key()),
+            loan_amount: amount,
+            duration,
+            status: LoanStatus::Funded,
+            interest_rate,
+            bump: bumps.loan_account,
+            start_time: Some(current_time),
+            time_of_liquidation_or_repayment: None,
+            pool: Some(self.lending_pool.key()),
+            loan_id: self.platform.loan_count,
+            borrower_referrer: None,
+            lender_referrer: None,
+            version: Loan::VERSION,
+            currency: None,
+            expires_at: None,
+            allowed_lenders: Vec::new(),
+            min_lender_reputation: 0,
+            insured: false,
+            protection: None,
+        });
+        //same limit `pool_fund_loan` puts on the repayment window, a direct borrow has no extension
+        require!(
+            self.loan_account.repayment_window() <= self.lending_pool.max_duration as i64,
+            ErrorCode::PoolDurationExceeded
+        );
+        self.platform.loan_count = self
+            .platform
+            .loan_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        //nft into the vault
+        let cpi_nft = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.borrower_nft_ata.to_account_info(),
+                mint: self.borrower_nft_mint.to_account_info(),
+                to: self.nft_vault.to_account_info(),
+                authority: self.borrower.to_account_info(),
+            },
+        );
+        let cpi_nft = cpi_nft.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_nft, self.borrower_nft_mint.decimals)?;
+
+        //funds out of the pool vault
+        let pool_key = self.lending_pool.key();
+        let seeds = &[
+            b"pool_vault".as_ref(),
+            pool_key.as_ref(),
+            &[self.lending_pool.vault_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.pool_vault.to_account_info(),
+                to: self.borrower.to_account_info(),
+            },
+            signer_seeds,
+        );
+        transfer(cpi_context, amount)?;
+
+        let deadline = current_time
+            .checked_add(self.loan_account.repayment_window())
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.lending_pool
+            .record_origination(self.loan_account.loan_id, amount, deadline)?;
+
+        let requested = LoanRequested {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            nft_mint: self.borrower_nft_mint.key(),
+            loan_amount: amount,
+            duration,
+            interest_rate,
+            timestamp: current_time,
+        };
+        let funded = LoanFunded {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            lender: self.pool_vault.key(),
+            loan_amount: amount,
+            interest_rate,
+            funded_at: current_time,
+        };
+
+        Ok((requested, funded))
+    }
+}
@@ -1,9 +1,17 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{ close_account, transfer_checked, CloseAccount, TransferChecked}, token_interface::{Mint, TokenAccount, TokenInterface}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked}};
 
-use crate::{error::ErrorCode, events::LoanCancelled, Loan, LoanStatus, Platform};
+use crate::{
+    collateral::transfer_nft,
+    error::ErrorCode,
+    events::LoanCancelled,
+    Loan,
+    LoanStatus,
+    Platform,
+};
 
 //borrower cancel the loan
+#[event_cpi]
 #[derive(Accounts)]
 pub struct BorrowerCancelLoan<'info> {
     #[account(mut)]
@@ -14,6 +22,7 @@ pub struct BorrowerCancelLoan<'info> {
         mut,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = borrower,
+        associated_token::token_program = token_program,
     )]
     pub borrower_nft_ata: InterfaceAccount<'info, TokenAccount>,
     
@@ -21,7 +30,7 @@ pub struct BorrowerCancelLoan<'info> {
         mut,
         has_one  = borrower,
         close = borrower,
-        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref()],
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
         bump=loan_account.bump,
     )]
     pub loan_account: Account<'info, Loan>,
@@ -30,13 +39,10 @@ pub struct BorrowerCancelLoan<'info> {
         mut,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
     )]
     pub nft_vault: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(
-        seeds = [b"platform"],
-        bump = platform.bump,
-    )]
     pub platform: Account<'info, Platform>,
 
     pub system_program: Program<'info, System>,
@@ -45,14 +51,16 @@ pub struct BorrowerCancelLoan<'info> {
 }
 
 impl<'info> BorrowerCancelLoan<'info>{
-    pub fn cancel_loan(&mut self) -> Result<()> {
+    pub fn cancel_loan(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<LoanCancelled> {
         require!(self.loan_account.status == LoanStatus::Requested , ErrorCode::LoanAlreadyFunded);
         
         //transfering nft to borrower
+        let loan_id = self.loan_account.loan_id.to_le_bytes();
         let seeds = &[
             b"loan".as_ref(),
             &self.borrower_nft_mint.key().to_bytes()[..],
             &self.platform.key().to_bytes()[..],
+            &loan_id[..],
             &[self.loan_account.bump],
         ];
         let signer_seeds = &[&seeds[..]];
@@ -67,7 +75,8 @@ impl<'info> BorrowerCancelLoan<'info>{
             },
             signer_seeds,
         );
-        transfer_checked(cpi_context, 1, self.borrower_nft_mint.decimals)?;
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
 
         //closing nft_vault account
         // close the nft_vault token account (returns rent to borrower)
@@ -82,15 +91,13 @@ impl<'info> BorrowerCancelLoan<'info>{
         );
         close_account(cpi_close)?;
 
-        emit!(LoanCancelled {
+        Ok(LoanCancelled {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
             borrower: self.borrower.key(),
             nft_mint: self.borrower_nft_mint.key(),
             platform: self.platform.key(),
             timestamp: Clock::get()?.unix_timestamp,
-        });
-
-
-
-        Ok(())
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    collateral::transfer_nft,
+    error::ErrorCode,
+    events::LoanRequestExpired,
+    lamports::move_lamports,
+    Loan,
+    LoanStatus,
+    Platform,
+};
+
+//anyone can close a request past its expiry, the nft and any protection premium go back to the borrower and the caller keeps the rent refunds as a bounty
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseExpiredRequest<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    #[account(mut)]
+    pub borrower: SystemAccount<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = caller,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program,
+    )]
+    pub borrower_nft_ata: InterfaceAccount<'info, TokenAccount>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = borrower,
+        close = caller,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    #[account(
+        mut,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
+    )]
+    pub nft_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> CloseExpiredRequest<'info> {
+    pub fn close_expired(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<LoanRequestExpired> {
+        require!(
+            self.loan_account.status == LoanStatus::Requested,
+            ErrorCode::LoanAlreadyFunded
+        );
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            self.loan_account.is_expired(current_time),
+            ErrorCode::RequestNotExpired
+        );
+
+        let loan_id = self.loan_account.loan_id.to_le_bytes();
+        let seeds = &[
+            b"loan".as_ref(),
+            &self.borrower_nft_mint.key().to_bytes()[..],
+            &self.platform.key().to_bytes()[..],
+            &loan_id[..],
+            &[self.loan_account.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.nft_vault.to_account_info(),
+                mint: self.borrower_nft_mint.to_account_info(),
+                to: self.borrower_nft_ata.to_account_info(),
+                authority: self.loan_account.to_account_info(),
+            },
+            signer_seeds,
+        );
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
+
+        //vault rent covers the borrower ata the caller may have paid for
+        let cpi_close = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.nft_vault.to_account_info(),
+                destination: self.caller.to_account_info(),
+                authority: self.loan_account.to_account_info(),
+            },
+            signer_seeds,
+        );
+        close_account(cpi_close)?;
+
+        //the protection premium is the borrower's, only the rent is the caller's bounty
+        move_lamports(
+            &self.loan_account.to_account_info(),
+            &self.borrower.to_account_info(),
+            self.loan_account.protection_premium(),
+        )?;
+
+        Ok(LoanRequestExpired {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            closed_by: self.caller.key(),
+            timestamp: current_time,
+        })
+    }
+}
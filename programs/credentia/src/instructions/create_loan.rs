@@ -1,15 +1,27 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
     metadata::{MasterEditionAccount, Metadata, MetadataAccount},
-    token::{transfer_checked, TransferChecked},
-    token_interface::{Mint, TokenAccount, TokenInterface},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::state::{Loan, LoanStatus, Platform};
-use crate::{error::ErrorCode, events::LoanRequested};
+use crate::collateral::{transfer_nft, verify_collateral};
+use crate::currency::verify_currency;
+use crate::state::{
+    AcceptanceConstraints, DefaultProtection, Loan, LoanStatus, Platform, ReferrerAccount,
+    MAX_PROTECTION_EXTENSION,
+};
+use crate::{
+    error::ErrorCode,
+    events::{DefaultProtectionPurchased, LoanRequested},
+    math::protection_premium,
+};
 
 //borrower create a loan
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CreateLoan<'info> {
     #[account(mut)]
@@ -20,8 +32,10 @@ pub struct CreateLoan<'info> {
         mut,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = borrower,
+        associated_token::token_program = token_program,
     )]
     pub borrower_nft_ata: InterfaceAccount<'info, TokenAccount>,
+    //metaplex nfts pass metadata and master edition, token-2022 group members leave both out
     #[account(
         seeds = [
             b"metadata",
@@ -30,10 +44,9 @@ pub struct CreateLoan<'info> {
         ],
         seeds::program = metadata_program.key(),
         bump,
-        constraint = metadata.collection.as_ref().unwrap().key.as_ref() == borrower_nft_collection.key().as_ref(),
-        constraint = metadata.collection.as_ref().unwrap().verified,
+        constraint = metadata.collection.as_ref().is_some_and(|c| c.key == borrower_nft_collection.key() && c.verified) @ ErrorCode::CollateralNotInCollection,
     )]
-    pub metadata: Account<'info, MetadataAccount>,
+    pub metadata: Option<Account<'info, MetadataAccount>>,
     #[account(
         seeds = [
             b"metadata",
@@ -44,13 +57,13 @@ pub struct CreateLoan<'info> {
         seeds::program = metadata_program.key(),
         bump,
     )]
-    pub master_edition: Account<'info, MasterEditionAccount>,
+    pub master_edition: Option<Account<'info, MasterEditionAccount>>,
 
     #[account(
         init,
         payer = borrower,
-        space = 8 + Loan::MAX_SPACE,
-        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref()],
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), platform.loan_count.to_le_bytes().as_ref()],
         bump,
     )]
     pub loan_account: Account<'info, Loan>,
@@ -60,14 +73,20 @@ pub struct CreateLoan<'info> {
         payer = borrower,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
     )]
     pub nft_vault: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(mut)]
+    pub platform: Account<'info, Platform>,
+    //frontend that brought the borrower in, gets a share of the platform fee on repayment
     #[account(
-        seeds = [b"platform"],
-        bump = platform.bump,
+        seeds = [b"referrer", platform.key().as_ref(), referrer_account.referrer.as_ref()],
+        bump = referrer_account.bump,
     )]
-    pub platform: Account<'info, Platform>,
+    pub referrer_account: Option<Account<'info, ReferrerAccount>>,
+    //spl token the loan is paid in, lamports when left out
+    pub currency_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
 
     pub metadata_program: Program<'info, Metadata>,
     pub system_program: Program<'info, System>,
@@ -81,27 +100,97 @@ impl<'info> CreateLoan<'info> {
         amount: u64,
         duration: u32,
         interest_rate: u16,
+        constraints: Option<AcceptanceConstraints>,
+        protection: Option<u32>,
         bumps: &CreateLoanBumps,
-    ) -> Result<()> {
+    ) -> Result<Option<DefaultProtectionPurchased>> {
         require!(amount > 0, ErrorCode::InvalidAmountError);
         require!(duration > 0, ErrorCode::InvalidDurationError);
+        verify_collateral(
+            &self.borrower_nft_mint,
+            &self.borrower_nft_collection.key(),
+            self.metadata.is_some() && self.master_edition.is_some(),
+        )?;
+        if let Some(currency_mint) = &self.currency_mint {
+            verify_currency(currency_mint)?;
+        }
 
         self.loan_account.set_inner(Loan {
             borrower: self.borrower.key(),
             nft_mint: self.borrower_nft_mint.key(),
             lender: None,
             loan_amount: amount,
-            duration: duration,
+            duration,
             status: LoanStatus::Requested,
-            interest_rate: interest_rate,
+            interest_rate,
             bump: bumps.loan_account,
             start_time: None,
             time_of_liquidation_or_repayment: None,
+            pool: None,
+            loan_id: self.platform.loan_count,
+            borrower_referrer: self.referrer_account.as_ref().map(|r| r.referrer),
+            lender_referrer: None,
+            version: Loan::VERSION,
+            currency: self.currency_mint.as_ref().map(|mint| mint.key()),
+            expires_at: None,
+            allowed_lenders: Vec::new(),
+            min_lender_reputation: 0,
+            insured: false,
+            protection: None,
         });
-        Ok(())
+        self.loan_account.check_referrers()?;
+        if let Some(constraints) = constraints {
+            self.loan_account
+                .set_constraints(constraints, Clock::get()?.unix_timestamp)?;
+        }
+        self.platform.loan_count = self
+            .platform
+            .loan_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        match protection {
+            Some(extension) => self.buy_protection(extension).map(Some),
+            None => Ok(None),
+        }
     }
 
-    pub fn transfer_nft_vault(&mut self) -> Result<()> {
+    //escrows the premium in the loan account, it is paid out when the request is funded
+    fn buy_protection(&mut self, extension: u32) -> Result<DefaultProtectionPurchased> {
+        require!(
+            self.currency_mint.is_none(),
+            ErrorCode::ProtectionNotSupported
+        );
+        require!(
+            extension > 0 && extension <= MAX_PROTECTION_EXTENSION,
+            ErrorCode::InvalidProtectionExtension
+        );
+        let loan = &self.loan_account;
+        let premium =
+            protection_premium(loan.loan_amount, loan.interest_rate, loan.duration, extension)?;
+        if premium > 0 {
+            let cpi_context = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.borrower.to_account_info(),
+                    to: self.loan_account.to_account_info(),
+                },
+            );
+            transfer(cpi_context, premium)?;
+        }
+        self.loan_account.protection = Some(DefaultProtection { extension, premium });
+
+        Ok(DefaultProtectionPurchased {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            extension,
+            premium,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+
+    pub fn transfer_nft_vault(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<LoanRequested> {
         let cpi_context = CpiContext::new(
             self.token_program.to_account_info(),
             TransferChecked {
@@ -112,17 +201,19 @@ impl<'info> CreateLoan<'info> {
             },
         );
 
-        transfer_checked(cpi_context, 1, self.borrower_nft_mint.decimals)?;
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
 
-        emit!(LoanRequested {
-            borrower: self.borrower.to_account_info().key(),
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
+
+        Ok(LoanRequested {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
             nft_mint: self.borrower_nft_mint.key(),
             loan_amount: self.loan_account.loan_amount,
             duration: self.loan_account.duration,
-            interest_rate: self.loan_account.interest_rate as u8,
-            timestamp: Clock::get()?.unix_timestamp as u64,
-        });
-
-        Ok(())
+            interest_rate: self.loan_account.interest_rate,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
     }
 }
@@ -0,0 +1,93 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token_interface::{Mint, TokenInterface};
+
+use crate::{error::ErrorCode, events::PoolCreated, LendingPool, Platform, RateCurve};
+
+//platform authority creates a lending pool
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(pool_id: u64)]
+pub struct CreatePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LendingPool::INIT_SPACE,
+        seeds = [b"pool", platform.key().as_ref(), pool_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        mut,
+        seeds = [b"pool_vault", lending_pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"pool_share_mint", lending_pool.key().as_ref()],
+        bump,
+        mint::decimals = 9,
+        mint::authority = lending_pool,
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreatePool<'info> {
+    pub fn create_pool(
+        &mut self,
+        pool_id: u64,
+        max_duration: u32,
+        rate_curve: RateCurve,
+        bumps: &CreatePoolBumps,
+    ) -> Result<PoolCreated> {
+        require!(rate_curve.is_valid(), ErrorCode::InvalidRateCurve);
+
+        self.lending_pool.set_inner(LendingPool {
+            authority: self.authority.key(),
+            platform: self.platform.key(),
+            pool_id,
+            share_mint: self.share_mint.key(),
+            max_duration,
+            rate_curve,
+            total_borrowed: 0,
+            available_liquidity: 0,
+            active_loans: 0,
+            vault_bump: bumps.pool_vault,
+            share_mint_bump: bumps.share_mint,
+            bump: bumps.lending_pool,
+            loans: Vec::new(),
+            collateral_held: 0,
+        });
+
+        //vault must stay rent exempt so it can always pay out its tracked liquidity, the reserve is
+        //left out of available_liquidity and so out of NAV and withdrawals
+        let rent = Rent::get()?.minimum_balance(0);
+        let cpi_context = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.authority.to_account_info(),
+                to: self.pool_vault.to_account_info(),
+            },
+        );
+        transfer(cpi_context, rent)?;
+
+        Ok(PoolCreated {
+            pool: self.lending_pool.key(),
+            platform: self.platform.key(),
+            authority: self.authority.key(),
+            pool_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -1,12 +1,21 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{close_account, transfer_checked, CloseAccount, TransferChecked},
-    token_interface::{Mint, TokenAccount, TokenInterface},
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 
-use crate::{error::ErrorCode, events::NFTClaimed, Loan, LoanStatus, Platform};
+use crate::{
+    collateral::transfer_nft,
+    error::ErrorCode,
+    events::LoanDefaulted,
+    Loan,
+    LoanStatus,
+    Platform,
+};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct DefaultLoan<'info> {
     #[account(mut)]
@@ -19,18 +28,15 @@ pub struct DefaultLoan<'info> {
         payer = lender,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = lender,
+        associated_token::token_program = token_program,
     )]
     pub lender_nft_ata: InterfaceAccount<'info, TokenAccount>,
-    #[account(
-        seeds = [b"platform"],
-        bump = platform.bump,
-    )]
     pub platform: Account<'info, Platform>,
     #[account(
         mut,
         has_one = borrower,
         close = borrower,
-        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref()],
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
         bump = loan_account.bump,
     )]
     pub loan_account: Account<'info, Loan>,
@@ -38,6 +44,7 @@ pub struct DefaultLoan<'info> {
         mut,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
     )]
     pub nft_vault: InterfaceAccount<'info, TokenAccount>,
     #[account(
@@ -52,13 +59,13 @@ pub struct DefaultLoan<'info> {
 }
 
 impl<'info> DefaultLoan<'info> {
-    pub fn claim_nft(&mut self) -> Result<()> {
+    pub fn claim_nft(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<LoanDefaulted> {
         let start_time = self
             .loan_account
             .start_time
             .ok_or(ErrorCode::LoanNotStarted)?;
         require!(
-            Clock::get()?.unix_timestamp - start_time >= (self.loan_account.duration as i64),
+            Clock::get()?.unix_timestamp - start_time >= self.loan_account.repayment_window(),
             ErrorCode::WaitForLoanToComplete
         );
 
@@ -76,10 +83,12 @@ impl<'info> DefaultLoan<'info> {
             ErrorCode::LoanAlreadyDefaulted
         );
 
+        let loan_id = self.loan_account.loan_id.to_le_bytes();
         let seeds = &[
             b"loan".as_ref(),
             &self.borrower_nft_mint.key().to_bytes()[..],
             &self.platform.key().to_bytes()[..],
+            &loan_id[..],
             &[self.loan_account.bump],
         ];
         let signer_seeds = &[&seeds[..]];
@@ -95,7 +104,9 @@ impl<'info> DefaultLoan<'info> {
             signer_seeds,
         );
 
-        transfer_checked(cpi_context, 1, self.borrower_nft_mint.decimals)?;
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
 
         self.loan_account.status = LoanStatus::Defaulted;
 
@@ -112,13 +123,14 @@ impl<'info> DefaultLoan<'info> {
         );
         close_account(cpi_close)?;
 
-        emit!(NFTClaimed {
+        Ok(LoanDefaulted {
+            loan_id: self.loan_account.loan_id,
             loan: self.loan_account.key(),
             borrower: self.borrower.key(),
+            lender: self.lender.key(),
             nft_mint: self.borrower_nft_mint.key(),
+            caller: self.lender.key(),
             timestamp: Clock::get()?.unix_timestamp,
-        });
-
-        Ok(())
+        })
     }
 }
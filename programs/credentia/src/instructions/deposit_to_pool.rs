@@ -0,0 +1,105 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{mint_to, MintTo},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, events::PoolDeposited, LendingPool};
+
+//lender deposits lamports into a pool and receives pool shares
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositToPool<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", lending_pool.platform.as_ref(), lending_pool.pool_id.to_le_bytes().as_ref()],
+        bump = lending_pool.bump,
+        has_one = share_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        mut,
+        seeds = [b"pool_vault", lending_pool.key().as_ref()],
+        bump = lending_pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = share_mint,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_share_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> DepositToPool<'info> {
+    pub fn deposit(&mut self, amount: u64) -> Result<PoolDeposited> {
+        require!(amount > 0, ErrorCode::InvalidAmountError);
+        require!(
+            self.depositor.lamports() >= amount,
+            ErrorCode::InsufficientBalance
+        );
+
+        //shares are priced before the deposit lands in the pool
+        let current_time = Clock::get()?.unix_timestamp;
+        let shares = self
+            .lending_pool
+            .shares_for_deposit(amount, self.share_mint.supply, current_time)?;
+        require!(shares > 0, ErrorCode::InvalidShareAmount);
+
+        let cpi_context = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.depositor.to_account_info(),
+                to: self.pool_vault.to_account_info(),
+            },
+        );
+        transfer(cpi_context, amount)?;
+
+        let pool_id = self.lending_pool.pool_id.to_le_bytes();
+        let seeds = &[
+            b"pool".as_ref(),
+            self.lending_pool.platform.as_ref(),
+            pool_id.as_ref(),
+            &[self.lending_pool.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_mint = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            MintTo {
+                mint: self.share_mint.to_account_info(),
+                to: self.depositor_share_ata.to_account_info(),
+                authority: self.lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+        mint_to(cpi_mint, shares)?;
+
+        self.lending_pool.available_liquidity = self
+            .lending_pool
+            .available_liquidity
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(PoolDeposited {
+            pool: self.lending_pool.key(),
+            depositor: self.depositor.key(),
+            amount,
+            shares_minted: shares,
+            timestamp: current_time,
+        })
+    }
+}
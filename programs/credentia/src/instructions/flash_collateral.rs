@@ -0,0 +1,177 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    Discriminator,
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    collateral::transfer_nft,
+    error::ErrorCode,
+    events::{CollateralFlashUsed, CollateralReturned},
+    Loan, LoanStatus, Platform,
+};
+
+//position of `loan_account` in the accounts of `return_collateral`
+pub const RETURN_COLLATERAL_LOAN_INDEX: usize = 3;
+
+//borrower takes the nft out of the vault for the rest of the transaction, to claim airdrops or
+//use it as a pass. a later `return_collateral` for the same loan has to put it back or nothing happens
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FlashUseCollateral<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program,
+    )]
+    pub borrower_nft_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        has_one = borrower,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    #[account(
+        mut,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
+    )]
+    pub nft_vault: InterfaceAccount<'info, TokenAccount>,
+    pub platform: Account<'info, Platform>,
+    /// CHECK: the instructions sysvar, read to find the matching `return_collateral`
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> FlashUseCollateral<'info> {
+    pub fn flash_use(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<CollateralFlashUsed> {
+        require!(
+            matches!(self.loan_account.status, LoanStatus::Requested | LoanStatus::Funded),
+            ErrorCode::LoanNotActive
+        );
+        self.check_returned()?;
+
+        let loan_id = self.loan_account.loan_id.to_le_bytes();
+        let seeds = &[
+            b"loan".as_ref(),
+            &self.borrower_nft_mint.key().to_bytes()[..],
+            &self.platform.key().to_bytes()[..],
+            &loan_id[..],
+            &[self.loan_account.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.nft_vault.to_account_info(),
+                mint: self.borrower_nft_mint.to_account_info(),
+                to: self.borrower_nft_ata.to_account_info(),
+                authority: self.loan_account.to_account_info(),
+            },
+            signer_seeds,
+        );
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
+
+        Ok(CollateralFlashUsed {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            nft_mint: self.borrower_nft_mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+
+    //some top level instruction after the current one must be this program's `return_collateral`
+    //for the loan. the transaction only lands if it succeeds, and it only succeeds by moving the
+    //nft back into the vault
+    fn check_returned(&self) -> Result<()> {
+        let sysvar = self.instructions.to_account_info();
+        let current = load_current_index_checked(&sysvar)? as usize;
+        let loan = self.loan_account.key();
+        let mut index = current + 1;
+        while let Ok(ix) = load_instruction_at_checked(index, &sysvar) {
+            if ix.program_id == crate::ID
+                && ix.data.starts_with(crate::instruction::ReturnCollateral::DISCRIMINATOR)
+                && ix
+                    .accounts
+                    .get(RETURN_COLLATERAL_LOAN_INDEX)
+                    .is_some_and(|meta| meta.pubkey == loan)
+            {
+                return Ok(());
+            }
+            index += 1;
+        }
+        err!(ErrorCode::CollateralNotReturned)
+    }
+}
+
+//borrower puts a flash used nft back into the vault
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReturnCollateral<'info> {
+    pub borrower: Signer<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program,
+    )]
+    pub borrower_nft_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        has_one = borrower,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    #[account(
+        mut,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
+    )]
+    pub nft_vault: InterfaceAccount<'info, TokenAccount>,
+    pub platform: Account<'info, Platform>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReturnCollateral<'info> {
+    pub fn return_nft(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<CollateralReturned> {
+        let cpi_context = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.borrower_nft_ata.to_account_info(),
+                mint: self.borrower_nft_mint.to_account_info(),
+                to: self.nft_vault.to_account_info(),
+                authority: self.borrower.to_account_info(),
+            },
+        );
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
+
+        Ok(CollateralReturned {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            nft_mint: self.borrower_nft_mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
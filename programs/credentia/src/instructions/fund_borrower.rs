@@ -4,12 +4,19 @@ use anchor_lang::{
 };
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{Mint, TokenInterface},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
 use crate::error::ErrorCode;
-use crate::{events::LoanFunded, Loan, LoanStatus, Platform};
+use crate::{
+    currency::transfer_currency,
+    events::{LoanFunded, LoanInsured},
+    lamports::move_lamports,
+    math::bps_of,
+    InsuranceFund, InsurancePolicy, LenderReputation, Loan, LoanStatus, Platform, ReferrerAccount,
+};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct FundBorrower<'info> {
     #[account(mut)]
@@ -21,15 +28,47 @@ pub struct FundBorrower<'info> {
     #[account(
         mut,
         has_one = borrower,
-        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref()],
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
         bump = loan_account.bump,
     )]
     pub loan_account: Account<'info, Loan>,
+    pub platform: Account<'info, Platform>,
+    //frontend that brought the lender in, gets a share of the platform fee on repayment
     #[account(
-        seeds = [b"platform"],
-        bump = platform.bump,
+        seeds = [b"referrer", platform.key().as_ref(), referrer_account.referrer.as_ref()],
+        bump = referrer_account.bump,
     )]
-    pub platform: Account<'info, Platform>,
+    pub referrer_account: Option<Account<'info, ReferrerAccount>>,
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + LenderReputation::INIT_SPACE,
+        seeds = [b"lender", platform.key().as_ref(), lender.key().as_ref()],
+        bump,
+    )]
+    pub lender_reputation: Account<'info, LenderReputation>,
+    //lenders opt into insurance on lamport loans by passing the platform's fund and the loan's policy
+    #[account(
+        mut,
+        seeds = [b"insurance_fund", platform.key().as_ref()],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Option<Box<Account<'info, InsuranceFund>>>,
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + InsurancePolicy::INIT_SPACE,
+        seeds = [b"insurance_policy", loan_account.key().as_ref()],
+        bump,
+    )]
+    pub insurance_policy: Option<Box<Account<'info, InsurancePolicy>>>,
+    //token loans pay out of the lender's currency account into the borrower's
+    pub currency_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(mut)]
+    pub lender_currency_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub borrower_currency_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub currency_token_program: Option<Interface<'info, TokenInterface>>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
@@ -37,38 +76,172 @@ pub struct FundBorrower<'info> {
 }
 
 impl<'info> FundBorrower<'info> {
-    pub fn fund_borrower(&mut self) -> Result<()> {
+    pub fn fund_borrower(
+        &mut self,
+        expected_amount: u64,
+        expected_duration: u32,
+        expected_interest_rate: u16,
+        bumps: &FundBorrowerBumps,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<(LoanFunded, Option<LoanInsured>)> {
         require!(self.loan_account.lender.is_none(), ErrorCode::LoanFunded);
         require!(
             self.loan_account.status == LoanStatus::Requested,
             ErrorCode::LoanNotActive
         );
+        self.loan_account
+            .check_terms(expected_amount, expected_duration, expected_interest_rate)?;
+        let current_time = Clock::get()?.unix_timestamp;
+        self.loan_account.check_lender(
+            &self.lender.key(),
+            self.lender_reputation.loans_repaid,
+            current_time,
+        )?;
+        //the fund pays out lamports, it only covers loans made in them
         require!(
-            self.lender.lamports() >= self.loan_account.loan_amount,
-            ErrorCode::InsufficientBalance
+            self.insurance_policy.is_none() || self.loan_account.currency.is_none(),
+            ErrorCode::InsuranceNotSupported
         );
 
-        //transfering fund to borrower
-        let cpi_context = CpiContext::new(
-            self.system_program.to_account_info(),
-            Transfer {
-                from: self.lender.to_account_info(),
-                to: self.borrower.to_account_info(),
-            },
-        );
-        transfer(cpi_context, self.loan_account.loan_amount)?;
+        match self.loan_account.currency {
+            Some(currency) => self.transfer_currency(currency, hook_accounts)?,
+            None => {
+                require!(
+                    self.lender.lamports() >= self.loan_account.loan_amount,
+                    ErrorCode::InsufficientBalance
+                );
+
+                //transfering fund to borrower
+                let cpi_context = CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.lender.to_account_info(),
+                        to: self.borrower.to_account_info(),
+                    },
+                );
+                transfer(cpi_context, self.loan_account.loan_amount)?;
+            }
+        }
 
         //updating the field
         self.loan_account.lender = Some(self.lender.key());
-        let current_time = Clock::get()?.unix_timestamp;
+        self.loan_account.lender_referrer = self.referrer_account.as_ref().map(|r| r.referrer);
+        self.loan_account.check_referrers()?;
         self.loan_account.start_time = Some(current_time);
         self.loan_account.status = LoanStatus::Funded;
-        emit!(LoanFunded {
+
+        self.lender_reputation
+            .open(self.lender.key(), self.platform.key(), bumps.lender_reputation);
+        let insured = self.insure(bumps, current_time)?;
+
+        //the borrower's protection premium compensates the lender for the extension
+        move_lamports(
+            &self.loan_account.to_account_info(),
+            &self.lender.to_account_info(),
+            self.loan_account.protection_premium(),
+        )?;
+
+        let funded = LoanFunded {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.loan_account.borrower,
             lender: self.lender.key(),
             loan_amount: self.loan_account.loan_amount,
+            interest_rate: self.loan_account.interest_rate,
             funded_at: current_time,
+        };
+        Ok((funded, insured))
+    }
+
+    //the lender pays the fund's premium on the principal and gets the fund's current coverage until
+    //the end of the repayment window, nothing happens without a policy account
+    fn insure(&mut self, bumps: &FundBorrowerBumps, now: i64) -> Result<Option<LoanInsured>> {
+        let Some(policy) = self.insurance_policy.as_mut() else {
+            return Ok(None);
+        };
+        let fund = self
+            .insurance_fund
+            .as_mut()
+            .ok_or(ErrorCode::InsuranceMismatch)?;
+        let principal = self.loan_account.loan_amount;
+        let premium = bps_of(principal, fund.premium_bps)?;
+        let deadline = now
+            .checked_add(self.loan_account.repayment_window())
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let cpi_context = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.lender.to_account_info(),
+                to: fund.to_account_info(),
+            },
+        );
+        transfer(cpi_context, premium)?;
+
+        policy.set_inner(InsurancePolicy {
+            platform: self.platform.key(),
+            loan: self.loan_account.key(),
+            loan_id: self.loan_account.loan_id,
+            borrower: self.loan_account.borrower,
+            lender: self.lender.key(),
+            nft_mint: self.loan_account.nft_mint,
+            principal,
+            premium,
+            coverage_bps: fund.coverage_bps,
+            deadline,
+            bump: bumps.insurance_policy.ok_or(ErrorCode::InsuranceMismatch)?,
         });
+        fund.total_premiums = fund
+            .total_premiums
+            .checked_add(premium)
+            .ok_or(ErrorCode::MathOverflow)?;
+        fund.policies_written = fund
+            .policies_written
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.loan_account.insured = true;
+
+        Ok(Some(LoanInsured {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.loan_account.borrower,
+            lender: self.lender.key(),
+            policy: policy.key(),
+            premium,
+            coverage_bps: fund.coverage_bps,
+            deadline,
+            timestamp: now,
+        }))
+    }
+
+    //the borrower is credited the full loan amount, the lender covers the transfer fee
+    fn transfer_currency(&self, currency: Pubkey, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let mint = self.currency_mint.as_ref().ok_or(ErrorCode::CurrencyMismatch)?;
+        require_keys_eq!(mint.key(), currency, ErrorCode::CurrencyMismatch);
+        let token_program = self
+            .currency_token_program
+            .as_ref()
+            .ok_or(ErrorCode::CurrencyMismatch)?;
+        let from = self
+            .lender_currency_account
+            .as_ref()
+            .ok_or(ErrorCode::CurrencyMismatch)?;
+        let to = self
+            .borrower_currency_account
+            .as_ref()
+            .ok_or(ErrorCode::CurrencyMismatch)?;
+        require_keys_eq!(to.owner, self.borrower.key(), ErrorCode::CurrencyMismatch);
 
-        Ok(())
+        let cpi_context = CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.lender.to_account_info(),
+            },
+        )
+        .with_remaining_accounts(hook_accounts.to_vec());
+        transfer_currency(cpi_context, mint, self.loan_account.loan_amount)
     }
 }
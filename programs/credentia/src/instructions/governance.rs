@@ -0,0 +1,226 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+    },
+};
+use anchor_spl::{
+    associated_token,
+    metadata::mpl_token_metadata,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    error::ErrorCode,
+    events::{GovernanceProgramRemoved, GovernanceProgramSet, GovernanceVoteRelayed},
+    GovernanceProgram, Loan, LoanStatus, Platform, MAX_GOVERNANCE_INSTRUCTIONS,
+    MAX_GOVERNANCE_PREFIX_LEN,
+};
+
+//programs that hold or move collateral, the loan account never signs for them
+fn custodial(program_id: &Pubkey) -> bool {
+    [
+        crate::ID,
+        System::id(),
+        anchor_spl::token::ID,
+        anchor_spl::token_2022::ID,
+        associated_token::ID,
+        mpl_token_metadata::ID,
+    ]
+    .contains(program_id)
+}
+
+//platform authority allows a governance program, or changes which of its instructions borrowers
+//can relay
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetGovernanceProgram<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub platform: Account<'info, Platform>,
+    /// CHECK: only its address is recorded, it has to be a program
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + GovernanceProgram::INIT_SPACE,
+        seeds = [b"governance_program", platform.key().as_ref(), target_program.key().as_ref()],
+        bump,
+    )]
+    pub governance_program: Account<'info, GovernanceProgram>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetGovernanceProgram<'info> {
+    pub fn set_program(
+        &mut self,
+        instructions: Vec<Vec<u8>>,
+        bumps: &SetGovernanceProgramBumps,
+    ) -> Result<GovernanceProgramSet> {
+        require!(
+            !custodial(&self.target_program.key()),
+            ErrorCode::GovernanceProgramNotAllowed
+        );
+        require!(
+            instructions.len() <= MAX_GOVERNANCE_INSTRUCTIONS
+                && instructions
+                    .iter()
+                    .all(|prefix| !prefix.is_empty() && prefix.len() <= MAX_GOVERNANCE_PREFIX_LEN),
+            ErrorCode::InvalidGovernanceInstructions
+        );
+
+        self.governance_program.set_inner(GovernanceProgram {
+            platform: self.platform.key(),
+            program_id: self.target_program.key(),
+            instructions: instructions.clone(),
+            bump: bumps.governance_program,
+        });
+
+        Ok(GovernanceProgramSet {
+            platform: self.platform.key(),
+            program_id: self.target_program.key(),
+            instructions,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+//platform authority stops relaying to a governance program
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemoveGovernanceProgram<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = platform,
+        close = authority,
+        seeds = [b"governance_program", platform.key().as_ref(), governance_program.program_id.as_ref()],
+        bump = governance_program.bump,
+    )]
+    pub governance_program: Account<'info, GovernanceProgram>,
+}
+
+impl<'info> RemoveGovernanceProgram<'info> {
+    //the account itself is closed by its constraint
+    pub fn remove_program(&mut self) -> Result<GovernanceProgramRemoved> {
+        Ok(GovernanceProgramRemoved {
+            platform: self.platform.key(),
+            program_id: self.governance_program.program_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+//borrower votes with the escrowed nft: the loan account, which owns the vault, signs an allowed
+//instruction of a governance program. its accounts are the remaining accounts
+#[event_cpi]
+#[derive(Accounts)]
+pub struct GovernanceVote<'info> {
+    pub borrower: Signer<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        has_one = borrower,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    #[account(
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
+    )]
+    pub nft_vault: InterfaceAccount<'info, TokenAccount>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        has_one = platform,
+        seeds = [b"governance_program", platform.key().as_ref(), target_program.key().as_ref()],
+        bump = governance_program.bump,
+    )]
+    pub governance_program: Account<'info, GovernanceProgram>,
+    /// CHECK: the program the governance account allows, by its seeds
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> GovernanceVote<'info> {
+    pub fn relay(
+        &mut self,
+        data: Vec<u8>,
+        governance_accounts: &[AccountInfo<'info>],
+    ) -> Result<GovernanceVoteRelayed> {
+        require!(
+            matches!(self.loan_account.status, LoanStatus::Requested | LoanStatus::Funded),
+            ErrorCode::LoanNotActive
+        );
+        require!(
+            self.governance_program.allows(&data),
+            ErrorCode::GovernanceInstructionNotAllowed
+        );
+
+        let loan = self.loan_account.key();
+        let accounts = governance_accounts
+            .iter()
+            .map(|info| AccountMeta {
+                pubkey: info.key(),
+                is_signer: info.is_signer || info.key() == loan,
+                is_writable: info.is_writable,
+            })
+            .collect();
+        let ix = Instruction {
+            program_id: self.target_program.key(),
+            accounts,
+            data,
+        };
+        let mut infos = governance_accounts.to_vec();
+        infos.push(self.loan_account.to_account_info());
+        infos.push(self.target_program.to_account_info());
+
+        let loan_id = self.loan_account.loan_id.to_le_bytes();
+        let seeds = &[
+            b"loan".as_ref(),
+            &self.borrower_nft_mint.key().to_bytes()[..],
+            &self.platform.key().to_bytes()[..],
+            &loan_id[..],
+            &[self.loan_account.bump],
+        ];
+        invoke_signed(&ix, &infos, &[&seeds[..]])?;
+
+        //the loan signs for the vault too, so whatever the program did the nft has to still sit in
+        //it with nobody else able to move it. a closed vault can't be reloaded, so check for that first
+        let vault_info = self.nft_vault.to_account_info();
+        require!(
+            vault_info.lamports() > 0
+                && vault_info.owner == &self.token_program.key()
+                && !vault_info.data_is_empty(),
+            ErrorCode::CollateralCustodyChanged
+        );
+        self.nft_vault.reload()?;
+        let vault = &self.nft_vault;
+        require!(
+            vault.amount == 1
+                && vault.owner == loan
+                && vault.delegate.is_none()
+                && vault.close_authority.is_none(),
+            ErrorCode::CollateralCustodyChanged
+        );
+
+        let prefix_len = ix.data.len().min(MAX_GOVERNANCE_PREFIX_LEN);
+        Ok(GovernanceVoteRelayed {
+            loan_id: self.loan_account.loan_id,
+            loan,
+            borrower: self.borrower.key(),
+            program_id: self.target_program.key(),
+            instruction: ix.data[..prefix_len].to_vec(),
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -1,10 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, TokenInterface};
 
-use crate::Platform;
+use crate::{error::ErrorCode, events::PlatformInitialized, Platform};
 
-//initializing platform(admin)
+//initializing platform(admin), any operator can start a platform under its own id
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(platform_id: u64)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
@@ -12,7 +14,7 @@ pub struct Initialize<'info> {
         init,
         payer = admin,
         space = 8 + Platform::INIT_SPACE,
-        seeds = [b"platform"],
+        seeds = [b"platform", platform_id.to_le_bytes().as_ref()],
         bump,
     )]
     pub platform: Account<'info, Platform>,
@@ -35,15 +37,28 @@ pub struct Initialize<'info> {
 }
 
 impl<'info> Initialize<'info> {
-    pub fn init(&mut self, fee_bps: u16, bumps: &InitializeBumps) -> Result<()> {
+    pub fn init(&mut self, platform_id: u64, fee_bps: u16, bumps: &InitializeBumps) -> Result<PlatformInitialized> {
+        require!(fee_bps <= 10000, ErrorCode::InvalidFeeBps);
+
         self.platform.set_inner(Platform {
             authority: self.admin.key(),
             fee_bps,
             reward_bump: bumps.reward_mint,
             treasury_bump: bumps.treasury_vault,
             bump: bumps.platform,
+            loan_count: 0,
+            platform_id,
+            referral_share_bps: 0,
+            version: Platform::VERSION,
+            insurance_share_bps: 0,
         });
 
-        Ok(())
+        Ok(PlatformInitialized {
+            platform: self.platform.key(),
+            authority: self.admin.key(),
+            platform_id,
+            fee_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
     }
 }
@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    error::ErrorCode,
+    events::{CollateralValued, InsuranceClaimed, InsuranceConfigured},
+    lamports::move_lamports,
+    math::{bps_of, insurance_payout},
+    CollateralValuation, InsuranceFund, InsurancePolicy, Platform,
+};
+
+//platform authority creates the insurance fund or changes its parameters
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ConfigureInsurance<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub platform: Account<'info, Platform>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + InsuranceFund::INIT_SPACE,
+        seeds = [b"insurance_fund", platform.key().as_ref()],
+        bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ConfigureInsurance<'info> {
+    pub fn configure(
+        &mut self,
+        insurance_share_bps: u16,
+        premium_bps: u16,
+        coverage_bps: u16,
+        max_claim_bps: u16,
+        oracle: Pubkey,
+        bumps: &ConfigureInsuranceBumps,
+    ) -> Result<InsuranceConfigured> {
+        require!(insurance_share_bps <= 10000, ErrorCode::InvalidFeeBps);
+        require!(premium_bps <= 10000, ErrorCode::InvalidFeeBps);
+        require!(coverage_bps <= 10000, ErrorCode::InvalidFeeBps);
+        require!(max_claim_bps <= 10000, ErrorCode::InvalidFeeBps);
+        //referrers and the fund are both paid out of the platform fee
+        require!(
+            self.platform.referral_share_bps as u32 + insurance_share_bps as u32 <= 10000,
+            ErrorCode::InvalidFeeBps
+        );
+
+        let fund = &mut self.insurance_fund;
+        fund.platform = self.platform.key();
+        fund.bump = bumps.insurance_fund;
+        fund.oracle = oracle;
+        fund.premium_bps = premium_bps;
+        fund.coverage_bps = coverage_bps;
+        fund.max_claim_bps = max_claim_bps;
+        self.platform.insurance_share_bps = insurance_share_bps;
+
+        Ok(InsuranceConfigured {
+            platform: self.platform.key(),
+            insurance_fund: self.insurance_fund.key(),
+            oracle,
+            insurance_share_bps,
+            premium_bps,
+            coverage_bps,
+            max_claim_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+//the fund's oracle posts what an nft is worth in lamports
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetCollateralValue<'info> {
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        has_one = platform,
+        has_one = oracle,
+        seeds = [b"insurance_fund", platform.key().as_ref()],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = oracle,
+        space = 8 + CollateralValuation::INIT_SPACE,
+        seeds = [b"valuation", platform.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    pub valuation: Account<'info, CollateralValuation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetCollateralValue<'info> {
+    pub fn set_value(
+        &mut self,
+        value: u64,
+        bumps: &SetCollateralValueBumps,
+    ) -> Result<CollateralValued> {
+        let now = Clock::get()?.unix_timestamp;
+        self.valuation.set_inner(CollateralValuation {
+            platform: self.platform.key(),
+            nft_mint: self.nft_mint.key(),
+            value,
+            updated_at: now,
+            bump: bumps.valuation,
+        });
+
+        Ok(CollateralValued {
+            platform: self.platform.key(),
+            nft_mint: self.nft_mint.key(),
+            value,
+            timestamp: now,
+        })
+    }
+}
+
+//insured lender claims part of the shortfall on a loan that was not repaid in time
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimInsurance<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = platform,
+        seeds = [b"insurance_fund", platform.key().as_ref()],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    #[account(
+        mut,
+        has_one = lender,
+        has_one = platform,
+        close = lender,
+        seeds = [b"insurance_policy", insurance_policy.loan.as_ref()],
+        bump = insurance_policy.bump,
+    )]
+    pub insurance_policy: Account<'info, InsurancePolicy>,
+    #[account(
+        seeds = [b"valuation", platform.key().as_ref(), insurance_policy.nft_mint.as_ref()],
+        bump = valuation.bump,
+    )]
+    pub valuation: Account<'info, CollateralValuation>,
+}
+
+impl<'info> ClaimInsurance<'info> {
+    pub fn claim(&mut self) -> Result<InsuranceClaimed> {
+        //repayment closes the policy, so an open policy past the deadline means the loan defaulted
+        let now = Clock::get()?.unix_timestamp;
+        let policy = &self.insurance_policy;
+        require!(now > policy.deadline, ErrorCode::PolicyNotClaimable);
+        require!(
+            self.valuation.updated_at > policy.deadline,
+            ErrorCode::StaleCollateralValuation
+        );
+
+        let fund_info = self.insurance_fund.to_account_info();
+        let rent = Rent::get()?.minimum_balance(fund_info.data_len());
+        let balance = fund_info.lamports().saturating_sub(rent);
+        let shortfall = policy.principal.saturating_sub(self.valuation.value);
+        let payout = insurance_payout(
+            policy.principal,
+            self.valuation.value,
+            policy.coverage_bps,
+            balance,
+            self.insurance_fund.max_claim_bps,
+        )?;
+        //an empty fund can't settle a claim that is owed something, the policy stays open until it can
+        require!(
+            payout > 0 || bps_of(shortfall, policy.coverage_bps)? == 0,
+            ErrorCode::NothingToClaim
+        );
+
+        move_lamports(&fund_info, &self.lender.to_account_info(), payout)?;
+
+        let fund = &mut self.insurance_fund;
+        fund.total_claimed = fund
+            .total_claimed
+            .checked_add(payout)
+            .ok_or(ErrorCode::MathOverflow)?;
+        fund.claims_paid = fund
+            .claims_paid
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(InsuranceClaimed {
+            loan_id: self.insurance_policy.loan_id,
+            loan: self.insurance_policy.loan,
+            borrower: self.insurance_policy.borrower,
+            lender: self.lender.key(),
+            policy: self.insurance_policy.key(),
+            collateral_value: self.valuation.value,
+            shortfall,
+            payout,
+            timestamp: now,
+        })
+    }
+}
@@ -0,0 +1,152 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    collateral::transfer_nft,
+    error::ErrorCode,
+    events::PoolCollateralLiquidated,
+    LendingPool,
+    PoolCollateral,
+};
+
+//anyone buys a defaulted nft held by the pool at its auction price, proceeds go back to the pool
+//and the auction's rent to whoever paid it in `pool_claim_default`
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LiquidatePoolCollateral<'info> {
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"pool", lending_pool.platform.as_ref(), lending_pool.pool_id.to_le_bytes().as_ref()],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        mut,
+        seeds = [b"pool_vault", lending_pool.key().as_ref()],
+        bump = lending_pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        has_one = payer,
+        close = payer,
+        seeds = [b"pool_collateral", lending_pool.key().as_ref(), nft_mint.key().as_ref()],
+        bump = pool_collateral.bump,
+    )]
+    pub pool_collateral: Account<'info, PoolCollateral>,
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = lending_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_nft_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> LiquidatePoolCollateral<'info> {
+    pub fn liquidate(
+        &mut self,
+        max_price: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<PoolCollateralLiquidated> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let price = self.pool_collateral.price_at(current_time)?;
+        require!(max_price >= price, ErrorCode::BelowAuctionPrice);
+        require!(
+            self.buyer.lamports() >= price,
+            ErrorCode::InsufficientBalance
+        );
+
+        //buyer pays the pool
+        if price > 0 {
+            let cpi_context = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: self.pool_vault.to_account_info(),
+                },
+            );
+            transfer(cpi_context, price)?;
+        }
+
+        let pool_id = self.lending_pool.pool_id.to_le_bytes();
+        let seeds = &[
+            b"pool".as_ref(),
+            self.lending_pool.platform.as_ref(),
+            pool_id.as_ref(),
+            &[self.lending_pool.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        //pool hands over the nft
+        let cpi_transfer = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.pool_nft_ata.to_account_info(),
+                mint: self.nft_mint.to_account_info(),
+                to: self.buyer_nft_ata.to_account_info(),
+                authority: self.lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+        let cpi_transfer = cpi_transfer.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_transfer, self.nft_mint.decimals)?;
+
+        let cpi_close = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.pool_nft_ata.to_account_info(),
+                destination: self.payer.to_account_info(),
+                authority: self.lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+        close_account(cpi_close)?;
+
+        let pool = &mut self.lending_pool;
+        pool.available_liquidity = pool
+            .available_liquidity
+            .checked_add(price)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pool.collateral_held = pool
+            .collateral_held
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(PoolCollateralLiquidated {
+            loan_id: self.pool_collateral.loan_id,
+            loan: self.pool_collateral.loan,
+            borrower: self.pool_collateral.borrower,
+            pool: self.lending_pool.key(),
+            buyer: self.buyer.key(),
+            nft_mint: self.nft_mint.key(),
+            price,
+            timestamp: current_time,
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{error::ErrorCode, events::AccountMigrated, Loan, Platform};
+
+//upgrades a loan written under an older layout to the current version (anyone can pay for it)
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateLoan<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub platform: Account<'info, Platform>,
+    /// CHECK: may still be in an older layout, discriminator and address are checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub loan_account: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateLoan<'info> {
+    pub fn migrate_loan(&mut self) -> Result<AccountMigrated> {
+        let info = self.loan_account.to_account_info();
+        grow(&info, &self.payer, &self.system_program, 8 + Loan::INIT_SPACE)?;
+
+        let mut data = info.try_borrow_mut_data()?;
+        let mut loan = Loan::try_deserialize(&mut &data[..])?;
+        //legacy loans share the discriminator but not the seeds, they go through migrate_legacy_loan
+        let loan_id = loan.loan_id.to_le_bytes();
+        let address = Pubkey::create_program_address(
+            &[
+                b"loan".as_ref(),
+                loan.nft_mint.as_ref(),
+                self.platform.key().as_ref(),
+                &loan_id[..],
+                &[loan.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::InvalidLoanAccount)?;
+        require_keys_eq!(address, info.key(), ErrorCode::InvalidLoanAccount);
+        require!(loan.version < Loan::VERSION, ErrorCode::AccountAlreadyMigrated);
+
+        let from_version = loan.version;
+        loan.version = Loan::VERSION;
+        loan.try_serialize(&mut &mut data[..])?;
+
+        Ok(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: Loan::VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+//upgrades a platform written under an older layout, it can't be deserialized by other instructions until then
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigratePlatform<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: may still be in an older layout, discriminator is checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub platform: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigratePlatform<'info> {
+    pub fn migrate_platform(&mut self) -> Result<AccountMigrated> {
+        let info = self.platform.to_account_info();
+        grow(&info, &self.payer, &self.system_program, 8 + Platform::INIT_SPACE)?;
+
+        let mut data = info.try_borrow_mut_data()?;
+        let mut platform = Platform::try_deserialize(&mut &data[..])?;
+        require!(platform.version < Platform::VERSION, ErrorCode::AccountAlreadyMigrated);
+
+        let from_version = platform.version;
+        platform.version = Platform::VERSION;
+        platform.try_serialize(&mut &mut data[..])?;
+
+        Ok(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: Platform::VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+//tops the account up to rent exemption and grows it to `space`, the new bytes are zeroed
+//so fields appended since the account was written deserialize as zero / None
+pub(crate) fn grow<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    if account.data_len() >= space {
+        return Ok(());
+    }
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if shortfall > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            },
+        );
+        transfer(cpi_context, shortfall)?;
+    }
+    account.realloc(space, true)?;
+    Ok(())
+}
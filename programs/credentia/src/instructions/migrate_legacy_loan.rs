@@ -0,0 +1,236 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{allocate, assign, transfer, Allocate, Assign, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use super::migrate_account::grow;
+use crate::{
+    collateral::transfer_nft,
+    error::ErrorCode,
+    events::LegacyLoanMigrated,
+    LegacyLoan,
+    Loan,
+    LoanStatus,
+    Platform,
+};
+
+//moves a loan opened before loan ids to its id seeded address (anyone can pay for it).
+//the platform can still be in the layout it had back then, it is grown first so the loan gets
+//the next id, `migrate_platform` still records the upgrade afterwards
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateLegacyLoan<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub borrower: SystemAccount<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: may still be in an older layout, discriminator is checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub platform: UncheckedAccount<'info>,
+    /// CHECK: old loan layout, owner and discriminator are checked in the handler
+    #[account(
+        mut,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref()],
+        bump,
+    )]
+    pub legacy_loan: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = legacy_loan,
+        associated_token::token_program = token_program,
+    )]
+    pub legacy_nft_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: its seeds need the platform's loan count, which can only be read once the platform
+    /// is grown, so the handler checks the address and creates it
+    #[account(mut)]
+    pub loan_account: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
+    )]
+    pub nft_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> MigrateLegacyLoan<'info> {
+    pub fn migrate(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<LegacyLoanMigrated> {
+        let legacy = {
+            require_keys_eq!(
+                *self.legacy_loan.owner,
+                crate::ID,
+                ErrorCode::InvalidLegacyLoan
+            );
+            let data = self.legacy_loan.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == *Loan::DISCRIMINATOR,
+                ErrorCode::InvalidLegacyLoan
+            );
+            LegacyLoan::deserialize(&mut &data[8..])?
+        };
+        require_keys_eq!(legacy.borrower, self.borrower.key(), ErrorCode::InvalidLegacyLoan);
+        require_keys_eq!(
+            legacy.nft_mint,
+            self.borrower_nft_mint.key(),
+            ErrorCode::InvalidLegacyLoan
+        );
+        require!(
+            legacy.status == LoanStatus::Requested || legacy.status == LoanStatus::Funded,
+            ErrorCode::LoanNotActive
+        );
+
+        let platform_info = self.platform.to_account_info();
+        grow(&platform_info, &self.payer, &self.system_program, 8 + Platform::INIT_SPACE)?;
+        let mut platform = Platform::try_deserialize(&mut &platform_info.try_borrow_data()?[..])?;
+        let loan_id = platform.loan_count;
+        let (address, loan_bump) = Pubkey::find_program_address(
+            &[
+                b"loan".as_ref(),
+                self.borrower_nft_mint.key().as_ref(),
+                platform_info.key().as_ref(),
+                &loan_id.to_le_bytes()[..],
+            ],
+            &crate::ID,
+        );
+        require_keys_eq!(address, self.loan_account.key(), ErrorCode::InvalidLoanAccount);
+        platform.loan_count = loan_id.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        platform.try_serialize(&mut &mut platform_info.try_borrow_mut_data()?[..])?;
+
+        let loan = Loan {
+            borrower: legacy.borrower,
+            lender: legacy.lender,
+            nft_mint: legacy.nft_mint,
+            loan_amount: legacy.loan_amount,
+            duration: legacy.duration,
+            start_time: legacy.start_time,
+            status: legacy.status,
+            time_of_liquidation_or_repayment: legacy.time_of_liquidation_or_repayment,
+            interest_rate: legacy.interest_rate,
+            bump: loan_bump,
+            pool: None,
+            loan_id,
+            borrower_referrer: None,
+            lender_referrer: None,
+            version: Loan::VERSION,
+            currency: None,
+            expires_at: None,
+            allowed_lenders: Vec::new(),
+            min_lender_reputation: 0,
+            insured: false,
+            protection: None,
+        };
+        self.create_loan_account(loan_id, loan_bump)?;
+        let loan_info = self.loan_account.to_account_info();
+        loan.try_serialize(&mut &mut loan_info.try_borrow_mut_data()?[..])?;
+
+        //moving the nft from the old vault to the new one
+        let seeds = &[
+            b"loan".as_ref(),
+            &self.borrower_nft_mint.key().to_bytes()[..],
+            &platform_info.key().to_bytes()[..],
+            &[legacy.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.legacy_nft_vault.to_account_info(),
+                mint: self.borrower_nft_mint.to_account_info(),
+                to: self.nft_vault.to_account_info(),
+                authority: self.legacy_loan.to_account_info(),
+            },
+            signer_seeds,
+        );
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
+
+        // close the old nft_vault and loan account (returns rent to borrower)
+        let cpi_close = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.legacy_nft_vault.to_account_info(),
+                destination: self.borrower.to_account_info(),
+                authority: self.legacy_loan.to_account_info(),
+            },
+            signer_seeds,
+        );
+        close_account(cpi_close)?;
+        let legacy_info = self.legacy_loan.to_account_info();
+        let borrower_info = self.borrower.to_account_info();
+        **borrower_info.lamports.borrow_mut() = borrower_info
+            .lamports()
+            .checked_add(legacy_info.lamports())
+            .ok_or(ErrorCode::MathOverflow)?;
+        **legacy_info.lamports.borrow_mut() = 0;
+        legacy_info.assign(&System::id());
+        legacy_info.realloc(0, false)?;
+
+        Ok(LegacyLoanMigrated {
+            loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            legacy_loan: legacy_info.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+    //creates the loan at its id seeded address the way `init` would, an address someone already
+    //sent lamports to is topped up, allocated and assigned instead
+    fn create_loan_account(&self, loan_id: u64, bump: u8) -> Result<()> {
+        let loan_id = loan_id.to_le_bytes();
+        let seeds = &[
+            b"loan".as_ref(),
+            &self.borrower_nft_mint.key().to_bytes()[..],
+            &self.platform.key().to_bytes()[..],
+            &loan_id[..],
+            &[bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        let space = 8 + Loan::INIT_SPACE;
+        let loan_info = self.loan_account.to_account_info();
+        let system_program = self.system_program.to_account_info();
+
+        let shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(loan_info.lamports());
+        if shortfall > 0 {
+            let cpi_context = CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: self.payer.to_account_info(),
+                    to: loan_info.clone(),
+                },
+            );
+            transfer(cpi_context, shortfall)?;
+        }
+        let cpi_context = CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: loan_info.clone(),
+            },
+            signer_seeds,
+        );
+        allocate(cpi_context, space as u64)?;
+        let cpi_context = CpiContext::new_with_signer(
+            system_program,
+            Assign {
+                account_to_assign: loan_info,
+            },
+            signer_seeds,
+        );
+        assign(cpi_context, &crate::ID)
+    }
+}
@@ -4,10 +4,50 @@ pub mod initialize_platform;
 pub mod create_loan;
 pub mod resolve_loan;
 pub mod borrower_cancel_loan;
+pub mod update_platform;
+pub mod referral;
+pub mod migrate_legacy_loan;
+pub mod create_pool;
+pub mod update_pool;
+pub mod set_pool_collection;
+pub mod deposit_to_pool;
+pub mod withdraw_from_pool;
+pub mod pool_fund_loan;
+pub mod borrow_from_pool;
+pub mod pool_claim_default;
+pub mod liquidate_pool_collateral;
+pub mod mark_defaulted;
+pub mod migrate_account;
+pub mod close_expired_request;
+pub mod negotiate_loan;
+pub mod update_loan_request;
+pub mod insurance;
+pub mod flash_collateral;
+pub mod governance;
 
 pub use default_loan::*;
 pub use fund_borrower::*;
 pub use initialize_platform::*;
 pub use create_loan::*;
 pub use resolve_loan::*;
-pub use borrower_cancel_loan::*;
\ No newline at end of file
+pub use borrower_cancel_loan::*;
+pub use update_platform::*;
+pub use referral::*;
+pub use migrate_legacy_loan::*;
+pub use create_pool::*;
+pub use update_pool::*;
+pub use set_pool_collection::*;
+pub use deposit_to_pool::*;
+pub use withdraw_from_pool::*;
+pub use pool_fund_loan::*;
+pub use borrow_from_pool::*;
+pub use pool_claim_default::*;
+pub use liquidate_pool_collateral::*;
+pub use mark_defaulted::*;
+pub use migrate_account::*;
+pub use close_expired_request::*;
+pub use negotiate_loan::*;
+pub use update_loan_request::*;
+pub use insurance::*;
+pub use flash_collateral::*;
+pub use governance::*;
\ No newline at end of file
@@ -0,0 +1,285 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::error::ErrorCode;
+use crate::lamports::{credit_lamports, debit_lamports};
+use crate::{
+    events::{CounterOfferAccepted, CounterOfferClosed, CounterOfferMade, LoanFunded},
+    CounterOffer, LenderReputation, Loan, LoanStatus, Platform, ReferrerAccount,
+};
+
+//lender proposes other terms for an open request and escrows the amount in the offer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MakeCounterOffer<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    #[account(
+        seeds = [b"loan" , loan_account.nft_mint.as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + CounterOffer::INIT_SPACE,
+        seeds = [b"counter_offer", loan_account.key().as_ref(), lender.key().as_ref()],
+        bump,
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+    //frontend that brought the lender in, carried over to the loan if the offer is accepted
+    #[account(
+        seeds = [b"referrer", platform.key().as_ref(), referrer_account.referrer.as_ref()],
+        bump = referrer_account.bump,
+    )]
+    pub referrer_account: Option<Account<'info, ReferrerAccount>>,
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + LenderReputation::INIT_SPACE,
+        seeds = [b"lender", platform.key().as_ref(), lender.key().as_ref()],
+        bump,
+    )]
+    pub lender_reputation: Account<'info, LenderReputation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeCounterOffer<'info> {
+    pub fn make_offer(
+        &mut self,
+        amount: u64,
+        duration: u32,
+        interest_rate: u16,
+        bumps: &MakeCounterOfferBumps,
+    ) -> Result<CounterOfferMade> {
+        require!(self.loan_account.lender.is_none(), ErrorCode::LoanFunded);
+        require!(
+            self.loan_account.status == LoanStatus::Requested,
+            ErrorCode::LoanNotActive
+        );
+        //the escrow holds lamports
+        require!(
+            self.loan_account.currency.is_none(),
+            ErrorCode::CounterOfferNotSupported
+        );
+        require!(amount > 0, ErrorCode::InvalidAmountError);
+        require!(duration > 0, ErrorCode::InvalidDurationError);
+        let current_time = Clock::get()?.unix_timestamp;
+        //the borrower's constraints apply to offers as they do to funding, and again on acceptance
+        self.loan_account.check_lender(
+            &self.lender.key(),
+            self.lender_reputation.loans_repaid,
+            current_time,
+        )?;
+
+        let cpi_context = CpiContext::new(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.lender.to_account_info(),
+                to: self.counter_offer.to_account_info(),
+            },
+        );
+        transfer(cpi_context, amount)?;
+
+        self.counter_offer.set_inner(CounterOffer {
+            loan: self.loan_account.key(),
+            loan_id: self.loan_account.loan_id,
+            borrower: self.loan_account.borrower,
+            lender: self.lender.key(),
+            amount,
+            duration,
+            interest_rate,
+            lender_referrer: self.referrer_account.as_ref().map(|r| r.referrer),
+            created_at: current_time,
+            bump: bumps.counter_offer,
+        });
+        self.lender_reputation
+            .open(self.lender.key(), self.platform.key(), bumps.lender_reputation);
+
+        Ok(CounterOfferMade {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.loan_account.borrower,
+            counter_offer: self.counter_offer.key(),
+            lender: self.lender.key(),
+            amount,
+            duration,
+            interest_rate,
+            timestamp: current_time,
+        })
+    }
+}
+
+//borrower takes an offer, the loan is rewritten to its terms and funded from the escrow
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptCounterOffer<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    #[account(mut)]
+    pub lender: SystemAccount<'info>,
+    #[account(
+        mut,
+        has_one = borrower,
+        seeds = [b"loan" , loan_account.nft_mint.as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = lender,
+        close = lender,
+        seeds = [b"counter_offer", loan_account.key().as_ref(), lender.key().as_ref()],
+        bump = counter_offer.bump,
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+    #[account(
+        seeds = [b"lender", platform.key().as_ref(), lender.key().as_ref()],
+        bump = lender_reputation.bump,
+    )]
+    pub lender_reputation: Account<'info, LenderReputation>,
+}
+
+impl<'info> AcceptCounterOffer<'info> {
+    pub fn accept_offer(
+        &mut self,
+        expected_amount: u64,
+        expected_duration: u32,
+        expected_interest_rate: u16,
+    ) -> Result<(CounterOfferAccepted, LoanFunded)> {
+        require!(self.loan_account.lender.is_none(), ErrorCode::LoanFunded);
+        require!(
+            self.loan_account.status == LoanStatus::Requested,
+            ErrorCode::LoanNotActive
+        );
+        self.counter_offer
+            .check_terms(expected_amount, expected_duration, expected_interest_rate)?;
+        let current_time = Clock::get()?.unix_timestamp;
+        self.loan_account.check_lender(
+            &self.lender.key(),
+            self.lender_reputation.loans_repaid,
+            current_time,
+        )?;
+
+        let offer = &self.counter_offer;
+        let loan = &mut self.loan_account;
+        loan.loan_amount = offer.amount;
+        loan.duration = offer.duration;
+        loan.interest_rate = offer.interest_rate;
+        //a protected request's premium is repriced for the offer's terms and paid to the lender,
+        //the borrower settles the difference out of the loan amount
+        let (old_premium, new_premium) = loan.reprice_protection()?;
+        let to_borrower = offer
+            .amount
+            .checked_add(old_premium)
+            .and_then(|amount| amount.checked_sub(new_premium))
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        //the escrow pays the borrower, closing the offer refunds its rent to the lender.
+        //to_borrower + new_premium is exactly what leaves the escrow and the loan, an escrow
+        //that can't cover the offer fails instead of funding less
+        debit_lamports(&offer.to_account_info(), offer.amount)?;
+        debit_lamports(&loan.to_account_info(), old_premium)?;
+        credit_lamports(&self.borrower.to_account_info(), to_borrower)?;
+        credit_lamports(&self.lender.to_account_info(), new_premium)?;
+
+        loan.lender = Some(offer.lender);
+        loan.lender_referrer = offer.lender_referrer;
+        loan.check_referrers()?;
+        loan.start_time = Some(current_time);
+        loan.status = LoanStatus::Funded;
+
+        Ok((
+            CounterOfferAccepted {
+                loan_id: loan.loan_id,
+                loan: loan.key(),
+                borrower: loan.borrower,
+                counter_offer: offer.key(),
+                lender: offer.lender,
+                amount: offer.amount,
+                duration: offer.duration,
+                interest_rate: offer.interest_rate,
+                timestamp: current_time,
+            },
+            LoanFunded {
+                loan_id: loan.loan_id,
+                loan: loan.key(),
+                borrower: loan.borrower,
+                lender: offer.lender,
+                loan_amount: offer.amount,
+                interest_rate: offer.interest_rate,
+                funded_at: current_time,
+            },
+        ))
+    }
+}
+
+//borrower turns an offer down, closing it refunds the escrow and rent to the lender
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RejectCounterOffer<'info> {
+    pub borrower: Signer<'info>,
+    #[account(mut)]
+    pub lender: SystemAccount<'info>,
+    #[account(
+        has_one = borrower,
+        seeds = [b"loan" , loan_account.nft_mint.as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = lender,
+        close = lender,
+        seeds = [b"counter_offer", loan_account.key().as_ref(), lender.key().as_ref()],
+        bump = counter_offer.bump,
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+}
+
+impl<'info> RejectCounterOffer<'info> {
+    pub fn reject_offer(&mut self) -> Result<CounterOfferClosed> {
+        closed(&self.counter_offer, true)
+    }
+}
+
+//lender takes an offer back, also once the request it was made on is gone
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawCounterOffer<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    #[account(
+        mut,
+        has_one = lender,
+        close = lender,
+        seeds = [b"counter_offer", counter_offer.loan.as_ref(), lender.key().as_ref()],
+        bump = counter_offer.bump,
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+}
+
+impl<'info> WithdrawCounterOffer<'info> {
+    pub fn withdraw_offer(&mut self) -> Result<CounterOfferClosed> {
+        closed(&self.counter_offer, false)
+    }
+}
+
+fn closed(offer: &Account<CounterOffer>, rejected: bool) -> Result<CounterOfferClosed> {
+    Ok(CounterOfferClosed {
+        loan_id: offer.loan_id,
+        loan: offer.loan,
+        borrower: offer.borrower,
+        counter_offer: offer.key(),
+        lender: offer.lender,
+        refunded: offer.amount,
+        rejected,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
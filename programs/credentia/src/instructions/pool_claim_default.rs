@@ -0,0 +1,172 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    collateral::transfer_nft,
+    error::ErrorCode,
+    events::LoanDefaulted,
+    math::Repayment,
+    LendingPool,
+    Loan,
+    LoanStatus,
+    Platform,
+    PoolCollateral,
+};
+
+//anyone can move the collateral of an expired pool loan into pool custody, which opens its
+//liquidation auction
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PoolClaimDefault<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    #[account(mut)]
+    pub borrower: SystemAccount<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = platform,
+        seeds = [b"pool", platform.key().as_ref(), lending_pool.pool_id.to_le_bytes().as_ref()],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        init_if_needed,
+        payer = caller,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = lending_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_nft_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + PoolCollateral::INIT_SPACE,
+        seeds = [b"pool_collateral", lending_pool.key().as_ref(), borrower_nft_mint.key().as_ref()],
+        bump,
+    )]
+    pub pool_collateral: Account<'info, PoolCollateral>,
+    #[account(
+        mut,
+        has_one = borrower,
+        close = borrower,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    #[account(
+        mut,
+        associated_token::mint = borrower_nft_mint,
+        associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
+    )]
+    pub nft_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> PoolClaimDefault<'info> {
+    pub fn claim_nft(
+        &mut self,
+        bumps: &PoolClaimDefaultBumps,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<LoanDefaulted> {
+        require!(
+            self.loan_account.pool == Some(self.lending_pool.key()),
+            ErrorCode::PoolMismatch
+        );
+        require!(
+            self.loan_account.status == LoanStatus::Funded,
+            ErrorCode::LoanNotActive
+        );
+        let start_time = self
+            .loan_account
+            .start_time
+            .ok_or(ErrorCode::LoanNotStarted)?;
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time - start_time >= self.loan_account.repayment_window(),
+            ErrorCode::WaitForLoanToComplete
+        );
+
+        let loan_id = self.loan_account.loan_id.to_le_bytes();
+        let seeds = &[
+            b"loan".as_ref(),
+            &self.borrower_nft_mint.key().to_bytes()[..],
+            &self.platform.key().to_bytes()[..],
+            &loan_id[..],
+            &[self.loan_account.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.nft_vault.to_account_info(),
+                mint: self.borrower_nft_mint.to_account_info(),
+                to: self.pool_nft_ata.to_account_info(),
+                authority: self.loan_account.to_account_info(),
+            },
+            signer_seeds,
+        );
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
+
+        self.loan_account.status = LoanStatus::Defaulted;
+        //the auction opens at what the borrower owed
+        let owed = Repayment::new(
+            self.loan_account.loan_amount,
+            self.loan_account.interest_rate,
+            0,
+        )?;
+        self.pool_collateral.set_inner(PoolCollateral {
+            pool: self.lending_pool.key(),
+            nft_mint: self.borrower_nft_mint.key(),
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.loan_account.borrower,
+            start_price: owed.total_repayment,
+            auction_start: current_time,
+            payer: self.caller.key(),
+            bump: bumps.pool_collateral,
+        });
+
+        //principal is written off, the nft is worth whatever liquidation brings back
+        let pool = &mut self.lending_pool;
+        pool.settle_loan(self.loan_account.loan_id)?;
+        pool.collateral_held = pool
+            .collateral_held
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // close the nft_vault token account (returns rent to borrower)
+        let cpi_close = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.nft_vault.to_account_info(),
+                destination: self.borrower.to_account_info(),
+                authority: self.loan_account.to_account_info(),
+            },
+            signer_seeds,
+        );
+        close_account(cpi_close)?;
+
+        Ok(LoanDefaulted {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            lender: self.loan_account.lender.unwrap_or_default(),
+            nft_mint: self.borrower_nft_mint.key(),
+            caller: self.caller.key(),
+            timestamp: current_time,
+        })
+    }
+}
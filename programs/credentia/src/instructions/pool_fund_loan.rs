@@ -0,0 +1,186 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    token_interface::Mint,
+};
+
+use crate::collateral::verify_collateral;
+use crate::error::ErrorCode;
+use crate::lamports::{credit_lamports, debit_lamports};
+use crate::{events::LoanFunded, CollectionConfig, LendingPool, Loan, LoanStatus, Platform};
+
+//anyone can crank a requested loan that fits the pool's limits, the pool becomes the lender
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PoolFundLoan<'info> {
+    pub caller: Signer<'info>,
+    #[account(mut)]
+    pub borrower: SystemAccount<'info>,
+    pub borrower_nft_mint: InterfaceAccount<'info, Mint>,
+    pub borrower_nft_collection: InterfaceAccount<'info, Mint>,
+    //metaplex nfts pass metadata and master edition, token-2022 group members leave both out
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            borrower_nft_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata.collection.as_ref().is_some_and(|c| c.key == borrower_nft_collection.key() && c.verified) @ ErrorCode::CollateralNotInCollection,
+    )]
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            borrower_nft_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Option<Box<Account<'info, MasterEditionAccount>>>,
+    #[account(
+        mut,
+        has_one = borrower,
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        mut,
+        has_one = platform,
+        seeds = [b"pool", platform.key().as_ref(), lending_pool.pool_id.to_le_bytes().as_ref()],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        mut,
+        seeds = [b"pool_vault", lending_pool.key().as_ref()],
+        bump = lending_pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(
+        seeds = [b"collection_config", lending_pool.key().as_ref(), borrower_nft_collection.key().as_ref()],
+        bump = collection_config.bump,
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PoolFundLoan<'info> {
+    pub fn fund_from_pool(&mut self) -> Result<LoanFunded> {
+        require!(self.loan_account.lender.is_none(), ErrorCode::LoanFunded);
+        require!(
+            self.loan_account.status == LoanStatus::Requested,
+            ErrorCode::LoanNotActive
+        );
+        //pools lend lamports
+        require!(
+            self.loan_account.currency.is_none(),
+            ErrorCode::UnsupportedCurrency
+        );
+        //the collection config is only meaningful once the nft is shown to be in the collection
+        verify_collateral(
+            &self.borrower_nft_mint,
+            &self.borrower_nft_collection.key(),
+            self.metadata.is_some() && self.master_edition.is_some(),
+        )?;
+        //pools have no lending record, so they can't fund requests asking for one
+        self.loan_account.check_lender(
+            &self.pool_vault.key(),
+            0,
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(
+            self.loan_account.loan_amount <= self.collection_config.max_loan_amount,
+            ErrorCode::PoolLoanAmountExceeded
+        );
+        //a protected loan can only default once its extension is over too
+        require!(
+            self.loan_account.repayment_window() <= self.lending_pool.max_duration as i64,
+            ErrorCode::PoolDurationExceeded
+        );
+        require!(
+            self.loan_account.loan_amount <= self.lending_pool.available_liquidity,
+            ErrorCode::InsufficientPoolLiquidity
+        );
+        //the borrower's requested rate is the most they will pay, the pool charges its curve rate
+        let rate = self
+            .lending_pool
+            .origination_rate(self.loan_account.loan_amount)?;
+        require!(
+            self.loan_account.interest_rate >= rate,
+            ErrorCode::PoolInterestRateTooLow
+        );
+
+        //transfering fund to borrower from the pool vault
+        let pool_key = self.lending_pool.key();
+        let seeds = &[
+            b"pool_vault".as_ref(),
+            pool_key.as_ref(),
+            &[self.lending_pool.vault_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.pool_vault.to_account_info(),
+                to: self.borrower.to_account_info(),
+            },
+            signer_seeds,
+        );
+        transfer(cpi_context, self.loan_account.loan_amount)?;
+
+        //repayments go to the pool vault, so it is recorded as the lender
+        self.loan_account.lender = Some(self.pool_vault.key());
+        self.loan_account.pool = Some(pool_key);
+        self.loan_account.interest_rate = rate;
+        let current_time = Clock::get()?.unix_timestamp;
+        self.loan_account.start_time = Some(current_time);
+        self.loan_account.status = LoanStatus::Funded;
+
+        let deadline = current_time
+            .checked_add(self.loan_account.repayment_window())
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.lending_pool.record_origination(
+            self.loan_account.loan_id,
+            self.loan_account.loan_amount,
+            deadline,
+        )?;
+
+        //the protection premium is repriced at the pool's rate and becomes pool income,
+        //the borrower gets back what they paid for the requested rate on top
+        //the pool's rate is at most the requested one, so the premium can't go up
+        let (old_premium, premium) = self.loan_account.reprice_protection()?;
+        let refund = old_premium
+            .checked_sub(premium)
+            .ok_or(ErrorCode::MathOverflow)?;
+        debit_lamports(&self.loan_account.to_account_info(), old_premium)?;
+        credit_lamports(&self.pool_vault.to_account_info(), premium)?;
+        credit_lamports(&self.borrower.to_account_info(), refund)?;
+        self.lending_pool.available_liquidity = self
+            .lending_pool
+            .available_liquidity
+            .checked_add(premium)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(LoanFunded {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.loan_account.borrower,
+            lender: self.pool_vault.key(),
+            loan_amount: self.loan_account.loan_amount,
+            interest_rate: rate,
+            funded_at: current_time,
+        })
+    }
+}
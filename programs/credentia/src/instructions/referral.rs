@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    events::{ReferralFeesClaimed, ReferrerRegistered},
+    lamports::move_lamports,
+    Platform, ReferrerAccount,
+};
+
+//frontend registers for fee sharing on a platform
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    pub platform: Account<'info, Platform>,
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + ReferrerAccount::INIT_SPACE,
+        seeds = [b"referrer", platform.key().as_ref(), referrer.key().as_ref()],
+        bump,
+    )]
+    pub referrer_account: Account<'info, ReferrerAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterReferrer<'info> {
+    pub fn register(&mut self, bumps: &RegisterReferrerBumps) -> Result<ReferrerRegistered> {
+        self.referrer_account.set_inner(ReferrerAccount {
+            referrer: self.referrer.key(),
+            platform: self.platform.key(),
+            total_earned: 0,
+            total_claimed: 0,
+            loans_paid: 0,
+            bump: bumps.referrer_account,
+        });
+
+        Ok(ReferrerRegistered {
+            referrer: self.referrer.key(),
+            platform: self.platform.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+//frontend withdraws the fees it has earned
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimReferralFees<'info> {
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    #[account(
+        mut,
+        has_one = referrer,
+        seeds = [b"referrer", referrer_account.platform.as_ref(), referrer.key().as_ref()],
+        bump = referrer_account.bump,
+    )]
+    pub referrer_account: Account<'info, ReferrerAccount>,
+}
+
+impl<'info> ClaimReferralFees<'info> {
+    pub fn claim(&mut self) -> Result<ReferralFeesClaimed> {
+        let account_info = self.referrer_account.to_account_info();
+        let rent = Rent::get()?.minimum_balance(account_info.data_len());
+        //everything above rent exemption is earned fees
+        let amount = account_info.lamports().saturating_sub(rent);
+        require!(amount > 0, ErrorCode::NothingToClaim);
+
+        move_lamports(&account_info, &self.referrer.to_account_info(), amount)?;
+
+        self.referrer_account.total_claimed = self
+            .referrer_account
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(ReferralFeesClaimed {
+            referrer: self.referrer.key(),
+            platform: self.referrer_account.platform,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -4,16 +4,22 @@ use anchor_lang::{
 };
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{close_account, transfer_checked, CloseAccount, TransferChecked},
-    token_interface::{Mint, TokenAccount, TokenInterface},
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 
 use crate::{
+    collateral::transfer_nft,
+    currency::transfer_currency,
     error::ErrorCode,
-    events::{LoanRepaid, NFTClaimed},
-    Loan, LoanStatus, Platform,
+    events::{InsuranceFeeCredited, LoanRepaid, NFTClaimed, PlatformFeeSplit},
+    math::{FeeSplit, Repayment},
+    InsuranceFund, InsurancePolicy, LenderReputation, LendingPool, Loan, LoanStatus, Platform,
+    ReferrerAccount,
 };
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ResolveLoan<'info> {
     #[account(mut)]
@@ -26,18 +32,15 @@ pub struct ResolveLoan<'info> {
         payer = borrower,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = borrower,
+        associated_token::token_program = token_program,
     )]
     pub borrower_nft_ata: InterfaceAccount<'info, TokenAccount>,
-    #[account(
-        seeds = [b"platform"],
-        bump = platform.bump,
-    )]
     pub platform: Account<'info, Platform>,
     #[account(
         mut,
         has_one = borrower,
         close = borrower,
-        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref()],
+        seeds = [b"loan" , borrower_nft_mint.key().as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
         bump = loan_account.bump,
     )]
     pub loan_account: Account<'info, Loan>,
@@ -45,6 +48,7 @@ pub struct ResolveLoan<'info> {
         mut,
         associated_token::mint = borrower_nft_mint,
         associated_token::authority = loan_account,
+        associated_token::token_program = token_program,
     )]
     pub nft_vault: InterfaceAccount<'info, TokenAccount>,
     #[account(
@@ -53,6 +57,61 @@ pub struct ResolveLoan<'info> {
         bump,
     )]
     pub treasury_vault: SystemAccount<'info>,
+    //required when the loan was funded by a pool (lender is then the pool vault)
+    #[account(mut)]
+    pub lending_pool: Option<Account<'info, LendingPool>>,
+    //required when the loan has a borrower side referrer
+    #[account(
+        mut,
+        seeds = [b"referrer", platform.key().as_ref(), borrower_referrer_account.referrer.as_ref()],
+        bump = borrower_referrer_account.bump,
+    )]
+    pub borrower_referrer_account: Option<Account<'info, ReferrerAccount>>,
+    //required when the loan has a lender side referrer
+    #[account(
+        mut,
+        seeds = [b"referrer", platform.key().as_ref(), lender_referrer_account.referrer.as_ref()],
+        bump = lender_referrer_account.bump,
+    )]
+    pub lender_referrer_account: Option<Account<'info, ReferrerAccount>>,
+    /// CHECK: the platform's insurance fund, may not exist yet. it is only read and credited
+    /// when the platform routes a share of its fee into insurance
+    #[account(
+        mut,
+        seeds = [b"insurance_fund", platform.key().as_ref()],
+        bump,
+    )]
+    pub insurance_fund: UncheckedAccount<'info>,
+    /// CHECK: the lender's reputation, opened when they funded the loan. pool and migrated loans
+    /// have none, it is only credited when it exists
+    #[account(
+        mut,
+        seeds = [b"lender", platform.key().as_ref(), lender.key().as_ref()],
+        bump,
+    )]
+    pub lender_reputation: UncheckedAccount<'info>,
+    //required when the loan is insured, repayment ends the cover and refunds its rent to the lender
+    #[account(
+        mut,
+        close = lender,
+        seeds = [b"insurance_policy", loan_account.key().as_ref()],
+        bump = insurance_policy.bump,
+    )]
+    pub insurance_policy: Option<Box<Account<'info, InsurancePolicy>>>,
+    //token loans are repaid out of the borrower's currency account into accounts owned by the
+    //lender, the treasury vault and the referrers' wallets
+    pub currency_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(mut)]
+    pub borrower_currency_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub lender_currency_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub treasury_currency_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub borrower_referrer_currency_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub lender_referrer_currency_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub currency_token_program: Option<Interface<'info, TokenInterface>>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
@@ -61,13 +120,16 @@ pub struct ResolveLoan<'info> {
 
 impl<'info> ResolveLoan<'info> {
     //borrower transfer fee(interest * percentage of fee) to marketplace and transfer fund to lender(amount + interest-marketplace fee)
-    pub fn transfer_amount(&mut self) -> Result<()> {
+    pub fn transfer_amount(
+        &mut self,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<(LoanRepaid, PlatformFeeSplit, Option<InsuranceFeeCredited>)> {
         let start_time = self
             .loan_account
             .start_time
             .ok_or(ErrorCode::LoanNotStarted)?;
         require!(
-            Clock::get()?.unix_timestamp - start_time <=(self.loan_account.duration as i64), 
+            Clock::get()?.unix_timestamp - start_time <= self.loan_account.repayment_window(),
             ErrorCode::LoanDefaulted
         );
         require!(
@@ -85,75 +147,175 @@ impl<'info> ResolveLoan<'info> {
         );
 
         let loan_amount = self.loan_account.loan_amount;
-        let interest_rate = self.loan_account.interest_rate;
-        let platform_fee = self.platform.fee_bps;
-        //total interest to pay
-        let interest_amount = loan_amount
-            .checked_mul(interest_rate as u64)
-            .unwrap()
-            .checked_div(10000_u64)
-            .unwrap();
-        //fee for platform
-        let fee_for_platform = interest_amount
-            .checked_mul(platform_fee as u64)
-            .unwrap()
-            .checked_div(10000_u64)
-            .unwrap();
-        //total amount to pay lender
-        let amount_to_pay_lender = loan_amount
-            .checked_add(interest_amount)
-            .unwrap()
-            .checked_sub(fee_for_platform)
-            .unwrap();
+        //interest, platform fee and lender payout, see math.rs for the rounding policy
+        let Repayment {
+            fee_for_platform,
+            amount_to_pay_lender,
+            total_repayment,
+            ..
+        } = Repayment::new(
+            loan_amount,
+            self.loan_account.interest_rate,
+            self.platform.fee_bps,
+        )?;
+        //token loans leave the balance check to the token program, each transfer carries its own fee
         require!(
-            self.borrower.lamports() >= amount_to_pay_lender + fee_for_platform,
+            self.loan_account.currency.is_some() || self.borrower.lamports() >= total_repayment,
             ErrorCode::InsufficientBalance
         );
 
-        //transfering fee to platform
-        let cpi_context_1 = CpiContext::new(
-            self.system_program.to_account_info(),
-            Transfer {
-                from: self.borrower.to_account_info(),
-                to: self.treasury_vault.to_account_info(),
-            },
+        require!(
+            !self.loan_account.insured || self.insurance_policy.is_some(),
+            ErrorCode::InsuranceMismatch
         );
-        transfer(cpi_context_1, fee_for_platform)?;
+
+        //referrers share the platform fee, split evenly when both sides were referred,
+        //the insurance fund only takes its share of lamport fees
+        let lamports = self.loan_account.currency.is_none();
+        let insurance_share_bps = if lamports { self.platform.insurance_share_bps } else { 0 };
+        let split = FeeSplit::new(
+            fee_for_platform,
+            self.platform.referral_share_bps,
+            self.loan_account.borrower_referrer.is_some(),
+            self.loan_account.lender_referrer.is_some(),
+        )?
+        .with_insurance(fee_for_platform, insurance_share_bps)?;
+
+        if let Some(referrer) = self.loan_account.borrower_referrer {
+            let account = self
+                .borrower_referrer_account
+                .as_ref()
+                .ok_or(ErrorCode::ReferrerNotMatched)?;
+            require_keys_eq!(account.referrer, referrer, ErrorCode::ReferrerNotMatched);
+            self.pay(
+                account.to_account_info(),
+                self.borrower_referrer_currency_account.as_deref(),
+                referrer,
+                split.borrower_referrer_amount,
+                hook_accounts,
+            )?;
+            record_referral(&mut self.borrower_referrer_account, split.borrower_referrer_amount, lamports)?;
+        }
+        if let Some(referrer) = self.loan_account.lender_referrer {
+            let account = self
+                .lender_referrer_account
+                .as_ref()
+                .ok_or(ErrorCode::ReferrerNotMatched)?;
+            require_keys_eq!(account.referrer, referrer, ErrorCode::ReferrerNotMatched);
+            self.pay(
+                account.to_account_info(),
+                self.lender_referrer_currency_account.as_deref(),
+                referrer,
+                split.lender_referrer_amount,
+                hook_accounts,
+            )?;
+            record_referral(&mut self.lender_referrer_account, split.lender_referrer_amount, lamports)?;
+        }
+
+        let insurance_credit = if split.insurance_amount > 0 {
+            let info = self.insurance_fund.to_account_info();
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::InsuranceMismatch);
+            let mut fund = InsuranceFund::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            self.pay(info.clone(), None, info.key(), split.insurance_amount, hook_accounts)?;
+            fund.fee_income = fund
+                .fee_income
+                .checked_add(split.insurance_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            fund.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            Some(InsuranceFeeCredited {
+                loan_id: self.loan_account.loan_id,
+                loan: self.loan_account.key(),
+                borrower: self.borrower.key(),
+                insurance_fund: info.key(),
+                amount: split.insurance_amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            })
+        } else {
+            None
+        };
+
+        //transfering fee to platform
+        self.pay(
+            self.treasury_vault.to_account_info(),
+            self.treasury_currency_account.as_deref(),
+            self.treasury_vault.key(),
+            split.treasury_amount,
+            hook_accounts,
+        )?;
 
         //transfering amount to lender
-        let cpi_context_2 = CpiContext::new(
-            self.system_program.to_account_info(),
-            Transfer {
-                from: self.borrower.to_account_info(),
-                to: self.lender.to_account_info(),
-            },
-        );
-        transfer(cpi_context_2, amount_to_pay_lender)?;
+        self.pay(
+            self.lender.to_account_info(),
+            self.lender_currency_account.as_deref(),
+            self.lender.key(),
+            amount_to_pay_lender,
+            hook_accounts,
+        )?;
+
+        //repayment flows back into the pool's liquidity
+        if let Some(pool_key) = self.loan_account.pool {
+            let pool = self
+                .lending_pool
+                .as_mut()
+                .ok_or(ErrorCode::PoolMismatch)?;
+            require_keys_eq!(pool.key(), pool_key, ErrorCode::PoolMismatch);
+            pool.settle_loan(self.loan_account.loan_id)?;
+            pool.available_liquidity = pool
+                .available_liquidity
+                .checked_add(amount_to_pay_lender)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        //a repaid lamport loan counts towards the lender's reputation, unless they lent to
+        //themselves or the fee was too small to cost anything
+        let reputation_info = self.lender_reputation.to_account_info();
+        if *reputation_info.owner == crate::ID && self.lender.key() != self.borrower.key() {
+            let mut reputation =
+                LenderReputation::try_deserialize(&mut &reputation_info.try_borrow_data()?[..])?;
+            reputation.record_repayment(self.loan_account.currency, fee_for_platform)?;
+            reputation.try_serialize(&mut &mut reputation_info.try_borrow_mut_data()?[..])?;
+        }
 
         //updating Field
         self.loan_account.status = LoanStatus::Repaid;
-        emit!(LoanRepaid {
+        let repaid = LoanRepaid {
+            loan_id: self.loan_account.loan_id,
             loan: self.loan_account.key(),
             borrower: self.borrower.key(),
             lender: self.lender.key(),
             repaid_amount: amount_to_pay_lender,
             fee_for_platform,
             timestamp: Clock::get()?.unix_timestamp,
-        });
+        };
+        let fee_split = PlatformFeeSplit {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            fee_for_platform,
+            treasury_amount: split.treasury_amount,
+            insurance_amount: split.insurance_amount,
+            borrower_referrer: self.loan_account.borrower_referrer,
+            borrower_referrer_amount: split.borrower_referrer_amount,
+            lender_referrer: self.loan_account.lender_referrer,
+            lender_referrer_amount: split.lender_referrer_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        };
 
-        Ok(())
+        Ok((repaid, fee_split, insurance_credit))
     }
 
     //now borrower can resolve the loan(transfering back the NFT to the borrower from the nft_vault and close the vault account and laon account)
-    pub fn claim_nft(&mut self) -> Result<()> {
+    pub fn claim_nft(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<NFTClaimed> {
         require!(
             self.loan_account.status == LoanStatus::Repaid,
             ErrorCode::LoanNotRepaided
         );
+        let loan_id = self.loan_account.loan_id.to_le_bytes();
         let seeds = &[
             b"loan".as_ref(),
             &self.borrower_nft_mint.key().to_bytes()[..],
             &self.platform.key().to_bytes()[..],
+            &loan_id[..],
             &[self.loan_account.bump],
         ];
         let signer_seeds = &[&seeds[..]];
@@ -169,7 +331,9 @@ impl<'info> ResolveLoan<'info> {
             signer_seeds,
         );
 
-        transfer_checked(cpi_context, 1, self.borrower_nft_mint.decimals)?;
+        let cpi_context = cpi_context.with_remaining_accounts(hook_accounts.to_vec());
+
+        transfer_nft(cpi_context, self.borrower_nft_mint.decimals)?;
 
         //closing nft_vault account
         // close the nft_vault token account (returns rent to borrower)
@@ -184,13 +348,82 @@ impl<'info> ResolveLoan<'info> {
         );
         close_account(cpi_close)?;
 
-        emit!(NFTClaimed {
+        Ok(NFTClaimed {
+            loan_id: self.loan_account.loan_id,
             loan: self.loan_account.key(),
             borrower: self.borrower.key(),
             nft_mint: self.borrower_nft_mint.key(),
             timestamp: Clock::get()?.unix_timestamp,
-        });
+        })
+    }
+
+    //moves `amount` from the borrower to `to`. token loans credit `currency_account` instead, which
+    //has to belong to `owner`, with exactly `amount` after the transfer fee
+    fn pay(
+        &self,
+        to: AccountInfo<'info>,
+        currency_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        owner: Pubkey,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let Some(currency) = self.loan_account.currency else {
+            let cpi_context = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.borrower.to_account_info(),
+                    to,
+                },
+            );
+            return transfer(cpi_context, amount);
+        };
+
+        let mint = self.currency_mint.as_ref().ok_or(ErrorCode::CurrencyMismatch)?;
+        require_keys_eq!(mint.key(), currency, ErrorCode::CurrencyMismatch);
+        let token_program = self
+            .currency_token_program
+            .as_ref()
+            .ok_or(ErrorCode::CurrencyMismatch)?;
+        let from = self
+            .borrower_currency_account
+            .as_ref()
+            .ok_or(ErrorCode::CurrencyMismatch)?;
+        let to = currency_account.ok_or(ErrorCode::CurrencyMismatch)?;
+        require_keys_eq!(to.owner, owner, ErrorCode::CurrencyMismatch);
+
+        let cpi_context = CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.borrower.to_account_info(),
+            },
+        )
+        .with_remaining_accounts(hook_accounts.to_vec());
+        transfer_currency(cpi_context, mint, amount)
+    }
+}
 
-        Ok(())
+//lamport shares sit in the referrer account until claimed and count towards total_earned,
+//token shares went straight to the referrer's wallet
+fn record_referral(
+    referrer_account: &mut Option<Account<'_, ReferrerAccount>>,
+    amount: u64,
+    lamports: bool,
+) -> Result<()> {
+    let account = referrer_account
+        .as_mut()
+        .ok_or(ErrorCode::ReferrerNotMatched)?;
+    if lamports {
+        account.total_earned = account
+            .total_earned
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
     }
+    account.loans_paid = account
+        .loans_paid
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
 }
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    error::ErrorCode,
+    events::{PoolCollectionRemoved, PoolCollectionSet},
+    CollectionConfig, LendingPool,
+};
+
+//pool authority whitelists a collection (or updates its limit)
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPoolCollection<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub lending_pool: Account<'info, LendingPool>,
+    pub collection: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CollectionConfig::INIT_SPACE,
+        seeds = [b"collection_config", lending_pool.key().as_ref(), collection.key().as_ref()],
+        bump,
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetPoolCollection<'info> {
+    pub fn set_collection(
+        &mut self,
+        max_loan_amount: u64,
+        bumps: &SetPoolCollectionBumps,
+    ) -> Result<PoolCollectionSet> {
+        require!(max_loan_amount > 0, ErrorCode::InvalidAmountError);
+
+        self.collection_config.set_inner(CollectionConfig {
+            pool: self.lending_pool.key(),
+            collection: self.collection.key(),
+            max_loan_amount,
+            bump: bumps.collection_config,
+        });
+
+        Ok(PoolCollectionSet {
+            pool: self.lending_pool.key(),
+            collection: self.collection.key(),
+            max_loan_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+//pool authority removes a collection from the whitelist
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemovePoolCollection<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"collection_config", lending_pool.key().as_ref(), collection_config.collection.as_ref()],
+        bump = collection_config.bump,
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+}
+
+impl<'info> RemovePoolCollection<'info> {
+    //the config account itself is closed by its constraint
+    pub fn remove_collection(&mut self) -> Result<PoolCollectionRemoved> {
+        Ok(PoolCollectionRemoved {
+            pool: self.lending_pool.key(),
+            collection: self.collection_config.collection,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{
+    error::ErrorCode, events::LoanTermsUpdated, lamports::move_lamports, Loan, LoanStatus, Platform,
+};
+
+//borrower changes the terms of a request nobody has funded yet, the nft stays in the vault
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateLoanRequest<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    #[account(
+        mut,
+        has_one = borrower,
+        seeds = [b"loan" , loan_account.nft_mint.as_ref() , platform.key().as_ref(), loan_account.loan_id.to_le_bytes().as_ref()],
+        bump = loan_account.bump,
+    )]
+    pub loan_account: Account<'info, Loan>,
+    pub platform: Account<'info, Platform>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateLoanRequest<'info> {
+    pub fn update_terms(
+        &mut self,
+        amount: u64,
+        duration: u32,
+        interest_rate: u16,
+    ) -> Result<LoanTermsUpdated> {
+        require!(self.loan_account.lender.is_none(), ErrorCode::LoanFunded);
+        require!(
+            self.loan_account.status == LoanStatus::Requested,
+            ErrorCode::LoanNotActive
+        );
+        require!(amount > 0, ErrorCode::InvalidAmountError);
+        require!(duration > 0, ErrorCode::InvalidDurationError);
+
+        self.loan_account.loan_amount = amount;
+        self.loan_account.duration = duration;
+        self.loan_account.interest_rate = interest_rate;
+
+        //a protected request escrows the premium for its current terms
+        let (old_premium, new_premium) = self.loan_account.reprice_protection()?;
+        if new_premium > old_premium {
+            let cpi_context = CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.borrower.to_account_info(),
+                    to: self.loan_account.to_account_info(),
+                },
+            );
+            transfer(cpi_context, new_premium - old_premium)?;
+        } else {
+            move_lamports(
+                &self.loan_account.to_account_info(),
+                &self.borrower.to_account_info(),
+                old_premium - new_premium,
+            )?;
+        }
+
+        Ok(LoanTermsUpdated {
+            loan_id: self.loan_account.loan_id,
+            loan: self.loan_account.key(),
+            borrower: self.borrower.key(),
+            loan_amount: amount,
+            duration,
+            interest_rate,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, events::PlatformUpdated, Platform};
+
+//platform authority updates fee configuration
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdatePlatform<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub platform: Account<'info, Platform>,
+}
+
+impl<'info> UpdatePlatform<'info> {
+    pub fn update(&mut self, fee_bps: u16, referral_share_bps: u16) -> Result<PlatformUpdated> {
+        require!(fee_bps <= 10000, ErrorCode::InvalidFeeBps);
+        require!(referral_share_bps <= 10000, ErrorCode::InvalidFeeBps);
+        require!(
+            referral_share_bps as u32 + self.platform.insurance_share_bps as u32 <= 10000,
+            ErrorCode::InvalidFeeBps
+        );
+
+        self.platform.fee_bps = fee_bps;
+        self.platform.referral_share_bps = referral_share_bps;
+
+        Ok(PlatformUpdated {
+            platform: self.platform.key(),
+            authority: self.authority.key(),
+            fee_bps,
+            referral_share_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, events::PoolUpdated, LendingPool, RateCurve};
+
+//pool authority updates pool limits and the rate curve for future loans
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"pool", lending_pool.platform.as_ref(), lending_pool.pool_id.to_le_bytes().as_ref()],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+}
+
+impl<'info> UpdatePool<'info> {
+    pub fn update_pool(&mut self, max_duration: u32, rate_curve: RateCurve) -> Result<PoolUpdated> {
+        require!(rate_curve.is_valid(), ErrorCode::InvalidRateCurve);
+
+        self.lending_pool.max_duration = max_duration;
+        self.lending_pool.rate_curve = rate_curve;
+
+        Ok(PoolUpdated {
+            pool: self.lending_pool.key(),
+            max_duration,
+            rate_curve,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,107 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{burn, Burn},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, events::PoolWithdrawn, LendingPool};
+
+//lender burns pool shares and receives lamports at the current NAV
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawFromPool<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", lending_pool.platform.as_ref(), lending_pool.pool_id.to_le_bytes().as_ref()],
+        bump = lending_pool.bump,
+        has_one = share_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+    #[account(
+        mut,
+        seeds = [b"pool_vault", lending_pool.key().as_ref()],
+        bump = lending_pool.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_share_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> WithdrawFromPool<'info> {
+    pub fn withdraw(&mut self, shares: u64) -> Result<PoolWithdrawn> {
+        require!(
+            shares > 0 && shares <= self.depositor_share_ata.amount,
+            ErrorCode::InvalidShareAmount
+        );
+
+        //priced without loans past their deadline, a withdrawal can't leave their loss to others
+        let current_time = Clock::get()?.unix_timestamp;
+        let amount = self
+            .lending_pool
+            .lamports_for_shares(shares, self.share_mint.supply, current_time)?;
+        require!(amount > 0, ErrorCode::InvalidShareAmount);
+        //principal out on loans can't be withdrawn until it is repaid
+        require!(
+            amount <= self.lending_pool.available_liquidity,
+            ErrorCode::InsufficientPoolLiquidity
+        );
+
+        let cpi_burn = CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                mint: self.share_mint.to_account_info(),
+                from: self.depositor_share_ata.to_account_info(),
+                authority: self.depositor.to_account_info(),
+            },
+        );
+        burn(cpi_burn, shares)?;
+
+        let pool_key = self.lending_pool.key();
+        let seeds = &[
+            b"pool_vault".as_ref(),
+            pool_key.as_ref(),
+            &[self.lending_pool.vault_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            Transfer {
+                from: self.pool_vault.to_account_info(),
+                to: self.depositor.to_account_info(),
+            },
+            signer_seeds,
+        );
+        transfer(cpi_context, amount)?;
+
+        self.lending_pool.available_liquidity = self
+            .lending_pool
+            .available_liquidity
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(PoolWithdrawn {
+            pool: self.lending_pool.key(),
+            depositor: self.depositor.key(),
+            amount,
+            shares_burned: shares,
+            timestamp: current_time,
+        })
+    }
+}
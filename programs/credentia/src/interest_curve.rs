@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+//utilization and rates are both expressed in basis points
+pub const MAX_BPS: u16 = 10_000;
+
+//kinked utilization curve used to price pool loans
+//rate is the flat interest (bps of principal) charged over the loan, same unit as `Loan::interest_rate`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct RateCurve {
+    //rate at zero utilization
+    pub base_rate: u16,
+    //rate added between zero utilization and the kink
+    pub slope1: u16,
+    //utilization where the second slope kicks in
+    pub kink: u16,
+    //rate added between the kink and full utilization
+    pub slope2: u16,
+}
+
+impl RateCurve {
+    //kink has to sit inside (0, 100%] so both segments are well defined
+    pub fn is_valid(&self) -> bool {
+        self.kink > 0 && self.kink <= MAX_BPS
+    }
+
+    //rate in force at the given utilization, saturating at u16::MAX
+    pub fn rate_at(&self, utilization: u16) -> u16 {
+        let utilization = utilization.min(MAX_BPS) as u64;
+        let kink = self.kink as u64;
+        let base = self.base_rate as u64;
+        let slope1 = self.slope1 as u64;
+        let slope2 = self.slope2 as u64;
+
+        let rate = if utilization <= kink {
+            base + slope1 * utilization / kink
+        } else {
+            //kink == MAX_BPS never reaches this branch
+            let excess = utilization - kink;
+            let span = MAX_BPS as u64 - kink;
+            base + slope1 + slope2 * excess / span
+        };
+        rate.min(u16::MAX as u64) as u16
+    }
+}
+
+//share of the pool's assets that is lent out
+pub fn utilization(total_borrowed: u64, available_liquidity: u64) -> u16 {
+    let total = total_borrowed as u128 + available_liquidity as u128;
+    if total == 0 {
+        return 0;
+    }
+    (total_borrowed as u128 * MAX_BPS as u128 / total) as u16
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+//lamports moved by hand between accounts, for balances the program owns (escrows, vaults, the
+//loan account) where a system transfer can't be signed. a balance that can't cover the debit or
+//would overflow is a MathOverflow instead of an arithmetic panic
+
+pub fn debit_lamports(account: &AccountInfo, amount: u64) -> Result<()> {
+    let balance = account
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    **account.try_borrow_mut_lamports()? = balance;
+    Ok(())
+}
+
+pub fn credit_lamports(account: &AccountInfo, amount: u64) -> Result<()> {
+    let balance = account
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    **account.try_borrow_mut_lamports()? = balance;
+    Ok(())
+}
+
+//`amount` out of `from`, which the program owns, into `to`
+pub fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    debit_lamports(from, amount)?;
+    credit_lamports(to, amount)
+}
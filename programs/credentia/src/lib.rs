@@ -1,15 +1,21 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
+pub mod collateral;
 pub mod constants;
+pub mod currency;
 pub mod error;
 pub mod events;
 pub mod instructions;
+pub mod interest_curve;
+pub mod lamports;
+pub mod math;
 pub mod state;
 
 use anchor_lang::prelude::*;
 
 pub use constants::*;
 pub use instructions::*;
+pub use interest_curve::*;
 pub use state::*;
 
 declare_id!("74RfkJTR8xAGJZZfapADruyj8rfvAv1qQpaz2pVfFxdb");
@@ -18,31 +24,249 @@ declare_id!("74RfkJTR8xAGJZZfapADruyj8rfvAv1qQpaz2pVfFxdb");
 pub mod credentia {
     use super::*;
     //admin initialized the platform
-    pub fn initialize_platform(ctx: Context<Initialize> , fee_bps: u16) -> Result<()> {
-        ctx.accounts.init(fee_bps, &ctx.bumps)?;
+    pub fn initialize_platform(ctx: Context<Initialize> , platform_id: u64, fee_bps: u16) -> Result<()> {
+        let event = ctx.accounts.init(platform_id, fee_bps, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //admin updates the platform fee and the referral share of it
+    pub fn update_platform(ctx: Context<UpdatePlatform>, fee_bps: u16, referral_share_bps: u16) -> Result<()> {
+        let event = ctx.accounts.update(fee_bps, referral_share_bps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //frontend registers as a referrer on a platform
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let event = ctx.accounts.register(&ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //referrer claims earned fees
+    pub fn claim_referral_fees(ctx: Context<ClaimReferralFees>) -> Result<()> {
+        let event = ctx.accounts.claim()?;
+        emit_cpi!(event);
         Ok(())
     }
     //borrower request the loan
     //duration in seconds
-    pub fn request_loan(ctx: Context<CreateLoan> , amount: u64,duration: u32,interest_rate: u16) -> Result<()> {
-        ctx.accounts.create_loan(amount, duration, interest_rate, &ctx.bumps)?;
-        ctx.accounts.transfer_nft_vault()?;
+    //constraints limit who can fund the request and until when
+    pub fn request_loan<'info>(ctx: Context<'_, '_, '_, 'info, CreateLoan<'info>> , amount: u64,duration: u32,interest_rate: u16, constraints: Option<AcceptanceConstraints>, protection: Option<u32>) -> Result<()> {
+        let protected = ctx.accounts.create_loan(amount, duration, interest_rate, constraints, protection, &ctx.bumps)?;
+        let event = ctx.accounts.transfer_nft_vault(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        if let Some(protected) = protected {
+            emit_cpi!(protected);
+        }
         Ok(())
     }
     //borrower resolve the loan
-    pub fn resolve_loan(ctx: Context<ResolveLoan>) -> Result<()>{
-        ctx.accounts.transfer_amount()?;
-        ctx.accounts.claim_nft()?;
+    pub fn resolve_loan<'info>(ctx: Context<'_, '_, '_, 'info, ResolveLoan<'info>>) -> Result<()>{
+        let (repaid, fee_split, insurance_credit) = ctx.accounts.transfer_amount(ctx.remaining_accounts)?;
+        let claimed = ctx.accounts.claim_nft(ctx.remaining_accounts)?;
+        emit_cpi!(repaid);
+        emit_cpi!(fee_split);
+        if let Some(insurance_credit) = insurance_credit {
+            emit_cpi!(insurance_credit);
+        }
+        emit_cpi!(claimed);
+        Ok(())
+    }
+    //lender fund the borrower at the terms they name, a request updated in the meantime is rejected
+    pub fn fund_borrower<'info>(ctx: Context<'_, '_, '_, 'info, FundBorrower<'info>>, expected_amount: u64, expected_duration: u32, expected_interest_rate: u16) -> Result<()>{
+        let (funded, insured) = ctx.accounts.fund_borrower(
+            expected_amount,
+            expected_duration,
+            expected_interest_rate,
+            &ctx.bumps,
+            ctx.remaining_accounts,
+        )?;
+        emit_cpi!(funded);
+        if let Some(insured) = insured {
+            emit_cpi!(insured);
+        }
+        Ok(())
+    }
+    //borrower cancels a loan that has not been funded
+    pub fn cancel_loan<'info>(ctx: Context<'_, '_, '_, 'info, BorrowerCancelLoan<'info>>) -> Result<()> {
+        let event = ctx.accounts.cancel_loan(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //borrower changes amount, duration and interest of a request that is not funded yet
+    pub fn update_loan_request(ctx: Context<UpdateLoanRequest>, amount: u64, duration: u32, interest_rate: u16) -> Result<()> {
+        let event = ctx.accounts.update_terms(amount, duration, interest_rate)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //anyone closes a request past its expiry and returns the nft to the borrower
+    pub fn close_expired_request<'info>(ctx: Context<'_, '_, '_, 'info, CloseExpiredRequest<'info>>) -> Result<()> {
+        let event = ctx.accounts.close_expired(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //lender escrows an offer with other terms for a requested loan
+    pub fn make_counter_offer(ctx: Context<MakeCounterOffer>, amount: u64, duration: u32, interest_rate: u16) -> Result<()> {
+        let event = ctx.accounts.make_offer(amount, duration, interest_rate, &ctx.bumps)?;
+        emit_cpi!(event);
         Ok(())
     }
-    //lender fund the borrower
-    pub fn fund_borrower(ctx: Context<FundBorrower>) -> Result<()>{
-        ctx.accounts.fund_borrower()?;
+    //borrower accepts a counter offer, the loan takes its terms and is funded from the escrow
+    pub fn accept_counter_offer(ctx: Context<AcceptCounterOffer>, expected_amount: u64, expected_duration: u32, expected_interest_rate: u16) -> Result<()> {
+        let (accepted, funded) =
+            ctx.accounts.accept_offer(expected_amount, expected_duration, expected_interest_rate)?;
+        emit_cpi!(accepted);
+        emit_cpi!(funded);
+        Ok(())
+    }
+    //borrower rejects a counter offer and the lender is refunded
+    pub fn reject_counter_offer(ctx: Context<RejectCounterOffer>) -> Result<()> {
+        let event = ctx.accounts.reject_offer()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //lender withdraws their counter offer
+    pub fn withdraw_counter_offer(ctx: Context<WithdrawCounterOffer>) -> Result<()> {
+        let event = ctx.accounts.withdraw_offer()?;
+        emit_cpi!(event);
         Ok(())
     }
     //lender default loan
-    pub fn default_loan(ctx: Context<DefaultLoan>) -> Result<()>{
-        ctx.accounts.claim_nft()?;
+    pub fn default_loan<'info>(ctx: Context<'_, '_, '_, 'info, DefaultLoan<'info>>) -> Result<()>{
+        let event = ctx.accounts.claim_nft(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //anyone defaults an expired loan for the lender and keeps the rent refunds
+    pub fn mark_defaulted<'info>(ctx: Context<'_, '_, '_, 'info, MarkDefaulted<'info>>) -> Result<()> {
+        let event = ctx.accounts.mark_defaulted(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //move a loan opened under the old seeds to its loan id address
+    pub fn migrate_legacy_loan<'info>(ctx: Context<'_, '_, '_, 'info, MigrateLegacyLoan<'info>>) -> Result<()> {
+        let event = ctx.accounts.migrate(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //grow a loan written under an older layout and bring it to the current version
+    pub fn migrate_loan(ctx: Context<MigrateLoan>) -> Result<()> {
+        let event = ctx.accounts.migrate_loan()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //grow a platform written under an older layout and bring it to the current version
+    pub fn migrate_platform(ctx: Context<MigratePlatform>) -> Result<()> {
+        let event = ctx.accounts.migrate_platform()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //admin creates a lending pool
+    pub fn create_pool(ctx: Context<CreatePool>, pool_id: u64, max_duration: u32, rate_curve: RateCurve) -> Result<()> {
+        let event = ctx.accounts.create_pool(pool_id, max_duration, rate_curve, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //pool authority updates the pool's limits and rate curve
+    pub fn update_pool(ctx: Context<UpdatePool>, max_duration: u32, rate_curve: RateCurve) -> Result<()> {
+        let event = ctx.accounts.update_pool(max_duration, rate_curve)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //pool authority whitelists a collection with a max loan amount
+    pub fn set_pool_collection(ctx: Context<SetPoolCollection>, max_loan_amount: u64) -> Result<()> {
+        let event = ctx.accounts.set_collection(max_loan_amount, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //pool authority removes a collection from the whitelist
+    pub fn remove_pool_collection(ctx: Context<RemovePoolCollection>) -> Result<()> {
+        let event = ctx.accounts.remove_collection()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //lender deposits into a pool for shares
+    pub fn deposit_to_pool(ctx: Context<DepositToPool>, amount: u64) -> Result<()> {
+        let event = ctx.accounts.deposit(amount)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //lender burns shares for lamports
+    pub fn withdraw_from_pool(ctx: Context<WithdrawFromPool>, shares: u64) -> Result<()> {
+        let event = ctx.accounts.withdraw(shares)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //anyone funds a requested loan from a pool
+    pub fn pool_fund_loan(ctx: Context<PoolFundLoan>) -> Result<()> {
+        let event = ctx.accounts.fund_from_pool()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //borrower deposits an nft and is funded by a pool in one instruction
+    pub fn borrow_from_pool<'info>(ctx: Context<'_, '_, '_, 'info, BorrowFromPool<'info>>, amount: u64, duration: u32, max_interest_rate: u16) -> Result<()> {
+        let (requested, funded) = ctx.accounts.borrow(amount, duration, max_interest_rate, &ctx.bumps, ctx.remaining_accounts)?;
+        emit_cpi!(requested);
+        emit_cpi!(funded);
+        Ok(())
+    }
+    //anyone moves an expired pool loan's nft into pool custody
+    pub fn pool_claim_default<'info>(ctx: Context<'_, '_, '_, 'info, PoolClaimDefault<'info>>) -> Result<()> {
+        let event = ctx.accounts.claim_nft(&ctx.bumps, ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //anyone buys a defaulted pool nft at its auction price, paying at most `max_price`
+    pub fn liquidate_pool_collateral<'info>(ctx: Context<'_, '_, '_, 'info, LiquidatePoolCollateral<'info>>, max_price: u64) -> Result<()> {
+        let event = ctx.accounts.liquidate(max_price, ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //admin sets up the insurance fund, its share of the platform fee and the oracle that values collateral
+    pub fn configure_insurance(ctx: Context<ConfigureInsurance>, insurance_share_bps: u16, premium_bps: u16, coverage_bps: u16, max_claim_bps: u16, oracle: Pubkey) -> Result<()> {
+        let event = ctx.accounts.configure(insurance_share_bps, premium_bps, coverage_bps, max_claim_bps, oracle, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //oracle posts the lamport value of an nft
+    pub fn set_collateral_value(ctx: Context<SetCollateralValue>, value: u64) -> Result<()> {
+        let event = ctx.accounts.set_value(value, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //insured lender claims part of the shortfall on a loan that was not repaid
+    pub fn claim_insurance(ctx: Context<ClaimInsurance>) -> Result<()> {
+        let event = ctx.accounts.claim()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //borrower borrows the escrowed nft for the rest of the transaction
+    pub fn flash_use_collateral<'info>(ctx: Context<'_, '_, '_, 'info, FlashUseCollateral<'info>>) -> Result<()> {
+        let event = ctx.accounts.flash_use(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //borrower puts a flash used nft back into the vault
+    pub fn return_collateral<'info>(ctx: Context<'_, '_, '_, 'info, ReturnCollateral<'info>>) -> Result<()> {
+        let event = ctx.accounts.return_nft(ctx.remaining_accounts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //platform authority allows borrowers to vote through a governance program
+    pub fn set_governance_program(ctx: Context<SetGovernanceProgram>, instructions: Vec<Vec<u8>>) -> Result<()> {
+        let event = ctx.accounts.set_program(instructions, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //platform authority removes a governance program
+    pub fn remove_governance_program(ctx: Context<RemoveGovernanceProgram>) -> Result<()> {
+        let event = ctx.accounts.remove_program()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+    //borrower casts or relinquishes a vote with the escrowed nft, the loan account signs
+    pub fn governance_vote<'info>(ctx: Context<'_, '_, '_, 'info, GovernanceVote<'info>>, data: Vec<u8>) -> Result<()> {
+        let event = ctx.accounts.relay(data, ctx.remaining_accounts)?;
+        emit_cpi!(event);
         Ok(())
     }
 
@@ -0,0 +1,131 @@
+use crate::{error::ErrorCode, interest_curve::MAX_BPS};
+
+//rounding policy: every bps share is computed with u128 intermediates and rounded down.
+//interest never exceeds the quoted rate, the platform fee never exceeds its share of the
+//interest and referrers never take more than their share of the fee, so any dust stays
+//with the lender (interest/fee) or the treasury (referral split)
+
+//a * b / denominator rounded down, MathOverflow if the result leaves u64
+pub fn mul_div_floor(a: u64, b: u64, denominator: u64) -> Result<u64, ErrorCode> {
+    if denominator == 0 {
+        return Err(ErrorCode::MathOverflow);
+    }
+    let value = a as u128 * b as u128 / denominator as u128;
+    u64::try_from(value).map_err(|_| ErrorCode::MathOverflow)
+}
+
+//`bps` basis points of `amount`, rounded down
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64, ErrorCode> {
+    mul_div_floor(amount, bps as u64, MAX_BPS as u64)
+}
+
+//what a borrower owes on repayment and where it goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Repayment {
+    pub interest: u64,
+    pub fee_for_platform: u64,
+    //principal + interest - fee_for_platform
+    pub amount_to_pay_lender: u64,
+    //what leaves the borrower's wallet, principal + interest
+    pub total_repayment: u64,
+}
+
+impl Repayment {
+    pub fn new(loan_amount: u64, interest_rate: u16, fee_bps: u16) -> Result<Self, ErrorCode> {
+        let interest = bps_of(loan_amount, interest_rate)?;
+        let fee_for_platform = bps_of(interest, fee_bps)?;
+        let total_repayment = loan_amount
+            .checked_add(interest)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let amount_to_pay_lender = total_repayment
+            .checked_sub(fee_for_platform)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(Self {
+            interest,
+            fee_for_platform,
+            amount_to_pay_lender,
+            total_repayment,
+        })
+    }
+}
+
+//platform fee split between the treasury, the loan's referrers and the insurance fund
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub treasury_amount: u64,
+    pub borrower_referrer_amount: u64,
+    pub lender_referrer_amount: u64,
+    pub insurance_amount: u64,
+}
+
+impl FeeSplit {
+    //referrers share `referral_share_bps` of the fee, split evenly when both sides were referred
+    pub fn new(
+        fee_for_platform: u64,
+        referral_share_bps: u16,
+        borrower_referred: bool,
+        lender_referred: bool,
+    ) -> Result<Self, ErrorCode> {
+        let referral_total = bps_of(fee_for_platform, referral_share_bps)?;
+        let referrers = borrower_referred as u64 + lender_referred as u64;
+        let per_referrer = referral_total.checked_div(referrers).unwrap_or(0);
+        let borrower_referrer_amount = if borrower_referred { per_referrer } else { 0 };
+        let lender_referrer_amount = if lender_referred { per_referrer } else { 0 };
+        let treasury_amount = fee_for_platform
+            .checked_sub(borrower_referrer_amount)
+            .and_then(|amount| amount.checked_sub(lender_referrer_amount))
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(Self {
+            treasury_amount,
+            borrower_referrer_amount,
+            lender_referrer_amount,
+            insurance_amount: 0,
+        })
+    }
+
+    //moves `insurance_share_bps` of the fee from the treasury's part into the insurance fund,
+    //MathOverflow when referrers and insurance together take more than the fee
+    pub fn with_insurance(
+        self,
+        fee_for_platform: u64,
+        insurance_share_bps: u16,
+    ) -> Result<Self, ErrorCode> {
+        let insurance_amount = bps_of(fee_for_platform, insurance_share_bps)?;
+        let treasury_amount = self
+            .treasury_amount
+            .checked_sub(insurance_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(Self {
+            treasury_amount,
+            insurance_amount,
+            ..self
+        })
+    }
+}
+
+//what an insured lender gets back for a defaulted loan: `coverage_bps` of the principal the
+//collateral no longer covers, capped at `max_claim_bps` of what the fund can pay out
+pub fn insurance_payout(
+    principal: u64,
+    collateral_value: u64,
+    coverage_bps: u16,
+    fund_balance: u64,
+    max_claim_bps: u16,
+) -> Result<u64, ErrorCode> {
+    let shortfall = principal.saturating_sub(collateral_value);
+    let covered = bps_of(shortfall, coverage_bps)?;
+    let cap = bps_of(fund_balance, max_claim_bps)?;
+    Ok(covered.min(cap))
+}
+
+//what a borrower pays up front for `extension` extra seconds to repay: the loan's interest
+//pro rated over the extension, so the lender earns the quoted rate for the delay
+pub fn protection_premium(
+    loan_amount: u64,
+    interest_rate: u16,
+    duration: u32,
+    extension: u32,
+) -> Result<u64, ErrorCode> {
+    let interest = bps_of(loan_amount, interest_rate)?;
+    mul_div_floor(interest, extension as u64, duration as u64)
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+//a lender's proposal of different terms for an open request, the offered amount sits in this account until
+//the borrower accepts or rejects it or the lender withdraws it
+#[account]
+#[derive(InitSpace)]
+pub struct CounterOffer {
+    pub loan: Pubkey,
+    pub loan_id: u64,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub duration: u32,
+    pub interest_rate: u16,
+    pub lender_referrer: Option<Pubkey>,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl CounterOffer {
+    //the lender can withdraw and remake an offer at the same address, the borrower names the terms
+    //they accept
+    pub fn check_terms(&self, amount: u64, duration: u32, interest_rate: u16) -> Result<()> {
+        require!(self.amount == amount, ErrorCode::LoanTermsChanged);
+        require!(self.duration == duration, ErrorCode::LoanTermsChanged);
+        require!(self.interest_rate == interest_rate, ErrorCode::LoanTermsChanged);
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+//instruction prefixes a governance program can be allowed, and the longest one (an anchor
+//discriminator)
+pub const MAX_GOVERNANCE_INSTRUCTIONS: usize = 8;
+pub const MAX_GOVERNANCE_PREFIX_LEN: usize = 8;
+
+//a governance program borrowers may vote through with their escrowed nft, at
+//[b"governance_program", platform, program_id]. the loan account signs the relayed
+//instruction, so only instructions starting with one of `instructions` go through,
+//e.g. the one byte cast and relinquish vote tags of spl governance or the
+//discriminators of an nft voter plugin
+#[account]
+#[derive(InitSpace)]
+pub struct GovernanceProgram {
+    pub platform: Pubkey,
+    pub program_id: Pubkey,
+    #[max_len(MAX_GOVERNANCE_INSTRUCTIONS, MAX_GOVERNANCE_PREFIX_LEN)]
+    pub instructions: Vec<Vec<u8>>,
+    pub bump: u8,
+}
+
+impl GovernanceProgram {
+    pub fn allows(&self, data: &[u8]) -> bool {
+        self.instructions
+            .iter()
+            .any(|prefix| !prefix.is_empty() && data.starts_with(prefix))
+    }
+}
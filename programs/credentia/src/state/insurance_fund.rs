@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+//per platform insurance for lenders at [b"insurance_fund", platform], funded by a share of every
+//lamport platform fee and by the premiums of insured loans. like referrer accounts it holds its
+//lamports itself, everything above rent exemption can be paid out to claims
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceFund {
+    pub platform: Pubkey,
+    //posts the collateral valuations claims are settled against
+    pub oracle: Pubkey,
+    //premium a lender pays to insure a loan, bps of principal
+    pub premium_bps: u16,
+    //share of the principal shortfall a policy pays out
+    pub coverage_bps: u16,
+    //most a single claim can take, bps of the fund's claimable balance
+    pub max_claim_bps: u16,
+    //lamports received from platform fees
+    pub fee_income: u64,
+    pub total_premiums: u64,
+    pub total_claimed: u64,
+    pub claims_paid: u64,
+    pub policies_written: u64,
+    pub bump: u8,
+}
+
+//a lender's cover on one loan at [b"insurance_policy", loan], closed to the lender on repayment
+//or once claimed
+#[account]
+#[derive(InitSpace)]
+pub struct InsurancePolicy {
+    pub platform: Pubkey,
+    pub loan: Pubkey,
+    pub loan_id: u64,
+    pub borrower: Pubkey,
+    pub lender: Pubkey,
+    pub nft_mint: Pubkey,
+    pub principal: u64,
+    pub premium: u64,
+    //coverage the fund offered when the policy was written
+    pub coverage_bps: u16,
+    //last moment the loan can be repaid, the loan defaulted if the policy is still open after it
+    pub deadline: i64,
+    pub bump: u8,
+}
+
+//oracle's value of an nft in lamports at [b"valuation", platform, nft_mint]
+#[account]
+#[derive(InitSpace)]
+pub struct CollateralValuation {
+    pub platform: Pubkey,
+    pub nft_mint: Pubkey,
+    pub value: u64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+//smallest platform fee, in lamports, a repayment has to pay to count
+pub const MIN_REPUTATION_FEE: u64 = 1_000_000;
+
+//a lender's track record on a platform, borrowers can ask for a minimum number of repaid loans.
+//only repayments of lamport loans by another wallet count, and only when they paid the platform
+//at least MIN_REPUTATION_FEE. someone repaying loans between two wallets they own gets the
+//interest back, so that fee is what every loan on the record costs them, at least
+//MIN_REPUTATION_FEE and more on larger or higher rate loans. token loans don't count, a raw
+//amount of a token says nothing about what it cost. on a platform charging no fee nothing counts
+#[account]
+#[derive(InitSpace)]
+pub struct LenderReputation {
+    pub lender: Pubkey,
+    pub platform: Pubkey,
+    pub loans_repaid: u64,
+    pub bump: u8,
+}
+
+impl LenderReputation {
+    //the first loan a lender funds on a platform opens their record
+    pub fn open(&mut self, lender: Pubkey, platform: Pubkey, bump: u8) {
+        if self.lender == Pubkey::default() {
+            self.lender = lender;
+            self.platform = platform;
+            self.bump = bump;
+        }
+    }
+
+    //a borrower other than the lender repaid one of the lender's loans in `currency` (none for
+    //lamports), paying `fee_for_platform`
+    pub fn record_repayment(&mut self, currency: Option<Pubkey>, fee_for_platform: u64) -> Result<()> {
+        if currency.is_some() || fee_for_platform < MIN_REPUTATION_FEE {
+            return Ok(());
+        }
+        self.loans_repaid = self
+            .loans_repaid
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
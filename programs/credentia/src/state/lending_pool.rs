@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    interest_curve::{utilization, RateCurve},
+    math::mul_div_floor,
+};
+
+//outstanding loans a pool tracks, NAV needs each one's deadline
+pub const MAX_POOL_LOANS: usize = 64;
+//how long the price of defaulted pool collateral takes to fall from the debt to nothing
+pub const LIQUIDATION_AUCTION: i64 = 7 * 24 * 60 * 60;
+
+//pool of lender deposits that funds loans automatically
+#[account]
+#[derive(InitSpace)]
+pub struct LendingPool {
+    pub authority: Pubkey,
+    pub platform: Pubkey,
+    pub pool_id: u64,
+    pub share_mint: Pubkey,
+    pub max_duration: u32,
+    pub rate_curve: RateCurve,
+    //principal currently lent out to borrowers
+    pub total_borrowed: u64,
+    //lamports in the pool vault that can be lent or withdrawn, the vault's rent exempt reserve
+    //is never part of it so paying all of it out can't leave the vault below rent exemption
+    pub available_liquidity: u64,
+    pub active_loans: u32,
+    pub vault_bump: u8,
+    pub share_mint_bump: u8,
+    pub bump: u8,
+    //outstanding loans, the ones past their deadline count for nothing until they are written off
+    #[max_len(MAX_POOL_LOANS)]
+    pub loans: Vec<PoolLoan>,
+    //defaulted nfts still in auction, their proceeds haven't reached the pool yet
+    pub collateral_held: u32,
+}
+
+//a loan the pool funded, it can no longer be repaid once `deadline` is reached
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct PoolLoan {
+    pub loan_id: u64,
+    pub principal: u64,
+    pub deadline: i64,
+}
+
+impl LendingPool {
+    //net asset value backing the share supply at `now`. principal of loans past their deadline is
+    //left out before `pool_claim_default` writes it off, so nobody withdraws at the old value
+    pub fn nav(&self, now: i64) -> std::result::Result<u64, ErrorCode> {
+        self.available_liquidity
+            .checked_add(self.total_borrowed)
+            .and_then(|nav| nav.checked_sub(self.overdue_principal(now)?))
+            .ok_or(ErrorCode::MathOverflow)
+    }
+
+    //principal of loans past their deadline and not written off yet
+    pub fn overdue_principal(&self, now: i64) -> Option<u64> {
+        self.loans
+            .iter()
+            .filter(|loan| now >= loan.deadline)
+            .try_fold(0u64, |sum, loan| sum.checked_add(loan.principal))
+    }
+
+    //deposits wait while defaults are unsettled: until a loan is written off and its collateral
+    //sold NAV jumps once the proceeds arrive, which a deposit just before would skim
+    pub fn check_deposits_open(&self, now: i64) -> std::result::Result<(), ErrorCode> {
+        let overdue = self.loans.iter().any(|loan| now >= loan.deadline);
+        if overdue || self.collateral_held > 0 {
+            return Err(ErrorCode::PoolSettlementPending);
+        }
+        Ok(())
+    }
+
+    //rate for a new loan, priced at the utilization the pool would have after funding it
+    pub fn origination_rate(&self, loan_amount: u64) -> std::result::Result<u16, ErrorCode> {
+        let borrowed = self
+            .total_borrowed
+            .checked_add(loan_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let available = self
+            .available_liquidity
+            .checked_sub(loan_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(self.rate_curve.rate_at(utilization(borrowed, available)))
+    }
+
+    //book a newly funded loan against the pool
+    pub fn record_origination(
+        &mut self,
+        loan_id: u64,
+        loan_amount: u64,
+        deadline: i64,
+    ) -> std::result::Result<(), ErrorCode> {
+        if self.loans.len() >= MAX_POOL_LOANS {
+            return Err(ErrorCode::PoolLoanLimitReached);
+        }
+        self.available_liquidity = self
+            .available_liquidity
+            .checked_sub(loan_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.total_borrowed = self
+            .total_borrowed
+            .checked_add(loan_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.active_loans = self
+            .active_loans
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.loans.push(PoolLoan {
+            loan_id,
+            principal: loan_amount,
+            deadline,
+        });
+        Ok(())
+    }
+
+    //takes a repaid or defaulted loan off the books, returns its principal
+    pub fn settle_loan(&mut self, loan_id: u64) -> std::result::Result<u64, ErrorCode> {
+        let index = self
+            .loans
+            .iter()
+            .position(|loan| loan.loan_id == loan_id)
+            .ok_or(ErrorCode::PoolMismatch)?;
+        let principal = self.loans.swap_remove(index).principal;
+        self.total_borrowed = self
+            .total_borrowed
+            .checked_sub(principal)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.active_loans = self
+            .active_loans
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(principal)
+    }
+
+    //shares minted for a deposit, first depositor gets shares 1:1 with lamports. once every
+    //lamport behind existing shares is written off the pool takes no deposits, a new depositor
+    //would otherwise share their lamports with the worthless shares
+    pub fn shares_for_deposit(
+        &self,
+        amount: u64,
+        share_supply: u64,
+        now: i64,
+    ) -> std::result::Result<u64, ErrorCode> {
+        self.check_deposits_open(now)?;
+        if share_supply == 0 {
+            return Ok(amount);
+        }
+        let nav = self.nav(now)?;
+        if nav == 0 {
+            return Err(ErrorCode::InvalidShareAmount);
+        }
+        mul_div_floor(amount, share_supply, nav)
+    }
+
+    //lamports paid out when burning shares at the current NAV
+    pub fn lamports_for_shares(
+        &self,
+        shares: u64,
+        share_supply: u64,
+        now: i64,
+    ) -> std::result::Result<u64, ErrorCode> {
+        if share_supply == 0 {
+            return Ok(0);
+        }
+        mul_div_floor(shares, self.nav(now)?, share_supply)
+    }
+}
+
+//defaulted collateral in pool custody, sold in a dutch auction anyone can take at its current price.
+//the pool authority neither picks the buyer nor the price
+#[account]
+#[derive(InitSpace)]
+pub struct PoolCollateral {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub loan_id: u64,
+    pub loan: Pubkey,
+    pub borrower: Pubkey,
+    //principal and interest the borrower owed, the auction opens at it
+    pub start_price: u64,
+    pub auction_start: i64,
+    //caller of `pool_claim_default`, it paid the rent and gets it back at liquidation
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+impl PoolCollateral {
+    //falls linearly from `start_price` to 0 over `LIQUIDATION_AUCTION`
+    pub fn price_at(&self, now: i64) -> std::result::Result<u64, ErrorCode> {
+        let elapsed = now.saturating_sub(self.auction_start).clamp(0, LIQUIDATION_AUCTION);
+        let remaining = (LIQUIDATION_AUCTION - elapsed) as u64;
+        mul_div_floor(self.start_price, remaining, LIQUIDATION_AUCTION as u64)
+    }
+}
+
+//collection whitelisted by a pool along with its per-loan limit
+#[account]
+#[derive(InitSpace)]
+pub struct CollectionConfig {
+    pub pool: Pubkey,
+    pub collection: Pubkey,
+    pub max_loan_amount: u64,
+    pub bump: u8,
+}
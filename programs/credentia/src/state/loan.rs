@@ -1,6 +1,14 @@
 use anchor_lang::prelude::*;
 
+use crate::{error::ErrorCode, math::protection_premium};
+
+//lenders a private request can name
+pub const MAX_ALLOWED_LENDERS: usize = 5;
+//longest extension default protection can buy
+pub const MAX_PROTECTION_EXTENSION: u32 = 14 * 24 * 60 * 60;
+
 #[account]
+#[derive(InitSpace)]
 pub struct Loan {
     pub borrower: Pubkey,
     pub lender: Option<Pubkey>,
@@ -12,22 +20,143 @@ pub struct Loan {
     pub time_of_liquidation_or_repayment: Option<u32>,
     pub interest_rate: u16,
     pub bump: u8,
+    pub pool: Option<Pubkey>,
+    pub loan_id: u64,
+    pub borrower_referrer: Option<Pubkey>,
+    pub lender_referrer: Option<Pubkey>,
+    //layout version, loans opened before it existed read 0 from their padding
+    //new fields go after it and must read as zero so `migrate_loan` can upgrade by growing the account
+    pub version: u8,
+    //spl token mint the loan is paid in, lamports when none
+    pub currency: Option<Pubkey>,
+    //acceptance constraints the borrower set on the request, see `AcceptanceConstraints`
+    pub expires_at: Option<i64>,
+    #[max_len(MAX_ALLOWED_LENDERS)]
+    pub allowed_lenders: Vec<Pubkey>,
+    pub min_lender_reputation: u64,
+    //the lender bought an `InsurancePolicy` when funding, it is closed on repayment
+    pub insured: bool,
+    //the borrower bought extra time to repay when requesting, see `DefaultProtection`
+    pub protection: Option<DefaultProtection>,
 }
 
 impl Loan {
-    pub const MAX_SPACE: usize = 32 + // borrower
-        1 + 32 + // Option<Pubkey> = tag + value
-        32 + // nft_mint
-        8 +  // loan_amount
-        4 +  // duration
-        1 + 8 + // Option<u32> (start_time)
-        1 + // LoanStatus
-        1 + 4 + // Option<u32> (time_of_liquidation_or_repayment)
-        1 + // interest_rate
-        1; // bump
+    pub const VERSION: u8 = 5;
+
+    pub fn set_constraints(&mut self, constraints: AcceptanceConstraints, now: i64) -> Result<()> {
+        if let Some(expires_at) = constraints.expires_at {
+            require!(expires_at > now, ErrorCode::InvalidRequestExpiry);
+        }
+        require!(
+            constraints.allowed_lenders.len() <= MAX_ALLOWED_LENDERS,
+            ErrorCode::TooManyAllowedLenders
+        );
+        self.expires_at = constraints.expires_at;
+        self.allowed_lenders = constraints.allowed_lenders;
+        self.min_lender_reputation = constraints.min_lender_reputation;
+        Ok(())
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    //seconds after `start_time` the borrower can still repay, defaults are only possible from then on
+    pub fn repayment_window(&self) -> i64 {
+        let extension = self.protection.as_ref().map_or(0, |p| p.extension);
+        self.duration as i64 + extension as i64
+    }
+
+    //premium held in the loan account until the request is funded
+    pub fn protection_premium(&self) -> u64 {
+        self.protection.as_ref().map_or(0, |p| p.premium)
+    }
+
+    //prices the protection for the current terms, returns the (old, new) premium
+    pub fn reprice_protection(&mut self) -> Result<(u64, u64)> {
+        let (loan_amount, interest_rate, duration) =
+            (self.loan_amount, self.interest_rate, self.duration);
+        match self.protection.as_mut() {
+            Some(protection) => {
+                let old = protection.premium;
+                protection.premium =
+                    protection_premium(loan_amount, interest_rate, duration, protection.extension)?;
+                Ok((old, protection.premium))
+            }
+            None => Ok((0, 0)),
+        }
+    }
+
+    //the borrower can update an unfunded request, whoever funds it names the terms they agreed to
+    pub fn check_terms(&self, amount: u64, duration: u32, interest_rate: u16) -> Result<()> {
+        require!(self.loan_amount == amount, ErrorCode::LoanTermsChanged);
+        require!(self.duration == duration, ErrorCode::LoanTermsChanged);
+        require!(self.interest_rate == interest_rate, ErrorCode::LoanTermsChanged);
+        Ok(())
+    }
+
+    //whether `lender`, with `reputation` loans repaid to them on the platform, may fund the request at `now`
+    pub fn check_lender(&self, lender: &Pubkey, reputation: u64, now: i64) -> Result<()> {
+        require!(!self.is_expired(now), ErrorCode::RequestExpired);
+        require!(
+            self.allowed_lenders.is_empty() || self.allowed_lenders.contains(lender),
+            ErrorCode::LenderNotAllowed
+        );
+        require!(
+            reputation >= self.min_lender_reputation,
+            ErrorCode::LenderReputationTooLow
+        );
+        Ok(())
+    }
+
+    //neither side of the loan may take a referral share of its fee, checked whenever a party or a
+    //referrer is attached
+    pub fn check_referrers(&self) -> Result<()> {
+        let parties = [Some(self.borrower), self.lender];
+        for referrer in [self.borrower_referrer, self.lender_referrer].into_iter().flatten() {
+            require!(!parties.contains(&Some(referrer)), ErrorCode::SelfReferral);
+        }
+        Ok(())
+    }
+}
+
+//what a borrower can require of whoever funds their request, no constraints by default
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AcceptanceConstraints {
+    //unix timestamp from which the request can no longer be funded and anyone may close it
+    pub expires_at: Option<i64>,
+    //lenders allowed to fund a private request, anyone when empty
+    pub allowed_lenders: Vec<Pubkey>,
+    //minimum `LenderReputation::loans_repaid`
+    pub min_lender_reputation: u64,
+}
+
+//automatic extension a borrower buys at request time: a missed deadline only becomes a default
+//`extension` seconds later. the premium is escrowed in the loan account, goes to whoever funds the
+//request and is refunded if nobody does
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct DefaultProtection {
+    pub extension: u32,
+    pub premium: u64,
+}
+
+//loan layout before loan ids, these accounts live at [b"loan", nft_mint, platform]
+//and are moved to the id seeded address by `migrate_legacy_loan`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LegacyLoan {
+    pub borrower: Pubkey,
+    pub lender: Option<Pubkey>,
+    pub nft_mint: Pubkey,
+    pub loan_amount: u64,
+    pub duration: u32,
+    pub start_time: Option<i64>,
+    pub status: LoanStatus,
+    pub time_of_liquidation_or_repayment: Option<u32>,
+    pub interest_rate: u16,
+    pub bump: u8,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
 pub enum LoanStatus {
     Requested,
     Funded,
@@ -1,5 +1,17 @@
+pub mod counter_offer;
+pub mod governance_program;
+pub mod insurance_fund;
+pub mod lender;
+pub mod lending_pool;
 pub mod loan;
 pub mod platform;
+pub mod referrer;
 
+pub use counter_offer::*;
+pub use governance_program::*;
+pub use insurance_fund::*;
+pub use lender::*;
+pub use lending_pool::*;
 pub use loan::*;
 pub use platform::*;
+pub use referrer::*;
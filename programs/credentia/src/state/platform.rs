@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+//one marketplace, new platforms live at [b"platform", platform_id]
+//instructions accept any platform owned by this program, loans, pools and vaults are bound to it through their seeds
 #[account]
 #[derive(InitSpace)]
 pub struct Platform {
@@ -8,4 +10,17 @@ pub struct Platform {
     pub reward_bump: u8,
     pub treasury_bump: u8,
     pub bump: u8,
+    //next loan id, every loan gets a unique id across the platform's history
+    pub loan_count: u64,
+    pub platform_id: u64,
+    //share of fee_for_platform paid to the frontends that referred the loan
+    pub referral_share_bps: u16,
+    //layout version, new fields go after it and must read as zero so `migrate_platform` can upgrade by growing the account
+    pub version: u8,
+    //share of fee_for_platform routed into the insurance fund on lamport loans
+    pub insurance_share_bps: u16,
+}
+
+impl Platform {
+    pub const VERSION: u8 = 2;
 }
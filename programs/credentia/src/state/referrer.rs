@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+//per platform accounting for a frontend that refers loans, earned fees sit in this account until claimed
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerAccount {
+    pub referrer: Pubkey,
+    pub platform: Pubkey,
+    pub total_earned: u64,
+    pub total_claimed: u64,
+    pub loans_paid: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,100 @@
+use credentia::interest_curve::{utilization, RateCurve, MAX_BPS};
+use proptest::prelude::*;
+
+fn curve() -> RateCurve {
+    RateCurve {
+        base_rate: 200,
+        slope1: 400,
+        kink: 8_000,
+        slope2: 3_000,
+    }
+}
+
+#[test]
+fn rate_hits_curve_anchor_points() {
+    let curve = curve();
+    assert_eq!(curve.rate_at(0), 200);
+    assert_eq!(curve.rate_at(4_000), 400);
+    assert_eq!(curve.rate_at(8_000), 600);
+    assert_eq!(curve.rate_at(9_000), 2_100);
+    assert_eq!(curve.rate_at(MAX_BPS), 3_600);
+}
+
+#[test]
+fn rate_clamps_utilization_above_full() {
+    let curve = curve();
+    assert_eq!(curve.rate_at(u16::MAX), curve.rate_at(MAX_BPS));
+}
+
+#[test]
+fn kink_at_full_utilization_is_single_slope() {
+    let curve = RateCurve {
+        base_rate: 100,
+        slope1: 1_000,
+        kink: MAX_BPS,
+        slope2: 5_000,
+    };
+    assert!(curve.is_valid());
+    assert_eq!(curve.rate_at(MAX_BPS), 1_100);
+}
+
+#[test]
+fn zero_kink_is_invalid() {
+    let curve = RateCurve {
+        kink: 0,
+        ..curve()
+    };
+    assert!(!curve.is_valid());
+}
+
+#[test]
+fn utilization_of_empty_pool_is_zero() {
+    assert_eq!(utilization(0, 0), 0);
+    assert_eq!(utilization(0, 1_000), 0);
+    assert_eq!(utilization(1_000, 0), MAX_BPS);
+    assert_eq!(utilization(250, 750), 2_500);
+}
+
+fn valid_curve() -> impl Strategy<Value = RateCurve> {
+    (any::<u16>(), any::<u16>(), 1..=MAX_BPS, any::<u16>()).prop_map(
+        |(base_rate, slope1, kink, slope2)| RateCurve {
+            base_rate,
+            slope1,
+            kink,
+            slope2,
+        },
+    )
+}
+
+proptest! {
+    #[test]
+    fn rate_is_monotonic_in_utilization(curve in valid_curve(), a in 0..=MAX_BPS, b in 0..=MAX_BPS) {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        prop_assert!(curve.rate_at(low) <= curve.rate_at(high));
+    }
+
+    #[test]
+    fn rate_stays_within_curve_bounds(curve in valid_curve(), u in 0..=MAX_BPS) {
+        let max = (curve.base_rate as u64 + curve.slope1 as u64 + curve.slope2 as u64)
+            .min(u16::MAX as u64) as u16;
+        let rate = curve.rate_at(u);
+        prop_assert!(rate >= curve.base_rate);
+        prop_assert!(rate <= max);
+    }
+
+    #[test]
+    fn rate_is_continuous_at_kink(curve in valid_curve()) {
+        let at_kink = (curve.base_rate as u64 + curve.slope1 as u64).min(u16::MAX as u64) as u16;
+        prop_assert_eq!(curve.rate_at(curve.kink), at_kink);
+    }
+
+    #[test]
+    fn utilization_is_bounded(borrowed in any::<u64>(), available in any::<u64>()) {
+        prop_assert!(utilization(borrowed, available) <= MAX_BPS);
+    }
+
+    #[test]
+    fn utilization_grows_with_borrowing(borrowed in 0..u64::MAX / 2, extra in 0..u64::MAX / 4, available in 0..u64::MAX / 4) {
+        prop_assert!(utilization(borrowed, available) <= utilization(borrowed + extra, available));
+    }
+}
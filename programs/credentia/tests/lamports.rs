@@ -0,0 +1,35 @@
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use credentia::{
+    error::ErrorCode,
+    lamports::{credit_lamports, debit_lamports, move_lamports},
+};
+
+fn overflowed(result: anchor_lang::Result<()>) -> bool {
+    result.unwrap_err() == ErrorCode::MathOverflow.into()
+}
+
+#[test]
+fn moves_lamports_between_accounts() {
+    let (from_key, to_key, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), credentia::ID);
+    let (mut from_lamports, mut to_lamports) = (1_000, 5);
+    let (mut from_data, mut to_data) = (vec![], vec![]);
+    let from = AccountInfo::new(&from_key, false, true, &mut from_lamports, &mut from_data, &owner, false, 0);
+    let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &owner, false, 0);
+
+    move_lamports(&from, &to, 400).unwrap();
+    assert_eq!(from.lamports(), 600);
+    assert_eq!(to.lamports(), 405);
+}
+
+#[test]
+fn short_or_overflowing_balances_are_an_error() {
+    let (key, owner) = (Pubkey::new_unique(), credentia::ID);
+    let mut lamports = 100;
+    let mut data = vec![];
+    let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+    assert!(overflowed(debit_lamports(&account, 101)));
+    assert!(overflowed(credit_lamports(&account, u64::MAX)));
+    //a failed move leaves the balance alone
+    assert_eq!(account.lamports(), 100);
+}
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::{AnchorDeserialize, Pubkey};
+use anchor_lang::Discriminator;
+use credentia::{LegacyLoan, Loan, LoanStatus};
+
+//8 byte discriminator + the baseline `Loan::MAX_SPACE`
+const BASELINE_ACCOUNT_LEN: usize = 8 + 126;
+
+//a loan account written by the deployed program before loan ids, field by field in the
+//baseline `Loan` order and padded to the space it allocated
+struct BaselineAccount(Vec<u8>);
+
+impl BaselineAccount {
+    fn new() -> Self {
+        Self(Loan::DISCRIMINATOR.to_vec())
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn option(self, bytes: Option<&[u8]>) -> Self {
+        match bytes {
+            Some(bytes) => self.bytes(&[1]).bytes(bytes),
+            None => self.bytes(&[0]),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        assert!(self.0.len() <= BASELINE_ACCOUNT_LEN);
+        self.0.resize(BASELINE_ACCOUNT_LEN, 0);
+        self.0
+    }
+}
+
+#[test]
+fn legacy_loan_decodes_a_funded_baseline_loan() {
+    let borrower = Pubkey::new_unique();
+    let lender = Pubkey::new_unique();
+    let nft_mint = Pubkey::new_unique();
+    let data = BaselineAccount::new()
+        .bytes(borrower.as_ref())
+        .option(Some(lender.as_ref()))
+        .bytes(nft_mint.as_ref())
+        .bytes(&1_000_000_000u64.to_le_bytes())
+        .bytes(&(7 * 86_400u32).to_le_bytes())
+        .option(Some(&1_700_000_000i64.to_le_bytes()))
+        .bytes(&[1]) //LoanStatus::Funded
+        .option(None)
+        .bytes(&1_000u16.to_le_bytes())
+        .bytes(&[254])
+        .finish();
+
+    let mut fields = &data[8..];
+    let legacy = LegacyLoan::deserialize(&mut fields).unwrap();
+    assert_eq!(legacy.borrower, borrower);
+    assert_eq!(legacy.lender, Some(lender));
+    assert_eq!(legacy.nft_mint, nft_mint);
+    assert_eq!(legacy.loan_amount, 1_000_000_000);
+    assert_eq!(legacy.duration, 7 * 86_400);
+    assert_eq!(legacy.start_time, Some(1_700_000_000));
+    assert!(legacy.status == LoanStatus::Funded);
+    assert_eq!(legacy.time_of_liquidation_or_repayment, None);
+    assert_eq!(legacy.interest_rate, 1_000);
+    assert_eq!(legacy.bump, 254);
+    //every field the baseline wrote was read, only the zeroed tail of the account is left
+    assert_eq!(data.len() - fields.len(), 8 + 32 + 33 + 32 + 8 + 4 + 9 + 1 + 1 + 2 + 1);
+    assert!(fields.iter().all(|byte| *byte == 0));
+}
+
+#[test]
+fn legacy_loan_decodes_a_requested_baseline_loan() {
+    let borrower = Pubkey::new_unique();
+    let nft_mint = Pubkey::new_unique();
+    let data = BaselineAccount::new()
+        .bytes(borrower.as_ref())
+        .option(None)
+        .bytes(nft_mint.as_ref())
+        .bytes(&5u64.to_le_bytes())
+        .bytes(&60u32.to_le_bytes())
+        .option(None)
+        .bytes(&[0]) //LoanStatus::Requested
+        .option(Some(&9u32.to_le_bytes()))
+        .bytes(&300u16.to_le_bytes())
+        .bytes(&1u8.to_le_bytes())
+        .finish();
+
+    let mut fields = &data[8..];
+    let legacy = LegacyLoan::deserialize(&mut fields).unwrap();
+    assert_eq!(legacy.borrower, borrower);
+    assert_eq!(legacy.lender, None);
+    assert_eq!(legacy.nft_mint, nft_mint);
+    assert_eq!(legacy.loan_amount, 5);
+    assert_eq!(legacy.duration, 60);
+    assert_eq!(legacy.start_time, None);
+    assert!(legacy.status == LoanStatus::Requested);
+    assert_eq!(legacy.time_of_liquidation_or_repayment, Some(9));
+    assert_eq!(legacy.interest_rate, 300);
+    assert_eq!(legacy.bump, 1);
+    assert_eq!(data.len() - fields.len(), 8 + 32 + 1 + 32 + 8 + 4 + 1 + 1 + 5 + 2 + 1);
+}
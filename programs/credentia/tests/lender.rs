@@ -0,0 +1,45 @@
+use anchor_lang::prelude::Pubkey;
+use credentia::{LenderReputation, MIN_REPUTATION_FEE};
+
+fn reputation() -> LenderReputation {
+    let mut reputation = LenderReputation {
+        lender: Pubkey::default(),
+        platform: Pubkey::default(),
+        loans_repaid: 0,
+        bump: 0,
+    };
+    reputation.open(Pubkey::new_unique(), Pubkey::new_unique(), 255);
+    reputation
+}
+
+#[test]
+fn repayments_below_the_minimum_fee_are_not_counted() {
+    let mut reputation = reputation();
+    //a zero interest loan pays no fee, repaying it between two wallets would be free
+    reputation.record_repayment(None, 0).unwrap();
+    reputation.record_repayment(None, MIN_REPUTATION_FEE - 1).unwrap();
+    assert_eq!(reputation.loans_repaid, 0);
+
+    reputation.record_repayment(None, MIN_REPUTATION_FEE).unwrap();
+    reputation.record_repayment(None, 10 * MIN_REPUTATION_FEE).unwrap();
+    assert_eq!(reputation.loans_repaid, 2);
+}
+
+#[test]
+fn token_loan_repayments_are_not_counted() {
+    let mut reputation = reputation();
+    //MIN_REPUTATION_FEE is lamports, the same raw amount of a 6 or 12 decimal token is worth
+    //anything from a lot to nothing
+    reputation.record_repayment(Some(Pubkey::new_unique()), 1_000 * MIN_REPUTATION_FEE).unwrap();
+    assert_eq!(reputation.loans_repaid, 0);
+}
+
+#[test]
+fn opening_an_existing_record_keeps_it() {
+    let mut reputation = reputation();
+    let lender = reputation.lender;
+    reputation.record_repayment(None, MIN_REPUTATION_FEE).unwrap();
+    reputation.open(Pubkey::new_unique(), Pubkey::new_unique(), 1);
+    assert_eq!(reputation.lender, lender);
+    assert_eq!(reputation.loans_repaid, 1);
+}
@@ -0,0 +1,162 @@
+use anchor_lang::prelude::Pubkey;
+use credentia::error::ErrorCode;
+use credentia::interest_curve::RateCurve;
+use credentia::{LendingPool, PoolCollateral, PoolLoan, LIQUIDATION_AUCTION, MAX_POOL_LOANS};
+
+const NOW: i64 = 1_700_000_000;
+
+fn pool(available_liquidity: u64, total_borrowed: u64) -> LendingPool {
+    LendingPool {
+        authority: Pubkey::new_unique(),
+        platform: Pubkey::new_unique(),
+        pool_id: 0,
+        share_mint: Pubkey::new_unique(),
+        max_duration: 30 * 86_400,
+        rate_curve: RateCurve {
+            base_rate: 200,
+            slope1: 400,
+            kink: 8_000,
+            slope2: 3_000,
+        },
+        total_borrowed,
+        available_liquidity,
+        active_loans: 0,
+        vault_bump: 0,
+        share_mint_bump: 0,
+        bump: 0,
+        loans: Vec::new(),
+        collateral_held: 0,
+    }
+}
+
+#[test]
+fn first_deposit_mints_shares_one_to_one() {
+    assert_eq!(pool(0, 0).shares_for_deposit(1_000, 0, NOW).unwrap(), 1_000);
+    //lamports left behind after every share was burned don't change the first price
+    assert_eq!(pool(7, 0).shares_for_deposit(1_000, 0, NOW).unwrap(), 1_000);
+}
+
+#[test]
+fn deposits_are_priced_at_nav() {
+    //nav 2_000 backing 1_000 shares, a share is worth 2 lamports
+    assert_eq!(pool(500, 1_500).shares_for_deposit(1_000, 1_000, NOW).unwrap(), 500);
+    //rounded down, the pool keeps the dust
+    assert_eq!(pool(3, 0).shares_for_deposit(1, 2, NOW).unwrap(), 0);
+}
+
+#[test]
+fn deposit_into_written_off_pool_is_rejected() {
+    //every lamport behind the outstanding shares was lost to defaults
+    assert!(matches!(
+        pool(0, 0).shares_for_deposit(1_000, 1_000, NOW),
+        Err(ErrorCode::InvalidShareAmount)
+    ));
+}
+
+#[test]
+fn deposit_shares_overflowing_u64_is_rejected() {
+    assert!(matches!(
+        pool(1, 0).shares_for_deposit(u64::MAX, 2, NOW),
+        Err(ErrorCode::MathOverflow)
+    ));
+}
+
+#[test]
+fn withdrawals_are_priced_at_nav() {
+    assert_eq!(pool(500, 1_500).lamports_for_shares(500, 1_000, NOW).unwrap(), 1_000);
+    assert_eq!(pool(0, 0).lamports_for_shares(500, 0, NOW).unwrap(), 0);
+}
+
+#[test]
+fn pool_accounting_overflow_is_an_error() {
+    assert!(matches!(pool(u64::MAX, 1).nav(NOW), Err(ErrorCode::MathOverflow)));
+    assert!(matches!(
+        pool(u64::MAX, 1).lamports_for_shares(1, 1, NOW),
+        Err(ErrorCode::MathOverflow)
+    ));
+    //a loan larger than the pool's liquidity can't be priced or booked
+    assert!(matches!(pool(1, 0).origination_rate(2), Err(ErrorCode::MathOverflow)));
+    let mut lending_pool = pool(1, 0);
+    assert!(matches!(lending_pool.record_origination(0, 2, NOW), Err(ErrorCode::MathOverflow)));
+    lending_pool.record_origination(0, 1, NOW).unwrap();
+    assert_eq!(lending_pool.available_liquidity, 0);
+    assert_eq!(lending_pool.total_borrowed, 1);
+    assert_eq!(lending_pool.active_loans, 1);
+}
+
+#[test]
+fn liquidation_auction_falls_from_the_debt_to_nothing() {
+    let collateral = PoolCollateral {
+        pool: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        loan_id: 0,
+        loan: Pubkey::new_unique(),
+        borrower: Pubkey::new_unique(),
+        start_price: 1_100,
+        auction_start: 1_000,
+        payer: Pubkey::new_unique(),
+        bump: 0,
+    };
+    assert_eq!(collateral.price_at(1_000).unwrap(), 1_100);
+    //a clock behind the claim doesn't raise the price
+    assert_eq!(collateral.price_at(0).unwrap(), 1_100);
+    assert_eq!(collateral.price_at(1_000 + LIQUIDATION_AUCTION / 2).unwrap(), 550);
+    assert_eq!(collateral.price_at(1_000 + LIQUIDATION_AUCTION).unwrap(), 0);
+    assert_eq!(collateral.price_at(i64::MAX).unwrap(), 0);
+}
+
+#[test]
+fn loans_past_their_deadline_are_left_out_of_nav() {
+    let mut lending_pool = pool(1_000, 0);
+    lending_pool.record_origination(1, 400, NOW + 10).unwrap();
+    lending_pool.record_origination(2, 100, NOW + 20).unwrap();
+    assert_eq!(lending_pool.nav(NOW).unwrap(), 1_000);
+    assert_eq!(lending_pool.nav(NOW + 10).unwrap(), 600);
+    assert_eq!(lending_pool.nav(NOW + 20).unwrap(), 500);
+    //a withdrawal before the write-off already takes its share of the loss
+    assert_eq!(lending_pool.lamports_for_shares(500, 1_000, NOW + 10).unwrap(), 300);
+
+    //writing the loan off doesn't move NAV again
+    assert_eq!(lending_pool.settle_loan(1).unwrap(), 400);
+    assert_eq!(lending_pool.nav(NOW + 10).unwrap(), 600);
+    assert_eq!(lending_pool.total_borrowed, 100);
+    assert_eq!(lending_pool.active_loans, 1);
+    assert_eq!(
+        lending_pool.loans,
+        vec![PoolLoan { loan_id: 2, principal: 100, deadline: NOW + 20 }]
+    );
+    assert!(matches!(lending_pool.settle_loan(1), Err(ErrorCode::PoolMismatch)));
+}
+
+#[test]
+fn deposits_wait_for_defaults_to_settle() {
+    let mut lending_pool = pool(1_000, 0);
+    lending_pool.record_origination(1, 400, NOW + 10).unwrap();
+    assert_eq!(lending_pool.shares_for_deposit(600, 1_000, NOW).unwrap(), 600);
+    assert!(matches!(
+        lending_pool.shares_for_deposit(600, 1_000, NOW + 10),
+        Err(ErrorCode::PoolSettlementPending)
+    ));
+
+    //written off, its collateral is in auction until the proceeds arrive
+    lending_pool.settle_loan(1).unwrap();
+    lending_pool.collateral_held = 1;
+    assert!(matches!(
+        lending_pool.shares_for_deposit(600, 1_000, NOW + 10),
+        Err(ErrorCode::PoolSettlementPending)
+    ));
+    lending_pool.collateral_held = 0;
+    assert_eq!(lending_pool.shares_for_deposit(600, 1_000, NOW + 10).unwrap(), 1_000);
+}
+
+#[test]
+fn pool_tracks_a_bounded_number_of_loans() {
+    let mut lending_pool = pool(1_000, 0);
+    for loan_id in 0..MAX_POOL_LOANS as u64 {
+        lending_pool.record_origination(loan_id, 1, NOW).unwrap();
+    }
+    assert!(matches!(
+        lending_pool.record_origination(MAX_POOL_LOANS as u64, 1, NOW),
+        Err(ErrorCode::PoolLoanLimitReached)
+    ));
+}
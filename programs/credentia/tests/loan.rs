@@ -0,0 +1,161 @@
+use anchor_lang::prelude::Pubkey;
+use credentia::error::ErrorCode;
+use credentia::{AcceptanceConstraints, Loan, LoanStatus, MAX_ALLOWED_LENDERS};
+
+const NOW: i64 = 1_700_000_000;
+
+fn request() -> Loan {
+    Loan {
+        borrower: Pubkey::new_unique(),
+        lender: None,
+        nft_mint: Pubkey::new_unique(),
+        loan_amount: 1_000_000_000,
+        duration: 7 * 86_400,
+        start_time: None,
+        status: LoanStatus::Requested,
+        time_of_liquidation_or_repayment: None,
+        interest_rate: 1_000,
+        bump: 0,
+        pool: None,
+        loan_id: 0,
+        borrower_referrer: None,
+        lender_referrer: None,
+        version: Loan::VERSION,
+        currency: None,
+        expires_at: None,
+        allowed_lenders: Vec::new(),
+        min_lender_reputation: 0,
+        insured: false,
+        protection: None,
+    }
+}
+
+//anchor error number of a failed check, None when it passed
+fn error(result: anchor_lang::Result<()>) -> Option<u32> {
+    match result {
+        Ok(()) => None,
+        Err(anchor_lang::error::Error::AnchorError(error)) => Some(error.error_code_number),
+        Err(error) => panic!("unexpected error {error:?}"),
+    }
+}
+
+fn code(error: ErrorCode) -> Option<u32> {
+    Some(error.into())
+}
+
+#[test]
+fn unconstrained_request_takes_any_lender() {
+    let loan = request();
+    assert_eq!(error(loan.check_lender(&Pubkey::new_unique(), 0, NOW)), None);
+}
+
+#[test]
+fn request_expires_at_its_expiry() {
+    let mut loan = request();
+    loan.expires_at = Some(NOW);
+    let lender = Pubkey::new_unique();
+    assert_eq!(error(loan.check_lender(&lender, 0, NOW - 1)), None);
+    assert_eq!(error(loan.check_lender(&lender, 0, NOW)), code(ErrorCode::RequestExpired));
+    assert_eq!(error(loan.check_lender(&lender, 0, NOW + 1)), code(ErrorCode::RequestExpired));
+}
+
+#[test]
+fn private_request_takes_only_named_lenders() {
+    let mut loan = request();
+    let named = Pubkey::new_unique();
+    loan.allowed_lenders = vec![Pubkey::new_unique(), named];
+    assert_eq!(error(loan.check_lender(&named, 0, NOW)), None);
+    assert_eq!(
+        error(loan.check_lender(&Pubkey::new_unique(), 0, NOW)),
+        code(ErrorCode::LenderNotAllowed)
+    );
+    //the borrower isn't implicitly allowed either
+    let borrower = loan.borrower;
+    assert_eq!(error(loan.check_lender(&borrower, 0, NOW)), code(ErrorCode::LenderNotAllowed));
+}
+
+#[test]
+fn minimum_reputation_is_inclusive() {
+    let mut loan = request();
+    loan.min_lender_reputation = 3;
+    let lender = Pubkey::new_unique();
+    assert_eq!(error(loan.check_lender(&lender, 3, NOW)), None);
+    assert_eq!(error(loan.check_lender(&lender, u64::MAX, NOW)), None);
+    assert_eq!(
+        error(loan.check_lender(&lender, 2, NOW)),
+        code(ErrorCode::LenderReputationTooLow)
+    );
+}
+
+#[test]
+fn expiry_is_checked_before_the_lender() {
+    let mut loan = request();
+    loan.expires_at = Some(NOW);
+    loan.allowed_lenders = vec![Pubkey::new_unique()];
+    loan.min_lender_reputation = 1;
+    assert_eq!(
+        error(loan.check_lender(&Pubkey::new_unique(), 0, NOW)),
+        code(ErrorCode::RequestExpired)
+    );
+}
+
+#[test]
+fn set_constraints_rejects_a_past_expiry_and_too_many_lenders() {
+    let mut loan = request();
+    let past = AcceptanceConstraints {
+        expires_at: Some(NOW),
+        ..Default::default()
+    };
+    assert_eq!(error(loan.set_constraints(past, NOW)), code(ErrorCode::InvalidRequestExpiry));
+
+    let crowded = AcceptanceConstraints {
+        allowed_lenders: (0..=MAX_ALLOWED_LENDERS).map(|_| Pubkey::new_unique()).collect(),
+        ..Default::default()
+    };
+    assert_eq!(
+        error(loan.set_constraints(crowded, NOW)),
+        code(ErrorCode::TooManyAllowedLenders)
+    );
+
+    let full = AcceptanceConstraints {
+        expires_at: Some(NOW + 1),
+        allowed_lenders: (0..MAX_ALLOWED_LENDERS).map(|_| Pubkey::new_unique()).collect(),
+        min_lender_reputation: 2,
+    };
+    assert_eq!(error(loan.set_constraints(full.clone(), NOW)), None);
+    assert_eq!(loan.expires_at, full.expires_at);
+    assert_eq!(loan.allowed_lenders, full.allowed_lenders);
+    assert_eq!(loan.min_lender_reputation, 2);
+}
+
+#[test]
+fn funding_requires_the_current_terms() {
+    let loan = request();
+    assert_eq!(error(loan.check_terms(1_000_000_000, 7 * 86_400, 1_000)), None);
+    let changed = code(ErrorCode::LoanTermsChanged);
+    assert_eq!(error(loan.check_terms(999_999_999, 7 * 86_400, 1_000)), changed);
+    assert_eq!(error(loan.check_terms(1_000_000_000, 86_400, 1_000)), changed);
+    assert_eq!(error(loan.check_terms(1_000_000_000, 7 * 86_400, 1_001)), changed);
+}
+
+#[test]
+fn parties_cant_refer_their_own_loan() {
+    let mut loan = request();
+    let frontend = Pubkey::new_unique();
+    loan.borrower_referrer = Some(frontend);
+    assert_eq!(error(loan.check_referrers()), None);
+    loan.borrower_referrer = Some(loan.borrower);
+    assert_eq!(error(loan.check_referrers()), code(ErrorCode::SelfReferral));
+
+    let lender = Pubkey::new_unique();
+    loan.borrower_referrer = Some(frontend);
+    loan.lender = Some(lender);
+    loan.lender_referrer = Some(frontend);
+    assert_eq!(error(loan.check_referrers()), None);
+    loan.lender_referrer = Some(lender);
+    assert_eq!(error(loan.check_referrers()), code(ErrorCode::SelfReferral));
+    //the lender can't collect the borrower's referral share either
+    loan.lender_referrer = None;
+    loan.borrower_referrer = Some(lender);
+    assert_eq!(error(loan.check_referrers()), code(ErrorCode::SelfReferral));
+}
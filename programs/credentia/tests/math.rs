@@ -0,0 +1,278 @@
+use credentia::error::ErrorCode;
+use credentia::interest_curve::MAX_BPS;
+use credentia::math::{
+    bps_of, insurance_payout, mul_div_floor, protection_premium, FeeSplit, Repayment,
+};
+use proptest::prelude::*;
+
+fn overflowed<T>(result: Result<T, ErrorCode>) -> bool {
+    matches!(result, Err(ErrorCode::MathOverflow))
+}
+
+#[test]
+fn bps_of_rounds_down() {
+    assert_eq!(bps_of(1_000, 500).unwrap(), 50);
+    assert_eq!(bps_of(999, 1).unwrap(), 0);
+    assert_eq!(bps_of(19_999, 1).unwrap(), 1);
+    assert_eq!(bps_of(1, MAX_BPS - 1).unwrap(), 0);
+    assert_eq!(bps_of(1, MAX_BPS).unwrap(), 1);
+}
+
+#[test]
+fn bps_of_handles_full_range_without_overflow() {
+    assert_eq!(bps_of(u64::MAX, MAX_BPS).unwrap(), u64::MAX);
+    assert_eq!(bps_of(u64::MAX, 5_000).unwrap(), u64::MAX / 2);
+    assert_eq!(bps_of(u64::MAX, 0).unwrap(), 0);
+}
+
+#[test]
+fn bps_of_every_rate_on_max_principal() {
+    for bps in 0..=MAX_BPS {
+        let share = bps_of(u64::MAX, bps).unwrap();
+        let exact = u64::MAX as u128 * bps as u128 / MAX_BPS as u128;
+        assert_eq!(share as u128, exact);
+    }
+}
+
+#[test]
+fn bps_of_above_full_overflows_only_when_result_does() {
+    assert_eq!(bps_of(10_000, u16::MAX).unwrap(), 65_535);
+    assert!(overflowed(bps_of(u64::MAX, MAX_BPS + 1)));
+    assert!(overflowed(bps_of(u64::MAX, u16::MAX)));
+}
+
+#[test]
+fn mul_div_by_zero_is_an_error() {
+    assert!(overflowed(mul_div_floor(1, 1, 0)));
+    assert!(overflowed(mul_div_floor(u64::MAX, u64::MAX, 1)));
+    assert_eq!(mul_div_floor(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+}
+
+#[test]
+fn repayment_matches_worked_example() {
+    //1 SOL at 10% with a 5% platform fee
+    let repayment = Repayment::new(1_000_000_000, 1_000, 500).unwrap();
+    assert_eq!(repayment.interest, 100_000_000);
+    assert_eq!(repayment.fee_for_platform, 5_000_000);
+    assert_eq!(repayment.amount_to_pay_lender, 1_095_000_000);
+    assert_eq!(repayment.total_repayment, 1_100_000_000);
+}
+
+#[test]
+fn repayment_dust_stays_with_lender() {
+    //interest of 1 lamport, fee share rounds to zero
+    let repayment = Repayment::new(10, 1_000, 9_999).unwrap();
+    assert_eq!(repayment.interest, 1);
+    assert_eq!(repayment.fee_for_platform, 0);
+    assert_eq!(repayment.amount_to_pay_lender, 11);
+}
+
+#[test]
+fn repayment_overflow_is_an_error() {
+    //interest alone no longer fits
+    assert!(overflowed(Repayment::new(u64::MAX, u16::MAX, 0)));
+    //interest fits but principal + interest does not
+    assert!(overflowed(Repayment::new(u64::MAX, MAX_BPS, 500)));
+    assert!(overflowed(Repayment::new(u64::MAX, 1, 0)));
+    //zero interest never overflows
+    assert_eq!(Repayment::new(u64::MAX, 0, MAX_BPS).unwrap().total_repayment, u64::MAX);
+}
+
+#[test]
+fn fee_split_without_referrers_goes_to_treasury() {
+    let split = FeeSplit::new(5_000_000, 2_000, false, false).unwrap();
+    assert_eq!(split.treasury_amount, 5_000_000);
+    assert_eq!(split.borrower_referrer_amount, 0);
+    assert_eq!(split.lender_referrer_amount, 0);
+}
+
+#[test]
+fn fee_split_between_referrers() {
+    let split = FeeSplit::new(5_000_000, 2_000, true, true).unwrap();
+    assert_eq!(split.borrower_referrer_amount, 500_000);
+    assert_eq!(split.lender_referrer_amount, 500_000);
+    assert_eq!(split.treasury_amount, 4_000_000);
+
+    let split = FeeSplit::new(5_000_000, 2_000, false, true).unwrap();
+    assert_eq!(split.borrower_referrer_amount, 0);
+    assert_eq!(split.lender_referrer_amount, 1_000_000);
+    assert_eq!(split.treasury_amount, 4_000_000);
+}
+
+#[test]
+fn fee_split_odd_referral_total_leaves_dust_in_treasury() {
+    let split = FeeSplit::new(10, 1_000, true, true).unwrap();
+    assert_eq!(split.borrower_referrer_amount, 0);
+    assert_eq!(split.lender_referrer_amount, 0);
+    assert_eq!(split.treasury_amount, 10);
+
+    let split = FeeSplit::new(30, MAX_BPS, true, true).unwrap();
+    assert_eq!(split.borrower_referrer_amount, 15);
+    assert_eq!(split.treasury_amount, 0);
+}
+
+#[test]
+fn fee_split_above_full_share_is_an_error() {
+    assert!(overflowed(FeeSplit::new(10_000, MAX_BPS + 1, true, false)));
+    assert!(overflowed(FeeSplit::new(100, u16::MAX, true, true)));
+}
+
+#[test]
+fn fee_split_insurance_share_comes_out_of_the_treasury() {
+    let split = FeeSplit::new(5_000_000, 2_000, true, true)
+        .unwrap()
+        .with_insurance(5_000_000, 1_000)
+        .unwrap();
+    assert_eq!(split.insurance_amount, 500_000);
+    assert_eq!(split.borrower_referrer_amount, 500_000);
+    assert_eq!(split.lender_referrer_amount, 500_000);
+    assert_eq!(split.treasury_amount, 3_500_000);
+
+    let split = FeeSplit::new(5_000_000, 0, false, false).unwrap();
+    assert_eq!(split.insurance_amount, 0);
+    assert_eq!(split.with_insurance(5_000_000, 0).unwrap(), split);
+}
+
+#[test]
+fn fee_split_insurance_and_referrers_above_the_fee_is_an_error() {
+    let split = FeeSplit::new(10_000, 6_000, true, false).unwrap();
+    assert!(overflowed(split.with_insurance(10_000, 5_000)));
+    assert!(split.with_insurance(10_000, 4_000).is_ok());
+}
+
+#[test]
+fn insurance_payout_covers_a_share_of_the_shortfall() {
+    //1 sol principal against collateral now worth 0.6 sol, 80% of the 0.4 sol shortfall
+    assert_eq!(insurance_payout(1_000_000_000, 600_000_000, 8_000, 10_000_000_000, 5_000).unwrap(), 320_000_000);
+    //collateral still covers the principal
+    assert_eq!(insurance_payout(1_000_000_000, 1_200_000_000, 8_000, 10_000_000_000, 5_000).unwrap(), 0);
+}
+
+#[test]
+fn insurance_payout_is_capped_by_the_fund() {
+    assert_eq!(insurance_payout(1_000_000_000, 0, MAX_BPS, 400_000_000, 5_000).unwrap(), 200_000_000);
+    assert_eq!(insurance_payout(1_000_000_000, 0, MAX_BPS, 0, MAX_BPS).unwrap(), 0);
+}
+
+#[test]
+fn protection_premium_is_interest_pro_rated_over_the_extension() {
+    //1 sol at 10% over 7 days, 0.1 sol of interest, a day of extension costs a seventh of it
+    assert_eq!(protection_premium(1_000_000_000, 1_000, 7 * 86_400, 86_400).unwrap(), 14_285_714);
+    //extending by the full duration costs the interest again
+    assert_eq!(protection_premium(1_000_000_000, 1_000, 86_400, 86_400).unwrap(), 100_000_000);
+    assert_eq!(protection_premium(1_000_000_000, 0, 86_400, 86_400).unwrap(), 0);
+    assert_eq!(protection_premium(1_000_000_000, 1_000, 86_400, 0).unwrap(), 0);
+}
+
+#[test]
+fn protection_premium_rejects_zero_duration() {
+    assert!(overflowed(protection_premium(1_000_000_000, 1_000, 0, 86_400)));
+}
+
+fn bps() -> impl Strategy<Value = u16> {
+    0..=MAX_BPS
+}
+
+proptest! {
+    #[test]
+    fn bps_of_is_exact_floor(amount in any::<u64>(), bps in bps()) {
+        let exact = amount as u128 * bps as u128 / MAX_BPS as u128;
+        prop_assert_eq!(bps_of(amount, bps).unwrap() as u128, exact);
+    }
+
+    #[test]
+    fn bps_of_never_exceeds_amount(amount in any::<u64>(), bps in bps()) {
+        prop_assert!(bps_of(amount, bps).unwrap() <= amount);
+    }
+
+    #[test]
+    fn bps_of_is_monotonic(a in any::<u64>(), b in any::<u64>(), bps in any::<u16>()) {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        if let Ok(high_share) = bps_of(high, bps) {
+            prop_assert!(bps_of(low, bps).unwrap() <= high_share);
+        }
+    }
+
+    #[test]
+    fn mul_div_errors_exactly_when_result_leaves_u64(a in any::<u64>(), b in any::<u64>(), d in 1..=u64::MAX) {
+        let exact = a as u128 * b as u128 / d as u128;
+        match mul_div_floor(a, b, d) {
+            Ok(value) => prop_assert_eq!(value as u128, exact),
+            Err(_) => prop_assert!(exact > u64::MAX as u128),
+        }
+    }
+
+    #[test]
+    fn repayment_conserves_lamports(amount in any::<u64>(), rate in any::<u16>(), fee_bps in bps()) {
+        match Repayment::new(amount, rate, fee_bps) {
+            Ok(r) => {
+                prop_assert!(r.fee_for_platform <= r.interest);
+                prop_assert_eq!(r.total_repayment, amount + r.interest);
+                prop_assert_eq!(r.amount_to_pay_lender + r.fee_for_platform, r.total_repayment);
+                prop_assert!(r.amount_to_pay_lender >= amount);
+            }
+            Err(_) => {
+                let interest = amount as u128 * rate as u128 / MAX_BPS as u128;
+                prop_assert!(amount as u128 + interest > u64::MAX as u128);
+            }
+        }
+    }
+
+    #[test]
+    fn repayment_never_fails_for_realistic_loans(amount in 0..u64::MAX / 8, rate in any::<u16>(), fee_bps in bps()) {
+        prop_assert!(Repayment::new(amount, rate, fee_bps).is_ok());
+    }
+
+    #[test]
+    fn fee_split_conserves_fee(fee in any::<u64>(), share in bps(), borrower in any::<bool>(), lender in any::<bool>()) {
+        let split = FeeSplit::new(fee, share, borrower, lender).unwrap();
+        prop_assert_eq!(
+            split.treasury_amount as u128
+                + split.borrower_referrer_amount as u128
+                + split.lender_referrer_amount as u128,
+            fee as u128
+        );
+        prop_assert!(split.borrower_referrer_amount + split.lender_referrer_amount <= bps_of(fee, share).unwrap());
+        prop_assert!(borrower || split.borrower_referrer_amount == 0);
+        prop_assert!(lender || split.lender_referrer_amount == 0);
+        if borrower && lender {
+            prop_assert_eq!(split.borrower_referrer_amount, split.lender_referrer_amount);
+        }
+    }
+
+    #[test]
+    fn fee_split_with_insurance_conserves_fee(fee in any::<u64>(), referral in 0..=5_000u16, insurance in 0..=5_000u16, borrower in any::<bool>(), lender in any::<bool>()) {
+        let split = FeeSplit::new(fee, referral, borrower, lender)
+            .unwrap()
+            .with_insurance(fee, insurance)
+            .unwrap();
+        prop_assert_eq!(
+            split.treasury_amount as u128
+                + split.borrower_referrer_amount as u128
+                + split.lender_referrer_amount as u128
+                + split.insurance_amount as u128,
+            fee as u128
+        );
+        prop_assert_eq!(split.insurance_amount, bps_of(fee, insurance).unwrap());
+    }
+
+    #[test]
+    fn insurance_payout_never_exceeds_its_caps(principal in any::<u64>(), value in any::<u64>(), coverage in bps(), balance in any::<u64>(), max_claim in bps()) {
+        let payout = insurance_payout(principal, value, coverage, balance, max_claim).unwrap();
+        prop_assert!(payout <= principal.saturating_sub(value));
+        prop_assert!(payout <= bps_of(balance, max_claim).unwrap());
+    }
+
+    #[test]
+    fn protection_premium_is_exact_floor(amount in any::<u64>(), rate in any::<u16>(), duration in 1..u32::MAX, extension in any::<u32>()) {
+        let interest = amount as u128 * rate as u128 / MAX_BPS as u128;
+        let exact = interest * extension as u128 / duration as u128;
+        match protection_premium(amount, rate, duration, extension) {
+            Ok(premium) => {
+                prop_assert_eq!(premium as u128, exact);
+                prop_assert!(extension > duration || premium as u128 <= interest);
+            }
+            Err(_) => prop_assert!(interest > u64::MAX as u128 || exact > u64::MAX as u128),
+        }
+    }
+}
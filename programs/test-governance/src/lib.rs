@@ -0,0 +1,85 @@
+//! Governance program for the LiteSVM suite that abuses the loan's signature. Credentia signs relayed
+//! votes as the loan, which also owns the nft vault, so each instruction here tries to take the
+//! collateral with it and the relay has to fail afterwards.
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    approve, close_account, transfer_checked, Approve, CloseAccount, Mint, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+
+declare_id!("3YcPiwRHJE5YvAxFQRVpchEwdZyUVYdgYWHYXrKJyEvq");
+
+#[program]
+pub mod test_governance {
+    use super::*;
+
+    //moves the nft out of the vault
+    pub fn move_collateral(ctx: Context<TakeCollateral>) -> Result<()> {
+        ctx.accounts.move_nft()
+    }
+
+    //leaves the nft in the vault but lets the attacker move it later
+    pub fn delegate_collateral(ctx: Context<TakeCollateral>) -> Result<()> {
+        let accounts = &ctx.accounts;
+        approve(
+            CpiContext::new(
+                accounts.token_program.to_account_info(),
+                Approve {
+                    to: accounts.vault.to_account_info(),
+                    delegate: accounts.attacker.to_account_info(),
+                    authority: accounts.loan.to_account_info(),
+                },
+            ),
+            1,
+        )
+    }
+
+    //empties the vault and closes it to the attacker
+    pub fn close_collateral(ctx: Context<TakeCollateral>) -> Result<()> {
+        ctx.accounts.move_nft()?;
+        let accounts = &ctx.accounts;
+        close_account(CpiContext::new(
+            accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: accounts.vault.to_account_info(),
+                destination: accounts.attacker.to_account_info(),
+                authority: accounts.loan.to_account_info(),
+            },
+        ))
+    }
+}
+
+#[derive(Accounts)]
+pub struct TakeCollateral<'info> {
+    pub loan: Signer<'info>,
+    #[account(mut, token::mint = mint, token::authority = loan, token::token_program = token_program)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: only receives the nft, the delegation or the vault's rent
+    #[account(mut)]
+    pub attacker: UncheckedAccount<'info>,
+    #[account(mut, token::mint = mint, token::authority = attacker, token::token_program = token_program)]
+    pub attacker_tokens: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl TakeCollateral<'_> {
+    fn move_nft(&self) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.attacker_tokens.to_account_info(),
+                    authority: self.loan.to_account_info(),
+                },
+            ),
+            1,
+            self.mint.decimals,
+        )
+    }
+}
@@ -0,0 +1,91 @@
+//! Transfer hook for the LiteSVM suite. It lists one extra account per mint, a counter it bumps on
+//! every transfer, so a transfer through Credentia only succeeds if the hook's accounts were
+//! forwarded and the counter shows how many went through.
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("DAKRncSuSm4Xt1GuuGxFa77JgB3crVeWpmiCiKfHd5as");
+
+#[program]
+pub mod test_transfer_hook {
+    use super::*;
+
+    //writes the validation account token-2022 reads the extra accounts from
+    pub fn initialize_extra_account_meta_list(ctx: Context<InitializeExtraAccountMetaList>) -> Result<()> {
+        let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_account_metas()?)?;
+        Ok(())
+    }
+
+    //called by token-2022 on every transfer of the mint
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn execute(ctx: Context<Execute>, _amount: u64) -> Result<()> {
+        let counter = &mut ctx.accounts.counter;
+        counter.transfers = counter.transfers.checked_add(1).unwrap();
+        Ok(())
+    }
+}
+
+//the counter is derived from the mint, account 1 of the execute instruction
+fn extra_account_metas() -> Result<[ExtraAccountMeta; 1]> {
+    let counter = ExtraAccountMeta::new_with_seeds(
+        &[Seed::Literal { bytes: b"counter".to_vec() }, Seed::AccountKey { index: 1 }],
+        false,
+        true,
+    )?;
+    Ok([counter])
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: the validation account, written by `ExtraAccountMetaList::init`
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(1)?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump,
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TransferCounter::INIT_SPACE,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, TransferCounter>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+//accounts in the order token-2022 passes them to the hook
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    /// CHECK: source token account, checked by token-2022
+    pub source: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: destination token account, checked by token-2022
+    pub destination: UncheckedAccount<'info>,
+    /// CHECK: owner or delegate of the source, checked by token-2022
+    pub authority: UncheckedAccount<'info>,
+    /// CHECK: the validation account, only read by token-2022
+    #[account(seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"counter", mint.key().as_ref()], bump)]
+    pub counter: Account<'info, TransferCounter>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TransferCounter {
+    pub transfers: u64,
+}
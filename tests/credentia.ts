@@ -35,9 +35,16 @@ describe("credentia", () => {
   };
 
   const [borrower, lender] = Array.from({ length: 2 }, () => anchor.web3.Keypair.generate());
-  const Platform = PublicKey.findProgramAddressSync([Buffer.from("platform")], programId)[0];
+  const platformId = new anchor.BN(1);
+  const platformPda = (id: anchor.BN) => PublicKey.findProgramAddressSync([Buffer.from("platform"), id.toArrayLike(Buffer, "le", 8)], programId)[0];
+  const Platform = platformPda(platformId);
   const reward_mint = PublicKey.findProgramAddressSync([Buffer.from("reward_mint"), Platform.toBuffer()], programId)[0];
   const treasuryVault = PublicKey.findProgramAddressSync([Buffer.from("treasury_vault"), Platform.toBuffer()], programId)[0];
+  //loans are seeded by the platform's loan counter at the time they are opened
+  const nextLoanPda = async (mint: PublicKey): Promise<PublicKey> => {
+    const loanCount = (await program.account.platform.fetch(Platform)).loanCount;
+    return PublicKey.findProgramAddressSync([Buffer.from("loan"), mint.toBuffer(), Platform.toBuffer(), loanCount.toArrayLike(Buffer, "le", 8)], programId)[0];
+  };
   //nfts stuff
   const metaplex = Metaplex.make(provider.connection).use(keypairIdentity(borrower));
   let borrowerNftMint: PublicKey
@@ -139,7 +146,7 @@ describe("credentia", () => {
   it("should Fails when admin is not signer", async () => {
     const fakeAdmin = Keypair.generate()
     await program.methods
-      .initializePlatform(500)
+      .initializePlatform(platformId, 500)
       .accountsPartial({ ...accountsForInitialization, admin: fakeAdmin.publicKey })
       .signers([]) // intentionally no signer for fake admin
       .rpc()
@@ -150,7 +157,7 @@ describe("credentia", () => {
   it("should Fails with incorrect PDA seeds", async () => {
     const wrongVault = Keypair.generate().publicKey
     program.methods
-      .initializePlatform(500)
+      .initializePlatform(platformId, 500)
       .accountsPartial({ ...accountsForInitialization, treasuryVault: wrongVault })
       .signers([])
       .rpc()
@@ -161,7 +168,7 @@ describe("credentia", () => {
   it("should Fails if insufficient funds for rent", async () => {
     const lowFundsAdmin = Keypair.generate()
     await program.methods
-      .initializePlatform(500)
+      .initializePlatform(platformId, 500)
       .accountsPartial({ ...accountsForInitialization, admin: lowFundsAdmin.publicKey })
       .signers([lowFundsAdmin])
       .rpc()
@@ -171,7 +178,7 @@ describe("credentia", () => {
 
   it("admin initializing the platform (in our case anchor provider wallet is admin)", async () => {
     let sig = await program.methods
-      .initializePlatform(500)
+      .initializePlatform(platformId, 500)
       .accountsPartial(accountsForInitialization)
       .signers([])
       .rpc()
@@ -182,7 +189,7 @@ describe("credentia", () => {
 
   it("should Fails if platform already initialized", async () => {
     await program.methods
-      .initializePlatform(500)
+      .initializePlatform(platformId, 500)
       .accountsPartial(accountsForInitialization)
       .signers([])
       .rpc()
@@ -194,6 +201,48 @@ describe("credentia", () => {
     const platformAccount = await program.account.platform.fetch(Platform)
     assert.equal(platformAccount.authority.toBase58(), provider.wallet.publicKey.toBase58())
     assert.equal(platformAccount.feeBps, 500)
+    assert.equal(Number(platformAccount.platformId), 1)
+  })
+
+  it("another operator runs its own platform on the same deployment", async () => {
+    const operator = Keypair.generate();
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({ fromPubkey: provider.wallet.publicKey, toPubkey: operator.publicKey, lamports: LAMPORTS_PER_SOL })
+    ));
+    const otherId = new anchor.BN(2);
+    const otherPlatform = platformPda(otherId);
+    await program.methods
+      .initializePlatform(otherId, 250)
+      .accountsPartial({
+        admin: operator.publicKey,
+        platform: otherPlatform,
+        treasuryVault: PublicKey.findProgramAddressSync([Buffer.from("treasury_vault"), otherPlatform.toBuffer()], programId)[0],
+        rewardMint: PublicKey.findProgramAddressSync([Buffer.from("reward_mint"), otherPlatform.toBuffer()], programId)[0],
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([operator])
+      .rpc()
+      .then(sig => confirm(sig));
+    const platformAccount = await program.account.platform.fetch(otherPlatform)
+    assert.equal(platformAccount.authority.toBase58(), operator.publicKey.toBase58())
+    assert.equal(platformAccount.feeBps, 250)
+  })
+
+  it("should Fails when fee is above 100%", async () => {
+    const otherId = new anchor.BN(3);
+    const otherPlatform = platformPda(otherId);
+    await program.methods
+      .initializePlatform(otherId, 10001)
+      .accountsPartial({
+        ...accountsForInitialization,
+        platform: otherPlatform,
+        treasuryVault: PublicKey.findProgramAddressSync([Buffer.from("treasury_vault"), otherPlatform.toBuffer()], programId)[0],
+        rewardMint: PublicKey.findProgramAddressSync([Buffer.from("reward_mint"), otherPlatform.toBuffer()], programId)[0],
+      })
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch((err) => assert.ok(err.error.errorCode.code === "InvalidFeeBps"));
   })
 
   it("Reward mint has correct config", async () => {
@@ -209,11 +258,7 @@ describe("credentia", () => {
  **************************************************/
   it("initializing loan account and nft vault", async () => {
     // Compute loan_account PDA (this is correct)
-    loan_account = PublicKey.findProgramAddressSync([
-      Buffer.from("loan"),
-      borrowerNftMint.toBuffer(),
-      Platform.toBuffer()
-    ], programId)[0];
+    loan_account = await nextLoanPda(borrowerNftMint);
 
     // Compute nft_vault AFTER loan_account PDA is computed
     nft_vault = await getAssociatedTokenAddress(
@@ -245,7 +290,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial(accountsForRequestLoan())
     .signers([])
     .rpc()
@@ -259,7 +304,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial({...accountsForRequestLoan , borrowerNftMint: fakeNftMint})
     .signers([borrower])
     .rpc()
@@ -279,7 +324,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial({...accountsForRequestLoan , borrowerNftMint: fakeNftMint})
     .signers([borrower])
     .rpc()
@@ -292,7 +337,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial(accountsForRequestLoan())
     .signers([borrower])
     .rpc()
@@ -305,7 +350,7 @@ describe("credentia", () => {
     let duration = 0;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial(accountsForRequestLoan())
     .signers([borrower])
     .rpc()
@@ -319,7 +364,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial({...accountsForRequestLoan , borrowerNftAta: randomAta})
     .signers([borrower])
     .rpc()
@@ -333,7 +378,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial({...accountsForRequestLoan , loanAccount: randomLoanAccount})
     .signers([borrower])
     .rpc()
@@ -347,7 +392,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-    .requestLoan(amount, duration, interest_rate)
+    .requestLoan(amount, duration, interest_rate, null, null)
     .accountsPartial({...accountsForRequestLoan , borrower: randomAddress.publicKey})
     .signers([randomAddress])
     .rpc()
@@ -360,7 +405,7 @@ describe("credentia", () => {
     let duration = 5;
     let interest_rate = 500;
     await program.methods
-      .requestLoan(amount, duration, interest_rate)
+      .requestLoan(amount, duration, interest_rate, null, null)
       .accountsPartial(accountsForRequestLoan())
       .signers([borrower])
       .rpc()
@@ -376,6 +421,12 @@ describe("credentia", () => {
     assert.equal(loanAccount.duration, 5)
     assert.equal(loanAccount.interestRate, 500)
     assert.deepEqual(loanAccount.status , { requested: {} })
+    assert.equal(Number(loanAccount.loanId), 0)
+  })
+
+  it("Platform loan counter is bumped", async () => {
+    const platformAccount = await program.account.platform.fetch(Platform)
+    assert.equal(Number(platformAccount.loanCount), 1)
   })
 
   it("nft vault nft balance", async () => {
@@ -388,6 +439,8 @@ describe("credentia", () => {
 /**************************************************
  *             LENDER ACCEPT LOAN TESTS            *
  **************************************************/
+  //the terms the loan was requested on, funding names them so an updated request is rejected
+  let fundedTerms = (): [anchor.BN, number, number] => [new anchor.BN(0.5 * LAMPORTS_PER_SOL), 5, 500];
   let accountsForFundingLoan = () => ({
         lender: lender.publicKey,
         borrower: borrower.publicKey,
@@ -401,7 +454,7 @@ describe("credentia", () => {
 
   it("shoudl fail when trying to fund different borrower" , async() => {
     let sig = await program.methods
-      .fundBorrower()
+      .fundBorrower(...fundedTerms())
       .accountsPartial({...accountsForFundingLoan() , borrower: Keypair.generate().publicKey})
       .signers([lender])
       .rpc()
@@ -413,7 +466,7 @@ describe("credentia", () => {
     let poor_lender = Keypair.generate();
     await program.provider.connection.requestAirdrop(poor_lender.publicKey, 1*LAMPORTS_PER_SOL);
     let sig = await program.methods
-      .fundBorrower()
+      .fundBorrower(...fundedTerms())
       .accountsPartial({...accountsForFundingLoan() , lender: poor_lender.publicKey})
       .signers([poor_lender])
       .rpc()
@@ -426,7 +479,7 @@ describe("credentia", () => {
     let borrower_initial_balance = await provider.connection.getBalance(borrower.publicKey);
     let lender_initial_balance = await provider.connection.getBalance(lender.publicKey);
     let sig = await program.methods
-      .fundBorrower()
+      .fundBorrower(...fundedTerms())
       .accountsPartial(accountsForFundingLoan())
       .signers([lender])
       .rpc()
@@ -449,7 +502,7 @@ describe("credentia", () => {
 
   it("should fail when lender try to again fund the loan" , async ()  => {
     let sig = await program.methods
-      .fundBorrower()
+      .fundBorrower(...fundedTerms())
       .accountsPartial(accountsForFundingLoan())
       .signers([lender])
       .rpc()
@@ -470,7 +523,7 @@ describe("credentia", () => {
     await provider.sendAndConfirm(tx);
 
     let sig = await program.methods
-      .fundBorrower()
+      .fundBorrower(...fundedTerms())
       .accountsPartial({...accountsForFundingLoan() , lender: lender2.publicKey})
       .signers([lender2])
       .rpc()
@@ -665,6 +718,554 @@ describe("credentia", () => {
       });
   });
 
+  /**************************************************
+ *                LENDING POOL TESTS               *
+ **************************************************/
+  const poolId = new anchor.BN(1);
+  const lendingPool = PublicKey.findProgramAddressSync([Buffer.from("pool"), Platform.toBuffer(), poolId.toArrayLike(Buffer, "le", 8)], programId)[0];
+  const poolVault = PublicKey.findProgramAddressSync([Buffer.from("pool_vault"), lendingPool.toBuffer()], programId)[0];
+  const shareMint = PublicKey.findProgramAddressSync([Buffer.from("pool_share_mint"), lendingPool.toBuffer()], programId)[0];
+  let poolNftMint: PublicKey
+  let poolLoanAccount: PublicKey
+  let poolNftVault: PublicKey
+  let collectionConfig: PublicKey
+  let lenderShareAta: PublicKey
+  const rateCurve = { baseRate: 100, slope1: 400, kink: 8000, slope2: 3000 };
+
+  it("admin creates a lending pool", async () => {
+    await program.methods
+      .createPool(poolId, 1000, rateCurve)
+      .accountsPartial({
+        authority: provider.wallet.publicKey,
+        platform: Platform,
+        lendingPool,
+        poolVault,
+        shareMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+      .then(sig => confirm(sig));
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    assert.equal(pool.maxDuration, 1000);
+    assert.deepEqual(pool.rateCurve, rateCurve);
+    assert.equal(pool.shareMint.toBase58(), shareMint.toBase58());
+  });
+
+  it("should fail when non authority creates a pool", async () => {
+    let stranger = Keypair.generate();
+    await program.methods
+      .createPool(new anchor.BN(2), 1000, rateCurve)
+      .accountsPartial({ authority: stranger.publicKey, platform: Platform })
+      .signers([stranger])
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch(() => assert.ok(true));
+  });
+
+  it("admin whitelists the collection", async () => {
+    collectionConfig = PublicKey.findProgramAddressSync([Buffer.from("collection_config"), lendingPool.toBuffer(), borrowerNftCollection.toBuffer()], programId)[0];
+    await program.methods
+      .setPoolCollection(new anchor.BN(LAMPORTS_PER_SOL))
+      .accountsPartial({
+        authority: provider.wallet.publicKey,
+        lendingPool,
+        collection: borrowerNftCollection,
+        collectionConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+      .then(sig => confirm(sig));
+    const config = await program.account.collectionConfig.fetch(collectionConfig);
+    assert.equal(Number(config.maxLoanAmount), LAMPORTS_PER_SOL);
+  });
+
+  it("lender deposits into the pool", async () => {
+    lenderShareAta = getAssociatedTokenAddressSync(shareMint, lender.publicKey);
+    await program.methods
+      .depositToPool(new anchor.BN(LAMPORTS_PER_SOL / 2))
+      .accountsPartial({
+        depositor: lender.publicKey,
+        lendingPool,
+        poolVault,
+        shareMint,
+        depositorShareAta: lenderShareAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([lender])
+      .rpc()
+      .then(sig => confirm(sig));
+    const shares = (await getAccount(connection, lenderShareAta)).amount;
+    assert.equal(shares, BigInt(LAMPORTS_PER_SOL / 2));
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    assert.equal(Number(pool.availableLiquidity), LAMPORTS_PER_SOL / 2);
+  });
+
+  it("borrower requests a loan that the pool can fund", async () => {
+    const { nft } = await metaplex.nfts().create({
+      uri: "https://arweave.net/item-metadata.json",
+      name: "Pool NFT",
+      sellerFeeBasisPoints: 0,
+      collection: borrowerNftCollection
+    });
+    poolNftMint = nft.address;
+    await metaplex.nfts().verifyCollection({ mintAddress: poolNftMint, collectionMintAddress: borrowerNftCollection });
+    poolLoanAccount = await nextLoanPda(poolNftMint);
+    poolNftVault = getAssociatedTokenAddressSync(poolNftMint, poolLoanAccount, true);
+    const metadataProgramId = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+    await program.methods
+      .requestLoan(new anchor.BN(LAMPORTS_PER_SOL / 4), 100, 500, null, null)
+      .accountsPartial({
+        ...accountsForRequestLoan(),
+        borrowerNftMint: poolNftMint,
+        borrowerNftAta: getAssociatedTokenAddressSync(poolNftMint, borrower.publicKey),
+        metadata: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), poolNftMint.toBuffer()], metadataProgramId)[0],
+        masterEdition: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), poolNftMint.toBuffer(), Buffer.from("edition")], metadataProgramId)[0],
+        loanAccount: poolLoanAccount,
+        nftVault: poolNftVault,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+  });
+
+  let accountsForPoolFunding = () => {
+    const metadataProgramId = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+    return {
+      caller: lender.publicKey,
+      borrower: borrower.publicKey,
+      borrowerNftMint: poolNftMint,
+      borrowerNftCollection: borrowerNftCollection,
+      metadata: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), poolNftMint.toBuffer()], metadataProgramId)[0],
+      loanAccount: poolLoanAccount,
+      platform: Platform,
+      lendingPool,
+      poolVault,
+      collectionConfig,
+      metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+  };
+
+  it("anyone cranks the pool to fund the loan", async () => {
+    let borrower_initial_balance = await connection.getBalance(borrower.publicKey);
+    await program.methods
+      .poolFundLoan()
+      .accountsPartial(accountsForPoolFunding())
+      .signers([lender])
+      .rpc()
+      .then(sig => confirm(sig));
+    let borrower_final_balance = await connection.getBalance(borrower.publicKey);
+    assert.equal(borrower_final_balance, borrower_initial_balance + LAMPORTS_PER_SOL / 4);
+    const loan = await program.account.loan.fetch(poolLoanAccount);
+    assert.equal(loan.lender.toBase58(), poolVault.toBase58());
+    assert.equal(loan.pool.toBase58(), lendingPool.toBase58());
+    //50% utilization after funding: 100 + 400 * 5000 / 8000
+    assert.equal(loan.interestRate, 350);
+    assert.deepEqual(loan.status, { funded: {} });
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    assert.equal(Number(pool.totalBorrowed), LAMPORTS_PER_SOL / 4);
+    assert.equal(pool.activeLoans, 1);
+  });
+
+  it("should fail when admin sets a curve with zero kink", async () => {
+    await program.methods
+      .updatePool(1000, { ...rateCurve, kink: 0 })
+      .accountsPartial({ authority: provider.wallet.publicKey, lendingPool })
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch((err) => assert.ok(err.error.errorCode.code === "InvalidRateCurve"));
+  });
+
+  it("should fail when withdrawing more than the idle liquidity", async () => {
+    await program.methods
+      .withdrawFromPool(new anchor.BN(LAMPORTS_PER_SOL / 2))
+      .accountsPartial({
+        depositor: lender.publicKey,
+        lendingPool,
+        poolVault,
+        shareMint,
+        depositorShareAta: lenderShareAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([lender])
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch((err) => assert.ok(err.error.errorCode.code === "InsufficientPoolLiquidity"));
+  });
+
+  it("borrower repays the pool loan and interest flows back to the pool", async () => {
+    await program.methods
+      .resolveLoan()
+      .accountsPartial({
+        ...BorrowerResolveLoanAccounts(),
+        lender: poolVault,
+        borrowerNftMint: poolNftMint,
+        borrowerNftAta: getAssociatedTokenAddressSync(poolNftMint, borrower.publicKey),
+        loanAccount: poolLoanAccount,
+        nftVault: poolNftVault,
+        lendingPool,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    let interest = LAMPORTS_PER_SOL / 4 * 350 / 10000;
+    let fee = interest * 500 / 10000;
+    assert.equal(Number(pool.totalBorrowed), 0);
+    assert.equal(Number(pool.availableLiquidity), LAMPORTS_PER_SOL / 2 + interest - fee);
+  });
+
+  it("lender withdraws shares at the new NAV", async () => {
+    let lender_initial_balance = await connection.getBalance(lender.publicKey);
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    await program.methods
+      .withdrawFromPool(new anchor.BN(LAMPORTS_PER_SOL / 2))
+      .accountsPartial({
+        depositor: lender.publicKey,
+        lendingPool,
+        poolVault,
+        shareMint,
+        depositorShareAta: lenderShareAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([lender])
+      .rpc()
+      .then(sig => confirm(sig));
+    let lender_final_balance = await connection.getBalance(lender.publicKey);
+    assert.equal(lender_final_balance, lender_initial_balance + Number(pool.availableLiquidity));
+  });
+
+  it("borrower borrows instantly from the pool", async () => {
+    await program.methods
+      .depositToPool(new anchor.BN(LAMPORTS_PER_SOL / 2))
+      .accountsPartial({
+        depositor: lender.publicKey,
+        lendingPool,
+        poolVault,
+        shareMint,
+        depositorShareAta: lenderShareAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([lender])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    const { nft } = await metaplex.nfts().create({
+      uri: "https://arweave.net/item-metadata.json",
+      name: "Instant NFT",
+      sellerFeeBasisPoints: 0,
+      collection: borrowerNftCollection
+    });
+    await metaplex.nfts().verifyCollection({ mintAddress: nft.address, collectionMintAddress: borrowerNftCollection });
+    const instantLoan = await nextLoanPda(nft.address);
+    const metadataProgramId = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+    let borrower_initial_balance = await connection.getBalance(borrower.publicKey);
+    await program.methods
+      .borrowFromPool(new anchor.BN(LAMPORTS_PER_SOL / 10), 100, 10_000)
+      .accountsPartial({
+        borrower: borrower.publicKey,
+        borrowerNftMint: nft.address,
+        borrowerNftCollection: borrowerNftCollection,
+        borrowerNftAta: getAssociatedTokenAddressSync(nft.address, borrower.publicKey),
+        metadata: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), nft.address.toBuffer()], metadataProgramId)[0],
+        masterEdition: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), nft.address.toBuffer(), Buffer.from("edition")], metadataProgramId)[0],
+        loanAccount: instantLoan,
+        nftVault: getAssociatedTokenAddressSync(nft.address, instantLoan, true),
+        platform: Platform,
+        lendingPool,
+        poolVault,
+        collectionConfig,
+        metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    const loan = await program.account.loan.fetch(instantLoan);
+    assert.deepEqual(loan.status, { funded: {} });
+    assert.equal(Number(loan.loanId), 2);
+    assert.equal(loan.lender.toBase58(), poolVault.toBase58());
+    const vaultAccount = await getAccount(connection, getAssociatedTokenAddressSync(nft.address, instantLoan, true));
+    assert.equal(Number(vaultAccount.amount), 1);
+    let borrower_final_balance = await connection.getBalance(borrower.publicKey);
+    assert.isAbove(borrower_final_balance, borrower_initial_balance);
+  });
+
+  it("should fail when borrowing more than the collection limit", async () => {
+    const { nft } = await metaplex.nfts().create({
+      uri: "https://arweave.net/item-metadata.json",
+      name: "Big NFT",
+      sellerFeeBasisPoints: 0,
+      collection: borrowerNftCollection
+    });
+    await metaplex.nfts().verifyCollection({ mintAddress: nft.address, collectionMintAddress: borrowerNftCollection });
+    const bigLoan = await nextLoanPda(nft.address);
+    const metadataProgramId = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+    await program.methods
+      .borrowFromPool(new anchor.BN(2 * LAMPORTS_PER_SOL), 100, 10_000)
+      .accountsPartial({
+        borrower: borrower.publicKey,
+        borrowerNftMint: nft.address,
+        borrowerNftCollection: borrowerNftCollection,
+        borrowerNftAta: getAssociatedTokenAddressSync(nft.address, borrower.publicKey),
+        metadata: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), nft.address.toBuffer()], metadataProgramId)[0],
+        masterEdition: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), nft.address.toBuffer(), Buffer.from("edition")], metadataProgramId)[0],
+        loanAccount: bigLoan,
+        nftVault: getAssociatedTokenAddressSync(nft.address, bigLoan, true),
+        platform: Platform,
+        lendingPool,
+        poolVault,
+        collectionConfig,
+        metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch((err) => assert.ok(err.error.errorCode.code === "PoolLoanAmountExceeded"));
+  });
+
+  /**************************************************
+ *               REFERRAL FEE TESTS                *
+ **************************************************/
+  const frontend = Keypair.generate();
+  const referrerAccount = PublicKey.findProgramAddressSync([Buffer.from("referrer"), Platform.toBuffer(), frontend.publicKey.toBuffer()], programId)[0];
+  let referredNftMint: PublicKey
+  let referredLoan: PublicKey
+
+  const mintVerifiedNft = async (name: string): Promise<PublicKey> => {
+    const { nft } = await metaplex.nfts().create({
+      uri: "https://arweave.net/item-metadata.json",
+      name,
+      sellerFeeBasisPoints: 0,
+      collection: borrowerNftCollection
+    });
+    await metaplex.nfts().verifyCollection({ mintAddress: nft.address, collectionMintAddress: borrowerNftCollection });
+    return nft.address;
+  };
+
+  const metadataAccountsFor = (mint: PublicKey) => {
+    const metadataProgramId = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+    return {
+      metadata: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), mint.toBuffer()], metadataProgramId)[0],
+      masterEdition: PublicKey.findProgramAddressSync([Buffer.from("metadata"), metadataProgramId.toBuffer(), mint.toBuffer(), Buffer.from("edition")], metadataProgramId)[0],
+    };
+  };
+
+  it("admin sets the referral share", async () => {
+    await program.methods
+      .updatePlatform(500, 5000)
+      .accountsPartial({ authority: provider.wallet.publicKey, platform: Platform })
+      .rpc()
+      .then(sig => confirm(sig));
+    const platformAccount = await program.account.platform.fetch(Platform);
+    assert.equal(platformAccount.referralShareBps, 5000);
+  });
+
+  it("should fail when non authority updates the platform", async () => {
+    await program.methods
+      .updatePlatform(0, 10000)
+      .accountsPartial({ authority: lender.publicKey, platform: Platform })
+      .signers([lender])
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch(() => assert.ok(true));
+  });
+
+  it("frontend registers as a referrer", async () => {
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({ fromPubkey: provider.wallet.publicKey, toPubkey: frontend.publicKey, lamports: LAMPORTS_PER_SOL / 10 })
+    ));
+    await program.methods
+      .registerReferrer()
+      .accountsPartial({ referrer: frontend.publicKey, platform: Platform, referrerAccount })
+      .signers([frontend])
+      .rpc()
+      .then(sig => confirm(sig));
+  });
+
+  it("borrower requests a loan through the frontend", async () => {
+    referredNftMint = await mintVerifiedNft("Referred NFT");
+    referredLoan = await nextLoanPda(referredNftMint);
+    await program.methods
+      .requestLoan(new anchor.BN(LAMPORTS_PER_SOL / 4), 100, 1000, null, null)
+      .accountsPartial({
+        ...accountsForRequestLoan(),
+        ...metadataAccountsFor(referredNftMint),
+        borrowerNftMint: referredNftMint,
+        borrowerNftAta: getAssociatedTokenAddressSync(referredNftMint, borrower.publicKey),
+        loanAccount: referredLoan,
+        nftVault: getAssociatedTokenAddressSync(referredNftMint, referredLoan, true),
+        referrerAccount,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+    const loan = await program.account.loan.fetch(referredLoan);
+    assert.equal(loan.borrowerReferrer.toBase58(), frontend.publicKey.toBase58());
+    assert.isNull(loan.lenderReferrer);
+  });
+
+  it("lender funds and borrower repays, the frontend earns half the platform fee", async () => {
+    await program.methods
+      .fundBorrower(new anchor.BN(LAMPORTS_PER_SOL / 4), 100, 1000)
+      .accountsPartial({ ...accountsForFundingLoan(), borrowerNftMint: referredNftMint, loanAccount: referredLoan })
+      .signers([lender])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    let referrer_initial_balance = await connection.getBalance(referrerAccount);
+    let treasury_initial_balance = await connection.getBalance(treasuryVault);
+    await program.methods
+      .resolveLoan()
+      .accountsPartial({
+        ...BorrowerResolveLoanAccounts(),
+        borrowerNftMint: referredNftMint,
+        borrowerNftAta: getAssociatedTokenAddressSync(referredNftMint, borrower.publicKey),
+        loanAccount: referredLoan,
+        nftVault: getAssociatedTokenAddressSync(referredNftMint, referredLoan, true),
+        borrowerReferrerAccount: referrerAccount,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    let interest = LAMPORTS_PER_SOL / 4 * 1000 / 10000;
+    let fee = interest * 500 / 10000;
+    let referrer_final_balance = await connection.getBalance(referrerAccount);
+    let treasury_final_balance = await connection.getBalance(treasuryVault);
+    assert.equal(referrer_final_balance - referrer_initial_balance, fee / 2);
+    assert.equal(treasury_final_balance - treasury_initial_balance, fee - fee / 2);
+    const account = await program.account.referrerAccount.fetch(referrerAccount);
+    assert.equal(Number(account.totalEarned), fee / 2);
+  });
+
+  it("frontend claims its referral fees", async () => {
+    let frontend_initial_balance = await connection.getBalance(frontend.publicKey);
+    await program.methods
+      .claimReferralFees()
+      .accountsPartial({ referrer: frontend.publicKey, referrerAccount })
+      .signers([frontend])
+      .rpc()
+      .then(sig => confirm(sig));
+    let frontend_final_balance = await connection.getBalance(frontend.publicKey);
+    const account = await program.account.referrerAccount.fetch(referrerAccount);
+    assert.equal(frontend_final_balance - frontend_initial_balance, Number(account.totalClaimed));
+  });
+
+  it("should fail when claiming with nothing earned", async () => {
+    await program.methods
+      .claimReferralFees()
+      .accountsPartial({ referrer: frontend.publicKey, referrerAccount })
+      .signers([frontend])
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch((err) => assert.ok(err.error.errorCode.code === "NothingToClaim"));
+  });
+
+  it("borrower cancels an unfunded loan and gets the nft back", async () => {
+    const mint = await mintVerifiedNft("Cancelled NFT");
+    const loanPda = await nextLoanPda(mint);
+    const borrowerAta = getAssociatedTokenAddressSync(mint, borrower.publicKey);
+    await program.methods
+      .requestLoan(new anchor.BN(LAMPORTS_PER_SOL / 4), 100, 1000, null, null)
+      .accountsPartial({
+        ...accountsForRequestLoan(),
+        ...metadataAccountsFor(mint),
+        borrowerNftMint: mint,
+        borrowerNftAta: borrowerAta,
+        loanAccount: loanPda,
+        nftVault: getAssociatedTokenAddressSync(mint, loanPda, true),
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    await program.methods
+      .cancelLoan()
+      .accountsPartial({
+        borrower: borrower.publicKey,
+        borrowerNftMint: mint,
+        borrowerNftAta: borrowerAta,
+        loanAccount: loanPda,
+        nftVault: getAssociatedTokenAddressSync(mint, loanPda, true),
+        platform: Platform,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    const ata = await getAccount(connection, borrowerAta);
+    assert.equal(Number(ata.amount), 1);
+    assert.isNull(await connection.getAccountInfo(loanPda));
+  });
+
+  it("anyone marks an expired loan defaulted and keeps the rent refunds", async () => {
+    const mint = await mintVerifiedNft("Abandoned NFT");
+    const loanPda = await nextLoanPda(mint);
+    const nftVault = getAssociatedTokenAddressSync(mint, loanPda, true);
+    await program.methods
+      .requestLoan(new anchor.BN(LAMPORTS_PER_SOL / 10), 2, 1000, null, null)
+      .accountsPartial({
+        ...accountsForRequestLoan(),
+        ...metadataAccountsFor(mint),
+        borrowerNftMint: mint,
+        borrowerNftAta: getAssociatedTokenAddressSync(mint, borrower.publicKey),
+        loanAccount: loanPda,
+        nftVault,
+      })
+      .signers([borrower])
+      .rpc()
+      .then(sig => confirm(sig));
+    await program.methods
+      .fundBorrower(new anchor.BN(LAMPORTS_PER_SOL / 10), 2, 1000)
+      .accountsPartial({ ...accountsForFundingLoan(), borrowerNftMint: mint, loanAccount: loanPda })
+      .signers([lender])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    const keeper = Keypair.generate();
+    await confirm(await connection.requestAirdrop(keeper.publicKey, LAMPORTS_PER_SOL));
+    const lenderNftAta = getAssociatedTokenAddressSync(mint, lender.publicKey);
+    const accountsForMarkDefaulted = {
+      caller: keeper.publicKey,
+      lender: lender.publicKey,
+      borrower: borrower.publicKey,
+      borrowerNftMint: mint,
+      lenderNftAta,
+      platform: Platform,
+      loanAccount: loanPda,
+      nftVault,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    await program.methods
+      .markDefaulted()
+      .accountsPartial(accountsForMarkDefaulted)
+      .signers([keeper])
+      .rpc()
+      .then(() => assert.fail("Should have failed"))
+      .catch((err) => assert.ok(err.error.errorCode.code === "WaitForLoanToComplete"));
+
+    await wait(3);
+    const keeper_initial_balance = await connection.getBalance(keeper.publicKey);
+    await program.methods
+      .markDefaulted()
+      .accountsPartial(accountsForMarkDefaulted)
+      .signers([keeper])
+      .rpc()
+      .then(sig => confirm(sig));
+
+    assert.equal(Number((await getAccount(connection, lenderNftAta)).amount), 1);
+    assert.isNull(await connection.getAccountInfo(loanPda));
+    //the vault refund covers the lender ata, the loan account rent is the bounty
+    assert.isAbove(await connection.getBalance(keeper.publicKey), keeper_initial_balance);
+  });
+
 });
 
 